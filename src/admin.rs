@@ -0,0 +1,291 @@
+//! A line based protocol over a Unix domain socket to control the composite logger at runtime,
+//! see [`crate::CompositeLoggerHandle::serve_admin`].
+//!
+//! Every command is a line, answered by `ok`, or by `error: ` and the reason,
+//! after the lines of output of the command:
+//!
+//! - `list` prints every named logger with whether it is enabled and its level
+//! - `level <name> <level>` changes the level of a named logger
+//! - `enable <name>` and `disable <name>` switch a named logger on and off
+//! - `group-level <group> <level>`, `group-enable <group>` and `group-disable <group>`
+//!   do the same for all loggers of a group
+//! - `target-level <target> <level>` lets records of the target through up to the level,
+//!   and `target-clear <target>` removes it again
+//! - `boost <level> <seconds>` lets every logger receive more verbose records for a while
+//! - `flush` flushes all loggers
+//! - `reopen` reopens the file loggers, see [`crate::CompositeLoggerHandle::reopen_files`]
+//! - `health` prints whether each logger delivers its records
+//! - `explain <level> <target>` prints which loggers would receive a record of the level
+//!   and target, see [`crate::CompositeLoggerHandle::explain`]
+//! - `stats` prints the counters of all loggers in the Prometheus text format
+//!
+//! For example with `socat - UNIX-CONNECT:/run/app/logging.sock`.
+
+use crate::CompositeLoggerHandle;
+use log::{Level, LevelFilter, Metadata};
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::time::Duration;
+
+/// Answer the commands of every connection, one connection at a time
+pub(crate) fn serve(handle: CompositeLoggerHandle, listener: UnixListener) {
+    for stream in listener.incoming().flatten() {
+        let _ = answer(&handle, stream);
+    }
+}
+
+/// Idle connections are closed, so that they do not keep others from connecting
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+fn answer(handle: &CompositeLoggerHandle, stream: UnixStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(IDLE_TIMEOUT))?;
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match execute(handle, &line) {
+            Ok(output) => writeln!(writer, "{}ok", output)?,
+            Err(error) => writeln!(writer, "error: {}", error)?,
+        }
+    }
+    Ok(())
+}
+
+/// Execute the command, returning its output lines
+fn execute(handle: &CompositeLoggerHandle, command: &str) -> Result<String, String> {
+    let words = command.split_whitespace().collect::<Vec<_>>();
+    let level = |level: &str| {
+        level
+            .parse::<LevelFilter>()
+            .map_err(|_| format!("`{}` is not a level", level))
+    };
+    let found = |found: bool, name: &str| {
+        found
+            .then(String::new)
+            .ok_or_else(|| format!("there is no logger or group `{}`", name))
+    };
+
+    match words.as_slice() {
+        ["list"] => Ok(handle
+            .logger_names()
+            .iter()
+            .map(|name| {
+                format!(
+                    "{} {} {}\n",
+                    name,
+                    if handle.is_enabled(name) == Some(true) {
+                        "enabled"
+                    } else {
+                        "disabled"
+                    },
+                    handle.level(name).unwrap_or(LevelFilter::Off)
+                )
+            })
+            .collect()),
+        ["level", name, value] => found(handle.set_level(name, level(value)?), name),
+        ["enable", name] => found(handle.set_enabled(name, true), name),
+        ["disable", name] => found(handle.set_enabled(name, false), name),
+        ["group-level", group, value] => found(handle.set_group_level(group, level(value)?), group),
+        ["group-enable", group] => found(handle.set_group_enabled(group, true), group),
+        ["group-disable", group] => found(handle.set_group_enabled(group, false), group),
+        ["target-level", target, value] => {
+            handle.set_target_level(target, level(value)?);
+            Ok(String::new())
+        }
+        ["target-clear", target] => handle
+            .clear_target_level(target)
+            .then(String::new)
+            .ok_or_else(|| format!("there is no level of the target `{}`", target)),
+        ["boost", value, seconds] => {
+            let seconds = seconds
+                .parse()
+                .map_err(|_| format!("`{}` is not a number of seconds", seconds))?;
+            handle.boost_level_for(level(value)?, Duration::from_secs(seconds));
+            Ok(String::new())
+        }
+        ["flush"] => {
+            handle.flush();
+            Ok(String::new())
+        }
+        ["reopen"] => handle
+            .reopen_files()
+            .map(|()| String::new())
+            .map_err(|error| error.to_string()),
+        ["health"] => Ok(handle
+            .health()
+            .iter()
+            .map(|logger| match &logger.name {
+                Some(name) => format!("{} {}\n", name, logger.health),
+                None => format!("#{} {}\n", logger.id.0, logger.health),
+            })
+            .collect()),
+        ["stats"] => Ok(handle.stats().to_prometheus()),
+        ["explain", value, target] => {
+            let level = value
+                .parse::<Level>()
+                .map_err(|_| format!("`{}` is not a level", value))?;
+            let metadata = Metadata::builder().level(level).target(target).build();
+            Ok(format!("{}\n", handle.explain(&metadata)))
+        }
+        _ => Err(format!("unknown command `{}`", command.trim())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, CaptureLogger};
+    use crate::{CompositeLogger, OwnedRecord};
+    use log::Log;
+    use std::sync::Arc;
+
+    fn handle(capture: &Arc<CaptureLogger>) -> CompositeLoggerHandle {
+        CompositeLogger::new()
+            .with_named_logger("capture", capture.clone())
+            .build()
+    }
+
+    fn log(handle: &CompositeLoggerHandle, level: Level, message: &str) {
+        OwnedRecord::new(level, "app", message).with_record(|record| handle.log(record));
+    }
+
+    #[test]
+    fn changes_the_level_and_switches_the_loggers() {
+        let capture = Arc::new(CaptureLogger::new());
+        let handle = handle(&capture);
+
+        assert_eq!(execute(&handle, "level capture warn"), Ok(String::new()));
+        log(&handle, Level::Info, "hidden");
+        assert_eq!(
+            execute(&handle, "list"),
+            Ok("capture enabled WARN\n".to_string())
+        );
+
+        assert_eq!(execute(&handle, "disable capture"), Ok(String::new()));
+        log(&handle, Level::Error, "disabled");
+        assert_eq!(execute(&handle, "enable capture"), Ok(String::new()));
+        log(&handle, Level::Error, "enabled");
+
+        assert_eq!(
+            capture.entries(),
+            vec![(Level::Error, "app".to_string(), "enabled".to_string())]
+        );
+    }
+
+    #[test]
+    fn changes_the_level_of_a_target() {
+        let capture = Arc::new(CaptureLogger::new());
+        let handle = handle(&capture);
+
+        assert_eq!(execute(&handle, "target-level app warn"), Ok(String::new()));
+        log(&handle, Level::Debug, "silenced");
+        assert_eq!(execute(&handle, "target-clear app"), Ok(String::new()));
+        log(&handle, Level::Debug, "through");
+
+        capture.assert_not_logged(Level::Debug, testing::equals("silenced"));
+        capture.assert_logged(Level::Debug, testing::equals("through"));
+        assert_eq!(
+            execute(&handle, "target-clear app"),
+            Err("there is no level of the target `app`".to_string())
+        );
+    }
+
+    #[test]
+    fn explains_the_loggers_receiving_a_record() {
+        let handle = handle(&Arc::new(CaptureLogger::new()));
+        execute(&handle, "level capture warn").unwrap();
+
+        let explanation = execute(&handle, "explain info app").unwrap();
+        let metadata = Metadata::builder().level(Level::Info).target("app").build();
+        assert_eq!(explanation, format!("{}\n", handle.explain(&metadata)));
+    }
+
+    #[test]
+    fn boosts_the_level_for_any_number_of_seconds() {
+        let capture = Arc::new(CaptureLogger::new());
+        let handle = handle(&capture);
+        execute(&handle, "level capture info").unwrap();
+
+        assert_eq!(
+            execute(&handle, "boost debug 18446744073709551615"),
+            Ok(String::new())
+        );
+        log(&handle, Level::Debug, "boosted");
+        handle.end_boost();
+
+        capture.assert_logged(Level::Debug, testing::equals("boosted"));
+    }
+
+    #[test]
+    fn rejects_invalid_commands() {
+        let handle = handle(&Arc::new(CaptureLogger::new()));
+
+        assert_eq!(
+            execute(&handle, "level capture loud"),
+            Err("`loud` is not a level".to_string())
+        );
+        assert_eq!(
+            execute(&handle, "enable missing"),
+            Err("there is no logger or group `missing`".to_string())
+        );
+        assert_eq!(
+            execute(&handle, "boost info soon"),
+            Err("`soon` is not a number of seconds".to_string())
+        );
+        assert_eq!(
+            execute(&handle, " restart  "),
+            Err("unknown command `restart`".to_string())
+        );
+    }
+
+    #[test]
+    fn answers_the_commands_of_a_connection() {
+        let path = std::env::temp_dir().join(format!(
+            "composite-logger-admin-{}.sock",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let handle = handle(&Arc::new(CaptureLogger::new()));
+        handle.serve_admin(&path).unwrap();
+
+        let mut stream = UnixStream::connect(&path).unwrap();
+        stream
+            .write_all(b"level capture debug\n\nlist\nenable missing\n")
+            .unwrap();
+        stream.shutdown(std::net::Shutdown::Write).unwrap();
+        let answers = BufReader::new(stream)
+            .lines()
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(
+            answers,
+            vec![
+                "ok",
+                "capture enabled DEBUG",
+                "ok",
+                "error: there is no logger or group `missing`",
+            ]
+        );
+    }
+
+    #[test]
+    fn does_not_replace_a_file_with_the_socket() {
+        let path = std::env::temp_dir().join(format!(
+            "composite-logger-admin-file-{}",
+            std::process::id()
+        ));
+        std::fs::write(&path, "kept").unwrap();
+        let handle = handle(&Arc::new(CaptureLogger::new()));
+
+        let error = handle.serve_admin(&path).unwrap_err();
+        let kept = std::fs::read_to_string(&path);
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(kept.unwrap(), "kept");
+    }
+}
@@ -0,0 +1,100 @@
+use crate::OwnedRecord;
+use log::{Log, Metadata, Record};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{sync_channel, SyncSender};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::Handle;
+use tokio::sync::mpsc::{self, Receiver, Sender};
+
+/// How long [`Log::flush`] waits for the driver task to flush the logger
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A logger whose logging and flushing are asynchronous, such as an HTTP shipper,
+/// see [`AsyncLogAdapter`]
+pub trait AsyncLog: Send + Sync + 'static {
+    /// Whether records with the given metadata should be sent to the logger
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        let _ = metadata;
+        true
+    }
+
+    fn log(&self, record: OwnedRecord) -> impl Future<Output = ()> + Send;
+
+    fn flush(&self) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+}
+
+enum Message {
+    Record(OwnedRecord),
+    Flush(SyncSender<()>),
+}
+
+/// Bridges the synchronous [`Log`] calls to an [`AsyncLog`] logger driven by a task
+/// spawned on a tokio runtime. Records are queued without blocking the logging thread
+/// and dropped while the queue is full. The task stops once the adapter is dropped.
+pub struct AsyncLogAdapter<L> {
+    logger: Arc<L>,
+    sender: Sender<Message>,
+    dropped: AtomicU64,
+}
+
+impl<L: AsyncLog> AsyncLogAdapter<L> {
+    /// Spawn the task driving the logger on the runtime, with a queue of the given capacity
+    pub fn spawn(logger: L, runtime: &Handle, capacity: usize) -> Self {
+        let logger = Arc::new(logger);
+        let (sender, receiver) = mpsc::channel(capacity.max(1));
+        runtime.spawn(Self::drive(logger.clone(), receiver));
+
+        Self {
+            logger,
+            sender,
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// How many records were dropped because the queue was full
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    async fn drive(logger: Arc<L>, mut receiver: Receiver<Message>) {
+        while let Some(message) = receiver.recv().await {
+            match message {
+                Message::Record(record) => logger.log(record).await,
+                Message::Flush(done) => {
+                    logger.flush().await;
+                    let _ = done.send(());
+                }
+            }
+        }
+    }
+}
+
+impl<L: AsyncLog> Log for AsyncLogAdapter<L> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.logger.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if self
+            .sender
+            .try_send(Message::Record(record.into()))
+            .is_err()
+        {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Waits for the queued records to be logged and the logger to be flushed,
+    /// up to a few seconds in case the runtime can not make progress meanwhile,
+    /// for example when flushing from the thread of a current thread runtime
+    fn flush(&self) {
+        let (done, flushed) = sync_channel(1);
+        if self.sender.try_send(Message::Flush(done)).is_ok() {
+            let _ = flushed.recv_timeout(FLUSH_TIMEOUT);
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use crate::dispatch::Dispatcher;
+use log::{Level, LevelFilter, Record};
+use std::fmt::Write;
+use std::time::Instant;
+
+/// The target of the startup and shutdown records, see [`crate::CompositeLogger::with_banner`]
+pub const BANNER_TARGET: &str = "composite_logger::banner";
+
+/// The environment variable with the git commit of the process, read at startup
+/// unless the banner has one, see [`Banner::with_git_hash`]
+pub const GIT_HASH_ENV: &str = "GIT_HASH";
+
+/// Describes the process in a record delivered to every logger once the composite logger
+/// is initialized, and in a matching record when it shuts down, so that every log file
+/// tells which process wrote it.
+///
+/// The startup record has the `composite_logger_version`, `os`, `arch`, `hostname`, `pid`,
+/// the `sinks` with their levels, the `max_level`, the `application`, `version` and `git_hash`
+/// when known, and the key-values of the banner. The shutdown record has the `application`,
+/// `pid` and `uptime_ms`. Use [`crate::banner!`] for the name, version and git hash
+/// of the application crate.
+#[derive(Debug, Clone, Default)]
+pub struct Banner {
+    application: Option<(String, String)>,
+    git_hash: Option<String>,
+    key_values: Vec<(String, String)>,
+    /// When the startup record was logged
+    started: Option<Instant>,
+}
+
+impl Banner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The name and version of the application
+    pub fn with_application(mut self, name: impl Into<String>, version: impl Into<String>) -> Self {
+        self.application = Some((name.into(), version.into()));
+        self
+    }
+
+    /// The git commit the application was built from, instead of the [`GIT_HASH_ENV`]
+    /// environment variable
+    pub fn with_git_hash(mut self, git_hash: impl Into<String>) -> Self {
+        self.git_hash = Some(git_hash.into());
+        self
+    }
+
+    /// Add a key-value to the startup record, such as the build profile or the deployment
+    pub fn with_kv(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.key_values.push((key.into(), value.into()));
+        self
+    }
+
+    /// Remember when the process started logging, for the uptime of the shutdown record
+    pub(crate) fn start(&mut self) {
+        self.started = Some(Instant::now());
+    }
+
+    /// Deliver the startup record to every logger of the dispatcher
+    pub(crate) fn log_startup(&self, dispatcher: &Dispatcher) {
+        let mut key_values = vec![(
+            "composite_logger_version".to_string(),
+            env!("CARGO_PKG_VERSION").to_string(),
+        )];
+        if let Some((name, version)) = &self.application {
+            key_values.push(("application".to_string(), name.clone()));
+            key_values.push(("version".to_string(), version.clone()));
+        }
+        if let Some(git_hash) = self
+            .git_hash
+            .clone()
+            .or_else(|| std::env::var(GIT_HASH_ENV).ok())
+        {
+            key_values.push(("git_hash".to_string(), git_hash));
+        }
+        key_values.extend([
+            ("os".to_string(), std::env::consts::OS.to_string()),
+            ("arch".to_string(), std::env::consts::ARCH.to_string()),
+            ("hostname".to_string(), crate::host::hostname()),
+            ("pid".to_string(), std::process::id().to_string()),
+            ("sinks".to_string(), sinks(dispatcher)),
+            ("max_level".to_string(), dispatcher.max_level().to_string()),
+        ]);
+        key_values.extend(self.key_values.iter().cloned());
+
+        let message = match &self.application {
+            Some((name, version)) => format!("{} {} started", name, version),
+            None => "logging started".to_string(),
+        };
+        dispatcher.announce(
+            &Record::builder()
+                .args(format_args!("{}", message))
+                .level(Level::Info)
+                .target(BANNER_TARGET)
+                .key_values(&key_values)
+                .build(),
+        );
+    }
+
+    /// Deliver the shutdown record to every logger of the dispatcher
+    pub(crate) fn log_shutdown(&self, dispatcher: &Dispatcher) {
+        let mut key_values = Vec::new();
+        if let Some((name, _)) = &self.application {
+            key_values.push(("application".to_string(), name.clone()));
+        }
+        key_values.push(("pid".to_string(), std::process::id().to_string()));
+        if let Some(started) = self.started {
+            let uptime = started.elapsed().as_millis();
+            key_values.push(("uptime_ms".to_string(), uptime.to_string()));
+        }
+
+        let message = match &self.application {
+            Some((name, _)) => format!("{} stopped", name),
+            None => "logging stopped".to_string(),
+        };
+        dispatcher.announce(
+            &Record::builder()
+                .args(format_args!("{}", message))
+                .level(Level::Info)
+                .target(BANNER_TARGET)
+                .key_values(&key_values)
+                .build(),
+        );
+    }
+}
+
+/// The loggers as `name=level`, `#id=level` if they have no name,
+/// such as `console=INFO file=DEBUG #2=TRACE fallback=TRACE`
+fn sinks(dispatcher: &Dispatcher) -> String {
+    let mut sinks = String::new();
+    let loggers = dispatcher.loggers.iter().map(|logger| (logger, false));
+    let fallback = dispatcher.fallback.iter().map(|logger| (logger, true));
+    for (logger, fallback) in loggers.chain(fallback) {
+        if !sinks.is_empty() {
+            sinks.push(' ');
+        }
+        let _ = match (&logger.name, fallback) {
+            (Some(name), _) => write!(sinks, "{}", name),
+            (None, true) => write!(sinks, "fallback"),
+            (None, false) => write!(sinks, "#{}", logger.id.0),
+        };
+        let level = match logger.is_active() {
+            true => logger.level(),
+            false => LevelFilter::Off,
+        };
+        let _ = write!(sinks, "={}", level);
+    }
+    sinks
+}
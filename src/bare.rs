@@ -0,0 +1,158 @@
+use crate::filter::{self, Filter, FilteredLogger};
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+
+/// A delegated logger together with the maximum level and target of records it should receive
+struct Entry {
+    level: LevelFilter,
+    /// Only records of the target or any of its submodules are received
+    target: Option<String>,
+    logger: Box<dyn Log>,
+}
+
+impl Entry {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+            && self
+                .target
+                .as_deref()
+                .is_none_or(|target| filter::target_matches(metadata.target(), target))
+            && self.logger.enabled(metadata)
+    }
+}
+
+/// The composite logger without the standard library, only delegating to its loggers
+/// from the logging thread. The loggers can not be changed once it is initialized,
+/// and a panicking logger is not isolated from the others.
+#[derive(Default)]
+pub struct CompositeLogger {
+    loggers: Vec<Entry>,
+    max_level: Option<LevelFilter>,
+}
+
+impl CompositeLogger {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Add a logger to delegate the logs to.
+    /// Since its level is unknown, it is assumed to accept every record.
+    pub fn with_logger(self, logger: impl Log + 'static) -> Self {
+        self.with_logger_at(LevelFilter::max(), logger)
+    }
+
+    /// Add a logger to delegate the logs to, that only receives records
+    /// at or above the given level.
+    /// The global maximum level is computed from the levels of all loggers.
+    pub fn with_logger_at(self, level: LevelFilter, logger: impl Log + 'static) -> Self {
+        self.with_entry(level, None, Box::new(logger))
+    }
+
+    /// Add an already boxed logger to delegate the logs to
+    pub fn with_boxed_logger(self, logger: Box<dyn Log>) -> Self {
+        self.with_entry(LevelFilter::max(), None, logger)
+    }
+
+    /// Add a logger that only receives records of the given target or any of its
+    /// submodules, for example `"hyper"` matches both `hyper` and `hyper::client`
+    pub fn with_target_route(self, target: impl Into<String>, logger: impl Log + 'static) -> Self {
+        self.with_entry(LevelFilter::max(), Some(target.into()), Box::new(logger))
+    }
+
+    /// Add a logger that only receives records accepted by the filter,
+    /// see the [`filter`] module for the available filters
+    pub fn with_filtered_logger(
+        self,
+        logger: impl Log + 'static,
+        filter: impl Filter + 'static,
+    ) -> Self {
+        self.with_logger(FilteredLogger::new(logger, filter))
+    }
+
+    /// Never dispatch records more verbose than the level, whatever the loggers accept
+    pub fn with_max_level(mut self, max_level: LevelFilter) -> Self {
+        self.max_level = Some(max_level);
+        self
+    }
+
+    /// The most verbose level accepted by any of the loggers,
+    /// which becomes the global maximum level once initialized
+    pub fn max_level(&self) -> LevelFilter {
+        let max_level = self
+            .loggers
+            .iter()
+            .map(|logger| logger.level)
+            .max()
+            .unwrap_or(LevelFilter::Off);
+
+        match self.max_level {
+            Some(limit) => max_level.min(limit),
+            None => max_level,
+        }
+    }
+
+    fn with_entry(
+        mut self,
+        level: LevelFilter,
+        target: Option<String>,
+        logger: Box<dyn Log>,
+    ) -> Self {
+        self.loggers.push(Entry {
+            level,
+            target,
+            logger,
+        });
+        self
+    }
+
+    /// Initializes the global logger with the built composite logger,
+    /// which is leaked as it lives for the rest of the program
+    ///
+    /// # Errors
+    ///
+    /// This function will fail if another logger was already installed.
+    pub fn try_init(self) -> Result<(), SetLoggerError> {
+        let max_level = self.max_level();
+        log::set_logger(Box::leak(Box::new(self)))?;
+        log::set_max_level(max_level);
+        Ok(())
+    }
+
+    /// Initializes the global logger with the built composite logger
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if another logger was already installed.
+    pub fn init(self) {
+        self.try_init()
+            .unwrap_or_else(|error| panic!("CompositeLogger::init failed: {}", error))
+    }
+}
+
+impl Log for CompositeLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.max_level
+            .is_none_or(|max_level| metadata.level() <= max_level)
+            && self.loggers.iter().any(|logger| logger.enabled(metadata))
+    }
+
+    fn log(&self, record: &Record) {
+        if self
+            .max_level
+            .is_some_and(|max_level| record.level() > max_level)
+        {
+            return;
+        }
+
+        self.loggers
+            .iter()
+            .filter(|logger| logger.enabled(record.metadata()))
+            .for_each(|logger| logger.logger.log(record));
+    }
+
+    fn flush(&self) {
+        self.loggers.iter().for_each(|logger| logger.logger.flush());
+    }
+}
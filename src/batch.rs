@@ -0,0 +1,256 @@
+use crate::time;
+use crate::OwnedRecord;
+use log::{Log, Metadata, Record};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A logger that receives records in batches, which is much cheaper
+/// for network or database sinks, see [`BatchedLogger`]
+pub trait BatchLog: Send + Sync + 'static {
+    /// Whether records with the given metadata should be batched for the logger
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        let _ = metadata;
+        true
+    }
+
+    /// Deliver the records, in the order they were logged
+    fn log_batch(&self, records: &[OwnedRecord]);
+
+    fn flush(&self) {}
+}
+
+/// Delivers the batches to a plain logger one record at a time
+pub struct PerRecord<L>(pub L);
+
+impl<L: Log + 'static> BatchLog for PerRecord<L> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.0.enabled(metadata)
+    }
+
+    fn log_batch(&self, records: &[OwnedRecord]) {
+        for record in records {
+            record.with_record(|record| self.0.log(record));
+        }
+    }
+
+    fn flush(&self) {
+        self.0.flush();
+    }
+}
+
+#[derive(Default)]
+struct State {
+    records: Vec<OwnedRecord>,
+    /// When the oldest buffered record was logged
+    since: Option<Instant>,
+    closed: bool,
+}
+
+struct Shared<B> {
+    logger: B,
+    state: Mutex<State>,
+    changed: Condvar,
+    max_batch: usize,
+    max_delay: Duration,
+}
+
+impl<B: BatchLog> Shared<B> {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    fn deliver(&self, records: Vec<OwnedRecord>) {
+        if !records.is_empty() {
+            self.logger.log_batch(&records);
+        }
+    }
+
+    fn take(state: &mut State) -> Vec<OwnedRecord> {
+        state.since = None;
+        std::mem::take(&mut state.records)
+    }
+
+    /// Deliver the batches that are older than the maximum delay until closed
+    fn run(&self) {
+        let mut state = self.lock();
+        loop {
+            if state.closed {
+                let records = Self::take(&mut state);
+                drop(state);
+                return self.deliver(records);
+            }
+
+            state = match state.since {
+                Some(since) if time::elapsed(since) >= self.max_delay => {
+                    let records = Self::take(&mut state);
+                    drop(state);
+                    self.deliver(records);
+                    self.lock()
+                }
+                Some(since) => {
+                    let wait = self.max_delay.saturating_sub(time::elapsed(since));
+                    self.changed
+                        .wait_timeout(state, wait)
+                        .unwrap_or_else(|error| error.into_inner())
+                        .0
+                }
+                None => self
+                    .changed
+                    .wait(state)
+                    .unwrap_or_else(|error| error.into_inner()),
+            };
+        }
+    }
+}
+
+/// Buffers records and delivers them to a [`BatchLog`] logger once `max_batch` records
+/// are buffered, at the latest `max_delay` after the oldest of them was logged
+/// from a background thread, when flushed and when dropped
+pub struct BatchedLogger<B: BatchLog> {
+    shared: Arc<Shared<B>>,
+}
+
+impl<B: BatchLog> BatchedLogger<B> {
+    pub fn new(logger: B, max_batch: usize, max_delay: Duration) -> Self {
+        let shared = Arc::new(Shared {
+            logger,
+            state: Mutex::new(State::default()),
+            changed: Condvar::new(),
+            max_batch: max_batch.max(1),
+            max_delay,
+        });
+
+        let worker = shared.clone();
+        thread::Builder::new()
+            .name("composite-logger-batch".to_string())
+            .spawn(move || worker.run())
+            .expect("failed to spawn the composite logger batch thread");
+
+        Self { shared }
+    }
+}
+
+impl<B: BatchLog> Log for BatchedLogger<B> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.shared.logger.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        let full = {
+            let mut state = self.shared.lock();
+            state.records.push(record.into());
+            if state.since.is_none() {
+                state.since = Some(time::instant());
+                self.shared.changed.notify_one();
+            }
+            (state.records.len() >= self.shared.max_batch).then(|| Shared::<B>::take(&mut state))
+        };
+
+        if let Some(records) = full {
+            self.shared.deliver(records);
+        }
+    }
+
+    fn flush(&self) {
+        let records = Shared::<B>::take(&mut self.shared.lock());
+        self.shared.deliver(records);
+        self.shared.logger.flush();
+    }
+}
+
+impl<B: BatchLog> Drop for BatchedLogger<B> {
+    fn drop(&mut self) {
+        self.shared.lock().closed = true;
+        self.shared.changed.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, CaptureLogger};
+    use log::Level;
+
+    /// Keeps the messages of every batch
+    #[derive(Clone, Default)]
+    struct Batches(Arc<Mutex<Vec<Vec<String>>>>);
+
+    impl Batches {
+        fn batches(&self) -> Vec<Vec<String>> {
+            self.0.lock().unwrap().clone()
+        }
+
+        /// The batches once there are the given number of them, waiting for the batch thread
+        fn wait_for(&self, count: usize) -> Vec<Vec<String>> {
+            for _ in 0..500 {
+                if self.batches().len() >= count {
+                    break;
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+            self.batches()
+        }
+    }
+
+    impl BatchLog for Batches {
+        fn log_batch(&self, records: &[OwnedRecord]) {
+            let messages = records.iter().map(|record| record.message().to_string());
+            self.0.lock().unwrap().push(messages.collect());
+        }
+    }
+
+    fn log(logger: &impl Log, message: &str) {
+        OwnedRecord::new(Level::Info, "app", message).with_record(|record| logger.log(record));
+    }
+
+    const HOUR: Duration = Duration::from_secs(3600);
+
+    #[test]
+    fn delivers_full_batches_right_away() {
+        let batches = Batches::default();
+        let logger = BatchedLogger::new(batches.clone(), 2, HOUR);
+        for message in ["first", "second", "third"] {
+            log(&logger, message);
+        }
+
+        assert_eq!(batches.batches(), [["first", "second"]]);
+        logger.flush();
+        assert_eq!(batches.batches(), [vec!["first", "second"], vec!["third"]]);
+    }
+
+    #[test]
+    fn delivers_the_batch_after_the_maximum_delay() {
+        testing::with_mock_clock(|clock| {
+            let batches = Batches::default();
+            let logger = BatchedLogger::new(batches.clone(), 100, Duration::from_millis(20));
+            log(&logger, "first");
+            log(&logger, "second");
+            assert_eq!(batches.batches(), Vec::<Vec<String>>::new());
+
+            clock.advance(Duration::from_millis(20));
+            assert_eq!(batches.wait_for(1), [["first", "second"]]);
+        });
+    }
+
+    #[test]
+    fn delivers_the_rest_once_dropped() {
+        let batches = Batches::default();
+        let logger = BatchedLogger::new(batches.clone(), 100, HOUR);
+        log(&logger, "buffered");
+        drop(logger);
+
+        assert_eq!(batches.wait_for(1), [["buffered"]]);
+    }
+
+    #[test]
+    fn delivers_batches_to_plain_loggers_record_by_record() {
+        let capture = Arc::new(CaptureLogger::new());
+        let logger = BatchedLogger::new(PerRecord(capture.clone()), 2, HOUR);
+        log(&logger, "first");
+        log(&logger, "second");
+
+        capture.assert_logged(Level::Info, testing::equals("first"));
+        capture.assert_logged(Level::Info, testing::equals("second"));
+    }
+}
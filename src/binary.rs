@@ -0,0 +1,206 @@
+use crate::OwnedRecord;
+use log::kv::{self, Key, ToValue, Value, VisitSource};
+use log::{Log, Metadata, Record};
+use std::fmt::{self, Write};
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// How many bytes a line of a hex dump shows
+const HEX_DUMP_WIDTH: usize = 16;
+
+/// A binary payload, such as a network packet, attached to a record as a key-value:
+///
+/// ```text
+/// log::debug!(packet = Binary::new(&bytes); "received a packet");
+/// ```
+///
+/// Every sink renders it in its own way: the [`crate::sinks::ConsoleLogger`] prints a hex dump
+/// below the line, the JSON sinks write a base64 string, the [`crate::msgpack::MessagePackFormat`]
+/// a MessagePack binary and the syslog sink leaves it out of the structured data.
+/// Other sinks and formats show it as a hex string, or as configured with a [`BinaryRenderedLogger`].
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Binary(Box<[u8]>);
+
+impl Binary {
+    pub fn new(bytes: impl AsRef<[u8]>) -> Self {
+        Self(bytes.as_ref().into())
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// The payload if the value is a [`Binary`]
+    pub(crate) fn of<'a>(value: &'a Value) -> Option<&'a Binary> {
+        value.to_borrowed_error()?.downcast_ref::<Binary>()
+    }
+}
+
+impl fmt::Display for Binary {
+    /// The bytes as lowercase hex digits
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+    }
+}
+
+impl fmt::Debug for Binary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Binary({} bytes: {})", self.0.len(), self)
+    }
+}
+
+/// Lets the sinks recognize the payload among the key-values, which can only
+/// be downcast from errors
+impl std::error::Error for Binary {}
+
+impl ToValue for Binary {
+    fn to_value(&self) -> Value<'_> {
+        Value::from_dyn_error(self)
+    }
+}
+
+/// How a [`BinaryRenderedLogger`] shows binary payloads to the logger it wraps
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum BinaryRendering {
+    /// A key-value of lowercase hex digits
+    #[default]
+    Hex,
+    /// A key-value with the length of the payload, and a hex dump below the message
+    HexDump,
+    /// A key-value of the payload encoded with the standard base64 alphabet
+    Base64,
+    /// Leave the payload out
+    Skip,
+}
+
+/// A logger that receives binary payloads rendered as strings, for sinks
+/// without their own rendering or to override it
+pub struct BinaryRenderedLogger<L> {
+    logger: L,
+    rendering: BinaryRendering,
+}
+
+impl<L: Log> BinaryRenderedLogger<L> {
+    pub fn new(logger: L, rendering: BinaryRendering) -> Self {
+        Self { logger, rendering }
+    }
+}
+
+impl<L: Log> Log for BinaryRenderedLogger<L> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.logger.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        let mut binaries = Binaries(Vec::new());
+        let _ = record.key_values().visit(&mut binaries);
+        if binaries.0.is_empty() {
+            return self.logger.log(record);
+        }
+
+        let owned = OwnedRecord::from(record);
+        let mut message = owned.message().to_string();
+        let key_values = owned
+            .key_values()
+            .iter()
+            .filter_map(|(key, value)| {
+                let Some((_, binary)) = binaries.0.iter().find(|(name, _)| name == key) else {
+                    return Some((key.clone(), value.clone()));
+                };
+                let value = match self.rendering {
+                    BinaryRendering::Hex => binary.to_string(),
+                    BinaryRendering::HexDump => {
+                        message.push('\n');
+                        message.push_str(&hex_dump(binary.as_bytes()));
+                        format!("{} bytes", binary.as_bytes().len())
+                    }
+                    BinaryRendering::Base64 => base64(binary.as_bytes()),
+                    BinaryRendering::Skip => return None,
+                };
+                Some((key.clone(), value))
+            })
+            .collect::<Vec<_>>();
+
+        key_values
+            .into_iter()
+            .fold(
+                owned.with_message(message).without_key_values(),
+                |rendered, (key, value)| rendered.with_key_value(key, value),
+            )
+            .with_record(|record| self.logger.log(record));
+    }
+
+    fn flush(&self) {
+        self.logger.flush();
+    }
+}
+
+/// Collects the binary payloads among key-values
+pub(crate) struct Binaries(pub(crate) Vec<(String, Binary)>);
+
+impl<'kvs> VisitSource<'kvs> for Binaries {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+        if let Some(binary) = Binary::of(&value) {
+            self.0.push((key.to_string(), binary.clone()));
+        }
+        Ok(())
+    }
+}
+
+/// The bytes encoded with the standard base64 alphabet, with padding
+pub(crate) fn base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (index, byte)| {
+            group | u32::from(*byte) << (16 - 8 * index)
+        });
+        for index in 0..4 {
+            if index <= chunk.len() {
+                let sextet = (group >> (18 - 6 * index)) & 0x3f;
+                encoded.push(char::from(BASE64_ALPHABET[sextet as usize]));
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// The bytes as lines of an offset, 16 hex bytes and their printable ASCII characters:
+///
+/// ```text
+/// 00000000  47 45 54 20 2f 20 48 54  54 50 2f 31 2e 31 0d 0a |GET / HTTP/1.1..|
+/// ```
+pub(crate) fn hex_dump(bytes: &[u8]) -> String {
+    let mut dump = String::with_capacity(bytes.len().div_ceil(HEX_DUMP_WIDTH) * 80);
+    for (line, chunk) in bytes.chunks(HEX_DUMP_WIDTH).enumerate() {
+        if line > 0 {
+            dump.push('\n');
+        }
+        let _ = write!(dump, "{:08x} ", line * HEX_DUMP_WIDTH);
+        for index in 0..HEX_DUMP_WIDTH {
+            if index % 8 == 0 {
+                dump.push(' ');
+            }
+            match chunk.get(index) {
+                Some(byte) => {
+                    let _ = write!(dump, "{:02x} ", byte);
+                }
+                None => dump.push_str("   "),
+            }
+        }
+        dump.push('|');
+        dump.extend(chunk.iter().map(|byte| match byte {
+            0x20..=0x7e => char::from(*byte),
+            _ => '.',
+        }));
+        dump.push('|');
+    }
+    dump
+}
@@ -0,0 +1,143 @@
+use crate::time;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Skips a logger that failed too often within a window, so that a flapping logger
+/// does not slow down the others. Once the cooldown has passed, the next record
+/// probes the logger, which is used again if it succeeds, or skipped for another
+/// cooldown if it fails. Failures are panics while logging.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitBreaker {
+    failures: usize,
+    window: Duration,
+    cooldown: Duration,
+}
+
+impl CircuitBreaker {
+    /// Skip a logger for the cooldown once it failed `failures` times within the window
+    pub fn new(failures: usize, window: Duration, cooldown: Duration) -> Self {
+        Self {
+            failures: failures.max(1),
+            window,
+            cooldown,
+        }
+    }
+
+    pub fn cooldown(&self) -> Duration {
+        self.cooldown
+    }
+}
+
+/// The instant durations are measured from, so that they fit in an atomic
+fn epoch() -> Instant {
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    *EPOCH.get_or_init(time::instant)
+}
+
+/// Milliseconds since the epoch, never zero
+fn millis(instant: Instant) -> u64 {
+    instant.saturating_duration_since(epoch()).as_millis() as u64 + 1
+}
+
+/// Whether the circuit of a logger is open, in which case it is skipped
+#[derive(Default)]
+pub(crate) struct BreakerState {
+    /// When the next probe is allowed, zero while the circuit is closed
+    open_until: AtomicU64,
+    failures: Mutex<VecDeque<Instant>>,
+}
+
+impl BreakerState {
+    /// Whether a record may be delivered, either because the circuit is closed
+    /// or because the cooldown has passed and the logger should be probed
+    pub(crate) fn allows(&self) -> bool {
+        let open_until = self.open_until.load(Ordering::Relaxed);
+        open_until == 0 || millis(time::instant()) >= open_until
+    }
+
+    /// Close the circuit after a successful delivery, returning whether it was open
+    pub(crate) fn succeeded(&self) -> bool {
+        self.open_until.load(Ordering::Relaxed) != 0
+            && self.open_until.swap(0, Ordering::Relaxed) != 0
+    }
+
+    /// Count the failure, returning whether the circuit was opened
+    pub(crate) fn failed(&self, breaker: &CircuitBreaker) -> bool {
+        let now = time::instant();
+        let mut failures = self
+            .failures
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+
+        let probing = self.open_until.load(Ordering::Relaxed) != 0;
+        failures.push_back(now);
+        while failures
+            .front()
+            .is_some_and(|failure| now.duration_since(*failure) > breaker.window)
+        {
+            failures.pop_front();
+        }
+
+        if probing || failures.len() >= breaker.failures {
+            failures.clear();
+            self.open_until
+                .store(millis(now + breaker.cooldown), Ordering::Relaxed);
+            return true;
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn skips_the_logger_for_the_cooldown_once_it_failed_too_often() {
+        testing::with_mock_clock(|clock| {
+            let breaker = CircuitBreaker::new(2, 10 * SECOND, 30 * SECOND);
+            let state = BreakerState::default();
+
+            assert!(!state.failed(&breaker));
+            assert!(state.allows());
+            assert!(state.failed(&breaker));
+            assert!(!state.allows());
+
+            clock.advance(30 * SECOND);
+            assert!(state.allows());
+            assert!(state.succeeded());
+            assert!(!state.succeeded());
+        });
+    }
+
+    #[test]
+    fn opens_again_when_the_probe_fails() {
+        testing::with_mock_clock(|clock| {
+            let breaker = CircuitBreaker::new(1, 10 * SECOND, 30 * SECOND);
+            let state = BreakerState::default();
+            state.failed(&breaker);
+
+            clock.advance(30 * SECOND);
+            assert!(state.failed(&breaker));
+            assert!(!state.allows());
+        });
+    }
+
+    #[test]
+    fn forgets_the_failures_outside_the_window() {
+        testing::with_mock_clock(|clock| {
+            let breaker = CircuitBreaker::new(2, 10 * SECOND, 30 * SECOND);
+            let state = BreakerState::default();
+
+            assert!(!state.failed(&breaker));
+            clock.advance(11 * SECOND);
+            assert!(!state.failed(&breaker));
+            assert!(state.allows());
+        });
+    }
+}
@@ -0,0 +1,78 @@
+//! The logging flags every command line tool repeats, ready to be flattened into its
+//! [`clap`] arguments:
+//!
+//! ```text
+//! #[derive(clap::Parser)]
+//! struct Cli {
+//!     #[command(flatten)]
+//!     log: composite_logger::cli::LogArgs,
+//! }
+//!
+//! let cli = Cli::parse();
+//! cli.log.logger()?.init();
+//! ```
+//!
+//! The flags are `--log-level <level>`, `--log-file <path>`, `--log-json` and the counted
+//! `-v`/`--verbose` and `-q`/`--quiet` selecting a built-in [`crate::Profile`].
+
+use crate::sinks::{ConsoleLogger, RotatingFileLogger};
+use crate::{CompositeLogger, JsonFormat, Profile};
+use log::LevelFilter;
+use std::io;
+use std::path::PathBuf;
+
+/// The logging flags of a command line tool, see the [module](self) documentation
+#[derive(Debug, Clone, Default, PartialEq, Eq, clap::Args)]
+#[command(about = None, long_about = None)]
+pub struct LogArgs {
+    /// Log records up to the level, overriding `--verbose` and `--quiet`
+    #[arg(long, value_name = "LEVEL", global = true)]
+    pub log_level: Option<LevelFilter>,
+    /// Also log to the file, rotated as it grows
+    #[arg(long, value_name = "PATH", global = true)]
+    pub log_file: Option<PathBuf>,
+    /// Log records as JSON lines
+    #[arg(long, global = true)]
+    pub log_json: bool,
+    /// Log more, `-vv` for everything
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    pub verbose: u8,
+    /// Log less
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    pub quiet: u8,
+}
+
+impl LogArgs {
+    /// The name of the built-in profile selected by the flags, one of [`crate::PROFILE_NAMES`]
+    pub fn profile_name(&self) -> &'static str {
+        Profile::name_for_verbosity(self.verbose, self.quiet)
+    }
+
+    /// A composite logger with a `console` logger and, with `--log-file`, a `file` logger,
+    /// limited to the level of the flags. Further loggers and options can be added
+    /// to the returned builder before initializing it.
+    ///
+    /// # Errors
+    ///
+    /// If the log file can not be opened.
+    pub fn logger(&self) -> io::Result<CompositeLogger> {
+        let mut console = ConsoleLogger::new();
+        if self.log_json {
+            console = console.with_format(JsonFormat);
+        }
+        let mut logger = CompositeLogger::new().with_named_logger("console", console);
+
+        if let Some(path) = &self.log_file {
+            let mut file = RotatingFileLogger::new(path)?;
+            if self.log_json {
+                file = file.with_format(JsonFormat);
+            }
+            logger = logger.with_named_logger("file", file);
+        }
+
+        Ok(match self.log_level {
+            Some(level) => logger.with_max_level(level),
+            None => logger.with_active_profile(self.profile_name()),
+        })
+    }
+}
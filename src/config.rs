@@ -0,0 +1,711 @@
+//! Build the composite logger from a description of its loggers,
+//! for example read from a TOML or YAML file, so that the routing of records
+//! can be changed per environment without recompiling.
+//!
+//! ```toml
+//! filter = "info,hyper=warn"
+//! async_capacity = 1024
+//!
+//! [[sinks]]
+//! kind = "file"
+//! name = "file"
+//! level = "debug"
+//! path = "app.log"
+//! max_size = 10485760
+//!
+//! [[sinks]]
+//! kind = "json"
+//! target = "audit"
+//! path = "audit.json"
+//! ```
+//!
+//! The built-in kinds are `console`, a [`ConsoleLogger`] configured with `colors`,
+//! `stderr_level`, `format` and `theme`, one of `plain`, `dark` or `light`, `file`, a [`RotatingFileLogger`] configured with `path`,
+//! `max_size`, `max_files`, `max_total_size`, `max_age_secs`, `rotation_interval_secs`,
+//! `compress`, `zstd_level`, `durability`, `sync_level`, `encryption_key_env`, the environment
+//! variable holding the hex encoded encryption key, `shared` and `format`, and `json`,
+//! a [`JsonLogger`] writing to `path` or to the standard error.
+//! All three also take `encoding`, `utf-8` or `utf-16le`, `newline`, `lf` or `crlf`,
+//! and `escape_controls`, see [`LineEncoding`].
+//! The `format` is `logfmt` for the [`LogfmtFormat`], `json` for the [`JsonFormat`],
+//! or else a template of a [`TemplateFormat`].
+//! The `path` of a file can contain the `{date}`, `{hostname}` and `{pid}` placeholders.
+//! Other kinds can be registered with [`SinkRegistry::register`].
+
+use crate::dispatch::Route;
+use crate::filter::FilteredLogger;
+use crate::format::DEFAULT_TEMPLATE;
+use crate::sinks::{ColorChoice, ConsoleLogger, Durability, JsonLogger, RotatingFileLogger};
+use crate::{
+    BacktraceMode, Banner, CompositeLogger, DispatchStrategy, EnvFilter, EnvFilterError, Format,
+    InitError, JsonFormat, LineEncoding, LogfmtFormat, Newline, OverflowPolicy, TemplateFormat,
+    TextEncoding, Theme, TimeZoneError, TruncatedLogger, Truncation,
+};
+use log::{Level, LevelFilter, Log};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+use std::fs::OpenOptions;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Describes how the composite logger is built
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggerConfig {
+    /// `RUST_LOG` style directives applied to every record, see [`EnvFilter`]
+    pub filter: Option<String>,
+    /// No record more verbose is dispatched, see [`CompositeLogger::with_max_level`]
+    pub max_level: Option<LevelFilter>,
+    /// Key-values added to every record, see [`CompositeLogger::with_kv`]
+    pub kv: BTreeMap<String, String>,
+    /// Dispatch the records from a dedicated thread with a queue of the given capacity
+    pub async_capacity: Option<usize>,
+    pub overflow_policy: OverflowPolicy,
+    pub flush_interval_ms: Option<u64>,
+    pub dispatch_strategy: DispatchStrategy,
+    pub deduplicate: bool,
+    /// Stamp records with sequence numbers, see [`CompositeLogger::with_sequence_numbers`]
+    pub sequence_numbers: bool,
+    /// Attach backtraces to records of this level or more severe, see [`CompositeLogger::with_backtraces`]
+    pub backtrace_level: Option<Level>,
+    pub backtrace_mode: BacktraceMode,
+    pub max_consecutive_panics: Option<usize>,
+    /// `rfc3339`, `epoch_millis` or a `strftime` pattern, see [`TimestampFormat`]
+    pub timestamp_format: Option<String>,
+    /// `utc`, `local` or an offset such as `+01:00`, see [`TimeZone`]
+    pub time_zone: Option<String>,
+    /// Describe the process in a record at startup and shutdown, see [`CompositeLogger::with_banner`]
+    pub banner: bool,
+    /// Records whose message matches any of the regular expressions are dropped,
+    /// see [`CompositeLogger::suppress_matching`]
+    #[cfg(feature = "regex")]
+    pub suppress: Vec<String>,
+    pub sinks: Vec<SinkConfig>,
+    /// Receives the records none of the sinks is enabled for
+    pub fallback: Option<SinkConfig>,
+}
+
+impl LoggerConfig {
+    /// Parse the configuration from a TOML document
+    ///
+    /// # Errors
+    ///
+    /// Fails if the document is not valid TOML or does not describe a configuration.
+    #[cfg(feature = "toml")]
+    pub fn from_toml_str(document: &str) -> Result<Self, ConfigError> {
+        toml::from_str(document).map_err(|error| ConfigError::Parse(error.to_string()))
+    }
+
+    /// Parse the configuration from a YAML document
+    ///
+    /// # Errors
+    ///
+    /// Fails if the document is not valid YAML or does not describe a configuration.
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml_str(document: &str) -> Result<Self, ConfigError> {
+        serde_yaml::from_str(document).map_err(|error| ConfigError::Parse(error.to_string()))
+    }
+
+    /// Read the configuration from a file, parsed as TOML or YAML depending on its extension
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file can not be read, its format is not supported by the enabled features,
+    /// or it does not describe a configuration.
+    #[cfg(any(feature = "toml", feature = "yaml"))]
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let document = std::fs::read_to_string(path).map_err(ConfigError::Io)?;
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            #[cfg(feature = "toml")]
+            Some("toml") => Self::from_toml_str(&document),
+            #[cfg(feature = "yaml")]
+            Some("yaml" | "yml") => Self::from_yaml_str(&document),
+            _ => Err(ConfigError::Parse(format!(
+                "unsupported configuration file `{}`",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Build the composite logger with the built-in sink kinds
+    ///
+    /// # Errors
+    ///
+    /// Fails if a filter is invalid, a sink kind is unknown or a sink can not be created.
+    pub fn build(&self) -> Result<CompositeLogger, ConfigError> {
+        SinkRegistry::new().build(self)
+    }
+}
+
+/// Describes a single logger of the composite logger
+#[derive(Debug, Clone, Deserialize)]
+pub struct SinkConfig {
+    /// Which factory of the [`SinkRegistry`] creates the logger
+    pub kind: String,
+    /// Allows to refer to the logger at runtime, see [`CompositeLogger::with_named_logger`]
+    pub name: Option<String>,
+    /// Allows to change this and other loggers at once at runtime,
+    /// see [`CompositeLogger::with_group`]
+    pub group: Option<String>,
+    pub level: Option<LevelFilter>,
+    /// Only records of the target or any of its submodules are received
+    pub target: Option<String>,
+    /// Only records whose target starts with the prefix are received
+    pub target_prefix: Option<String>,
+    /// Only records logged from threads whose name matches the pattern are received,
+    /// see [`CompositeLogger::with_thread_route`]
+    pub thread: Option<String>,
+    /// `RUST_LOG` style directives only applied to this logger
+    pub filter: Option<String>,
+    /// Only records whose message matches the regular expression are received
+    #[cfg(feature = "regex")]
+    pub message: Option<String>,
+    /// See [`CompositeLogger::with_logger_timeout`]
+    pub timeout_ms: Option<u64>,
+    /// Longer messages are truncated as configured by `truncation`, see [`TruncatedLogger`]
+    pub max_message_length: Option<usize>,
+    #[serde(default)]
+    pub truncation: Truncation,
+    /// Everything else, interpreted by the factory of the kind
+    #[serde(flatten)]
+    pub options: BTreeMap<String, ConfigValue>,
+}
+
+impl SinkConfig {
+    /// The string option, or `None` if it is not set
+    ///
+    /// # Errors
+    ///
+    /// Fails if the option is not a string.
+    pub fn string(&self, key: &str) -> Result<Option<&str>, ConfigError> {
+        match self.options.get(key) {
+            None => Ok(None),
+            Some(ConfigValue::String(value)) => Ok(Some(value)),
+            Some(_) => Err(self.invalid_option(key, "a string")),
+        }
+    }
+
+    /// The non-negative integer option, or `None` if it is not set
+    ///
+    /// # Errors
+    ///
+    /// Fails if the option is not a non-negative integer.
+    pub fn integer(&self, key: &str) -> Result<Option<u64>, ConfigError> {
+        match self.options.get(key) {
+            None => Ok(None),
+            Some(ConfigValue::Integer(value)) if *value >= 0 => Ok(Some(*value as u64)),
+            Some(_) => Err(self.invalid_option(key, "a non-negative integer")),
+        }
+    }
+
+    /// The boolean option, or `None` if it is not set
+    ///
+    /// # Errors
+    ///
+    /// Fails if the option is not a boolean.
+    pub fn boolean(&self, key: &str) -> Result<Option<bool>, ConfigError> {
+        match self.options.get(key) {
+            None => Ok(None),
+            Some(ConfigValue::Bool(value)) => Ok(Some(*value)),
+            Some(_) => Err(self.invalid_option(key, "a boolean")),
+        }
+    }
+
+    /// An error of this sink, to be returned by the factory creating it
+    pub fn error(&self, message: impl Into<String>) -> ConfigError {
+        ConfigError::Sink {
+            kind: self.kind.clone(),
+            message: message.into(),
+        }
+    }
+
+    fn invalid_option(&self, key: &str, expected: &str) -> ConfigError {
+        self.error(format!("option `{}` must be {}", key, expected))
+    }
+
+    /// The format named by the `format` option, or else its [`TemplateFormat`]
+    fn format(&self) -> Result<Option<Box<dyn Format>>, ConfigError> {
+        Ok(Some(match self.string("format")? {
+            None => return Ok(None),
+            Some("logfmt") => Box::new(LogfmtFormat),
+            Some("json") => Box::new(JsonFormat),
+            Some(template) => Box::new(
+                TemplateFormat::new(template).map_err(|error| self.error(error.to_string()))?,
+            ),
+        }))
+    }
+
+    /// The line encoding of the `encoding`, `newline` and `escape_controls` options
+    fn line_encoding(&self) -> Result<LineEncoding, ConfigError> {
+        let mut line_encoding = LineEncoding::new();
+        if let Some(encoding) = self.string("encoding")? {
+            let encoding = match encoding {
+                "utf-8" => TextEncoding::Utf8,
+                "utf-16le" => TextEncoding::Utf16Le,
+                _ => return Err(self.invalid_option("encoding", "`utf-8` or `utf-16le`")),
+            };
+            line_encoding = line_encoding.with_encoding(encoding);
+        }
+        if let Some(newline) = self.string("newline")? {
+            let newline = match newline {
+                "lf" => Newline::Lf,
+                "crlf" => Newline::CrLf,
+                _ => return Err(self.invalid_option("newline", "`lf` or `crlf`")),
+            };
+            line_encoding = line_encoding.with_newline(newline);
+        }
+        if let Some(escape_controls) = self.boolean("escape_controls")? {
+            line_encoding = line_encoding.with_escaped_controls(escape_controls);
+        }
+        Ok(line_encoding)
+    }
+
+    fn route(&self) -> Route {
+        match (&self.target, &self.target_prefix, &self.thread) {
+            (Some(target), _, _) => Route::Target(target.clone()),
+            (None, Some(prefix), _) => Route::TargetPrefix(prefix.clone()),
+            (None, None, Some(thread)) => Route::ThreadName(thread.clone()),
+            (None, None, None) => Route::All,
+        }
+    }
+
+    fn env_filter(&self) -> Result<Option<EnvFilter>, ConfigError> {
+        self.filter
+            .as_deref()
+            .map(EnvFilter::parse)
+            .transpose()
+            .map_err(ConfigError::Filter)
+    }
+}
+
+/// The value of a sink option
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum ConfigValue {
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+}
+
+/// Creates a logger from its configuration
+pub type SinkFactory = Box<dyn Fn(&SinkConfig) -> Result<Box<dyn Log>, ConfigError> + Send + Sync>;
+
+/// The factories of the sink kinds known to the configuration, by kind
+pub struct SinkRegistry {
+    factories: HashMap<String, SinkFactory>,
+}
+
+impl Default for SinkRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SinkRegistry {
+    /// A registry with the built-in sink kinds
+    pub fn new() -> Self {
+        Self {
+            factories: HashMap::new(),
+        }
+        .register("console", console_sink)
+        .register("file", file_sink)
+        .register("json", json_sink)
+    }
+
+    /// Register a custom sink kind, replacing a kind with the same name
+    pub fn register(
+        mut self,
+        kind: impl Into<String>,
+        factory: impl Fn(&SinkConfig) -> Result<Box<dyn Log>, ConfigError> + Send + Sync + 'static,
+    ) -> Self {
+        self.factories.insert(kind.into(), Box::new(factory));
+        self
+    }
+
+    /// Build the composite logger described by the configuration
+    ///
+    /// # Errors
+    ///
+    /// Fails if a filter is invalid, a sink kind is unknown or a sink can not be created.
+    pub fn build(&self, config: &LoggerConfig) -> Result<CompositeLogger, ConfigError> {
+        let mut logger = CompositeLogger::new()
+            .with_overflow_policy(config.overflow_policy)
+            .with_dispatch_strategy(config.dispatch_strategy);
+
+        if let Some(filter) = &config.filter {
+            logger = logger.with_env_filter(EnvFilter::parse(filter).map_err(ConfigError::Filter)?);
+        }
+        if let Some(max_level) = config.max_level {
+            logger = logger.with_max_level(max_level);
+        }
+        for (key, value) in &config.kv {
+            logger = logger.with_kv(key, value);
+        }
+        if let Some(capacity) = config.async_capacity {
+            logger = logger.into_async(capacity);
+        }
+        if let Some(interval) = config.flush_interval_ms {
+            logger = logger.with_flush_interval(Duration::from_millis(interval));
+        }
+        if config.deduplicate {
+            logger = logger.with_deduplication();
+        }
+        if config.sequence_numbers {
+            logger = logger.with_sequence_numbers();
+        }
+        if let Some(level) = config.backtrace_level {
+            logger = logger.with_backtraces(level, config.backtrace_mode);
+        }
+        if let Some(max_panics) = config.max_consecutive_panics {
+            logger = logger.with_max_consecutive_panics(max_panics);
+        }
+        if let Some(format) = &config.timestamp_format {
+            let Ok(format) = format.parse();
+            logger = logger.with_timestamp_format(format);
+        }
+        if let Some(zone) = &config.time_zone {
+            let zone = zone
+                .parse()
+                .map_err(|error: TimeZoneError| ConfigError::Parse(error.to_string()))?;
+            logger = logger.with_time_zone(zone);
+        }
+        if config.banner {
+            logger = logger.with_banner(Banner::new());
+        }
+        #[cfg(feature = "regex")]
+        for pattern in &config.suppress {
+            logger = logger
+                .suppress_matching(pattern)
+                .map_err(|error| ConfigError::Parse(error.to_string()))?;
+        }
+
+        for sink in &config.sinks {
+            let level = sink.level.unwrap_or(LevelFilter::max());
+            let entry = logger.push_entry(level, sink.route(), Box::new(self.create(sink)?));
+            entry.name = sink.name.clone();
+            entry.group = sink.group.clone();
+            entry.timeout = sink.timeout_ms.map(Duration::from_millis);
+        }
+        if let Some(fallback) = &config.fallback {
+            logger = logger.with_fallback_logger(self.create(fallback)?);
+        }
+
+        Ok(logger)
+    }
+
+    /// Create the logger of the sink, applying its filter
+    fn create(&self, sink: &SinkConfig) -> Result<Box<dyn Log>, ConfigError> {
+        let factory = self
+            .factories
+            .get(&sink.kind)
+            .ok_or_else(|| ConfigError::UnknownSink(sink.kind.clone()))?;
+        let logger = factory(sink)?;
+        #[cfg(feature = "regex")]
+        let logger: Box<dyn Log> = match &sink.message {
+            Some(pattern) => Box::new(FilteredLogger::new(
+                logger,
+                crate::filter::MessageMatches::new(pattern)
+                    .map_err(|error| sink.error(error.to_string()))?,
+            )),
+            None => logger,
+        };
+
+        let logger: Box<dyn Log> = match sink.max_message_length {
+            Some(max_length) => Box::new(TruncatedLogger::new(logger, max_length, sink.truncation)),
+            None => logger,
+        };
+
+        Ok(match sink.env_filter()? {
+            Some(filter) => Box::new(FilteredLogger::new(logger, filter)),
+            None => logger,
+        })
+    }
+}
+
+fn console_sink(sink: &SinkConfig) -> Result<Box<dyn Log>, ConfigError> {
+    let mut logger = ConsoleLogger::new();
+
+    if let Some(colors) = sink.string("colors")? {
+        let colors = match colors {
+            "auto" => ColorChoice::Auto,
+            "always" => ColorChoice::Always,
+            "never" => ColorChoice::Never,
+            _ => return Err(sink.invalid_option("colors", "`auto`, `always` or `never`")),
+        };
+        logger = logger.with_colors(colors);
+    }
+    if let Some(level) = sink.string("stderr_level")? {
+        let level = level
+            .parse()
+            .map_err(|_| sink.invalid_option("stderr_level", "a level"))?;
+        logger = logger.with_stderr_level(level);
+    }
+    let theme = match sink.string("theme")? {
+        None => None,
+        Some("plain") => Some(Theme::plain()),
+        Some("dark") => Some(Theme::dark()),
+        Some("light") => Some(Theme::light()),
+        Some(_) => return Err(sink.invalid_option("theme", "`plain`, `dark` or `light`")),
+    };
+    if let Some(theme) = theme {
+        let template = match sink.string("format")? {
+            Some("logfmt" | "json") => {
+                return Err(sink.error("option `theme` requires a template `format`"))
+            }
+            Some(template) => template,
+            None => DEFAULT_TEMPLATE,
+        };
+        let format =
+            TemplateFormat::new(template).map_err(|error| sink.error(error.to_string()))?;
+        logger = logger.with_format(format.with_theme(theme));
+    } else if let Some(format) = sink.format()? {
+        logger = logger.with_format(format);
+    }
+    logger = logger.with_line_encoding(sink.line_encoding()?);
+
+    Ok(Box::new(logger))
+}
+
+fn file_sink(sink: &SinkConfig) -> Result<Box<dyn Log>, ConfigError> {
+    let path = sink
+        .string("path")?
+        .ok_or_else(|| sink.error("option `path` is required"))?;
+    let mut logger =
+        RotatingFileLogger::new(path).map_err(|error| sink.error(error.to_string()))?;
+
+    if let Some(max_size) = sink.integer("max_size")? {
+        logger = logger.with_max_size(max_size);
+    }
+    if let Some(max_files) = sink.integer("max_files")? {
+        logger = logger.with_max_files(max_files as usize);
+    }
+    if let Some(max_total_size) = sink.integer("max_total_size")? {
+        logger = logger.with_max_total_size(max_total_size);
+    }
+    if let Some(max_age) = sink.integer("max_age_secs")? {
+        logger = logger.with_max_age(Duration::from_secs(max_age));
+    }
+    if let Some(interval) = sink.integer("rotation_interval_secs")? {
+        logger = logger.with_rotation_interval(Duration::from_secs(interval));
+    }
+    if let Some(format) = sink.format()? {
+        logger = logger.with_format(format);
+    }
+    if let Some(durability) = sink.string("durability")? {
+        let durability = match durability {
+            "block" => Durability::Block,
+            "line" => Durability::Line,
+            "record" => Durability::Record,
+            _ => return Err(sink.invalid_option("durability", "`block`, `line` or `record`")),
+        };
+        logger = logger.with_durability(durability);
+    }
+    if let Some(level) = sink.string("sync_level")? {
+        let level = level
+            .parse()
+            .map_err(|_| sink.invalid_option("sync_level", "a level"))?;
+        logger = logger.with_sync_level(level);
+    }
+    if let Some(variable) = sink.string("encryption_key_env")? {
+        #[cfg(feature = "encryption")]
+        {
+            let key = std::env::var(variable)
+                .ok()
+                .and_then(|key| crate::encryption::EncryptionKey::from_hex(&key))
+                .ok_or_else(|| {
+                    sink.error(format!(
+                        "`{}` does not hold a key of 64 hexadecimal digits",
+                        variable
+                    ))
+                })?;
+            logger = logger.with_encryption(key);
+        }
+        #[cfg(not(feature = "encryption"))]
+        {
+            let _ = variable;
+            return Err(sink.error("encryption requires the `encryption` feature"));
+        }
+    }
+    if let Some(shared) = sink.boolean("shared")? {
+        logger = logger.with_shared_access(shared);
+    }
+    if let Some(compress) = sink.boolean("compress")? {
+        #[cfg(feature = "gzip")]
+        {
+            logger = logger.with_compression(compress);
+        }
+        #[cfg(not(feature = "gzip"))]
+        if compress {
+            return Err(sink.error("compression requires the `gzip` feature"));
+        }
+    }
+    if let Some(level) = sink.integer("zstd_level")? {
+        #[cfg(feature = "zstd")]
+        {
+            logger = logger.with_zstd_compression(level as i32);
+        }
+        #[cfg(not(feature = "zstd"))]
+        {
+            let _ = level;
+            return Err(sink.error("zstd compression requires the `zstd` feature"));
+        }
+    }
+    logger = logger.with_line_encoding(sink.line_encoding()?);
+
+    Ok(Box::new(logger))
+}
+
+fn json_sink(sink: &SinkConfig) -> Result<Box<dyn Log>, ConfigError> {
+    let line_encoding = sink.line_encoding()?;
+    match sink.string("path")? {
+        Some(path) => {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(PathBuf::from(path))
+                .map_err(|error| sink.error(error.to_string()))?;
+            Ok(Box::new(
+                JsonLogger::new(file).with_line_encoding(line_encoding),
+            ))
+        }
+        None => Ok(Box::new(
+            JsonLogger::new(std::io::stderr()).with_line_encoding(line_encoding),
+        )),
+    }
+}
+
+/// The composite logger could not be built from the configuration
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The document could not be parsed
+    Parse(String),
+    /// A filter has an invalid directive
+    Filter(EnvFilterError),
+    /// No factory is registered for the sink kind
+    UnknownSink(String),
+    /// The factory failed to create the sink
+    Sink { kind: String, message: String },
+    /// The configuration file could not be read
+    Io(io::Error),
+    /// The built composite logger is invalid, see [`InitError`]
+    Init(InitError),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Parse(message) => write!(f, "invalid logger configuration: {}", message),
+            ConfigError::Filter(error) => error.fmt(f),
+            ConfigError::UnknownSink(kind) => write!(f, "unknown sink kind `{}`", kind),
+            ConfigError::Sink { kind, message } => {
+                write!(f, "failed to create `{}` sink: {}", kind, message)
+            }
+            ConfigError::Io(error) => write!(f, "failed to read logger configuration: {}", error),
+            ConfigError::Init(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Filter(error) => Some(error),
+            ConfigError::Io(error) => Some(error),
+            ConfigError::Init(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "toml"))]
+mod tests {
+    use super::*;
+    use crate::testing::{self, CaptureLogger};
+    use crate::OwnedRecord;
+    use std::sync::Arc;
+
+    /// Builds the `capture` sinks of the document as the captures in the order of the sinks
+    fn build(document: &str, captures: &[Arc<CaptureLogger>]) -> crate::CompositeLoggerHandle {
+        let config = LoggerConfig::from_toml_str(document).unwrap();
+        let captures = captures.to_vec();
+        let registry = SinkRegistry::new().register("capture", move |sink| {
+            let index = sink.integer("index")?.unwrap_or_default() as usize;
+            Ok(Box::new(captures[index].clone()))
+        });
+        registry.build(&config).unwrap().build()
+    }
+
+    fn log(logger: &impl Log, level: Level, target: &str, message: &str) {
+        OwnedRecord::new(level, target, message).with_record(|record| logger.log(record));
+    }
+
+    fn error(document: &str) -> String {
+        let config = match LoggerConfig::from_toml_str(document) {
+            Ok(config) => config,
+            Err(error) => return error.to_string(),
+        };
+        match config.build() {
+            Ok(_) => panic!("the configuration is valid"),
+            Err(error) => error.to_string(),
+        }
+    }
+
+    #[test]
+    fn builds_the_sinks_of_the_document() {
+        let captures = [
+            Arc::new(CaptureLogger::new()),
+            Arc::new(CaptureLogger::new()),
+        ];
+        let handle = build(
+            r#"
+            filter = "info"
+
+            [[sinks]]
+            kind = "capture"
+            name = "main"
+            level = "warn"
+            index = 0
+
+            [[sinks]]
+            kind = "capture"
+            target = "audit"
+            index = 1
+            "#,
+            &captures,
+        );
+        log(&handle, Level::Warn, "app", "warning");
+        log(&handle, Level::Info, "app", "hidden");
+        log(&handle, Level::Info, "audit::login", "login");
+        log(&handle, Level::Debug, "audit", "filtered");
+
+        assert_eq!(handle.logger_names(), ["main"]);
+        assert_eq!(
+            captures[0].entries(),
+            [(Level::Warn, "app".to_string(), "warning".to_string())]
+        );
+        captures[1].assert_logged(Level::Info, testing::equals("login"));
+        assert_eq!(captures[1].entries().len(), 1);
+    }
+
+    #[test]
+    fn reports_invalid_configurations() {
+        assert_eq!(
+            error("[[sinks]]\nkind = \"missing\""),
+            "unknown sink kind `missing`"
+        );
+        assert_eq!(
+            error("[[sinks]]\nkind = \"console\"\ncolors = 1"),
+            "failed to create `console` sink: option `colors` must be a string"
+        );
+        assert_eq!(
+            error("[[sinks]]\nkind = \"file\""),
+            "failed to create `file` sink: option `path` is required"
+        );
+        assert!(error("colour = true").starts_with("invalid logger configuration: "));
+    }
+}
@@ -0,0 +1,79 @@
+//! Contextual key-values, such as a request id, added to every record logged
+//! while they are in scope, without passing them to every log call.
+//!
+//! Key-values pushed with [`push`] are in scope on the current thread until the returned
+//! guard is dropped. With the `tokio` feature, [`scope`] keeps key-values in scope
+//! for a task, even when it moves between threads.
+//! Key-values of the record itself take precedence, then the innermost context.
+
+use std::cell::RefCell;
+use std::marker::PhantomData;
+
+thread_local! {
+    static CONTEXT: RefCell<Vec<(String, String)>> = const { RefCell::new(Vec::new()) };
+}
+
+#[cfg(feature = "tokio")]
+tokio::task_local! {
+    static TASK_CONTEXT: Vec<(String, String)>;
+}
+
+/// Removes the key-value from the context of the thread when dropped.
+/// Guards should be dropped in the reverse order they were created.
+#[must_use = "the key-value is removed from the context when the guard is dropped"]
+pub struct ContextGuard {
+    len: usize,
+    /// The context belongs to the thread the guard was created on
+    _thread: PhantomData<*const ()>,
+}
+
+impl Drop for ContextGuard {
+    fn drop(&mut self) {
+        CONTEXT.with(|context| context.borrow_mut().truncate(self.len));
+    }
+}
+
+/// Add the key-value to every record logged from the current thread
+/// until the guard is dropped
+pub fn push(key: impl Into<String>, value: impl ToString) -> ContextGuard {
+    CONTEXT.with(|context| {
+        let mut context = context.borrow_mut();
+        let len = context.len();
+        context.push((key.into(), value.to_string()));
+        ContextGuard {
+            len,
+            _thread: PhantomData,
+        }
+    })
+}
+
+/// Add the key-values to every record logged from the task while running the future,
+/// in addition to those of enclosing scopes
+#[cfg(feature = "tokio")]
+pub async fn scope<F: std::future::Future>(
+    key_values: impl IntoIterator<Item = (String, String)>,
+    future: F,
+) -> F::Output {
+    let mut context = TASK_CONTEXT
+        .try_with(|context| context.clone())
+        .unwrap_or_default();
+    context.extend(key_values);
+    TASK_CONTEXT.scope(context, future).await
+}
+
+/// The key-values in scope, the innermost first and without repeated keys
+pub(crate) fn key_values() -> Vec<(String, String)> {
+    let mut key_values: Vec<(String, String)> = Vec::new();
+    let mut add = |context: &[(String, String)]| {
+        for (key, value) in context.iter().rev() {
+            if !key_values.iter().any(|(existing, _)| existing == key) {
+                key_values.push((key.clone(), value.clone()));
+            }
+        }
+    };
+
+    CONTEXT.with(|context| add(&context.borrow()));
+    #[cfg(feature = "tokio")]
+    let _ = TASK_CONTEXT.try_with(|context| add(context));
+    key_values
+}
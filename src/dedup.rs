@@ -0,0 +1,264 @@
+use crate::persist::{self, StateFile};
+use crate::OwnedRecord;
+use log::{Level, Log, Metadata, Record};
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Instant, SystemTime};
+
+#[derive(Default)]
+struct State {
+    last: Option<(Level, String, String)>,
+    repeated: u64,
+    /// When the first of the suppressed repetitions was logged
+    repeated_since: Option<SystemTime>,
+    saved_at: Option<Instant>,
+}
+
+impl State {
+    fn load(file: &StateFile) -> Self {
+        let values = file.load();
+        let last = (|| {
+            let level = values.get("level")?.parse().ok()?;
+            Some((
+                level,
+                values.get("target")?.clone(),
+                values.get("message")?.clone(),
+            ))
+        })();
+        Self {
+            repeated: last
+                .as_ref()
+                .and_then(|_| values.get("repeated")?.parse().ok())
+                .unwrap_or_default(),
+            repeated_since: values
+                .get("repeated_since")
+                .and_then(|since| persist::decode_time(since)),
+            last,
+            saved_at: None,
+        }
+    }
+
+    fn values(&self) -> Vec<(&'static str, String)> {
+        let Some((level, target, message)) = &self.last else {
+            return Vec::new();
+        };
+        let mut values = vec![
+            ("level", level.to_string()),
+            ("target", target.clone()),
+            ("message", message.clone()),
+            ("repeated", self.repeated.to_string()),
+        ];
+        if let Some(since) = self.repeated_since {
+            values.push(("repeated_since", persist::encode_time(since)));
+        }
+        values
+    }
+}
+
+/// Detects consecutive records with the same level, target and message
+#[derive(Default)]
+pub(crate) struct Deduplication {
+    state: Mutex<State>,
+    file: Option<StateFile>,
+}
+
+impl Deduplication {
+    /// Keep the last record and its repetitions in the state file, see
+    /// [`DeduplicatedLogger::with_state_file`]
+    pub(crate) fn persisted(path: impl Into<PathBuf>) -> Self {
+        let file = StateFile::new(path);
+        Self {
+            state: Mutex::new(State::load(&file)),
+            file: Some(file),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    fn save(&self, state: &mut State, force: bool) {
+        if let Some(file) = &self.file {
+            let values = state.values();
+            file.save(&mut state.saved_at, force, || values);
+        }
+    }
+
+    /// Returns `None` if the record repeats the previous one and should be suppressed,
+    /// otherwise the summary of the suppressed repetitions to log before the record, if any
+    pub(crate) fn check(&self, record: &Record) -> Option<Option<OwnedRecord>> {
+        let message = record.args().to_string();
+        let mut state = self.lock();
+
+        if let Some((level, target, last_message)) = &state.last {
+            if *level == record.level() && target == record.target() && *last_message == message {
+                state.repeated += 1;
+                state.repeated_since.get_or_insert_with(crate::time::now);
+                self.save(&mut state, false);
+                return None;
+            }
+        }
+
+        let summary = self.summary(&mut state);
+        state.last = Some((record.level(), record.target().to_string(), message));
+        self.save(&mut state, false);
+        Some(summary)
+    }
+
+    /// Takes the summary of the suppressed repetitions of the last record, if any.
+    /// With a state file the repetitions are saved and keep being counted instead.
+    pub(crate) fn take_summary(&self) -> Option<OwnedRecord> {
+        let mut state = self.lock();
+        if self.file.is_some() {
+            self.save(&mut state, true);
+            return None;
+        }
+        self.summary(&mut state)
+    }
+
+    /// The summary tells since when the record was repeated if the repetitions
+    /// may span restarts
+    fn summary(&self, state: &mut State) -> Option<OwnedRecord> {
+        let repeated = std::mem::take(&mut state.repeated);
+        let since = state.repeated_since.take();
+        if repeated == 0 {
+            return None;
+        }
+
+        state.last.as_ref().map(|(level, target, _)| {
+            let message = match since.filter(|_| self.file.is_some()) {
+                Some(since) => format!(
+                    "last message repeated {} times since {}",
+                    repeated,
+                    crate::timestamp::timestamp(since)
+                ),
+                None => format!("last message repeated {} times", repeated),
+            };
+            OwnedRecord::new(*level, target.as_str(), message)
+        })
+    }
+}
+
+impl Drop for Deduplication {
+    fn drop(&mut self) {
+        let mut state = self.lock();
+        let state = &mut *state;
+        self.save(state, true);
+    }
+}
+
+/// A logger that receives consecutive identical records only once,
+/// followed by a `last message repeated N times` record with the same level and target
+pub struct DeduplicatedLogger<L> {
+    logger: L,
+    deduplication: Deduplication,
+}
+
+impl<L: Log> DeduplicatedLogger<L> {
+    pub fn new(logger: L) -> Self {
+        Self {
+            logger,
+            deduplication: Deduplication::default(),
+        }
+    }
+
+    /// Keep the last record and the number of its repetitions in a small state file,
+    /// so that the repetitions are suppressed and counted across restarts, for example
+    /// of a crash-looping service. The summary then tells since when the record was repeated,
+    /// such as `last message repeated 400 times since 2024-01-31T10:02:00.000Z`.
+    /// The state is saved at most once a second and when the logger is flushed or dropped,
+    /// and flushing keeps counting the repetitions instead of logging their summary.
+    pub fn with_state_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.deduplication = Deduplication::persisted(path);
+        self
+    }
+
+    fn log_summary(&self, summary: Option<OwnedRecord>) {
+        if let Some(summary) = summary {
+            summary.with_record(|summary| self.logger.log(summary));
+        }
+    }
+}
+
+impl<L: Log> Log for DeduplicatedLogger<L> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.logger.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if let Some(summary) = self.deduplication.check(record) {
+            self.log_summary(summary);
+            self.logger.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.log_summary(self.deduplication.take_summary());
+        self.logger.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::CaptureLogger;
+    use std::sync::Arc;
+
+    fn log(logger: &impl Log, level: Level, message: &str) {
+        OwnedRecord::new(level, "my_app", message).with_record(|record| logger.log(record));
+    }
+
+    fn messages(capture: &CaptureLogger) -> Vec<String> {
+        capture
+            .entries()
+            .into_iter()
+            .map(|(_, _, message)| message)
+            .collect()
+    }
+
+    #[test]
+    fn repetitions_are_summarized_before_the_next_record() {
+        let capture = Arc::new(CaptureLogger::new());
+        let logger = DeduplicatedLogger::new(capture.clone());
+        for _ in 0..3 {
+            log(&logger, Level::Warn, "disk full");
+        }
+        log(&logger, Level::Info, "disk freed");
+        assert_eq!(
+            capture.entries(),
+            [
+                (Level::Warn, "my_app".to_string(), "disk full".to_string()),
+                (
+                    Level::Warn,
+                    "my_app".to_string(),
+                    "last message repeated 2 times".to_string()
+                ),
+                (Level::Info, "my_app".to_string(), "disk freed".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn records_differing_in_level_are_not_repetitions() {
+        let capture = Arc::new(CaptureLogger::new());
+        let logger = DeduplicatedLogger::new(capture.clone());
+        log(&logger, Level::Warn, "disk full");
+        log(&logger, Level::Error, "disk full");
+        log(&logger, Level::Warn, "disk full");
+        assert_eq!(messages(&capture), ["disk full"; 3]);
+    }
+
+    #[test]
+    fn flush_logs_the_pending_summary_once() {
+        let capture = Arc::new(CaptureLogger::new());
+        let logger = DeduplicatedLogger::new(capture.clone());
+        log(&logger, Level::Warn, "disk full");
+        log(&logger, Level::Warn, "disk full");
+        logger.flush();
+        logger.flush();
+        assert_eq!(
+            messages(&capture),
+            ["disk full", "last message repeated 1 times"]
+        );
+    }
+}
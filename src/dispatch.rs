@@ -0,0 +1,1515 @@
+use crate::banner::Banner;
+use crate::breaker::{BreakerState, CircuitBreaker};
+use crate::context;
+use crate::dedup::Deduplication;
+use crate::enabled_cache::EnabledCache;
+use crate::escalate::{Escalation, ESCALATED_FROM_KEY};
+use crate::explain::{LoggerDecision, Rejection, RoutingReport};
+use crate::format;
+use crate::health::{self, SinkHealth};
+use crate::kv::WithAmbient;
+use crate::origin::{self, Origin};
+use crate::profile::Profile;
+use crate::sink_error::{self, ErrorHandler, SinkError, SinkErrorKind};
+use crate::stats::{Counters, LoggerStats};
+use crate::worker::Lane;
+use crate::{filter, formatted, EnvFilter, Filter, FlushResult, LoggerId, OwnedRecord, TargetTree};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::cell::Cell;
+use std::fmt::{Arguments, Write};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex, PoisonError};
+use std::thread::{self, ThreadId};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Decides which records are dispatched to a delegated logger based on their target
+#[derive(Clone)]
+pub(crate) enum Route {
+    /// Every record is dispatched
+    All,
+    /// Records with the exact target or any of its submodules (`target::*`)
+    Target(String),
+    /// Records whose target starts with the given string
+    TargetPrefix(String),
+    /// Records logged from threads whose name matches the pattern, in which `*` matches
+    /// any sequence of characters
+    ThreadName(String),
+    /// Records logged from the given thread
+    ThreadId(ThreadId),
+}
+
+impl Route {
+    fn matches(&self, target: &str) -> bool {
+        match self {
+            Route::All => true,
+            Route::Target(route) => filter::target_matches(target, route),
+            Route::TargetPrefix(prefix) => target.starts_with(prefix.as_str()),
+            Route::ThreadName(pattern) => origin::thread_name(|name| {
+                name.is_some_and(|name| origin::wildcard_matches(pattern, name))
+            }),
+            Route::ThreadId(id) => origin::thread_id() == *id,
+        }
+    }
+
+    /// The records of the route, for explaining why a record does not match it
+    fn describe(&self) -> String {
+        match self {
+            Route::All => "all records".to_string(),
+            Route::Target(target) => format!("the target `{}` and its submodules", target),
+            Route::TargetPrefix(prefix) => format!("the targets starting with `{}`", prefix),
+            Route::ThreadName(pattern) => format!("the threads named `{}`", pattern),
+            Route::ThreadId(id) => format!("the thread {:?}", id),
+        }
+    }
+}
+
+/// A delegated logger that can be downcast to its concrete type
+pub(crate) trait AnyLog: Log {
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Log + 'static> AnyLog for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// A delegated logger together with the maximum level and route of records it should receive
+pub(crate) struct SinkEntry {
+    pub(crate) id: LoggerId,
+    /// Allows to refer to the logger at runtime by a stable name instead of its id
+    pub(crate) name: Option<String>,
+    /// Allows to change several loggers at once at runtime
+    pub(crate) group: Option<String>,
+    /// The maximum level as a `LevelFilter` discriminant,
+    /// which can be changed at runtime while records are dispatched
+    level: AtomicUsize,
+    pub(crate) route: Route,
+    /// The filter of the composite logger the logger was merged from, see
+    /// [`crate::CompositeLogger::merge`]
+    pub(crate) filter: Option<Arc<EnvFilter>>,
+    pub(crate) logger: Box<dyn AnyLog>,
+    pub(crate) panics: AtomicU64,
+    pub(crate) counters: Counters,
+    /// Skip asking the logger whether it is enabled, only its level and route are checked
+    pub(crate) always_enabled: bool,
+    /// Switched off at runtime, the logger receives no records until switched on again
+    paused: AtomicBool,
+    /// Deliveries taking longer are reported, and in the asynchronous mode
+    /// the records queued in the meantime are dropped for the logger
+    pub(crate) timeout: Option<Duration>,
+    /// Microseconds since the unix epoch when the last delivery that took longer than
+    /// the timeout returned, the logger does not receive records logged before
+    stalled_until: AtomicU64,
+    consecutive_panics: AtomicUsize,
+    /// Deliveries in a row that took longer than the slow threshold
+    consecutive_slow: AtomicUsize,
+    disabled: AtomicBool,
+    breaker: BreakerState,
+    /// Checks the health of a logger whose type is not known to the composite logger
+    pub(crate) health_check: Option<health::Checker>,
+    /// Delivers the records to the logger from a thread of its own
+    pub(crate) lane: Option<Lane>,
+    /// Receives the records of the priority level from the logging thread, flushed right away
+    pub(crate) priority: bool,
+}
+
+/// How the failures of the loggers are handled
+#[derive(Clone, Copy)]
+struct Policy {
+    /// Loggers are disabled after panicking this many times in a row
+    max_panics: Option<usize>,
+    circuit_breaker: Option<CircuitBreaker>,
+    /// Loggers receive records up to this level whatever their own level
+    boost: LevelFilter,
+    slow: Option<SlowThreshold>,
+}
+
+/// Loggers taking longer than the duration to log this many records in a row are reported
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SlowThreshold {
+    pub(crate) duration: Duration,
+    pub(crate) repeats: usize,
+}
+
+impl SinkEntry {
+    pub(crate) fn new(
+        id: LoggerId,
+        level: LevelFilter,
+        route: Route,
+        logger: Box<dyn AnyLog>,
+    ) -> Self {
+        Self {
+            id,
+            name: None,
+            group: None,
+            level: AtomicUsize::new(level as usize),
+            route,
+            filter: None,
+            logger,
+            panics: AtomicU64::new(0),
+            counters: Counters::default(),
+            always_enabled: false,
+            paused: AtomicBool::new(false),
+            timeout: None,
+            stalled_until: AtomicU64::new(0),
+            consecutive_panics: AtomicUsize::new(0),
+            consecutive_slow: AtomicUsize::new(0),
+            disabled: AtomicBool::new(false),
+            breaker: BreakerState::default(),
+            health_check: None,
+            lane: None,
+            priority: false,
+        }
+    }
+
+    /// A new entry for the logger replacing this one, with the same id, level, route and filter
+    pub(crate) fn replaced_by(&self, logger: Box<dyn AnyLog>) -> Self {
+        let mut entry = Self::new(self.id, self.level(), self.route.clone(), logger);
+        entry.filter = self.filter.clone();
+        entry.name = self.name.clone();
+        entry.group = self.group.clone();
+        entry.always_enabled = self.always_enabled;
+        entry.paused = AtomicBool::new(self.is_paused());
+        entry.timeout = self.timeout;
+        entry.priority = self.priority;
+        entry.lane = self
+            .lane
+            .as_ref()
+            .map(|lane| Lane::spawn(self.name.as_deref(), lane.capacity()));
+        entry
+    }
+
+    pub(crate) fn level(&self) -> LevelFilter {
+        LevelFilter::iter()
+            .nth(self.level.load(Ordering::Relaxed))
+            .unwrap_or(LevelFilter::max())
+    }
+
+    pub(crate) fn set_level(&self, level: LevelFilter) {
+        self.level.store(level as usize, Ordering::Relaxed);
+    }
+
+    pub(crate) fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    /// Neither switched off at runtime nor disabled after panicking too often
+    pub(crate) fn is_active(&self) -> bool {
+        !self.is_paused() && !self.disabled.load(Ordering::Relaxed)
+    }
+
+    /// The logger as its concrete type, rather than the box holding it
+    pub(crate) fn logger(&self) -> &dyn Any {
+        (*self.logger).as_any()
+    }
+
+    pub(crate) fn health(&self) -> SinkHealth {
+        if self.disabled.load(Ordering::Relaxed) {
+            return SinkHealth::Disabled("disabled after panicking too often".to_string());
+        }
+        if self.is_paused() {
+            return SinkHealth::Disabled("switched off".to_string());
+        }
+        if !self.breaker.allows() {
+            return SinkHealth::Degraded("skipped for a while after failing too often".to_string());
+        }
+
+        let logger = self.logger();
+        self.health_check
+            .map_or_else(|| health::check_builtin(logger), |check| check(logger))
+            .unwrap_or(SinkHealth::Healthy)
+    }
+
+    fn enabled(&self, metadata: &Metadata, policy: Policy) -> bool {
+        metadata.level() <= self.level().max(policy.boost)
+            && self.route.matches(metadata.target())
+            && self
+                .filter
+                .as_ref()
+                .is_none_or(|filter| filter.enabled(metadata))
+            && self.is_active()
+            && (policy.circuit_breaker.is_none() || self.breaker.allows())
+            && (self.always_enabled
+                || self
+                    .isolate(policy, || self.logger.enabled(metadata))
+                    .unwrap_or(false))
+    }
+
+    /// Why the logger is not enabled for the metadata, like [`SinkEntry::enabled`]
+    /// but without counting the panics of the logger
+    fn rejection(&self, metadata: &Metadata, policy: Policy) -> Option<Rejection> {
+        let level = self.level().max(policy.boost);
+        if metadata.level() > level {
+            return Some(Rejection::LoggerLevel(level));
+        }
+        if !self.route.matches(metadata.target()) {
+            return Some(Rejection::Route(self.route.describe()));
+        }
+        if let Some(filter) = self
+            .filter
+            .as_ref()
+            .filter(|filter| !filter.enabled(metadata))
+        {
+            return Some(Rejection::Filter(filter.level_for(metadata.target())));
+        }
+        if self.is_paused() {
+            return Some(Rejection::Paused);
+        }
+        if self.disabled.load(Ordering::Relaxed) {
+            return Some(Rejection::Disabled);
+        }
+        if policy.circuit_breaker.is_some() && !self.breaker.allows() {
+            return Some(Rejection::CircuitOpen);
+        }
+        if self.always_enabled {
+            return None;
+        }
+        match catch_unwind(AssertUnwindSafe(|| self.logger.enabled(metadata))) {
+            Ok(true) => None,
+            Ok(false) => Some(Rejection::NotEnabled),
+            Err(_) => Some(Rejection::Panicked),
+        }
+    }
+
+    fn decision(&self, rejection: Option<Rejection>) -> LoggerDecision {
+        LoggerDecision {
+            id: self.id,
+            name: self.name.clone(),
+            rejection,
+        }
+    }
+
+    /// Deliver the record, passing the timeouts and recoveries of the logger to `notify`
+    fn log(
+        &self,
+        record: &Record,
+        policy: Policy,
+        notify: impl Fn(SinkError),
+    ) -> Result<(), SinkError> {
+        if self.timeout.is_some() && self.is_stale() {
+            self.counters.dropped();
+            return Ok(());
+        }
+
+        let start = Instant::now();
+        let logged = self.isolate(policy, || dispatching(|| self.logger.log(record)));
+        if logged.is_ok() {
+            let elapsed = start.elapsed();
+            self.counters.delivered(elapsed);
+            if let Some(error) = self.check_timeout(elapsed) {
+                notify(error);
+            }
+            if let Some(error) = policy.slow.and_then(|slow| self.check_slow(elapsed, slow)) {
+                notify(error);
+            }
+        }
+        let Some(breaker) = &policy.circuit_breaker else {
+            return logged;
+        };
+
+        match logged {
+            Ok(()) => {
+                if self.breaker.succeeded() {
+                    notify(
+                        SinkError::new(
+                            SinkErrorKind::Recovered,
+                            "recovered, records are delivered again",
+                        )
+                        .with_logger(self.id, self.name.as_deref()),
+                    );
+                }
+                Ok(())
+            }
+            Err(error) if self.breaker.failed(breaker) => Err(SinkError::new(
+                SinkErrorKind::Tripped,
+                format!(
+                    "skipped for {:?} after failing: {}",
+                    breaker.cooldown(),
+                    error.message()
+                ),
+            )
+            .with_logger(self.id, self.name.as_deref())),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Whether the record being delivered was logged while a previous delivery
+    /// took longer than the timeout, which only happens in the asynchronous mode
+    fn is_stale(&self) -> bool {
+        let stalled_until = self.stalled_until.load(Ordering::Relaxed);
+        stalled_until != 0 && micros_since_epoch(crate::time::now()) < stalled_until
+    }
+
+    /// Count a delivery that took longer than the timeout
+    fn check_timeout(&self, elapsed: Duration) -> Option<SinkError> {
+        let timeout = self.timeout.filter(|timeout| elapsed > *timeout)?;
+        self.counters.timed_out(elapsed - timeout);
+        self.stalled_until.store(
+            micros_since_epoch(crate::time::wall_time()),
+            Ordering::Relaxed,
+        );
+
+        Some(
+            SinkError::new(
+                SinkErrorKind::TimedOut,
+                format!("took {:?}, longer than {:?}", elapsed, timeout),
+            )
+            .with_logger(self.id, self.name.as_deref()),
+        )
+    }
+
+    /// Count a slow delivery, reporting every time the logger was slow so many times in a row
+    fn check_slow(&self, elapsed: Duration, slow: SlowThreshold) -> Option<SinkError> {
+        if elapsed <= slow.duration {
+            self.consecutive_slow.store(0, Ordering::Relaxed);
+            return None;
+        }
+        let consecutive_slow = self.consecutive_slow.fetch_add(1, Ordering::Relaxed) + 1;
+        if !consecutive_slow.is_multiple_of(slow.repeats.max(1)) {
+            return None;
+        }
+
+        let latency = self.counters.latency();
+        Some(
+            SinkError::new(
+                SinkErrorKind::Slow,
+                format!(
+                    "took longer than {:?} to log {} records in a row, the last in {:?} \
+                     (p50 {:?}, p99 {:?})",
+                    slow.duration,
+                    consecutive_slow,
+                    elapsed,
+                    latency.percentile(0.5).unwrap_or_default(),
+                    latency.percentile(0.99).unwrap_or_default()
+                ),
+            )
+            .with_logger(self.id, self.name.as_deref()),
+        )
+    }
+
+    /// Flush the logger once the records queued for an isolated logger are delivered
+    fn flush(&self, policy: Policy) -> Result<(), SinkError> {
+        if let Some(lane) = &self.lane {
+            lane.drain();
+        }
+        self.isolate(policy, || dispatching(|| self.logger.flush()))
+    }
+
+    pub(crate) fn stats(&self) -> LoggerStats {
+        self.counters.snapshot(
+            self.id,
+            self.name.clone(),
+            self.panics.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Catch a panic of the logger so that it does not escape from the log call site,
+    /// disabling the logger after too many consecutive panics
+    fn isolate<T>(&self, policy: Policy, f: impl FnOnce() -> T) -> Result<T, SinkError> {
+        match catch_unwind(AssertUnwindSafe(f)) {
+            Ok(result) => {
+                self.consecutive_panics.store(0, Ordering::Relaxed);
+                Ok(result)
+            }
+            Err(payload) => {
+                self.panics.fetch_add(1, Ordering::Relaxed);
+                let consecutive_panics =
+                    self.consecutive_panics.fetch_add(1, Ordering::Relaxed) + 1;
+                let message = sink_error::panic_message(payload.as_ref());
+                let error = if policy
+                    .max_panics
+                    .is_some_and(|max_panics| consecutive_panics >= max_panics)
+                    && !self.disabled.swap(true, Ordering::Relaxed)
+                {
+                    SinkError::new(
+                        SinkErrorKind::Disabled,
+                        format!(
+                            "disabled after panicking {} times in a row: {}",
+                            consecutive_panics, message
+                        ),
+                    )
+                } else {
+                    SinkError::new(SinkErrorKind::Panicked, format!("panicked: {}", message))
+                };
+                Err(error.with_logger(self.id, self.name.as_deref()))
+            }
+        }
+    }
+}
+
+/// How a record is delivered to the loggers enabled for it
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum DispatchStrategy {
+    /// Deliver the record to every enabled logger
+    #[default]
+    Broadcast,
+    /// Deliver the record only to the first enabled logger in the order they were added,
+    /// which allows exclusive routing without mirror-image exclusion filters.
+    /// Loggers are asked with [`Log::enabled`], so filters that only decide on
+    /// the whole record still consume records they reject.
+    FirstMatch,
+    /// Deliver the record to every enabled logger at the same time, each from its own
+    /// short-lived thread, so that logging takes as long as the slowest logger instead
+    /// of all of them together. Each logger still receives the records of a thread
+    /// in order, as logging returns once all loggers are done.
+    /// Only worth it for several slow loggers, as it copies the record and spawns threads.
+    Parallel,
+}
+
+/// How a backtrace captured for a record is attached to it
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum BacktraceMode {
+    /// As the `backtrace` key-value, unless the record defines it itself
+    #[default]
+    KeyValue,
+    /// Appended to the message, on the lines after it
+    Message,
+}
+
+/// Which loggers a record is dispatched to,
+/// see [`crate::CompositeLogger::with_priority_logger`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Delivery {
+    /// Every enabled logger
+    All,
+    /// Only the priority loggers, from the logging thread before the record is queued
+    Priority,
+    /// All but the priority loggers if they received the record from the logging thread
+    Queued,
+}
+
+/// Which of the first 64 loggers are enabled for a record, computed once on the logging thread
+/// so that the worker of the asynchronous mode does not have to ask the loggers again
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct EnabledSet {
+    /// Of the dispatcher the set was computed for, the set is ignored once the loggers changed
+    generation: u64,
+    mask: u64,
+}
+
+impl EnabledSet {
+    fn contains(&self, index: usize) -> Option<bool> {
+        (index < u64::BITS as usize).then(|| self.mask & (1 << index) != 0)
+    }
+
+    /// Count the record as dropped by the loggers it was meant for,
+    /// unless the loggers changed since the set was computed
+    pub(crate) fn dropped(&self, dispatcher: &Dispatcher) {
+        if self.generation != dispatcher.generation {
+            return;
+        }
+        dispatcher
+            .loggers
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| self.contains(*index) == Some(true))
+            .for_each(|(_, logger)| logger.counters.dropped());
+    }
+}
+
+/// The target of the records about the composite logger itself,
+/// see [`crate::CompositeLogger::with_internal_events`]
+pub const INTERNAL_TARGET: &str = "composite_logger::internal";
+
+/// The key of the sequence number, see [`crate::CompositeLogger::with_sequence_numbers`]
+pub const SEQUENCE_KEY: &str = "sequence";
+
+/// The key of the panic message of a record whose message panicked while it was formatted,
+/// the record is then dispatched with a placeholder message
+pub const FORMAT_ERROR_KEY: &str = "format_error";
+
+/// The message of a record whose message panicked while it was formatted
+const FORMAT_PANIC_MESSAGE: &str = "<the message panicked while being formatted>";
+
+thread_local! {
+    /// Set while an internal event is dispatched, so that the failures
+    /// to deliver it are not dispatched as events again
+    static DISPATCHING_EVENT: Cell<bool> = const { Cell::new(false) };
+    /// Set while a sequenced record is dispatched, so that records logged by the loggers
+    /// themselves do not wait for the dispatch they are part of
+    static SEQUENCING: Cell<bool> = const { Cell::new(false) };
+    /// Set while this thread processes a record or runs a logger, so that the panic hook
+    /// does not log a panic of a logger to the logger holding its locks
+    static DISPATCHING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Whether this thread is processing a record or running a logger
+pub(crate) fn is_dispatching() -> bool {
+    DISPATCHING.try_with(Cell::get).unwrap_or(true)
+}
+
+/// Run the action with this thread marked as dispatching, see [`is_dispatching`]
+fn dispatching<T>(action: impl FnOnce() -> T) -> T {
+    /// Restores the mark once the action returns or unwinds
+    struct Reset(bool);
+
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            let _ = DISPATCHING.try_with(|dispatching| dispatching.set(self.0));
+        }
+    }
+
+    let _reset = Reset(
+        DISPATCHING
+            .try_with(|dispatching| dispatching.replace(true))
+            .unwrap_or(true),
+    );
+    action()
+}
+
+/// Numbers the records in the order they are dispatched
+#[derive(Default)]
+pub(crate) struct Sequencer {
+    next: AtomicU64,
+    /// Held while a record is dispatched, so that all loggers receive the records
+    /// in the order of their numbers
+    dispatching: Mutex<()>,
+}
+
+/// The id of the fallback logger, which can not be changed at runtime
+pub(crate) const FALLBACK_ID: LoggerId = LoggerId(u64::MAX);
+
+/// Dispatches records to the delegated loggers.
+/// Once installed it is never changed, changes are made to a clone which replaces it,
+/// so that dispatching never waits for them. Clones share the loggers and their state.
+#[derive(Default, Clone)]
+pub(crate) struct Dispatcher {
+    pub(crate) loggers: Vec<Arc<SinkEntry>>,
+    /// Applied to every record before it is dispatched to the loggers
+    pub(crate) filter: Option<Arc<EnvFilter>>,
+    /// No record more verbose than this level is dispatched, whatever the loggers accept
+    pub(crate) max_level: Option<LevelFilter>,
+    /// Loggers are disabled after panicking this many times in a row
+    pub(crate) max_consecutive_panics: Option<usize>,
+    /// Skips loggers that failed too often for a while
+    pub(crate) circuit_breaker: Option<CircuitBreaker>,
+    /// Added to every record that does not define them itself
+    pub(crate) key_values: Vec<(String, String)>,
+    /// Receives the records none of the loggers is enabled for
+    pub(crate) fallback: Option<Arc<SinkEntry>>,
+    pub(crate) strategy: DispatchStrategy,
+    /// Collapses consecutive identical records before they are dispatched
+    pub(crate) deduplication: Option<Arc<Deduplication>>,
+    /// Remembers whether any logger is enabled for a target and level
+    pub(crate) enabled_cache: Option<Arc<EnabledCache>>,
+    /// Incremented whenever the loggers or their levels change
+    generation: u64,
+    /// Records whose formatted message matches any of them are dropped
+    #[cfg(feature = "regex")]
+    pub(crate) suppressions: Vec<regex::Regex>,
+    /// Records of the level or more severe get a backtrace of where they were logged
+    pub(crate) backtraces: Option<(Level, BacktraceMode)>,
+    /// Levels of targets and their submodules taking precedence over the maximum level
+    /// and the filter, changed at runtime
+    pub(crate) target_levels: TargetTree<LevelFilter>,
+    /// Named sets of levels, see [`crate::CompositeLoggerHandle::apply_profile`]
+    pub(crate) profiles: Vec<(String, Profile)>,
+    /// Until the instant, or until ended without one, loggers receive records up to the level
+    /// whatever their own level
+    pub(crate) boost: Option<(LevelFilter, Option<Instant>)>,
+    /// Records of this level or more severe are delivered to the priority loggers
+    /// from the logging thread in the asynchronous mode
+    pub(crate) priority_level: Option<Level>,
+    /// Records accepted by the filter have their routing printed to the standard error
+    pub(crate) explain: Option<Arc<EnvFilter>>,
+    /// Describes the process in records delivered to every logger at startup and shutdown
+    pub(crate) banner: Option<Arc<Banner>>,
+    /// Events of the composite logger itself up to this level are dispatched
+    /// as records of the internal target
+    pub(crate) internal_events: Option<LevelFilter>,
+    /// Promote repeated records to more severe levels
+    pub(crate) escalations: Vec<Arc<Escalation>>,
+    /// Report loggers that are slow to log
+    pub(crate) slow_threshold: Option<SlowThreshold>,
+    /// Stamps every record with a sequence number before it is dispatched
+    pub(crate) sequencer: Option<Arc<Sequencer>>,
+    /// Called whenever records are not delivered
+    pub(crate) error_handler: Option<ErrorHandler>,
+    /// Receives the records that were not delivered
+    pub(crate) dead_letter: Option<Arc<dyn Log>>,
+    /// Adds process and call site information to every record
+    #[cfg(feature = "enrich")]
+    pub(crate) enrichment: Option<crate::enrich::Enrichment>,
+}
+
+impl Dispatcher {
+    /// The most verbose level any of the loggers accepts, limited by the configured
+    /// maximum level and by the maximum level the `log` macros were compiled with
+    pub(crate) fn max_level(&self) -> LevelFilter {
+        let max_level = self
+            .loggers
+            .iter()
+            .chain(self.fallback.iter())
+            .filter(|logger| !logger.is_paused())
+            .map(|logger| logger.level())
+            .max()
+            .unwrap_or(LevelFilter::Off)
+            .max(self.boosted_level());
+
+        let limit = self
+            .filter
+            .as_ref()
+            .map_or(LevelFilter::max(), |filter| filter.max_level())
+            .min(self.max_level.map_or(LevelFilter::max(), |max_level| {
+                max_level.max(self.boosted_level())
+            }))
+            .max(
+                self.target_levels
+                    .values()
+                    .copied()
+                    .max()
+                    .unwrap_or(LevelFilter::Off),
+            );
+        let explained = self
+            .explain
+            .as_ref()
+            .map_or(LevelFilter::Off, |explain| explain.max_level());
+        max_level
+            .min(limit)
+            .max(explained)
+            .min(log::STATIC_MAX_LEVEL)
+    }
+
+    /// The level of the most specific target level matching the target
+    fn target_level(&self, target: &str) -> Option<LevelFilter> {
+        self.target_levels.find(target).copied()
+    }
+
+    /// Let records of the target and its submodules through up to the level,
+    /// replacing the level set for the same target before
+    pub(crate) fn set_target_level(&mut self, target: &str, level: LevelFilter) {
+        self.target_levels.insert(target, level);
+    }
+
+    /// The level of the boost while it lasts, `Off` otherwise
+    fn boosted_level(&self) -> LevelFilter {
+        match self.boost {
+            Some((level, until)) if until.is_none_or(|until| crate::time::instant() < until) => {
+                level
+            }
+            _ => LevelFilter::Off,
+        }
+    }
+
+    /// Whether the dispatcher only has loggers and a filter, so that its loggers
+    /// can be moved to another dispatcher by applying the filter to each of them
+    pub(crate) fn is_flat(&self) -> bool {
+        #[cfg(feature = "enrich")]
+        if self.enrichment.is_some() {
+            return false;
+        }
+        #[cfg(feature = "regex")]
+        if !self.suppressions.is_empty() {
+            return false;
+        }
+
+        self.max_consecutive_panics.is_none()
+            && self.circuit_breaker.is_none()
+            && self.key_values.is_empty()
+            && self.fallback.is_none()
+            && self.strategy == DispatchStrategy::Broadcast
+            && self.deduplication.is_none()
+            && self.target_levels.is_empty()
+            && self.explain.is_none()
+            && self.boost.is_none()
+            && self.backtraces.is_none()
+            && self.internal_events.is_none()
+            && self.sequencer.is_none()
+            && self.escalations.is_empty()
+            && self.slow_threshold.is_none()
+            && self.error_handler.is_none()
+            && self.dead_letter.is_none()
+    }
+
+    /// Must be called whenever the loggers or their levels change
+    pub(crate) fn invalidate(&mut self) {
+        self.generation += 1;
+        if let Some(cache) = &self.enabled_cache {
+            cache.clear();
+        }
+    }
+
+    /// Take over from the replaced dispatcher,
+    /// so that enabled sets computed for its loggers are no longer used
+    pub(crate) fn succeed(&mut self, replaced: &Dispatcher) {
+        self.generation = replaced.generation;
+        if self.sequencer.is_some() && replaced.sequencer.is_some() {
+            // continue numbering the records
+            self.sequencer = replaced.sequencer.clone();
+        }
+        self.invalidate();
+    }
+
+    /// Give access to the loggers while building the dispatcher, before they are shared
+    pub(crate) fn building(entry: &mut Arc<SinkEntry>) -> &mut SinkEntry {
+        Arc::get_mut(entry).expect("loggers are not shared before the dispatcher is installed")
+    }
+
+    fn policy(&self) -> Policy {
+        Policy {
+            max_panics: self.max_consecutive_panics,
+            circuit_breaker: self.circuit_breaker,
+            boost: self.boosted_level(),
+            slow: self.slow_threshold,
+        }
+    }
+
+    /// Pass the error to the error handler and the record that was not delivered
+    /// to the dead-letter logger, neither of which may panic out of the log call site
+    pub(crate) fn report(&self, error: SinkError, record: Option<&Record>) {
+        let level = match error.kind() {
+            SinkErrorKind::Recovered => Level::Info,
+            _ => Level::Warn,
+        };
+        let mut key_values = vec![("kind", format!("{:?}", error.kind()))];
+        if let Some(logger) = error.logger() {
+            key_values.push(("logger", logger.0.to_string()));
+        }
+        self.event(level, format_args!("{}", error), &key_values);
+
+        if let Some(handler) = &self.error_handler {
+            let _ = catch_unwind(AssertUnwindSafe(|| handler(error)));
+        }
+        if let (Some(dead_letter), Some(record)) = (&self.dead_letter, record) {
+            let _ = catch_unwind(AssertUnwindSafe(|| dead_letter.log(record)));
+        }
+    }
+
+    /// Flush every logger on a thread of its own, waiting for them until the deadline,
+    /// so that a logger that does not return does not keep the others from being waited for
+    pub(crate) fn flush_until(&self, deadline: Instant, drained: bool) -> FlushResult {
+        if let Some(deduplication) = &self.deduplication {
+            self.process_summary(deduplication.take_summary(), &|record| {
+                self.dispatch(record)
+            });
+        }
+
+        let policy = self.policy();
+        let mut pending = self
+            .loggers
+            .iter()
+            .chain(self.fallback.iter())
+            .cloned()
+            .collect::<Vec<_>>();
+        let (sender, receiver) = mpsc::channel();
+        for entry in &pending {
+            let flush = {
+                let entry = entry.clone();
+                let sender = sender.clone();
+                move || {
+                    let _ = sender.send((entry.id, entry.flush(policy)));
+                }
+            };
+            let spawned = thread::Builder::new()
+                .name("composite-logger-flush".to_string())
+                .spawn(flush);
+            if spawned.is_err() {
+                let _ = sender.send((entry.id, entry.flush(policy)));
+            }
+        }
+        drop(sender);
+
+        let mut result = FlushResult {
+            drained,
+            timed_out: Vec::new(),
+            failed: Vec::new(),
+        };
+        while !pending.is_empty() {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            let Ok((id, flushed)) = receiver.recv_timeout(timeout) else {
+                break;
+            };
+            let Some(index) = pending.iter().position(|entry| entry.id == id) else {
+                continue;
+            };
+            let entry = pending.swap_remove(index);
+            if let Err(error) = flushed {
+                result.failed.push((entry.id, entry.name.clone()));
+                self.report(error, None);
+            }
+        }
+        result.timed_out = pending
+            .iter()
+            .map(|entry| (entry.id, entry.name.clone()))
+            .collect();
+
+        if let Some(dead_letter) = &self.dead_letter {
+            let _ = catch_unwind(AssertUnwindSafe(|| dead_letter.flush()));
+        }
+        result
+    }
+
+    /// Dispatch an event of the composite logger itself as a record of the internal target,
+    /// unless its level is more verbose than the events that should be dispatched
+    pub(crate) fn event(&self, level: Level, message: Arguments, key_values: &[(&str, String)]) {
+        if self.internal_events.is_none_or(|events| level > events)
+            || DISPATCHING_EVENT.with(|event| event.replace(true))
+        {
+            return;
+        }
+        self.log(
+            &Record::builder()
+                .level(level)
+                .target(INTERNAL_TARGET)
+                .args(message)
+                .key_values(&key_values)
+                .build(),
+        );
+        DISPATCHING_EVENT.with(|event| event.set(false));
+    }
+
+    /// Flush a logger that was removed or replaced once the records queued for it
+    /// are delivered, reporting its failure like the failures of the installed loggers
+    pub(crate) fn flush_removed(&self, logger: &SinkEntry) {
+        if let Err(error) = logger.flush(self.policy()) {
+            self.report(error, None);
+        }
+    }
+
+    /// Deliver the record to every active logger and to the fallback logger whatever
+    /// their levels and routes, like the records of the [`Banner`]
+    pub(crate) fn announce(&self, record: &Record) {
+        self.enrich(record, &|record| {
+            self.loggers
+                .iter()
+                .chain(self.fallback.iter())
+                .filter(|logger| logger.is_active())
+                .for_each(|logger| self.log_to(logger, record))
+        });
+    }
+
+    fn accepts(&self, metadata: &Metadata) -> bool {
+        if let Some(level) = self.target_level(metadata.target()) {
+            return metadata.level() <= level.max(self.boosted_level());
+        }
+
+        self.max_level
+            .is_none_or(|max_level| metadata.level() <= max_level.max(self.boosted_level()))
+            && self
+                .filter
+                .as_ref()
+                .is_none_or(|filter| filter.enabled(metadata))
+    }
+
+    /// Why the record would be dropped before it is dispatched, like [`Dispatcher::accepts`]
+    fn rejection(&self, metadata: &Metadata, message: Option<&str>) -> Option<Rejection> {
+        let boost = self.boosted_level();
+        let rejection = match self.target_levels.find_target(metadata.target()) {
+            Some((target, level)) => {
+                (metadata.level() > (*level).max(boost)).then(|| Rejection::TargetLevel {
+                    target: target.to_string(),
+                    level: *level,
+                })
+            }
+            None => match (self.max_level, &self.filter) {
+                (Some(max_level), _) if metadata.level() > max_level.max(boost) => {
+                    Some(Rejection::MaxLevel(max_level))
+                }
+                (_, Some(filter)) if !filter.enabled(metadata) => {
+                    Some(Rejection::Filter(filter.level_for(metadata.target())))
+                }
+                _ => None,
+            },
+        };
+
+        #[cfg(feature = "regex")]
+        let rejection = rejection.or_else(|| {
+            self.suppressions
+                .iter()
+                .find(|suppression| message.is_some_and(|message| suppression.is_match(message)))
+                .map(|suppression| Rejection::Suppressed(suppression.as_str().to_string()))
+        });
+        #[cfg(not(feature = "regex"))]
+        let _ = message;
+
+        rejection
+    }
+
+    /// Which loggers would receive a record of the metadata and the message, if known,
+    /// and why the others would not, without delivering anything
+    pub(crate) fn explain(&self, metadata: &Metadata, message: Option<&str>) -> RoutingReport {
+        let policy = self.policy();
+        let mut first = None;
+        let loggers = self
+            .loggers
+            .iter()
+            .map(|logger| {
+                let rejection = logger.rejection(metadata, policy);
+                let rejection = match (rejection, first) {
+                    (None, Some(first)) if self.strategy == DispatchStrategy::FirstMatch => {
+                        Some(Rejection::FirstMatch(first))
+                    }
+                    (rejection, _) => rejection,
+                };
+                if rejection.is_none() {
+                    first.get_or_insert(logger.id);
+                }
+                logger.decision(rejection)
+            })
+            .collect::<Vec<_>>();
+        let fallback = self.fallback.as_ref().map(|fallback| {
+            let rejection = match first {
+                Some(_) => Some(Rejection::Delivered),
+                None => fallback.rejection(metadata, policy),
+            };
+            fallback.decision(rejection)
+        });
+
+        RoutingReport {
+            level: metadata.level(),
+            target: metadata.target().to_string(),
+            rejection: self.rejection(metadata, message),
+            loggers,
+            fallback,
+        }
+    }
+
+    /// Print the routing of the record to the standard error if it is explained,
+    /// see [`crate::EXPLAIN_ENV`]
+    pub(crate) fn explain_if_asked(&self, record: &Record) {
+        if self
+            .explain
+            .as_ref()
+            .is_some_and(|explain| explain.enabled(record.metadata()))
+        {
+            let message = catch_unwind(AssertUnwindSafe(|| record.args().to_string())).ok();
+            eprintln!(
+                "composite_logger: {}",
+                self.explain(record.metadata(), message.as_deref())
+            );
+        }
+    }
+
+    /// Filters, deduplicates and enriches the record, passing the resulting records to `deliver`.
+    /// This happens on the logging thread, while in the asynchronous mode
+    /// the delivered records are dispatched to the loggers by the worker.
+    pub(crate) fn process(&self, record: &Record, deliver: &dyn Fn(&Record)) {
+        if !self.accepts(record.metadata()) {
+            return;
+        }
+        dispatching(|| {
+            with_formatted_message(record, |record| self.process_formatted(record, deliver))
+        });
+    }
+
+    fn process_formatted(&self, record: &Record, deliver: &dyn Fn(&Record)) {
+        #[cfg(feature = "regex")]
+        if !self.suppressions.is_empty() {
+            let message = formatted::FormattedRecord::new(record);
+            let message = message.message();
+            if self
+                .suppressions
+                .iter()
+                .any(|suppression| suppression.is_match(message))
+            {
+                return;
+            }
+        }
+
+        match self
+            .escalations
+            .iter()
+            .find_map(|escalation| escalation.escalate(record.metadata()))
+        {
+            Some(level) => {
+                let key_values = [(
+                    ESCALATED_FROM_KEY.to_string(),
+                    record.level().as_str().to_string(),
+                )];
+                let key_values = WithAmbient {
+                    record: record.key_values(),
+                    ambient: &key_values,
+                };
+                let escalated = record
+                    .to_builder()
+                    .level(level)
+                    .key_values(&key_values)
+                    .build();
+                self.deduplicate(&escalated, deliver);
+            }
+            None => self.deduplicate(record, deliver),
+        }
+    }
+
+    fn deduplicate(&self, record: &Record, deliver: &dyn Fn(&Record)) {
+        if let Some(deduplication) = &self.deduplication {
+            match deduplication.check(record) {
+                Some(summary) => self.process_summary(summary, deliver),
+                None => return,
+            }
+        }
+
+        self.enrich(record, deliver);
+    }
+
+    fn process_summary(&self, summary: Option<OwnedRecord>, deliver: &dyn Fn(&Record)) {
+        if let Some(summary) = summary {
+            summary.with_record(|summary| self.enrich(summary, deliver));
+        }
+    }
+
+    /// Add a backtrace, the context, enrichment and ambient key-values to the record
+    fn enrich(&self, record: &Record, deliver: &dyn Fn(&Record)) {
+        match self.backtraces {
+            Some((level, mode)) if record.level() <= level => {
+                let backtrace = Backtrace::force_capture().to_string();
+                match mode {
+                    BacktraceMode::KeyValue => {
+                        self.add_key_values(record, Some(backtrace), deliver)
+                    }
+                    BacktraceMode::Message => self.add_key_values(
+                        &record
+                            .to_builder()
+                            .args(format_args!("{}\n{}", record.args(), backtrace.trim_end()))
+                            .build(),
+                        None,
+                        deliver,
+                    ),
+                }
+            }
+            _ => self.add_key_values(record, None, deliver),
+        }
+    }
+
+    fn add_key_values(
+        &self,
+        record: &Record,
+        backtrace: Option<String>,
+        deliver: &dyn Fn(&Record),
+    ) {
+        let mut key_values = context::key_values();
+        if let Some(backtrace) = backtrace {
+            key_values.push(("backtrace".to_string(), backtrace));
+        }
+        #[cfg(feature = "enrich")]
+        if let Some(enrichment) = &self.enrichment {
+            key_values.extend(enrichment.key_values());
+        }
+
+        if key_values.is_empty() {
+            if self.key_values.is_empty() {
+                return deliver(record);
+            }
+            return Self::with_key_values(record, &self.key_values, deliver);
+        }
+
+        key_values.extend(self.key_values.iter().cloned());
+        Self::with_key_values(record, &key_values, deliver);
+    }
+
+    fn with_key_values(
+        record: &Record,
+        key_values: &[(String, String)],
+        deliver: &dyn Fn(&Record),
+    ) {
+        let key_values = WithAmbient {
+            record: record.key_values(),
+            ambient: key_values,
+        };
+        deliver(&record.to_builder().key_values(&key_values).build());
+    }
+
+    /// Which loggers are enabled for the metadata, `None` if none of them nor the fallback is
+    pub(crate) fn enabled_set(&self, metadata: &Metadata) -> Option<EnabledSet> {
+        if !self.accepts(metadata) {
+            return None;
+        }
+
+        let mask = self
+            .loggers
+            .iter()
+            .take(u64::BITS as usize)
+            .enumerate()
+            .filter(|(_, logger)| logger.enabled(metadata, self.policy()))
+            .fold(0, |mask, (index, _)| mask | 1 << index);
+
+        let unknown = self.loggers.len() > u64::BITS as usize || self.fallback.is_some();
+        (mask != 0 || unknown).then_some(EnabledSet {
+            generation: self.generation,
+            mask,
+        })
+    }
+
+    /// Deliver the record to the loggers enabled for it
+    pub(crate) fn dispatch(&self, record: &Record) {
+        self.dispatch_with(record, None, Delivery::All);
+    }
+
+    /// Deliver the record to the selected loggers in the enabled set,
+    /// as long as the loggers did not change since the set was computed
+    pub(crate) fn dispatch_with(
+        &self,
+        record: &Record,
+        enabled: Option<EnabledSet>,
+        delivery: Delivery,
+    ) {
+        let enabled = enabled.filter(|enabled| enabled.generation == self.generation);
+        self.sequenced(record, &|record| {
+            formatted::dispatching(record, || {
+                self.dispatch_formatted(record, enabled, delivery)
+            })
+        });
+    }
+
+    /// Whether the record is delivered to the priority loggers from the logging thread
+    pub(crate) fn is_priority(&self, record: &Record) -> bool {
+        self.priority_level
+            .is_some_and(|level| record.level() <= level)
+    }
+
+    /// Stamp the record with the next sequence number, if records are sequenced,
+    /// and deliver it before the next record is stamped. Records logged by the loggers
+    /// themselves and records dispatched in parallel are stamped without waiting.
+    fn sequenced(&self, record: &Record, deliver: &dyn Fn(&Record)) {
+        let Some(sequencer) = &self.sequencer else {
+            return deliver(record);
+        };
+
+        let ordered = self.strategy != DispatchStrategy::Parallel
+            && !SEQUENCING.with(|sequencing| sequencing.replace(true));
+        let dispatching = ordered.then(|| {
+            sequencer
+                .dispatching
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+        });
+        let sequence = sequencer.next.fetch_add(1, Ordering::Relaxed);
+        Self::with_key_values(
+            record,
+            &[(SEQUENCE_KEY.to_string(), sequence.to_string())],
+            deliver,
+        );
+        drop(dispatching);
+        if ordered {
+            SEQUENCING.with(|sequencing| sequencing.set(false));
+        }
+    }
+
+    fn dispatch_formatted(&self, record: &Record, enabled: Option<EnabledSet>, delivery: Delivery) {
+        let mut delivered = false;
+        let mut delivered_before = false;
+        let enabled = self
+            .loggers
+            .iter()
+            .enumerate()
+            .filter(|(_, logger)| match delivery {
+                Delivery::All => true,
+                Delivery::Priority => logger.priority,
+                Delivery::Queued if logger.priority && self.is_priority(record) => {
+                    delivered_before |= logger.enabled(record.metadata(), self.policy());
+                    false
+                }
+                Delivery::Queued => true,
+            })
+            .filter(|(index, logger)| {
+                let enabled = enabled
+                    .and_then(|enabled| enabled.contains(*index))
+                    .unwrap_or_else(|| logger.enabled(record.metadata(), self.policy()));
+                if !enabled {
+                    logger.counters.filtered();
+                }
+                enabled
+            })
+            .map(|(_, logger)| logger);
+        match self.strategy {
+            DispatchStrategy::Broadcast => enabled.for_each(|logger| {
+                delivered = true;
+                self.log_to(logger, record);
+            }),
+            DispatchStrategy::FirstMatch => enabled.take(1).for_each(|logger| {
+                delivered = true;
+                self.log_to(logger, record);
+            }),
+            DispatchStrategy::Parallel => {
+                let enabled = enabled.collect::<Vec<_>>();
+                delivered = !enabled.is_empty();
+                self.log_in_parallel(&enabled, record);
+            }
+        }
+
+        if !delivered && !delivered_before && delivery != Delivery::Priority {
+            self.fallback
+                .iter()
+                .filter(|logger| logger.enabled(record.metadata(), self.policy()))
+                .for_each(|logger| self.log_to(logger, record));
+        }
+    }
+
+    /// Deliver the record to the first logger from this thread and to the others
+    /// from scoped threads, to the ones whose thread fails to spawn from this thread too
+    fn log_in_parallel(&self, loggers: &[&Arc<SinkEntry>], record: &Record) {
+        let Some((first, others)) = loggers.split_first() else {
+            return;
+        };
+        if others.is_empty() {
+            return self.log_to(first, record);
+        }
+
+        let owned = OwnedRecord::from(record);
+        thread::scope(|scope| {
+            for logger in others {
+                let owned = &owned;
+                let deliver = move || {
+                    Origin::of(owned)
+                        .dispatching(|| owned.with_record(|record| self.log_to(logger, record)))
+                };
+                let spawned = thread::Builder::new()
+                    .name("composite-logger-fanout".to_string())
+                    .spawn_scoped(scope, deliver);
+                if spawned.is_err() {
+                    self.log_to(logger, record);
+                }
+            }
+            self.log_to(first, record);
+        });
+    }
+
+    fn log_to(&self, logger: &Arc<SinkEntry>, record: &Record) {
+        let Some(lane) = &logger.lane else {
+            if let Err(error) =
+                logger.log(record, self.policy(), |notice| self.report(notice, None))
+            {
+                self.report(error, Some(record));
+            }
+            if logger.priority && self.is_priority(record) {
+                if let Err(error) = logger.flush(self.policy()) {
+                    self.report(error, None);
+                }
+            }
+            return;
+        };
+
+        self.report_lane_failures(logger);
+        let policy = self.policy();
+        let entry = logger.clone();
+        let owned = OwnedRecord::from(record);
+        let queued = lane.deliver(move || {
+            let Some(lane) = &entry.lane else {
+                return;
+            };
+            Origin::of(&owned).dispatching(|| {
+                owned.with_record(|record| {
+                    let logged = entry.log(record, policy, |notice| lane.failed(notice, None));
+                    if let Err(error) = logged {
+                        lane.failed(error, Some(OwnedRecord::from(record)));
+                    }
+                })
+            })
+        });
+        if !queued {
+            logger.counters.dropped();
+        }
+    }
+
+    /// Report the failures of an isolated logger since the last records were dispatched to it
+    fn report_lane_failures(&self, logger: &SinkEntry) {
+        let Some(lane) = &logger.lane else {
+            return;
+        };
+        for (error, record) in lane.take_failures() {
+            match record {
+                Some(record) => record.with_record(|record| self.report(error, Some(record))),
+                None => self.report(error, None),
+            }
+        }
+    }
+
+    /// Waits until the records queued for the isolated loggers are delivered
+    pub(crate) fn drain_lanes(&self) {
+        self.loggers
+            .iter()
+            .filter_map(|logger| logger.lane.as_ref())
+            .for_each(Lane::drain);
+    }
+}
+
+impl Log for Dispatcher {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        let compute = || {
+            self.accepts(metadata)
+                && self
+                    .loggers
+                    .iter()
+                    .chain(self.fallback.iter())
+                    .any(|logger| logger.enabled(metadata, self.policy()))
+        };
+
+        match &self.enabled_cache {
+            Some(cache) => cache.enabled(metadata, compute),
+            None => compute(),
+        }
+    }
+
+    fn log(&self, record: &Record) {
+        self.process(record, &|record| self.dispatch(record));
+    }
+
+    fn flush(&self) {
+        if let Some(deduplication) = &self.deduplication {
+            self.process_summary(deduplication.take_summary(), &|record| {
+                self.dispatch(record)
+            });
+        }
+
+        self.loggers
+            .iter()
+            .chain(self.fallback.iter())
+            .filter_map(|logger| logger.flush(self.policy()).err())
+            .for_each(|error| self.report(error, None));
+        self.loggers
+            .iter()
+            .for_each(|logger| self.report_lane_failures(logger));
+
+        if let Some(dead_letter) = &self.dead_letter {
+            let _ = catch_unwind(AssertUnwindSafe(|| dead_letter.flush()));
+        }
+    }
+}
+
+/// Call `f` with the record once its message is formatted, catching a panic of a `Display`
+/// or `Debug` implementation so that it does not escape from the log call site.
+/// The loggers then receive the formatted message, so that they do not format it again.
+/// The message is formatted into the message buffer of the thread instead of a new string,
+/// see [`format::with_line_buffer`].
+fn with_formatted_message(record: &Record, f: impl FnOnce(&Record)) {
+    if record.args().as_str().is_some() {
+        return f(record);
+    }
+
+    format::with_message_buffer(|message| {
+        let formatted = catch_unwind(AssertUnwindSafe(|| write!(message, "{}", record.args())));
+        match formatted {
+            Ok(_) => f(&record
+                .to_builder()
+                .args(format_args!("{}", message))
+                .build()),
+            Err(payload) => {
+                let key_values = [(
+                    FORMAT_ERROR_KEY.to_string(),
+                    sink_error::panic_message(payload.as_ref()).to_string(),
+                )];
+                let key_values = WithAmbient {
+                    record: record.key_values(),
+                    ambient: &key_values,
+                };
+                f(&record
+                    .to_builder()
+                    .args(format_args!("{}", FORMAT_PANIC_MESSAGE))
+                    .key_values(&key_values)
+                    .build())
+            }
+        }
+    })
+}
+
+fn micros_since_epoch(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_micros() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CompositeLogger;
+    use std::fmt::{self, Display, Formatter};
+
+    /// Keeps the messages the loggers receive with their format errors
+    #[derive(Default)]
+    struct Messages(Mutex<Vec<(String, Option<String>)>>);
+
+    impl Log for Messages {
+        fn enabled(&self, _metadata: &Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            let error = record
+                .key_values()
+                .get(FORMAT_ERROR_KEY.into())
+                .map(|error| error.to_string());
+            self.0
+                .lock()
+                .unwrap()
+                .push((record.args().to_string(), error));
+        }
+
+        fn flush(&self) {}
+    }
+
+    /// Counts how often it is formatted
+    struct Counted(AtomicUsize);
+
+    impl Display for Counted {
+        fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+            let count = self.0.fetch_add(1, Ordering::Relaxed) + 1;
+            write!(formatter, "formatted {}", count)
+        }
+    }
+
+    struct Panicking;
+
+    impl Display for Panicking {
+        fn fmt(&self, _formatter: &mut Formatter) -> fmt::Result {
+            panic!("no display")
+        }
+    }
+
+    fn log_formatted(messages: &Arc<Messages>, message: impl Display) {
+        CompositeLogger::new()
+            .with_logger(messages.clone())
+            .with_logger(messages.clone())
+            .build()
+            .log(&Record::builder().args(format_args!("{}", message)).build());
+    }
+
+    #[test]
+    fn formats_the_message_once_for_all_loggers() {
+        let messages = Arc::new(Messages::default());
+        log_formatted(&messages, Counted(AtomicUsize::new(0)));
+        log_formatted(&messages, Counted(AtomicUsize::new(1)));
+
+        assert_eq!(
+            *messages.0.lock().unwrap(),
+            vec![
+                ("formatted 1".to_string(), None),
+                ("formatted 1".to_string(), None),
+                ("formatted 2".to_string(), None),
+                ("formatted 2".to_string(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn delivers_a_placeholder_when_the_message_panics() {
+        let messages = Arc::new(Messages::default());
+        log_formatted(&messages, Panicking);
+
+        let placeholder = (
+            FORMAT_PANIC_MESSAGE.to_string(),
+            Some("no display".to_string()),
+        );
+        assert_eq!(
+            *messages.0.lock().unwrap(),
+            vec![placeholder.clone(), placeholder]
+        );
+    }
+}
@@ -0,0 +1,117 @@
+use crate::handle::SharedLogger;
+use crate::origin::Origin;
+use crate::OwnedRecord;
+use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError};
+
+/// The global logger installed before the composite logger, see
+/// [`crate::CompositeLogger::buffer_early`]
+static EARLY: EarlyLogger = EarlyLogger {
+    installed: AtomicBool::new(false),
+    composite: OnceLock::new(),
+    buffer: Mutex::new(Buffer {
+        records: Vec::new(),
+        capacity: 0,
+        dropped: 0,
+    }),
+};
+
+/// Keeps the records until the composite logger is installed, then forwards to it
+struct EarlyLogger {
+    installed: AtomicBool,
+    composite: OnceLock<Arc<SharedLogger>>,
+    buffer: Mutex<Buffer>,
+}
+
+/// The records logged before the composite logger was installed
+pub(crate) struct Buffer {
+    records: Vec<OwnedRecord>,
+    capacity: usize,
+    /// Records logged once the buffer was full
+    dropped: u64,
+}
+
+impl EarlyLogger {
+    fn buffer(&self) -> MutexGuard<'_, Buffer> {
+        self.buffer.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Install the early logger as the global logger, keeping at most `capacity` records
+pub(crate) fn install(capacity: usize) -> Result<(), SetLoggerError> {
+    EARLY.buffer().capacity = capacity;
+    log::set_logger(&EARLY)?;
+    EARLY.installed.store(true, Ordering::Relaxed);
+    log::set_max_level(LevelFilter::Trace);
+    Ok(())
+}
+
+/// Forward the records to the composite logger if the early logger is the global logger,
+/// returning the records it kept so far
+pub(crate) fn take_over(composite: &Arc<SharedLogger>) -> Option<Buffer> {
+    if !EARLY.installed.load(Ordering::Relaxed) {
+        return None;
+    }
+
+    let mut buffer = EARLY.buffer();
+    EARLY.composite.set(composite.clone()).ok()?;
+    Some(Buffer {
+        records: std::mem::take(&mut buffer.records),
+        capacity: buffer.capacity,
+        dropped: std::mem::take(&mut buffer.dropped),
+    })
+}
+
+impl Buffer {
+    /// Log the kept records through the composite logger, at the time and from the thread
+    /// they were logged, followed by a warning if some were dropped
+    pub(crate) fn replay(self, composite: &SharedLogger) {
+        for record in &self.records {
+            Origin::of(record).dispatching(|| record.with_record(|record| composite.log(record)));
+        }
+
+        if self.dropped > 0 {
+            composite.log(
+                &Record::builder()
+                    .args(format_args!(
+                        "{} records logged before the logger was initialized were dropped",
+                        self.dropped
+                    ))
+                    .level(Level::Warn)
+                    .target("composite_logger")
+                    .build(),
+            );
+        }
+    }
+}
+
+impl Log for EarlyLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.composite
+            .get()
+            .is_none_or(|composite| composite.enabled(metadata))
+    }
+
+    fn log(&self, record: &Record) {
+        if let Some(composite) = self.composite.get() {
+            return composite.log(record);
+        }
+
+        let mut buffer = self.buffer();
+        match self.composite.get() {
+            Some(composite) => {
+                drop(buffer);
+                composite.log(record);
+            }
+            None if buffer.records.len() < buffer.capacity => buffer.records.push(record.into()),
+            None => buffer.dropped += 1,
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(composite) = self.composite.get() {
+            composite.flush();
+        }
+    }
+}
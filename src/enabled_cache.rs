@@ -0,0 +1,59 @@
+use log::{Level, Metadata};
+use std::collections::HashMap;
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// How many targets are remembered at most, decisions for further targets are not cached
+const MAX_TARGETS: usize = 1024;
+
+/// Remembers whether any logger is enabled for a target and level.
+/// Only valid as long as the loggers decide on the metadata alone and do not change.
+#[derive(Default)]
+pub(crate) struct EnabledCache {
+    /// Decisions per target, indexed by level
+    decisions: RwLock<HashMap<String, [Option<bool>; 5]>>,
+}
+
+impl EnabledCache {
+    fn read(&self) -> RwLockReadGuard<'_, HashMap<String, [Option<bool>; 5]>> {
+        self.decisions
+            .read()
+            .unwrap_or_else(|error| error.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, HashMap<String, [Option<bool>; 5]>> {
+        self.decisions
+            .write()
+            .unwrap_or_else(|error| error.into_inner())
+    }
+
+    fn index(level: Level) -> usize {
+        level as usize - 1
+    }
+
+    pub(crate) fn enabled(&self, metadata: &Metadata, compute: impl FnOnce() -> bool) -> bool {
+        let index = Self::index(metadata.level());
+        if let Some(enabled) = self
+            .read()
+            .get(metadata.target())
+            .and_then(|decisions| decisions[index])
+        {
+            return enabled;
+        }
+
+        let enabled = compute();
+        let mut decisions = self.write();
+        if let Some(target) = decisions.get_mut(metadata.target()) {
+            target[index] = Some(enabled);
+        } else if decisions.len() < MAX_TARGETS {
+            let mut target = [None; 5];
+            target[index] = Some(enabled);
+            decisions.insert(metadata.target().to_string(), target);
+        }
+        enabled
+    }
+
+    /// Forget all decisions, for example after the loggers or their levels changed
+    pub(crate) fn clear(&self) {
+        self.write().clear();
+    }
+}
@@ -0,0 +1,130 @@
+//! Encryption of log output at rest with AES-256-GCM.
+//!
+//! Encrypted output is a sequence of frames, each a 4 byte big-endian length followed by
+//! a random 12 byte nonce and the encrypted bytes with their authentication tag, so that
+//! encrypted files can be appended to and rotated like plain ones.
+//! [`crate::sinks::RotatingFileLogger::with_encryption`] encrypts every record as a frame,
+//! [`EncryptingWriter`] encrypts any other writer, and [`decrypt`] restores the text.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use std::io::{self, Read, Write};
+
+const NONCE_LENGTH: usize = 12;
+
+/// A 256 bit key to encrypt and decrypt log output
+#[derive(Clone)]
+pub struct EncryptionKey {
+    cipher: Aes256Gcm,
+}
+
+impl EncryptionKey {
+    pub fn new(key: [u8; 32]) -> Self {
+        Self {
+            cipher: Aes256Gcm::new(&key.into()),
+        }
+    }
+
+    /// Parse a key of 64 hexadecimal digits, `None` if it is not one
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.trim();
+        if hex.len() != 64 || !hex.is_ascii() {
+            return None;
+        }
+
+        let mut key = [0; 32];
+        for (index, byte) in key.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[index * 2..index * 2 + 2], 16).ok()?;
+        }
+        Some(Self::new(key))
+    }
+
+    /// Encrypt the bytes as a single frame
+    pub(crate) fn seal(&self, plaintext: &[u8]) -> Vec<u8> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext)
+            .expect("the message is not too long to encrypt");
+
+        let length = (NONCE_LENGTH + ciphertext.len()) as u32;
+        let mut frame = Vec::with_capacity(4 + length as usize);
+        frame.extend_from_slice(&length.to_be_bytes());
+        frame.extend_from_slice(&nonce);
+        frame.extend_from_slice(&ciphertext);
+        frame
+    }
+
+    fn open(&self, frame: &[u8]) -> io::Result<Vec<u8>> {
+        if frame.len() < NONCE_LENGTH {
+            return Err(invalid_frame());
+        }
+        let (nonce, ciphertext) = frame.split_at(NONCE_LENGTH);
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| invalid_frame())
+    }
+}
+
+/// Encrypts everything written to the wrapped writer, each write as a separate frame,
+/// so wrap it in a `BufWriter` to avoid encrypting small pieces
+pub struct EncryptingWriter<W: Write> {
+    writer: W,
+    key: EncryptionKey,
+}
+
+impl<W: Write> EncryptingWriter<W> {
+    pub fn new(writer: W, key: EncryptionKey) -> Self {
+        Self { writer, key }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> Write for EncryptingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writer.write_all(&self.key.seal(buf))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Decrypt encrypted log output from the reader and write the text to the writer
+///
+/// # Errors
+///
+/// Fails if reading or writing fails, and with [`io::ErrorKind::InvalidData`] if the output
+/// was encrypted with another key, was changed or ends in the middle of a frame.
+pub fn decrypt(
+    key: &EncryptionKey,
+    mut reader: impl Read,
+    mut writer: impl Write,
+) -> io::Result<()> {
+    let mut length = [0; 4];
+    loop {
+        match reader.read_exact(&mut length) {
+            Ok(()) => {}
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(error) => return Err(error),
+        }
+
+        let mut frame = vec![0; u32::from_be_bytes(length) as usize];
+        reader
+            .read_exact(&mut frame)
+            .map_err(|error| match error.kind() {
+                io::ErrorKind::UnexpectedEof => invalid_frame(),
+                _ => error,
+            })?;
+        writer.write_all(&key.open(&frame)?)?;
+    }
+    writer.flush()
+}
+
+fn invalid_frame() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "invalid encrypted log frame")
+}
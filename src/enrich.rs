@@ -0,0 +1,42 @@
+use std::thread;
+
+/// Computes the process and call site information attached to every record
+#[derive(Clone)]
+pub(crate) struct Enrichment {
+    pid: String,
+    hostname: String,
+}
+
+impl Enrichment {
+    pub(crate) fn new() -> Self {
+        Self {
+            pid: std::process::id().to_string(),
+            hostname: crate::host::hostname(),
+        }
+    }
+
+    /// The `timestamp`, `pid`, `thread`, `thread_id` and `hostname` key-values
+    /// for a record logged right now from the current thread
+    pub(crate) fn key_values(&self) -> Vec<(String, String)> {
+        let thread = thread::current();
+        let thread_id = format!("{:?}", thread.id());
+        let thread_id = thread_id
+            .trim_start_matches("ThreadId(")
+            .trim_end_matches(')')
+            .to_string();
+
+        vec![
+            (
+                "timestamp".to_string(),
+                crate::timestamp::timestamp(crate::time::now()),
+            ),
+            ("pid".to_string(), self.pid.clone()),
+            (
+                "thread".to_string(),
+                thread.name().unwrap_or("<unnamed>").to_string(),
+            ),
+            ("thread_id".to_string(), thread_id),
+            ("hostname".to_string(), self.hostname.clone()),
+        ]
+    }
+}
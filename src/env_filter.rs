@@ -0,0 +1,258 @@
+use crate::filter::Filter;
+use crate::TargetTree;
+use log::{LevelFilter, Metadata, Record};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The environment variable read by [`EnvFilter::from_default_env`]
+pub const DEFAULT_FILTER_ENV: &str = "RUST_LOG";
+
+struct Directive {
+    target: Option<String>,
+    level: LevelFilter,
+}
+
+/// A filter parsed from `RUST_LOG` style directives, such as `info,hyper=warn,my_app::db=trace`.
+///
+/// A directive is either a level applied to all targets, a `target=level` pair,
+/// or a bare target that enables all levels of that target.
+/// A target also applies to its submodules, and the most specific target wins.
+/// If several directives have the same target, or none, the last one wins.
+/// Targets not matched by any directive are disabled,
+/// and without any directives only errors are accepted.
+///
+/// It can be attached to the whole composite with [`crate::CompositeLogger::with_env_filter`]
+/// or to a single logger with [`crate::CompositeLogger::with_filtered_logger`].
+pub struct EnvFilter {
+    /// The level of the directive without a target, if any
+    level: Option<LevelFilter>,
+    /// The levels of the targets, inherited by their submodules
+    targets: TargetTree<LevelFilter>,
+}
+
+impl EnvFilter {
+    /// Parse comma separated directives
+    ///
+    /// # Errors
+    ///
+    /// Fails if a directive has an empty target or an unknown level.
+    pub fn parse(directives: &str) -> Result<Self, EnvFilterError> {
+        let mut filter = Self {
+            level: None,
+            targets: TargetTree::new(),
+        };
+        for directive in directives
+            .split(',')
+            .map(str::trim)
+            .filter(|directive| !directive.is_empty())
+        {
+            // the last directive for a target wins
+            match Self::parse_directive(directive)? {
+                Directive {
+                    target: Some(target),
+                    level,
+                } => {
+                    filter.targets.insert(&target, level);
+                }
+                Directive {
+                    target: None,
+                    level,
+                } => {
+                    filter.level = Some(level);
+                }
+            }
+        }
+        Ok(filter)
+    }
+
+    /// Parse the directives from the given environment variable,
+    /// a missing variable results in a filter without directives
+    ///
+    /// # Errors
+    ///
+    /// Fails if the variable contains an invalid directive.
+    pub fn from_env(variable: &str) -> Result<Self, EnvFilterError> {
+        Self::parse(&std::env::var(variable).unwrap_or_default())
+    }
+
+    /// Parse the directives from the `RUST_LOG` environment variable
+    ///
+    /// # Errors
+    ///
+    /// Fails if the variable contains an invalid directive.
+    pub fn from_default_env() -> Result<Self, EnvFilterError> {
+        Self::from_env(DEFAULT_FILTER_ENV)
+    }
+
+    fn parse_directive(directive: &str) -> Result<Directive, EnvFilterError> {
+        let error = || EnvFilterError {
+            directive: directive.to_string(),
+        };
+
+        match directive.split_once('=') {
+            Some((target, level)) => {
+                let target = target.trim();
+                if target.is_empty() {
+                    return Err(error());
+                }
+                Ok(Directive {
+                    target: Some(target.to_string()),
+                    level: LevelFilter::from_str(level.trim()).map_err(|_| error())?,
+                })
+            }
+            None => Ok(match LevelFilter::from_str(directive) {
+                Ok(level) => Directive {
+                    target: None,
+                    level,
+                },
+                Err(_) => Directive {
+                    target: Some(directive.to_string()),
+                    level: LevelFilter::max(),
+                },
+            }),
+        }
+    }
+
+    /// The level of the most specific directive matching the target
+    pub fn level_for(&self, target: &str) -> LevelFilter {
+        self.targets
+            .find(target)
+            .copied()
+            .or(self.level)
+            .unwrap_or(self.default_level())
+    }
+
+    /// Like `env_logger`, targets not matched by any directive are disabled,
+    /// unless there are no directives at all
+    fn default_level(&self) -> LevelFilter {
+        if self.level.is_none() && self.targets.is_empty() {
+            LevelFilter::Error
+        } else {
+            LevelFilter::Off
+        }
+    }
+
+    /// The most verbose level accepted for any target
+    pub fn max_level(&self) -> LevelFilter {
+        self.targets
+            .values()
+            .copied()
+            .chain(self.level)
+            .max()
+            .unwrap_or(self.default_level())
+    }
+}
+
+impl FromStr for EnvFilter {
+    type Err = EnvFilterError;
+
+    fn from_str(directives: &str) -> Result<Self, Self::Err> {
+        Self::parse(directives)
+    }
+}
+
+impl Filter for EnvFilter {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level_for(metadata.target())
+    }
+
+    fn matches(&self, record: &Record) -> bool {
+        self.enabled(record.metadata())
+    }
+}
+
+/// A directive of an [`EnvFilter`] could not be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvFilterError {
+    directive: String,
+}
+
+impl EnvFilterError {
+    pub fn directive(&self) -> &str {
+        &self.directive
+    }
+}
+
+impl Display for EnvFilterError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid log filter directive `{}`", self.directive)
+    }
+}
+
+impl std::error::Error for EnvFilterError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::Level;
+
+    fn filter(directives: &str) -> EnvFilter {
+        EnvFilter::parse(directives).unwrap()
+    }
+
+    #[test]
+    fn only_errors_without_directives() {
+        let filter = filter("");
+        assert_eq!(filter.level_for("my_app"), LevelFilter::Error);
+        assert_eq!(filter.max_level(), LevelFilter::Error);
+    }
+
+    #[test]
+    fn targets_without_directive_are_disabled() {
+        let filter = filter("my_app=debug");
+        assert_eq!(filter.level_for("my_app"), LevelFilter::Debug);
+        assert_eq!(filter.level_for("hyper"), LevelFilter::Off);
+    }
+
+    #[test]
+    fn most_specific_target_wins() {
+        let filter = filter("info,hyper=warn,my_app::db=trace");
+        assert_eq!(filter.level_for("other"), LevelFilter::Info);
+        assert_eq!(filter.level_for("hyper::client"), LevelFilter::Warn);
+        assert_eq!(filter.level_for("my_app"), LevelFilter::Info);
+        assert_eq!(filter.level_for("my_app::db::pool"), LevelFilter::Trace);
+        assert_eq!(filter.level_for("my_app_db"), LevelFilter::Info);
+        assert_eq!(filter.max_level(), LevelFilter::Trace);
+    }
+
+    #[test]
+    fn bare_target_enables_all_levels() {
+        let filter = filter("warn, my_app ");
+        assert_eq!(filter.level_for("my_app::net"), LevelFilter::Trace);
+        assert_eq!(filter.level_for("hyper"), LevelFilter::Warn);
+    }
+
+    #[test]
+    fn last_directive_wins() {
+        let filter = filter("info,my_app=warn,debug,my_app=trace");
+        assert_eq!(filter.level_for("other"), LevelFilter::Debug);
+        assert_eq!(filter.level_for("my_app"), LevelFilter::Trace);
+    }
+
+    #[test]
+    fn levels_are_case_insensitive() {
+        assert_eq!(filter("my_app=WARN").level_for("my_app"), LevelFilter::Warn);
+        assert_eq!(filter("Off").level_for("my_app"), LevelFilter::Off);
+    }
+
+    #[test]
+    fn invalid_directives_are_reported() {
+        let error = EnvFilter::parse("info,=debug").err().unwrap();
+        assert_eq!(error.directive(), "=debug");
+        let error = EnvFilter::parse("my_app=loud").err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "invalid log filter directive `my_app=loud`"
+        );
+    }
+
+    #[test]
+    fn filters_metadata_by_level_of_target() {
+        let filter = filter("warn,my_app=debug");
+        let metadata = |target, level| Metadata::builder().target(target).level(level).build();
+        assert!(filter.enabled(&metadata("my_app", Level::Debug)));
+        assert!(!filter.enabled(&metadata("my_app", Level::Trace)));
+        assert!(filter.enabled(&metadata("hyper", Level::Warn)));
+        assert!(!filter.enabled(&metadata("hyper", Level::Info)));
+    }
+}
@@ -0,0 +1,65 @@
+use log::kv::{Error, Key, Source, Value, VisitSource};
+use log::{Level, Record};
+use std::fmt::Arguments;
+
+/// The key of the error itself
+const ERROR_KEY: &str = "error";
+
+/// The key-values describing an error and the chain of its sources:
+/// the error itself under `error`, its source under `error.source.1`,
+/// the source of that under `error.source.2` and so on
+pub struct ErrorChain {
+    pairs: Vec<(String, String)>,
+}
+
+impl ErrorChain {
+    pub fn new(error: &dyn std::error::Error) -> Self {
+        let mut pairs = vec![(ERROR_KEY.to_string(), error.to_string())];
+        let mut source = error.source();
+        while let Some(error) = source {
+            pairs.push((
+                format!("{}.source.{}", ERROR_KEY, pairs.len()),
+                error.to_string(),
+            ));
+            source = error.source();
+        }
+        Self { pairs }
+    }
+}
+
+impl Source for ErrorChain {
+    fn visit<'kvs>(&'kvs self, visitor: &mut dyn VisitSource<'kvs>) -> Result<(), Error> {
+        for (key, value) in &self.pairs {
+            visitor.visit_pair(Key::from_str(key), Value::from(value.as_str()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Log the error with the chain of its sources, see [`crate::log_error`]
+#[doc(hidden)]
+pub fn log_error(
+    level: Level,
+    target: &str,
+    error: &dyn std::error::Error,
+    message: Arguments,
+    location: (&'static str, &'static str, u32),
+) {
+    if level > log::max_level() {
+        return;
+    }
+
+    let (module_path, file, line) = location;
+    let chain = ErrorChain::new(error);
+    log::logger().log(
+        &Record::builder()
+            .level(level)
+            .target(target)
+            .args(message)
+            .module_path_static(Some(module_path))
+            .file_static(Some(file))
+            .line(Some(line))
+            .key_values(&chain)
+            .build(),
+    );
+}
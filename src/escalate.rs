@@ -0,0 +1,94 @@
+use crate::filter;
+use log::{Level, Metadata};
+use std::collections::VecDeque;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// The key of the original level of an escalated record
+pub(crate) const ESCALATED_FROM_KEY: &str = "escalated_from";
+
+/// Promotes the records of a target to a more severe level once they occur
+/// often enough within a window, see [`crate::CompositeLogger::escalate_after`]
+pub(crate) struct Escalation {
+    target: String,
+    count: usize,
+    window: Duration,
+    level: Level,
+    /// When the records counted in the current window occurred
+    occurrences: Mutex<VecDeque<Instant>>,
+}
+
+impl Escalation {
+    pub(crate) fn new(target: String, count: usize, window: Duration, level: Level) -> Self {
+        Self {
+            target,
+            count: count.max(1),
+            window,
+            level,
+            occurrences: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Count a record of the target that is a warning or more severe but less severe than
+    /// the level, returning the level to deliver it at once enough records were counted
+    pub(crate) fn escalate(&self, metadata: &Metadata) -> Option<Level> {
+        if metadata.level() <= self.level
+            || metadata.level() > Level::Warn
+            || !filter::target_matches(metadata.target(), &self.target)
+        {
+            return None;
+        }
+
+        let now = crate::time::instant();
+        let mut occurrences = self
+            .occurrences
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        while occurrences
+            .front()
+            .is_some_and(|occurred| now.duration_since(*occurred) > self.window)
+        {
+            occurrences.pop_front();
+        }
+        occurrences.push_back(now);
+        (occurrences.len() >= self.count).then_some(self.level)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    fn escalate(escalation: &Escalation, level: Level, target: &str) -> Option<Level> {
+        escalation.escalate(&Metadata::builder().level(level).target(target).build())
+    }
+
+    #[test]
+    fn escalates_repeated_warnings_of_the_target() {
+        testing::with_mock_clock(|clock| {
+            let escalation =
+                Escalation::new("app".to_string(), 2, Duration::from_secs(10), Level::Error);
+
+            assert_eq!(escalate(&escalation, Level::Warn, "app::db"), None);
+            assert_eq!(escalate(&escalation, Level::Info, "app"), None);
+            assert_eq!(escalate(&escalation, Level::Warn, "other"), None);
+            assert_eq!(
+                escalate(&escalation, Level::Warn, "app"),
+                Some(Level::Error)
+            );
+
+            clock.advance(Duration::from_secs(11));
+            assert_eq!(escalate(&escalation, Level::Warn, "app"), None);
+        });
+    }
+
+    #[test]
+    fn leaves_records_at_least_as_severe_as_the_level() {
+        let escalation =
+            Escalation::new("app".to_string(), 1, Duration::from_secs(10), Level::Warn);
+
+        assert_eq!(escalate(&escalation, Level::Warn, "app"), None);
+        assert_eq!(escalate(&escalation, Level::Error, "app"), None);
+    }
+}
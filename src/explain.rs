@@ -0,0 +1,158 @@
+use crate::LoggerId;
+use log::{Level, LevelFilter};
+use std::fmt::{Display, Formatter};
+
+/// The environment variable with `RUST_LOG` style directives of the records whose routing
+/// is printed to the standard error as they are logged, see [`crate::CompositeLogger::with_explain`]
+pub const EXPLAIN_ENV: &str = "COMPOSITE_LOGGER_EXPLAIN";
+
+/// Why a record would not be delivered, by the composite logger or by one of its loggers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rejection {
+    /// More verbose than the level set for the target or its parent module,
+    /// see [`crate::CompositeLoggerHandle::set_target_level`]
+    TargetLevel { target: String, level: LevelFilter },
+    /// More verbose than the maximum level of the composite logger
+    MaxLevel(LevelFilter),
+    /// The filter of the composite logger, or of the composite logger the logger was
+    /// merged from, lets the target through up to the level only
+    Filter(LevelFilter),
+    /// The message matches a suppressed pattern
+    Suppressed(String),
+    /// More verbose than the level of the logger
+    LoggerLevel(LevelFilter),
+    /// The logger only receives the records of its route, such as a target
+    Route(String),
+    /// The logger is switched off
+    Paused,
+    /// The logger was disabled after panicking too often
+    Disabled,
+    /// The logger is skipped for a while after failing too often
+    CircuitOpen,
+    /// The logger itself is not enabled for the record, for example because of its own filter
+    NotEnabled,
+    /// The logger panicked when asked whether it is enabled
+    Panicked,
+    /// An earlier logger receives the record, and only the first enabled logger does
+    FirstMatch(LoggerId),
+    /// The fallback logger only receives the records no other logger does
+    Delivered,
+}
+
+impl Display for Rejection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rejection::TargetLevel { target, level } => {
+                write!(
+                    f,
+                    "more verbose than the level {} of the target `{}`",
+                    level, target
+                )
+            }
+            Rejection::MaxLevel(level) => {
+                write!(f, "more verbose than the maximum level {}", level)
+            }
+            Rejection::Filter(level) => {
+                write!(f, "the filter lets the target through up to {}", level)
+            }
+            Rejection::Suppressed(pattern) => {
+                write!(
+                    f,
+                    "the message matches the suppressed pattern `{}`",
+                    pattern
+                )
+            }
+            Rejection::LoggerLevel(level) => write!(f, "more verbose than its level {}", level),
+            Rejection::Route(route) => write!(f, "only receives the records of {}", route),
+            Rejection::Paused => f.write_str("switched off"),
+            Rejection::Disabled => f.write_str("disabled after panicking too often"),
+            Rejection::CircuitOpen => f.write_str("skipped for a while after failing too often"),
+            Rejection::NotEnabled => f.write_str("not enabled for the record"),
+            Rejection::Panicked => f.write_str("panicked when asked whether it is enabled"),
+            Rejection::FirstMatch(logger) => {
+                write!(f, "only the first enabled logger receives it, {:?}", logger)
+            }
+            Rejection::Delivered => f.write_str("other loggers receive the record"),
+        }
+    }
+}
+
+/// Whether a logger would receive the record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoggerDecision {
+    pub id: LoggerId,
+    pub name: Option<String>,
+    /// Why the logger would not receive the record, `None` if it would
+    pub rejection: Option<Rejection>,
+}
+
+impl LoggerDecision {
+    pub fn is_accepted(&self) -> bool {
+        self.rejection.is_none()
+    }
+}
+
+impl Display for LoggerDecision {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "logger `{}`: ", name)?,
+            None => write!(f, "logger {:?}: ", self.id)?,
+        }
+        match &self.rejection {
+            Some(rejection) => rejection.fmt(f),
+            None => f.write_str("accepted"),
+        }
+    }
+}
+
+/// Which loggers would receive a record and why the others would not,
+/// see [`crate::CompositeLoggerHandle::explain`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoutingReport {
+    pub level: Level,
+    pub target: String,
+    /// Why the composite logger would drop the record before asking its loggers, if it would
+    pub rejection: Option<Rejection>,
+    /// The decisions of the loggers in the order they receive records,
+    /// made even if the composite logger drops the record
+    pub loggers: Vec<LoggerDecision>,
+    pub fallback: Option<LoggerDecision>,
+}
+
+impl RoutingReport {
+    /// Whether any logger would receive the record
+    pub fn is_delivered(&self) -> bool {
+        self.rejection.is_none() && self.accepted().next().is_some()
+    }
+
+    /// The loggers that would receive the record, including the fallback logger
+    pub fn accepted(&self) -> impl Iterator<Item = &LoggerDecision> {
+        self.loggers
+            .iter()
+            .chain(self.fallback.iter())
+            .filter(|logger| logger.is_accepted())
+    }
+}
+
+impl Display for RoutingReport {
+    /// A line for the record followed by a line for each logger
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} [{}] ", self.level, self.target)?;
+        match &self.rejection {
+            Some(rejection) => write!(f, "is dropped: {}", rejection)?,
+            None => write!(
+                f,
+                "is delivered to {} of {} loggers",
+                self.accepted().count(),
+                self.loggers.len() + self.fallback.iter().count()
+            )?,
+        }
+        for logger in &self.loggers {
+            write!(f, "\n  {}", logger)?;
+        }
+        if let Some(fallback) = &self.fallback {
+            write!(f, "\n  fallback {}", fallback)?;
+        }
+        Ok(())
+    }
+}
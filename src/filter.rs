@@ -0,0 +1,390 @@
+//! Filters decide which records are delegated to a logger based on the whole [`Record`].
+//!
+//! Any `Fn(&Record) -> bool` closure is a filter, and filters can be combined
+//! with [`FilterExt::and`], [`FilterExt::or`] and [`FilterExt::not`].
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::sync::Arc;
+use log::{Level, Log, Metadata, Record};
+
+pub trait Filter: Send + Sync {
+    /// Whether records with the given metadata may be accepted.
+    /// Returning `false` allows to skip the record before it is built,
+    /// so filters that can decide on the metadata alone should do so.
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        let _ = metadata;
+        true
+    }
+
+    /// Whether the record should be delegated to the logger
+    fn matches(&self, record: &Record) -> bool;
+}
+
+impl<F> Filter for F
+where
+    F: Fn(&Record) -> bool + Send + Sync,
+{
+    fn matches(&self, record: &Record) -> bool {
+        self(record)
+    }
+}
+
+impl Filter for Box<dyn Filter> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.as_ref().enabled(metadata)
+    }
+
+    fn matches(&self, record: &Record) -> bool {
+        self.as_ref().matches(record)
+    }
+}
+
+impl<F: Filter + ?Sized> Filter for Arc<F> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.as_ref().enabled(metadata)
+    }
+
+    fn matches(&self, record: &Record) -> bool {
+        self.as_ref().matches(record)
+    }
+}
+
+pub trait FilterExt: Filter + Sized {
+    /// Accept records accepted by both filters
+    fn and<F: Filter>(self, other: F) -> And<Self, F> {
+        And(self, other)
+    }
+
+    /// Accept records accepted by any of the filters
+    fn or<F: Filter>(self, other: F) -> Or<Self, F> {
+        Or(self, other)
+    }
+
+    /// Accept records rejected by the filter
+    fn not(self) -> Not<Self> {
+        Not(self)
+    }
+}
+
+impl<T: Filter> FilterExt for T {}
+
+pub struct And<A, B>(pub A, pub B);
+
+impl<A: Filter, B: Filter> Filter for And<A, B> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.0.enabled(metadata) && self.1.enabled(metadata)
+    }
+
+    fn matches(&self, record: &Record) -> bool {
+        self.0.matches(record) && self.1.matches(record)
+    }
+}
+
+pub struct Or<A, B>(pub A, pub B);
+
+impl<A: Filter, B: Filter> Filter for Or<A, B> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.0.enabled(metadata) || self.1.enabled(metadata)
+    }
+
+    fn matches(&self, record: &Record) -> bool {
+        self.0.matches(record) || self.1.matches(record)
+    }
+}
+
+/// Since a filter enabled for some metadata may still reject the record,
+/// the negation can only decide on the whole record
+pub struct Not<A>(pub A);
+
+impl<A: Filter> Filter for Not<A> {
+    fn matches(&self, record: &Record) -> bool {
+        !self.0.matches(record)
+    }
+}
+
+/// Accepts records at the given level or more severe
+pub struct LevelAtLeast(pub Level);
+
+impl Filter for LevelAtLeast {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.0
+    }
+
+    fn matches(&self, record: &Record) -> bool {
+        self.enabled(record.metadata())
+    }
+}
+
+/// Accepts records of exactly the given level
+pub struct LevelExactly(pub Level);
+
+impl Filter for LevelExactly {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() == self.0
+    }
+
+    fn matches(&self, record: &Record) -> bool {
+        self.enabled(record.metadata())
+    }
+}
+
+/// Accepts records of the given target or any of its submodules,
+/// for example `"hyper"` matches both `hyper` and `hyper::client`
+pub struct TargetMatches(pub String);
+
+impl TargetMatches {
+    pub fn new(target: impl Into<String>) -> Self {
+        Self(target.into())
+    }
+}
+
+impl Filter for TargetMatches {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        target_matches(metadata.target(), &self.0)
+    }
+
+    fn matches(&self, record: &Record) -> bool {
+        self.enabled(record.metadata())
+    }
+}
+
+/// Accepts records whose formatted message matches the regular expression
+#[cfg(feature = "regex")]
+pub struct MessageMatches(pub regex::Regex);
+
+#[cfg(feature = "regex")]
+impl MessageMatches {
+    /// # Errors
+    ///
+    /// Fails if the pattern is not a valid regular expression.
+    pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+        Ok(Self(regex::Regex::new(pattern)?))
+    }
+}
+
+#[cfg(feature = "regex")]
+impl Filter for MessageMatches {
+    fn matches(&self, record: &Record) -> bool {
+        self.0
+            .is_match(crate::FormattedRecord::new(record).message())
+    }
+}
+
+/// Accepts records while the condition holds, for example a feature flag,
+/// evaluated for every record
+pub struct When<F>(pub F);
+
+impl<F: Fn() -> bool + Send + Sync> Filter for When<F> {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        (self.0)()
+    }
+
+    fn matches(&self, _record: &Record) -> bool {
+        (self.0)()
+    }
+}
+
+/// Accepts records while the condition holds, evaluated at most once per time to live,
+/// for conditions that are expensive to evaluate such as a remote feature flag
+#[cfg(feature = "std")]
+pub struct CachedWhen<F> {
+    condition: F,
+    time_to_live: std::time::Duration,
+    cached: std::sync::Mutex<Option<(std::time::Instant, bool)>>,
+}
+
+#[cfg(feature = "std")]
+impl<F: Fn() -> bool + Send + Sync> CachedWhen<F> {
+    pub fn new(condition: F, time_to_live: std::time::Duration) -> Self {
+        Self {
+            condition,
+            time_to_live,
+            cached: std::sync::Mutex::new(None),
+        }
+    }
+
+    fn holds(&self) -> bool {
+        let mut cached = self
+            .cached
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        match *cached {
+            Some((evaluated, holds)) if crate::time::elapsed(evaluated) < self.time_to_live => {
+                holds
+            }
+            _ => {
+                let holds = (self.condition)();
+                *cached = Some((crate::time::instant(), holds));
+                holds
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<F: Fn() -> bool + Send + Sync> Filter for CachedWhen<F> {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        self.holds()
+    }
+
+    fn matches(&self, _record: &Record) -> bool {
+        self.holds()
+    }
+}
+
+/// Whether the target is the given module or any of its submodules
+pub(crate) fn target_matches(target: &str, module: &str) -> bool {
+    target
+        .strip_prefix(module)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+}
+
+/// A logger that only receives records accepted by the filter
+pub struct FilteredLogger<L, F> {
+    logger: L,
+    filter: F,
+}
+
+impl<L: Log, F: Filter> FilteredLogger<L, F> {
+    pub fn new(logger: L, filter: F) -> Self {
+        Self { logger, filter }
+    }
+}
+
+impl<L: Log, F: Filter> Log for FilteredLogger<L, F> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.filter.enabled(metadata) && self.logger.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.filter.matches(record) {
+            self.logger.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.logger.flush();
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::testing::{self, CaptureLogger};
+    use crate::OwnedRecord;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    fn record(level: Level, target: &str, message: &str) -> OwnedRecord {
+        OwnedRecord::new(level, target, message)
+    }
+
+    fn matches(filter: &impl Filter, record: &OwnedRecord) -> bool {
+        record.with_record(|record| filter.enabled(record.metadata()) && filter.matches(record))
+    }
+
+    #[test]
+    fn level_filters() {
+        let warn = record(Level::Warn, "my_app", "disk almost full");
+        let debug = record(Level::Debug, "my_app", "checked disk");
+        assert!(matches(&LevelAtLeast(Level::Info), &warn));
+        assert!(!matches(&LevelAtLeast(Level::Info), &debug));
+        assert!(matches(&LevelExactly(Level::Debug), &debug));
+        assert!(!matches(&LevelExactly(Level::Debug), &warn));
+    }
+
+    #[test]
+    fn target_matches_submodules_only() {
+        let filter = TargetMatches::new("hyper");
+        assert!(matches(&filter, &record(Level::Info, "hyper", "")));
+        assert!(matches(&filter, &record(Level::Info, "hyper::client", "")));
+        assert!(!matches(&filter, &record(Level::Info, "hyperlocal", "")));
+        assert!(!matches(&filter, &record(Level::Info, "my_app::hyper", "")));
+    }
+
+    #[test]
+    fn combinators() {
+        let errors_of_hyper = LevelAtLeast(Level::Error).and(TargetMatches::new("hyper"));
+        let hyper_or_errors = TargetMatches::new("hyper").or(LevelAtLeast(Level::Error));
+        let not_hyper = TargetMatches::new("hyper").not();
+
+        let hyper_error = record(Level::Error, "hyper", "");
+        let hyper_info = record(Level::Info, "hyper", "");
+        let my_app_error = record(Level::Error, "my_app", "");
+        let my_app_info = record(Level::Info, "my_app", "");
+
+        assert!(matches(&errors_of_hyper, &hyper_error));
+        assert!(!matches(&errors_of_hyper, &hyper_info));
+        assert!(!matches(&errors_of_hyper, &my_app_error));
+
+        assert!(matches(&hyper_or_errors, &hyper_info));
+        assert!(matches(&hyper_or_errors, &my_app_error));
+        assert!(!matches(&hyper_or_errors, &my_app_info));
+
+        assert!(!matches(&not_hyper, &hyper_info));
+        assert!(matches(&not_hyper, &my_app_info));
+    }
+
+    #[test]
+    fn closures_decide_on_the_record() {
+        let filter = |record: &Record| record.args().to_string().contains("disk");
+        assert!(matches(
+            &filter,
+            &record(Level::Info, "my_app", "disk full")
+        ));
+        assert!(!matches(
+            &filter,
+            &record(Level::Info, "my_app", "memory full")
+        ));
+    }
+
+    #[test]
+    fn filtered_logger_only_receives_accepted_records() {
+        let capture = Arc::new(CaptureLogger::new());
+        let logger = FilteredLogger::new(capture.clone(), LevelAtLeast(Level::Warn));
+        for record in [
+            record(Level::Error, "my_app", "failed"),
+            record(Level::Info, "my_app", "started"),
+        ] {
+            record.with_record(|record| logger.log(record));
+        }
+        capture.assert_logged(Level::Error, testing::equals("failed"));
+        capture.assert_not_logged(Level::Info, testing::equals("started"));
+    }
+
+    #[test]
+    fn when_evaluates_the_condition_for_every_record() {
+        let active = AtomicBool::new(false);
+        let filter = When(|| active.load(Ordering::Relaxed));
+        let record = record(Level::Info, "my_app", "");
+        assert!(!matches(&filter, &record));
+        active.store(true, Ordering::Relaxed);
+        assert!(matches(&filter, &record));
+    }
+
+    #[test]
+    fn cached_when_evaluates_the_condition_once_per_time_to_live() {
+        testing::with_mock_clock(|clock| {
+            let evaluations = AtomicUsize::new(0);
+            let active = AtomicBool::new(true);
+            let filter = CachedWhen::new(
+                || {
+                    evaluations.fetch_add(1, Ordering::Relaxed);
+                    active.load(Ordering::Relaxed)
+                },
+                Duration::from_secs(10),
+            );
+            let record = record(Level::Info, "my_app", "");
+            assert!(matches(&filter, &record));
+            active.store(false, Ordering::Relaxed);
+            clock.advance(Duration::from_secs(9));
+            assert!(matches(&filter, &record));
+            assert_eq!(evaluations.load(Ordering::Relaxed), 1);
+
+            clock.advance(Duration::from_secs(1));
+            assert!(!matches(&filter, &record));
+            assert_eq!(evaluations.load(Ordering::Relaxed), 2);
+        });
+    }
+}
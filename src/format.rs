@@ -0,0 +1,903 @@
+//! Formats turn a [`Record`] into a human readable line, so that sinks can share
+//! the same output format.
+//!
+//! Any `Fn(&Record, bool) -> String` closure is a format, and [`TemplateFormat`]
+//! formats records from a template such as `"{time} {level:>5} [{target}] {message}"`.
+//! [`JsonFormat`] formats records as JSON objects, [`LogfmtFormat`] as `key=value` pairs,
+//! and [`RoutedFormat`] chooses a format
+//! depending on the level or target of the record, so that a single sink can write
+//! human readable lines for some records and JSON for others, in order.
+//!
+//! A [`BinaryFormat`] encodes records as bytes instead, such as the
+//! [`crate::msgpack::MessagePackFormat`].
+//!
+//! The built-in formats also write into a given buffer with [`Format::format_into`]
+//! and [`BinaryFormat::encode_into`]. The built-in sinks format into the reusable buffers
+//! of [`with_line_buffer`] and [`with_byte_buffer`], one of each per thread, so that
+//! delivering a record to many sinks does not allocate a line for every sink.
+
+use crate::kv::Collect;
+use crate::sinks::JsonLogger;
+use crate::FormattedRecord;
+use log::kv::{Error, Key, Value, VisitSource};
+use log::{Level, Record};
+use std::cell::RefCell;
+use std::fmt::{Display, Formatter, Write};
+use std::io;
+use std::thread::LocalKey;
+
+/// The template of the default format, used by the built-in sinks
+pub const DEFAULT_TEMPLATE: &str = "{time} {level:<5} [{target}] {message}";
+
+/// Reused buffers that grew larger are released instead, so that a single huge record
+/// does not keep its memory for the life of the thread
+const MAX_REUSED_CAPACITY: usize = 64 * 1024;
+
+thread_local! {
+    static LINE_BUFFER: RefCell<String> = const { RefCell::new(String::new()) };
+    static BYTE_BUFFER: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+    static MESSAGE_BUFFER: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+trait Buffer: Default {
+    fn clear(&mut self);
+    fn capacity(&self) -> usize;
+}
+
+impl Buffer for String {
+    fn clear(&mut self) {
+        self.clear();
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl Buffer for Vec<u8> {
+    fn clear(&mut self) {
+        self.clear();
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity()
+    }
+}
+
+fn with_buffer<B: Buffer, T>(
+    buffer: &'static LocalKey<RefCell<B>>,
+    action: impl FnOnce(&mut B) -> T,
+) -> T {
+    let mut action = Some(action);
+    let reused = buffer.try_with(|buffer| {
+        let mut buffer = buffer.try_borrow_mut().ok()?;
+        buffer.clear();
+        let result = action.take().map(|action| action(&mut buffer));
+        if buffer.capacity() > MAX_REUSED_CAPACITY {
+            *buffer = B::default();
+        }
+        result
+    });
+    match (reused, action) {
+        (Ok(Some(result)), _) => result,
+        // the buffer is in use further up the stack or the thread is exiting
+        (_, Some(action)) => action(&mut B::default()),
+        (_, None) => unreachable!("the action ran with the reused buffer"),
+    }
+}
+
+/// Run the action with the empty line buffer of this thread, reused by the next record
+/// instead of allocating a line for every record. Nested calls get a new buffer.
+pub fn with_line_buffer<T>(action: impl FnOnce(&mut String) -> T) -> T {
+    with_buffer(&LINE_BUFFER, action)
+}
+
+/// Run the action with the empty message buffer of this thread, like [`with_line_buffer`].
+/// The composite logger formats the messages of records into it, separate from the line
+/// buffer, so that the sinks formatting the same record still reuse their line buffer.
+pub(crate) fn with_message_buffer<T>(action: impl FnOnce(&mut String) -> T) -> T {
+    with_buffer(&MESSAGE_BUFFER, action)
+}
+
+/// Run the action with the empty byte buffer of this thread, like [`with_line_buffer`]
+pub fn with_byte_buffer<T>(action: impl FnOnce(&mut Vec<u8>) -> T) -> T {
+    with_buffer(&BYTE_BUFFER, action)
+}
+
+/// Format the record into the line buffer of this thread and hand the line
+/// to the action, see [`with_line_buffer`]
+pub fn with_formatted_line<T>(
+    format: &(impl Format + ?Sized),
+    record: &Record,
+    colors: bool,
+    action: impl FnOnce(&str) -> T,
+) -> T {
+    with_line_buffer(|line| {
+        format.format_into(record, colors, line);
+        action(line)
+    })
+}
+
+/// Encode the record into the byte buffer of this thread and hand the bytes
+/// to the action, see [`with_byte_buffer`]
+pub fn with_encoded_record<T>(
+    format: &(impl BinaryFormat + ?Sized),
+    record: &Record,
+    action: impl FnOnce(&[u8]) -> T,
+) -> T {
+    with_byte_buffer(|bytes| {
+        format.encode_into(record, bytes);
+        action(bytes)
+    })
+}
+
+pub trait Format: Send + Sync {
+    /// Format the record as a single line without the line break,
+    /// with ANSI colors when `colors` is set
+    fn format(&self, record: &Record, colors: bool) -> String;
+
+    /// Append the [`Format::format`] of the record to the line, so that the line can be reused.
+    /// The built-in formats write into the line directly.
+    fn format_into(&self, record: &Record, colors: bool, line: &mut String) {
+        line.push_str(&self.format(record, colors));
+    }
+}
+
+impl<F> Format for F
+where
+    F: Fn(&Record, bool) -> String + Send + Sync,
+{
+    fn format(&self, record: &Record, colors: bool) -> String {
+        self(record, colors)
+    }
+}
+
+impl Format for Box<dyn Format> {
+    fn format(&self, record: &Record, colors: bool) -> String {
+        self.as_ref().format(record, colors)
+    }
+
+    fn format_into(&self, record: &Record, colors: bool, line: &mut String) {
+        self.as_ref().format_into(record, colors, line);
+    }
+}
+
+pub trait BinaryFormat: Send + Sync {
+    /// Encode the record as a self-delimiting sequence of bytes,
+    /// so that records can be written back to back
+    fn encode(&self, record: &Record) -> Vec<u8>;
+
+    /// Append the [`BinaryFormat::encode`] of the record to the bytes,
+    /// so that the bytes can be reused
+    fn encode_into(&self, record: &Record, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.encode(record));
+    }
+}
+
+impl BinaryFormat for Box<dyn BinaryFormat> {
+    fn encode(&self, record: &Record) -> Vec<u8> {
+        self.as_ref().encode(record)
+    }
+
+    fn encode_into(&self, record: &Record, bytes: &mut Vec<u8>) {
+        self.as_ref().encode_into(record, bytes);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Time,
+    Level,
+    Target,
+    Message,
+    Module,
+    File,
+    Line,
+    Kv,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "time" => Field::Time,
+            "level" => Field::Level,
+            "target" => Field::Target,
+            "message" => Field::Message,
+            "module" => Field::Module,
+            "file" => Field::File,
+            "line" => Field::Line,
+            "kv" => Field::Kv,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Alignment {
+    Left,
+    Right,
+    Center,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Piece {
+    Literal(String),
+    Field {
+        field: Field,
+        alignment: Alignment,
+        width: usize,
+    },
+}
+
+/// Formats records from a template of `{field}` placeholders and literal text.
+///
+/// The fields are `time`, `level`, `target`, `message`, `module`, `file`, `line`
+/// and `kv`, the key-values as space separated `key=value` pairs.
+/// A field can be padded to a width with `{field:<5}`, `{field:>5}` or `{field:^5}`,
+/// and `{{` and `}}` are literal braces.
+/// The level is shown with the labels of the [`Theme`] and, with colors, colored by it.
+#[derive(Debug, Clone)]
+pub struct TemplateFormat {
+    pieces: Vec<Piece>,
+    theme: Theme,
+}
+
+impl Default for TemplateFormat {
+    fn default() -> Self {
+        Self::new(DEFAULT_TEMPLATE).expect("the default template is valid")
+    }
+}
+
+impl TemplateFormat {
+    /// Parse the template
+    ///
+    /// # Errors
+    ///
+    /// Fails if a placeholder is not closed, names an unknown field or has an invalid width.
+    pub fn new(template: &str) -> Result<Self, TemplateError> {
+        Ok(Self {
+            pieces: Self::parse(template)?,
+            theme: Theme::default(),
+        })
+    }
+
+    /// Color the level with the given ANSI foreground color code,
+    /// by default errors are red, warnings yellow, infos green, debug blue and trace magenta
+    pub fn with_level_color(mut self, level: Level, color: u8) -> Self {
+        self.theme = self.theme.with_color(level, color.to_string());
+        self
+    }
+
+    /// Show the levels with the labels, colors and emoji of the theme, by default [`Theme::dark`]
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    fn parse(template: &str) -> Result<Vec<Piece>, TemplateError> {
+        let error = || TemplateError {
+            template: template.to_string(),
+        };
+        let mut pieces = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars();
+
+        while let Some(char) = chars.next() {
+            match char {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let rest = chars.as_str();
+                    let end = rest.find('}').ok_or_else(error)?;
+                    let (name, spec) = rest[..end].split_once(':').unwrap_or((&rest[..end], ""));
+                    let field = Field::parse(name.trim()).ok_or_else(error)?;
+                    let (alignment, width) = match spec.chars().next() {
+                        None => (Alignment::Left, "0"),
+                        Some('<') => (Alignment::Left, &spec[1..]),
+                        Some('>') => (Alignment::Right, &spec[1..]),
+                        Some('^') => (Alignment::Center, &spec[1..]),
+                        Some(_) => (Alignment::Left, spec),
+                    };
+                    let width = width.parse().map_err(|_| error())?;
+
+                    if !literal.is_empty() {
+                        pieces.push(Piece::Literal(std::mem::take(&mut literal)));
+                    }
+                    pieces.push(Piece::Field {
+                        field,
+                        alignment,
+                        width,
+                    });
+                    chars = rest[end + 1..].chars();
+                }
+                '}' => return Err(error()),
+                char => literal.push(char),
+            }
+        }
+
+        if !literal.is_empty() {
+            pieces.push(Piece::Literal(literal));
+        }
+        Ok(pieces)
+    }
+
+    fn write_value(field: Field, record: &Record, line: &mut String) {
+        match field {
+            Field::Time => crate::timestamp::write_timestamp(line, crate::time::now()),
+            Field::Level => line.push_str(record.level().as_str()),
+            Field::Target => line.push_str(record.target()),
+            Field::Message => line.push_str(FormattedRecord::new(record).message()),
+            Field::Module => line.push_str(record.module_path().unwrap_or_default()),
+            Field::File => line.push_str(record.file().unwrap_or_default()),
+            Field::Line => {
+                if let Some(number) = record.line() {
+                    let _ = write!(line, "{}", number);
+                }
+            }
+            Field::Kv => {
+                let _ = record
+                    .key_values()
+                    .visit(&mut WritePairs { line, first: true });
+            }
+        }
+    }
+}
+
+/// Writes key-values as space separated `key=value` pairs
+struct WritePairs<'a> {
+    line: &'a mut String,
+    first: bool,
+}
+
+impl<'kvs> VisitSource<'kvs> for WritePairs<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), Error> {
+        if !std::mem::take(&mut self.first) {
+            self.line.push(' ');
+        }
+        let _ = write!(self.line, "{}={}", key, value);
+        Ok(())
+    }
+}
+
+/// Spaces to pad fields with, in chunks
+const PADDING: &str = "                                ";
+
+fn pad(line: &mut String, at: usize, mut width: usize) {
+    while width > 0 {
+        let chunk = width.min(PADDING.len());
+        line.insert_str(at, &PADDING[..chunk]);
+        width -= chunk;
+    }
+}
+
+impl Format for TemplateFormat {
+    fn format(&self, record: &Record, colors: bool) -> String {
+        let mut line = String::with_capacity(128);
+        self.format_into(record, colors, &mut line);
+        line
+    }
+
+    fn format_into(&self, record: &Record, colors: bool, line: &mut String) {
+        for piece in &self.pieces {
+            match piece {
+                Piece::Literal(literal) => line.push_str(literal),
+                Piece::Field {
+                    field,
+                    alignment,
+                    width,
+                } => {
+                    let color = match self.theme.color(record.level()) {
+                        Some(color) if colors && *field == Field::Level => Some(color),
+                        _ => None,
+                    };
+                    if let Some(color) = color {
+                        let _ = write!(line, "\x1b[{}m", color);
+                    }
+
+                    let start = line.len();
+                    match field {
+                        Field::Level => self.theme.write_label(record.level(), line),
+                        _ => Self::write_value(*field, record, line),
+                    }
+                    let missing = width.saturating_sub(line[start..].chars().count());
+                    match alignment {
+                        Alignment::Left => pad(line, line.len(), missing),
+                        Alignment::Right => pad(line, start, missing),
+                        Alignment::Center => {
+                            pad(line, line.len(), missing - missing / 2);
+                            pad(line, start, missing / 2);
+                        }
+                    }
+
+                    if color.is_some() {
+                        line.push_str("\x1b[0m");
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LevelStyle {
+    label: String,
+    /// The ANSI SGR parameters, such as `1;31` for bold red
+    color: Option<String>,
+    emoji: Option<String>,
+}
+
+/// How a [`TemplateFormat`] shows the levels: their labels padded to a width,
+/// their colors when writing to a terminal, and an optional emoji before the label,
+/// so that all binaries of a team can share the same console output:
+///
+/// ```text
+/// let theme = Theme::light().with_label(Level::Warn, "WARNING").with_padding(7).with_emojis();
+/// let logger = ConsoleLogger::new().with_theme(theme);
+/// ```
+///
+/// Colors are only used where the sink writes them, for example the [`crate::sinks::ConsoleLogger`]
+/// does not when the `NO_COLOR` environment variable is set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    /// Indexed by level
+    levels: [LevelStyle; 5],
+    padding: usize,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    /// No colors
+    pub fn plain() -> Self {
+        let style = |level: Level| LevelStyle {
+            label: level.to_string(),
+            color: None,
+            emoji: None,
+        };
+        Self {
+            levels: [
+                style(Level::Error),
+                style(Level::Warn),
+                style(Level::Info),
+                style(Level::Debug),
+                style(Level::Trace),
+            ],
+            padding: 0,
+        }
+    }
+
+    /// Colors for terminals with a dark background: errors are red, warnings yellow,
+    /// infos green, debug blue and trace magenta
+    pub fn dark() -> Self {
+        Self::plain().with_colors(["31", "33", "32", "34", "35"])
+    }
+
+    /// Colors for terminals with a light background, on which yellow is hard to read:
+    /// errors are bold red, warnings orange, infos green, debug blue and trace gray
+    pub fn light() -> Self {
+        Self::plain().with_colors(["1;31", "38;5;166", "32", "34", "90"])
+    }
+
+    fn with_colors(mut self, colors: [&str; 5]) -> Self {
+        for (style, color) in self.levels.iter_mut().zip(colors) {
+            style.color = Some(color.to_string());
+        }
+        self
+    }
+
+    fn style(&mut self, level: Level) -> &mut LevelStyle {
+        &mut self.levels[level as usize - 1]
+    }
+
+    /// Show the level with the label instead of its name, such as `WARNING` or `wrn`
+    pub fn with_label(mut self, level: Level, label: impl Into<String>) -> Self {
+        self.style(level).label = label.into();
+        self
+    }
+
+    /// Color the level with the ANSI SGR parameters, such as `31` for red,
+    /// `1;31` for bold red or `38;5;166` for an orange of the 256 color palette
+    pub fn with_color(mut self, level: Level, color: impl Into<String>) -> Self {
+        self.style(level).color = Some(color.into());
+        self
+    }
+
+    /// Do not color the level
+    pub fn without_color(mut self, level: Level) -> Self {
+        self.style(level).color = None;
+        self
+    }
+
+    /// Show the emoji before the label of the level
+    pub fn with_emoji(mut self, level: Level, emoji: impl Into<String>) -> Self {
+        self.style(level).emoji = Some(emoji.into());
+        self
+    }
+
+    /// Show an emoji before the label of every level: ❌ for errors, ⚠️ for warnings,
+    /// ℹ️ for infos, 🐛 for debug and 🔍 for trace
+    pub fn with_emojis(self) -> Self {
+        self.with_emoji(Level::Error, "❌")
+            .with_emoji(Level::Warn, "⚠️")
+            .with_emoji(Level::Info, "ℹ️")
+            .with_emoji(Level::Debug, "🐛")
+            .with_emoji(Level::Trace, "🔍")
+    }
+
+    /// Pad the labels to the width, so that the messages line up
+    /// even if the template does not pad the level
+    pub fn with_padding(mut self, width: usize) -> Self {
+        self.padding = width;
+        self
+    }
+
+    fn write_label(&self, level: Level, line: &mut String) {
+        let style = &self.levels[level as usize - 1];
+        let width = self.padding;
+        let _ = match &style.emoji {
+            Some(emoji) => write!(line, "{} {:<width$}", emoji, style.label),
+            None => write!(line, "{:<width$}", style.label),
+        };
+    }
+
+    fn color(&self, level: Level) -> Option<&str> {
+        self.levels[level as usize - 1].color.as_deref()
+    }
+}
+
+/// Formats records as single line JSON objects like the [`JsonLogger`]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct JsonFormat;
+
+impl Format for JsonFormat {
+    fn format(&self, record: &Record, _colors: bool) -> String {
+        let mut line = JsonLogger::<io::Sink>::format(record);
+        line.pop();
+        line
+    }
+
+    fn format_into(&self, record: &Record, _colors: bool, line: &mut String) {
+        JsonLogger::<io::Sink>::format_into(record, line);
+        line.pop();
+    }
+}
+
+/// Formats records as logfmt lines, such as
+/// `ts=2024-01-31T12:00:00.000Z level=info target=app msg="listening on :80" port=80`,
+/// with the key-values of the record after the message
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LogfmtFormat;
+
+impl LogfmtFormat {
+    /// Keys may not contain spaces, `=` or quotes, which are replaced by `_`
+    fn write_key(line: &mut String, key: &str) {
+        line.extend(key.chars().map(|c| match c {
+            ' ' | '=' | '"' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        }));
+    }
+
+    /// Values are quoted when empty or when they contain spaces, `=` or quotes
+    fn is_quoted(value: &str) -> bool {
+        value.is_empty()
+            || value
+                .chars()
+                .any(|c| c == ' ' || c == '=' || c == '"' || c.is_control())
+    }
+
+    fn write_value(line: &mut String, value: &str) {
+        if !Self::is_quoted(value) {
+            line.push_str(value);
+            return;
+        }
+        line.push('"');
+        for c in value.chars() {
+            match c {
+                '"' => line.push_str("\\\""),
+                '\\' => line.push_str("\\\\"),
+                '\n' => line.push_str("\\n"),
+                '\r' => line.push_str("\\r"),
+                '\t' => line.push_str("\\t"),
+                c if c.is_control() => {
+                    let _ = write!(line, "\\u{:04x}", c as u32);
+                }
+                c => line.push(c),
+            }
+        }
+        line.push('"');
+    }
+}
+
+impl Format for LogfmtFormat {
+    fn format(&self, record: &Record, colors: bool) -> String {
+        let mut line = String::with_capacity(128);
+        self.format_into(record, colors, &mut line);
+        line
+    }
+
+    fn format_into(&self, record: &Record, _colors: bool, line: &mut String) {
+        line.push_str("ts=");
+        let start = line.len();
+        crate::timestamp::write_timestamp(line, crate::time::now());
+        if Self::is_quoted(&line[start..]) {
+            // only custom timestamp formats need quoting
+            let timestamp = line.split_off(start);
+            Self::write_value(line, &timestamp);
+        }
+        line.push_str(" level=");
+        line.extend(
+            record
+                .level()
+                .as_str()
+                .chars()
+                .map(|c| c.to_ascii_lowercase()),
+        );
+        line.push_str(" target=");
+        Self::write_value(line, record.target());
+        line.push_str(" msg=");
+        Self::write_value(line, FormattedRecord::new(record).message());
+
+        let mut collect = Collect(Vec::new());
+        let _ = record.key_values().visit(&mut collect);
+        for (key, value) in &collect.0 {
+            line.push(' ');
+            Self::write_key(line, key);
+            line.push('=');
+            Self::write_value(line, value);
+        }
+    }
+}
+
+enum Selector {
+    /// Records of the level or more severe
+    Level(Level),
+    /// Records with the exact target or any of its submodules
+    Target(String),
+}
+
+/// Formats every record with the first format whose level or target matches it,
+/// or with the default format if none does
+pub struct RoutedFormat {
+    formats: Vec<(Selector, Box<dyn Format>)>,
+    default: Box<dyn Format>,
+}
+
+impl RoutedFormat {
+    pub fn new(default: impl Format + 'static) -> Self {
+        Self {
+            formats: Vec::new(),
+            default: Box::new(default),
+        }
+    }
+
+    /// Format the records of the given level or more severe with the format
+    pub fn with_level_format(mut self, level: Level, format: impl Format + 'static) -> Self {
+        self.formats
+            .push((Selector::Level(level), Box::new(format)));
+        self
+    }
+
+    /// Format the records with the target or any of its submodules (`target::*`) with the format
+    pub fn with_target_format(
+        mut self,
+        target: impl Into<String>,
+        format: impl Format + 'static,
+    ) -> Self {
+        self.formats
+            .push((Selector::Target(target.into()), Box::new(format)));
+        self
+    }
+}
+
+impl RoutedFormat {
+    fn select(&self, record: &Record) -> &dyn Format {
+        self.formats
+            .iter()
+            .find(|(selector, _)| match selector {
+                Selector::Level(level) => record.level() <= *level,
+                Selector::Target(target) => crate::filter::target_matches(record.target(), target),
+            })
+            .map_or(&self.default, |(_, format)| format)
+    }
+}
+
+impl Format for RoutedFormat {
+    fn format(&self, record: &Record, colors: bool) -> String {
+        self.select(record).format(record, colors)
+    }
+
+    fn format_into(&self, record: &Record, colors: bool, line: &mut String) {
+        self.select(record).format_into(record, colors, line);
+    }
+}
+
+/// A template of a [`TemplateFormat`] could not be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateError {
+    template: String,
+}
+
+impl TemplateError {
+    pub fn template(&self) -> &str {
+        &self.template
+    }
+}
+
+impl Display for TemplateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid log format template `{}`", self.template)
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OwnedRecord;
+
+    fn record() -> OwnedRecord {
+        OwnedRecord::new(Level::Warn, "app::db", "slow query")
+            .with_module_path(Some("app::db".to_string()))
+            .with_file(Some("src/db.rs".to_string()))
+            .with_line(Some(42))
+            .with_key_value("table", "users")
+            .with_key_value("ms", "1200")
+    }
+
+    fn formatted(format: &impl Format, record: &OwnedRecord, colors: bool) -> String {
+        record.with_record(|record| format.format(record, colors))
+    }
+
+    fn template(template: &str) -> String {
+        formatted(&TemplateFormat::new(template).unwrap(), &record(), false)
+    }
+
+    #[test]
+    fn formats_the_fields_of_the_template() {
+        assert_eq!(
+            template("{level} [{target}] {message} ({file}:{line} in {module}) {kv}"),
+            "WARN [app::db] slow query (src/db.rs:42 in app::db) table=users ms=1200"
+        );
+        assert_eq!(template("{{{message}}}"), "{slow query}");
+    }
+
+    #[test]
+    fn pads_the_fields_to_their_width() {
+        assert_eq!(template("[{level:<6}]"), "[WARN  ]");
+        assert_eq!(template("[{level:>6}]"), "[  WARN]");
+        assert_eq!(template("[{level:^7}]"), "[ WARN  ]");
+        assert_eq!(template("[{level:6}]"), "[WARN  ]");
+        assert_eq!(template("[{target:3}]"), "[app::db]");
+    }
+
+    #[test]
+    fn colors_the_level() {
+        let format = TemplateFormat::new("{level:<5} {message}")
+            .unwrap()
+            .with_level_color(Level::Warn, 36);
+
+        assert_eq!(
+            formatted(&format, &record(), true),
+            "\x1b[36mWARN \x1b[0m slow query"
+        );
+        assert_eq!(formatted(&format, &record(), false), "WARN  slow query");
+    }
+
+    #[test]
+    fn rejects_invalid_templates() {
+        for invalid in ["{message", "message}", "{unknown}", "{level:<wide}"] {
+            assert_eq!(
+                TemplateFormat::new(invalid).unwrap_err().to_string(),
+                format!("invalid log format template `{}`", invalid)
+            );
+        }
+    }
+
+    #[test]
+    fn closures_are_formats() {
+        let format = |record: &Record, _colors: bool| format!("{}!", record.args());
+        let mut line = "> ".to_string();
+        record().with_record(|record| format.format_into(record, false, &mut line));
+
+        assert_eq!(line, "> slow query!");
+    }
+
+    #[test]
+    fn formats_json_objects_without_the_line_break() {
+        let line = formatted(&JsonFormat, &record(), false);
+
+        assert!(line.starts_with("{\"timestamp\":"), "{}", line);
+        assert!(
+            line.ends_with("\"kv\":{\"table\":\"users\",\"ms\":\"1200\"}}"),
+            "{}",
+            line
+        );
+    }
+
+    #[test]
+    fn routes_the_records_to_the_first_matching_format() {
+        let named = |name: &'static str| move |_: &Record, _: bool| name.to_string();
+        let format = RoutedFormat::new(named("default"))
+            .with_level_format(Level::Error, named("errors"))
+            .with_target_format("app::db", named("database"))
+            .with_target_format("app", named("app"));
+
+        let routed = |level: Level, target: &str| {
+            formatted(&format, &OwnedRecord::new(level, target, "message"), false)
+        };
+        assert_eq!(routed(Level::Error, "app::db"), "errors");
+        assert_eq!(routed(Level::Warn, "app::db::pool"), "database");
+        assert_eq!(routed(Level::Warn, "app::dbx"), "app");
+        assert_eq!(routed(Level::Info, "other"), "default");
+    }
+
+    /// The logfmt line of the record without its timestamp
+    fn logfmt(record: &OwnedRecord) -> String {
+        let line = formatted(&LogfmtFormat, record, false);
+        let (timestamp, rest) = line.split_once(' ').unwrap();
+        assert!(timestamp.starts_with("ts="), "{}", line);
+        rest.to_string()
+    }
+
+    #[test]
+    fn formats_logfmt_pairs() {
+        assert_eq!(
+            logfmt(&record()),
+            "level=warn target=app::db msg=\"slow query\" table=users ms=1200"
+        );
+    }
+
+    #[test]
+    fn quotes_and_escapes_the_logfmt_values() {
+        let record = OwnedRecord::new(Level::Info, "app", "say \"hi\"\n")
+            .with_key_value("user name", "")
+            .with_key_value("a=b", "x=y\\z");
+
+        assert_eq!(
+            logfmt(&record),
+            "level=info target=app msg=\"say \\\"hi\\\"\\n\" user_name=\"\" a_b=\"x=y\\\\z\""
+        );
+    }
+
+    #[test]
+    fn shows_the_levels_with_the_theme() {
+        let theme = Theme::light()
+            .with_label(Level::Warn, "WARNING")
+            .with_emoji(Level::Warn, "!")
+            .with_padding(8);
+        let format = TemplateFormat::new("{level}|{message}")
+            .unwrap()
+            .with_theme(theme);
+
+        assert_eq!(
+            formatted(&format, &record(), true),
+            "\x1b[38;5;166m! WARNING \x1b[0m|slow query"
+        );
+        let info = OwnedRecord::new(Level::Info, "app", "started");
+        assert_eq!(formatted(&format, &info, false), "INFO    |started");
+    }
+
+    #[test]
+    fn plain_themes_have_no_colors() {
+        let format = TemplateFormat::new("{level} {message}")
+            .unwrap()
+            .with_theme(Theme::dark().without_color(Level::Warn));
+        assert_eq!(formatted(&format, &record(), true), "WARN slow query");
+
+        let format = format.with_theme(Theme::plain());
+        let error = OwnedRecord::new(Level::Error, "app", "failed");
+        assert_eq!(formatted(&format, &error, true), "ERROR failed");
+    }
+}
@@ -0,0 +1,94 @@
+use log::{Level, Record};
+use std::cell::RefCell;
+use std::sync::Arc;
+
+/// Identifies the record being dispatched, records rewritten by a logger
+/// (for example mapped or summary records) have a different identity
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Identity {
+    level: Level,
+    target: (*const u8, usize),
+    file: Option<(*const u8, usize)>,
+    line: Option<u32>,
+}
+
+impl Identity {
+    fn of(record: &Record) -> Self {
+        Self {
+            level: record.level(),
+            target: (record.target().as_ptr(), record.target().len()),
+            file: record.file().map(|file| (file.as_ptr(), file.len())),
+            line: record.line(),
+        }
+    }
+}
+
+struct Dispatching {
+    identity: Identity,
+    message: Option<Arc<str>>,
+}
+
+thread_local! {
+    static DISPATCHING: RefCell<Option<Dispatching>> = const { RefCell::new(None) };
+}
+
+/// Remember the message of the record while it is dispatched to the loggers,
+/// so that it is formatted at most once no matter how many loggers ask for it
+pub(crate) fn dispatching<T>(record: &Record, f: impl FnOnce() -> T) -> T {
+    let previous = DISPATCHING.with(|dispatching| {
+        dispatching.replace(Some(Dispatching {
+            identity: Identity::of(record),
+            message: None,
+        }))
+    });
+    let result = f();
+    DISPATCHING.with(|dispatching| dispatching.replace(previous));
+    result
+}
+
+/// A record together with its formatted message.
+///
+/// While the composite logger dispatches a record, the message is formatted
+/// by the first logger that asks for it and shared with all others,
+/// outside of the composite logger it is formatted every time.
+pub struct FormattedRecord<'a> {
+    record: &'a Record<'a>,
+    message: Arc<str>,
+}
+
+impl<'a> FormattedRecord<'a> {
+    pub fn new(record: &'a Record<'a>) -> Self {
+        let message = DISPATCHING
+            .with(|dispatching| {
+                let mut dispatching = dispatching.try_borrow_mut().ok()?;
+                let dispatching = dispatching.as_mut()?;
+                if dispatching.identity != Identity::of(record) {
+                    return None;
+                }
+                Some(
+                    dispatching
+                        .message
+                        .get_or_insert_with(|| Self::format(record))
+                        .clone(),
+                )
+            })
+            .unwrap_or_else(|| Self::format(record));
+
+        Self { record, message }
+    }
+
+    fn format(record: &Record) -> Arc<str> {
+        match record.args().as_str() {
+            Some(message) => Arc::from(message),
+            None => Arc::from(record.args().to_string()),
+        }
+    }
+
+    pub fn record(&self) -> &'a Record<'a> {
+        self.record
+    }
+
+    pub fn message(&self) -> &Arc<str> {
+        &self.message
+    }
+}
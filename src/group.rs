@@ -0,0 +1,44 @@
+use crate::dispatch::{AnyLog, Route, SinkEntry};
+use crate::LoggerId;
+use log::{LevelFilter, Log};
+
+/// Loggers that are switched on and off or re-leveled together at runtime,
+/// see [`crate::CompositeLogger::with_group`]
+#[derive(Default)]
+pub struct LoggerGroup {
+    pub(crate) loggers: Vec<SinkEntry>,
+}
+
+impl LoggerGroup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a logger to the group
+    pub fn with_logger(self, logger: impl Log + 'static) -> Self {
+        self.with_logger_at(LevelFilter::max(), logger)
+    }
+
+    /// Add a logger to the group, that only receives records at or above the given level
+    pub fn with_logger_at(mut self, level: LevelFilter, logger: impl Log + 'static) -> Self {
+        self.push(level, Box::new(logger));
+        self
+    }
+
+    /// Add a logger to the group that can also be referred to by its own name
+    pub fn with_named_logger(
+        mut self,
+        name: impl Into<String>,
+        logger: impl Log + 'static,
+    ) -> Self {
+        self.push(LevelFilter::max(), Box::new(logger)).name = Some(name.into());
+        self
+    }
+
+    /// The ids are assigned once the group is added to the composite logger
+    fn push(&mut self, level: LevelFilter, logger: Box<dyn AnyLog>) -> &mut SinkEntry {
+        self.loggers
+            .push(SinkEntry::new(LoggerId(0), level, Route::All, logger));
+        self.loggers.last_mut().expect("the logger was just added")
+    }
+}
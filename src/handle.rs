@@ -0,0 +1,1230 @@
+#[cfg(all(unix, feature = "sighup", any(feature = "toml", feature = "yaml")))]
+use crate::config::SinkRegistry;
+#[cfg(feature = "serde")]
+use crate::config::{ConfigError, LoggerConfig};
+use crate::dispatch::{self, Delivery, Dispatcher, Route, SinkEntry};
+use crate::early;
+use crate::lazy::LazyLogger;
+use crate::profile::{self, Profile};
+use crate::scoped;
+use crate::sink_error::{self, SinkError, SinkErrorKind};
+use crate::sinks::RotatingFileLogger;
+use crate::stats::Stats;
+use crate::worker::{Barrier, Worker};
+use crate::{
+    CompositeLogger, InitError, LoadShedding, LoggerHealth, OverflowPolicy, RoutingReport,
+    SinkHealth,
+};
+use arc_swap::{ArcSwap, Guard};
+use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+use std::backtrace::Backtrace;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::panic::{self, PanicHookInfo};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Identifies a logger delegated to by the composite logger
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LoggerId(pub(crate) u64);
+
+/// The installed composite logger whose loggers can be changed at runtime
+pub(crate) struct SharedLogger {
+    /// Replaced as a whole whenever it changes, so that logging never waits for changes
+    dispatcher: Arc<ArcSwap<Dispatcher>>,
+    /// Held while the dispatcher is changed, so that concurrent changes are not lost
+    changing: Mutex<()>,
+    next_id: AtomicU64,
+    worker: Option<Worker>,
+    /// Records dropped because the queue of the asynchronous mode was full
+    queue_dropped: AtomicU64,
+}
+
+/// The target of the records with the counters of the loggers
+const STATS_TARGET: &str = "composite_logger::stats";
+
+/// The composite logger installed as the global logger
+static INSTALLED: OnceLock<Arc<SharedLogger>> = OnceLock::new();
+
+/// The handle of the composite logger installed as the global logger
+pub(crate) fn installed() -> Option<CompositeLoggerHandle> {
+    INSTALLED.get().cloned().map(CompositeLoggerHandle::new)
+}
+
+/// Recompute the global maximum level from the loggers of the installed composite logger
+pub(crate) fn restore_max_level() {
+    if let Some(shared) = INSTALLED.get() {
+        shared.set_max_level(shared.read().max_level());
+    }
+}
+
+impl SharedLogger {
+    pub(crate) fn new(
+        dispatcher: Dispatcher,
+        async_queue: Option<(usize, OverflowPolicy, Option<LoadShedding>)>,
+    ) -> Self {
+        let next_id = AtomicU64::new(dispatcher.loggers.len() as u64);
+        let dispatcher = Arc::new(ArcSwap::from_pointee(dispatcher));
+        let worker = async_queue.map(|(capacity, overflow, shedding)| {
+            Worker::spawn(capacity, overflow, shedding, dispatcher.clone())
+        });
+
+        Self {
+            dispatcher,
+            changing: Mutex::new(()),
+            next_id,
+            worker,
+            queue_dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// Install as the global logger, or take over from the logger installed
+    /// by [`CompositeLogger::buffer_early`] and replay the records it kept
+    pub(crate) fn install(self: &Arc<Self>) -> Result<(), SetLoggerError> {
+        let early = early::take_over(self);
+        if early.is_none() {
+            log::set_boxed_logger(Box::new(self.clone()))?;
+        }
+        let _ = INSTALLED.set(self.clone());
+        self.set_max_level(self.read().max_level());
+
+        if let Some(early) = early {
+            early.replay(self);
+        }
+        Ok(())
+    }
+
+    fn is_installed(self: &Arc<Self>) -> bool {
+        INSTALLED
+            .get()
+            .is_some_and(|installed| Arc::ptr_eq(installed, self))
+    }
+
+    /// Only the global logger changes the global maximum level,
+    /// which stays `Trace` while records are delivered to scoped loggers
+    fn set_max_level(self: &Arc<Self>, max_level: LevelFilter) {
+        if self.is_installed() {
+            if scoped::is_active() {
+                log::set_max_level(LevelFilter::Trace);
+            } else {
+                log::set_max_level(max_level);
+            }
+        }
+    }
+
+    fn read(&self) -> Guard<Arc<Dispatcher>> {
+        self.dispatcher.load()
+    }
+
+    /// Change the loggers of a copy of the dispatcher which then replaces it,
+    /// and recompute the global maximum level
+    fn update<T>(self: &Arc<Self>, f: impl FnOnce(&mut Vec<Arc<SinkEntry>>) -> T) -> T {
+        self.change(|dispatcher| f(&mut dispatcher.loggers))
+    }
+
+    /// Change a copy of the dispatcher which then replaces it,
+    /// and recompute the global maximum level
+    fn change<T>(self: &Arc<Self>, f: impl FnOnce(&mut Dispatcher) -> T) -> T {
+        let _changing = self.changing.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut dispatcher = Dispatcher::clone(&self.read());
+        let result = f(&mut dispatcher);
+        dispatcher.invalidate();
+        let max_level = dispatcher.max_level();
+        self.dispatcher.store(Arc::new(dispatcher));
+        self.set_max_level(max_level);
+        result
+    }
+
+    /// Replace the whole dispatcher, so that loggers and filters change at once.
+    /// Every logger receives a new id, and the replaced loggers are flushed.
+    pub(crate) fn reload(self: &Arc<Self>, mut dispatcher: Dispatcher) {
+        for entry in &mut dispatcher.loggers {
+            Dispatcher::building(entry).id = LoggerId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        }
+
+        let replaced = {
+            let _changing = self.changing.lock().unwrap_or_else(PoisonError::into_inner);
+            let replaced = self.dispatcher.load_full();
+            dispatcher.succeed(&replaced);
+            let max_level = dispatcher.max_level();
+            self.dispatcher.store(Arc::new(dispatcher));
+            self.set_max_level(max_level);
+            replaced
+        };
+        replaced.flush();
+        self.event(Level::Info, format_args!("reloaded the loggers"), &[]);
+    }
+
+    /// Dispatch an event of the composite logger itself, see [`Dispatcher::event`]
+    fn event(&self, level: Level, message: std::fmt::Arguments, key_values: &[(&str, String)]) {
+        self.read().event(level, message, key_values);
+    }
+
+    /// Let the loggers receive records up to the level for the duration,
+    /// reverting from a background thread once it is over. A duration too long
+    /// to end at an instant lasts until the boost is ended.
+    pub(crate) fn boost(self: &Arc<Self>, level: LevelFilter, duration: Duration) {
+        let until = crate::time::instant().checked_add(duration);
+        self.change(|dispatcher| dispatcher.boost = Some((level, until)));
+        if until.is_none() {
+            return;
+        }
+
+        let shared = Arc::downgrade(self);
+        let revert = move || {
+            crate::time::sleep(duration);
+            if let Some(shared) = shared.upgrade() {
+                shared.change(|dispatcher| {
+                    // unless boosted again in the meantime
+                    if dispatcher
+                        .boost
+                        .is_some_and(|(_, boosted)| boosted == until)
+                    {
+                        dispatcher.boost = None;
+                    }
+                });
+            }
+        };
+        // the boost ends on time without the thread, only the global maximum level stays raised
+        let _ = thread::Builder::new()
+            .name("composite-logger-boost".to_string())
+            .spawn(revert);
+    }
+
+    /// Flush the loggers on a schedule from a background thread,
+    /// which stops once the logger is dropped
+    pub(crate) fn spawn_periodic_flush(self: &Arc<Self>, interval: Duration) {
+        let shared = Arc::downgrade(self);
+        thread::Builder::new()
+            .name("composite-logger-flush".to_string())
+            .spawn(move || Self::flush_periodically(shared, interval))
+            .expect("failed to spawn the composite logger flush thread");
+    }
+
+    fn flush_periodically(shared: Weak<Self>, interval: Duration) {
+        loop {
+            crate::time::sleep(interval);
+            match shared.upgrade() {
+                Some(shared) => shared.flush(),
+                None => return,
+            }
+        }
+    }
+
+    /// Log the counters of every logger on a schedule from a background thread,
+    /// which stops once the logger is dropped
+    pub(crate) fn spawn_periodic_stats(self: &Arc<Self>, interval: Duration) {
+        let shared = Arc::downgrade(self);
+        thread::Builder::new()
+            .name("composite-logger-stats".to_string())
+            .spawn(move || loop {
+                crate::time::sleep(interval);
+                match shared.upgrade() {
+                    Some(shared) => shared.log_stats(),
+                    None => return,
+                }
+            })
+            .expect("failed to spawn the composite logger stats thread");
+    }
+
+    pub(crate) fn stats(&self) -> Stats {
+        let dispatcher = self.read();
+        Stats {
+            loggers: dispatcher
+                .loggers
+                .iter()
+                .map(|logger| logger.stats())
+                .collect(),
+            fallback: dispatcher.fallback.as_ref().map(|logger| logger.stats()),
+            queue_dropped: self.queue_dropped.load(Ordering::Relaxed),
+            queue_len: self.worker.as_ref().map_or(0, Worker::len),
+            queue_bytes: self.worker.as_ref().map_or(0, Worker::bytes),
+            queue_shed: self.worker.as_ref().map_or(0, Worker::shed),
+        }
+    }
+
+    /// Deliver the startup record of the banner to every logger, if there is a banner
+    pub(crate) fn log_startup(&self) {
+        let dispatcher = self.read();
+        if let Some(banner) = &dispatcher.banner {
+            banner.log_startup(&dispatcher);
+        }
+    }
+
+    fn log_stats(&self) {
+        let stats = self.stats();
+        for logger in stats.loggers.iter().chain(stats.fallback.iter()) {
+            let mut key_values = vec![("logger".to_string(), format!("{}", logger.id.0))];
+            if let Some(name) = &logger.name {
+                key_values.push(("name".to_string(), name.clone()));
+            }
+            key_values.extend([
+                ("delivered".to_string(), logger.delivered.to_string()),
+                ("filtered".to_string(), logger.filtered.to_string()),
+                ("dropped".to_string(), logger.dropped.to_string()),
+                ("errored".to_string(), logger.errored.to_string()),
+            ]);
+            self.log(
+                &Record::builder()
+                    .args(format_args!("logger stats"))
+                    .level(Level::Info)
+                    .target(STATS_TARGET)
+                    .key_values(&key_values)
+                    .build(),
+            );
+        }
+    }
+
+    /// Log panics at the `Error` level with their backtrace and flush the loggers,
+    /// before the previously registered panic hook prints the panic as usual.
+    /// Panics while the thread is dispatching, such as panics of the loggers, are not logged,
+    /// since the panicking logger may still hold its locks.
+    pub(crate) fn install_panic_hook(self: &Arc<Self>) {
+        let shared = Arc::downgrade(self);
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if let Some(shared) = shared.upgrade().filter(|_| !dispatch::is_dispatching()) {
+                Self::log_panic(&shared, info);
+            }
+            previous(info);
+        }));
+    }
+
+    fn log_panic(shared: &Arc<Self>, info: &PanicHookInfo) {
+        let message = sink_error::panic_message(info.payload());
+        let thread = thread::current();
+        let thread = thread.name().unwrap_or("<unnamed>");
+        let backtrace = Backtrace::force_capture();
+
+        let record_args = format_args!("thread '{}' panicked: {}\n{}", thread, message, backtrace);
+        let mut record = Record::builder();
+        record.args(record_args).level(Level::Error).target("panic");
+        if let Some(location) = info.location() {
+            record
+                .file(Some(location.file()))
+                .line(Some(location.line()));
+        }
+        shared.log(&record.build());
+        shared.flush();
+    }
+
+    /// The worker to dispatch records to, unless called from the worker itself
+    fn worker(&self) -> Option<&Worker> {
+        self.worker.as_ref().filter(|_| !Worker::is_worker_thread())
+    }
+}
+
+impl Log for SharedLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        scoped::enabled(metadata).unwrap_or_else(|| self.read().enabled(metadata))
+    }
+
+    fn log(&self, record: &Record) {
+        if scoped::log(record) {
+            return;
+        }
+        self.read().explain_if_asked(record);
+
+        match self.worker() {
+            Some(worker) => {
+                let dispatcher = self.read();
+                if let Some(enabled) = dispatcher.enabled_set(record.metadata()) {
+                    let level = record.level();
+                    dispatcher.process(record, &|record| {
+                        // the loggers enabled for an escalated record are not known yet
+                        let enabled = (record.level() == level).then_some(enabled);
+                        if dispatcher.is_priority(record) {
+                            dispatcher.dispatch_with(record, None, Delivery::Priority);
+                        }
+                        if let Some((dropped, enabled)) = worker.log(record.into(), enabled) {
+                            self.queue_dropped.fetch_add(1, Ordering::Relaxed);
+                            if let Some(enabled) = enabled {
+                                enabled.dropped(&dispatcher);
+                            }
+                            let error = SinkError::new(
+                                SinkErrorKind::Overflow,
+                                "the queue of the asynchronous mode is full",
+                            );
+                            dropped.with_record(|dropped| dispatcher.report(error, Some(dropped)));
+                        }
+                    });
+                }
+            }
+            None => self.read().log(record),
+        }
+    }
+
+    fn flush(&self) {
+        let start = Instant::now();
+        match self.worker() {
+            Some(worker) => worker.flush(),
+            None => self.read().flush(),
+        }
+        self.event(
+            Level::Debug,
+            format_args!("flushed the loggers"),
+            &[("duration_us", start.elapsed().as_micros().to_string())],
+        );
+    }
+}
+
+/// Allows to change the loggers of an installed composite logger at runtime.
+/// The global maximum level is recomputed and cached decisions are forgotten
+/// whenever the loggers change.
+///
+/// The handle is cheap to clone, and all clones log to the same loggers, so it can be
+/// installed globally and also passed to code that expects a logger of its own.
+#[derive(Clone)]
+pub struct CompositeLoggerHandle {
+    shared: Arc<SharedLogger>,
+}
+
+impl CompositeLoggerHandle {
+    pub(crate) fn new(shared: Arc<SharedLogger>) -> Self {
+        Self { shared }
+    }
+
+    /// The handle of the composite logger installed as the global logger, if any,
+    /// for code that was not passed the handle returned when initializing
+    pub fn installed() -> Option<Self> {
+        installed()
+    }
+
+    /// The composite logger as a shared logger, for code expecting any logger
+    pub fn into_arc(self) -> Arc<dyn Log> {
+        Arc::new(self)
+    }
+
+    /// Whether the composite logger is the global logger, it is not once
+    /// [`CompositeLogger::init_wrapping`] or [`CompositeLogger::init_or_compose`]
+    /// found another global logger
+    pub fn is_installed(&self) -> bool {
+        self.shared.is_installed()
+    }
+
+    /// Flush all loggers, waiting for the queued records to be dispatched in the asynchronous mode
+    pub fn flush(&self) {
+        self.shared.flush();
+    }
+
+    /// Deliver the shutdown record of the banner to every logger after the queued records,
+    /// if there is a banner, see [`crate::CompositeLogger::with_banner`].
+    /// The [`FlushGuard`] does when dropped.
+    pub fn log_shutdown(&self) {
+        let dispatcher = self.shared.read();
+        if let Some(banner) = &dispatcher.banner {
+            self.shared.flush();
+            banner.log_shutdown(&dispatcher);
+        }
+    }
+
+    /// Flush all loggers like [`CompositeLoggerHandle::flush`], but give up waiting after the
+    /// timeout, reporting whether the queued records were dispatched and which loggers did not
+    /// finish flushing in time. Every logger is flushed on a thread of its own, which keeps
+    /// running if the logger does not finish in time.
+    pub fn flush_timeout(&self, timeout: Duration) -> FlushResult {
+        let deadline = Instant::now() + timeout;
+        let drained = self
+            .shared
+            .worker()
+            .is_none_or(|worker| worker.drain(timeout));
+        self.shared.read().flush_until(deadline, drained)
+    }
+
+    /// A barrier reached once every logger received the records logged before, for example
+    /// so that a test knows all loggers caught up before it asserts on their outputs:
+    ///
+    /// ```text
+    /// handle.barrier().wait();
+    /// // or from async code
+    /// handle.barrier().await;
+    /// ```
+    ///
+    /// Unlike [`CompositeLoggerHandle::flush`] the loggers are not flushed, so records
+    /// buffered by the loggers themselves are not written yet, and loggers with queues
+    /// of their own such as the [`crate::AsyncLogAdapter`] may not have processed them yet.
+    /// Without the asynchronous mode the barrier is reached right away, since the loggers
+    /// receive the records as they are logged.
+    pub fn barrier(&self) -> Barrier {
+        self.shared
+            .worker()
+            .map_or_else(Barrier::reached, Worker::barrier)
+    }
+
+    /// The most verbose level accepted by any of the installed loggers
+    pub fn max_level(&self) -> LevelFilter {
+        self.shared.read().max_level()
+    }
+
+    /// Atomically replace the loggers, filters and key-values by those of the builder,
+    /// flushing the replaced loggers. Records logged meanwhile are received either
+    /// by the replaced or the new loggers. The active profile, overrides, timestamps
+    /// and boost of the builder are applied, while the asynchronous mode, the flush interval
+    /// and the panic hook are kept as initialized. The new loggers receive new ids.
+    ///
+    /// # Errors
+    ///
+    /// Fails like [`CompositeLogger::try_init`] if no logger was added or if filter directives,
+    /// overrides or the profile are invalid, in which case the loggers are not changed.
+    pub fn reload(&self, logger: CompositeLogger) -> Result<(), InitError> {
+        logger.validate()?;
+        logger.reload_into(&self.shared);
+        Ok(())
+    }
+
+    /// Flush and remove all loggers, filters and key-values, so that records are ignored
+    /// until the loggers are replaced again with [`CompositeLoggerHandle::reload`].
+    /// The global logger can be set only once, so this is how it is torn down.
+    pub fn shutdown(&self) {
+        self.flush();
+        self.shared.reload(Dispatcher::default());
+    }
+
+    /// Atomically replace the loggers by those described by the configuration
+    /// with the built-in sink kinds, see [`CompositeLoggerHandle::reload`].
+    /// Use [`crate::config::SinkRegistry::build`] for custom sink kinds.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the composite logger can not be built from the configuration,
+    /// in which case the loggers are not changed.
+    #[cfg(feature = "serde")]
+    pub fn reload_config(&self, config: &LoggerConfig) -> Result<(), ConfigError> {
+        self.reload(config.build()?).map_err(ConfigError::Init)
+    }
+
+    /// Re-read the configuration file and reload the loggers whenever the process receives
+    /// `SIGHUP`, as expected from daemons. Failures to reload are logged as errors
+    /// and keep the current loggers.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the signal handler can not be registered.
+    #[cfg(all(unix, feature = "sighup", any(feature = "toml", feature = "yaml")))]
+    pub fn reload_on_sighup(
+        &self,
+        path: impl Into<std::path::PathBuf>,
+        registry: SinkRegistry,
+    ) -> std::io::Result<()> {
+        let path = path.into();
+        let handle = self.clone();
+        let mut signals = signal_hook::iterator::Signals::new([signal_hook::consts::SIGHUP])?;
+
+        thread::Builder::new()
+            .name("composite-logger-reload".to_string())
+            .spawn(move || {
+                for _ in signals.forever() {
+                    let reloaded = LoggerConfig::from_file(&path)
+                        .and_then(|config| registry.build(&config))
+                        .and_then(|logger| handle.reload(logger).map_err(ConfigError::Init));
+                    if let Err(error) = reloaded {
+                        log::error!(
+                            target: module_path!(),
+                            "failed to reload {}: {}",
+                            path.display(),
+                            error
+                        );
+                    }
+                }
+            })?;
+        Ok(())
+    }
+
+    /// Reopen every [`RotatingFileLogger`] at its path, so that records go to a new file
+    /// once an external tool such as logrotate renamed the current one.
+    /// File loggers wrapped in other loggers are not reopened.
+    ///
+    /// # Errors
+    ///
+    /// Fails with the error of the first file that could not be reopened,
+    /// the other files are reopened anyway.
+    pub fn reopen_files(&self) -> std::io::Result<()> {
+        let reopened = self
+            .shared
+            .read()
+            .loggers
+            .iter()
+            .filter_map(|entry| entry.logger().downcast_ref::<RotatingFileLogger>())
+            .map(RotatingFileLogger::reopen)
+            .collect::<Vec<_>>();
+        reopened.into_iter().collect()
+    }
+
+    /// Reopen the file loggers whenever the process receives `SIGUSR1`, the signal
+    /// logrotate is usually configured to send, see [`CompositeLoggerHandle::reopen_files`].
+    /// Failures to reopen are logged as errors.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the signal handler can not be registered.
+    #[cfg(all(unix, feature = "sigusr1"))]
+    pub fn reopen_on_sigusr1(&self) -> std::io::Result<()> {
+        let handle = self.clone();
+        let mut signals = signal_hook::iterator::Signals::new([signal_hook::consts::SIGUSR1])?;
+
+        thread::Builder::new()
+            .name("composite-logger-reopen".to_string())
+            .spawn(move || {
+                for _ in signals.forever() {
+                    if let Err(error) = handle.reopen_files() {
+                        log::error!(target: module_path!(), "failed to reopen a log file: {}", error);
+                    }
+                }
+            })?;
+        Ok(())
+    }
+
+    /// Flush all loggers and dispatch the queued records, waiting up to the timeout,
+    /// before the process terminates on `SIGINT` or `SIGTERM`, or on Windows on the console
+    /// control events such as Ctrl+C, so that short-lived programs do not lose their last
+    /// records. The process then terminates as it would have without the handler,
+    /// so this is meant for programs that do not handle these signals themselves.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the signal handlers can not be registered.
+    #[cfg(feature = "shutdown")]
+    pub fn flush_on_shutdown_signals(&self, timeout: Duration) -> std::io::Result<()> {
+        crate::shutdown::install(self.clone(), timeout)
+    }
+
+    /// Answer the commands of the [`crate::admin`] protocol on a Unix domain socket
+    /// at the path from a background thread, replacing an existing socket at the path.
+    /// The socket is only accessible to the user running the process: it is created
+    /// in a directory only the user can enter and moved to the path once restricted.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`std::io::ErrorKind::AlreadyExists`] if something other than a socket
+    /// exists at the path, and fails if the socket can not be created or the thread
+    /// can not be spawned.
+    #[cfg(all(unix, feature = "admin"))]
+    pub fn serve_admin(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        use std::os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt};
+
+        let path = path.as_ref();
+        let Some(name) = path.file_name() else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "the path of the admin socket has no file name",
+            ));
+        };
+        match std::fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(path)?,
+            Ok(_) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::AlreadyExists,
+                    format!("{} exists and is not a socket", path.display()),
+                ));
+            }
+            Err(_) => {}
+        }
+
+        // other users can not connect to the socket before its permissions are restricted
+        let directory = path.with_file_name(format!(
+            ".{}.{}",
+            name.to_string_lossy(),
+            std::process::id()
+        ));
+        std::fs::DirBuilder::new().mode(0o700).create(&directory)?;
+        let staged = directory.join("socket");
+        let listener = std::os::unix::net::UnixListener::bind(&staged).and_then(|listener| {
+            std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o600))?;
+            std::fs::rename(&staged, path)?;
+            Ok(listener)
+        });
+        let _ = std::fs::remove_file(&staged);
+        let _ = std::fs::remove_dir(&directory);
+        let listener = listener?;
+
+        let handle = self.clone();
+        thread::Builder::new()
+            .name("composite-logger-admin".to_string())
+            .spawn(move || crate::admin::serve(handle, listener))?;
+        Ok(())
+    }
+
+    /// Ids of the currently installed loggers in the order they receive records
+    pub fn logger_ids(&self) -> Vec<LoggerId> {
+        self.shared
+            .read()
+            .loggers
+            .iter()
+            .map(|logger| logger.id)
+            .collect()
+    }
+
+    /// Names of the currently installed named loggers in the order they receive records
+    pub fn logger_names(&self) -> Vec<String> {
+        self.shared
+            .read()
+            .loggers
+            .iter()
+            .filter_map(|logger| logger.name.clone())
+            .collect()
+    }
+
+    /// The named logger as its concrete type, for example to read what a [`crate::sinks::RingBufferLogger`]
+    /// kept. Returns `None` if there is no logger with the given name or it has another type.
+    /// Loggers added boxed or shared can only be accessed as `Box<dyn Log>` or `Arc<dyn Log>`,
+    /// and loggers wrapped by the composite logger, for example in a filter, not at all.
+    pub fn get<T: Log + 'static>(&self, name: &str) -> Option<LoggerRef<T>> {
+        self.find(|entry| entry.name.as_deref() == Some(name))
+    }
+
+    /// The logger with the given id as its concrete type, see [`CompositeLoggerHandle::get`]
+    pub fn get_by_id<T: Log + 'static>(&self, id: LoggerId) -> Option<LoggerRef<T>> {
+        self.find(|entry| entry.id == id)
+    }
+
+    fn find<T: Log + 'static>(&self, f: impl Fn(&SinkEntry) -> bool) -> Option<LoggerRef<T>> {
+        self.shared
+            .read()
+            .loggers
+            .iter()
+            .find(|entry| f(entry))
+            .filter(|entry| entry.logger().is::<T>())
+            .map(|entry| LoggerRef {
+                entry: entry.clone(),
+                logger: PhantomData,
+            })
+    }
+
+    /// Whether the named logger receives records, it does not once switched off
+    /// with [`CompositeLoggerHandle::set_enabled`] or disabled after panicking too often.
+    /// Returns `None` if there is no logger with the given name.
+    pub fn is_enabled(&self, name: &str) -> Option<bool> {
+        self.shared
+            .read()
+            .loggers
+            .iter()
+            .find(|logger| logger.name.as_deref() == Some(name))
+            .map(|logger| logger.is_active())
+    }
+
+    /// Switch the named logger off or back on, recomputing the global maximum level.
+    /// If several loggers have the same name, the first one is changed.
+    /// Returns `false` if there is no logger with the given name.
+    pub fn set_enabled(&self, name: &str, enabled: bool) -> bool {
+        self.shared.update(|loggers| {
+            loggers
+                .iter_mut()
+                .find(|entry| entry.name.as_deref() == Some(name))
+                .map(|entry| entry.set_paused(!enabled))
+                .is_some()
+        })
+    }
+
+    /// Build the named lazy logger right away instead of when it receives its first record,
+    /// see [`CompositeLogger::with_lazy_logger`].
+    /// Returns `false` if there is no lazy logger with the given name.
+    pub fn activate(&self, name: &str) -> bool {
+        self.shared
+            .read()
+            .loggers
+            .iter()
+            .filter(|entry| entry.name.as_deref() == Some(name))
+            .find_map(|entry| entry.logger().downcast_ref::<LazyLogger>())
+            .map(LazyLogger::activate)
+            .is_some()
+    }
+
+    /// The level of the named logger, or `None` if there is no logger with the given name
+    pub fn level(&self, name: &str) -> Option<LevelFilter> {
+        self.shared
+            .read()
+            .loggers
+            .iter()
+            .find(|logger| logger.name.as_deref() == Some(name))
+            .map(|logger| logger.level())
+    }
+
+    /// Change the level of the named logger, recomputing the global maximum level,
+    /// for example to temporarily receive `Trace` records in one of the loggers.
+    /// Returns `false` if there is no logger with the given name.
+    pub fn set_level(&self, name: &str, level: LevelFilter) -> bool {
+        self.shared.update(|loggers| {
+            loggers
+                .iter_mut()
+                .find(|entry| entry.name.as_deref() == Some(name))
+                .map(|entry| entry.set_level(level))
+                .is_some()
+        })
+    }
+
+    /// Switch all loggers of the group off or back on, recomputing the global maximum level.
+    /// Returns `false` if there is no group with the given name.
+    pub fn set_group_enabled(&self, group: &str, enabled: bool) -> bool {
+        self.shared.update(|loggers| {
+            loggers
+                .iter_mut()
+                .filter(|entry| entry.group.as_deref() == Some(group))
+                .fold(false, |_, entry| {
+                    entry.set_paused(!enabled);
+                    true
+                })
+        })
+    }
+
+    /// Change the level of all loggers of the group, recomputing the global maximum level.
+    /// Returns `false` if there is no group with the given name.
+    pub fn set_group_level(&self, group: &str, level: LevelFilter) -> bool {
+        self.shared.update(|loggers| {
+            loggers
+                .iter_mut()
+                .filter(|entry| entry.group.as_deref() == Some(group))
+                .fold(false, |_, entry| {
+                    entry.set_level(level);
+                    true
+                })
+        })
+    }
+
+    /// Let records of the target and its submodules through up to the level before they
+    /// are dispatched, taking precedence over the maximum level and the filter of the
+    /// composite logger, for example to silence a noisy module for all loggers at once.
+    /// The loggers still only receive records at or above their own levels.
+    /// The most specific target applies, and the levels last until a reload.
+    pub fn set_target_level(&self, target: &str, level: LevelFilter) {
+        self.shared
+            .change(|dispatcher| dispatcher.set_target_level(target, level));
+    }
+
+    /// Remove the level of the target set with [`CompositeLoggerHandle::set_target_level`].
+    /// Returns `false` if no level was set for the target.
+    pub fn clear_target_level(&self, target: &str) -> bool {
+        self.shared
+            .change(|dispatcher| dispatcher.target_levels.remove(target).is_some())
+    }
+
+    /// Apply the named profile to the whole composite logger at once, see [`crate::Profile`].
+    /// Returns `false` if the profile is neither registered nor built-in.
+    pub fn apply_profile(&self, name: &str) -> bool {
+        self.shared
+            .change(|dispatcher| match profile::find(dispatcher, name) {
+                Some(profile) => {
+                    profile.apply(dispatcher);
+                    true
+                }
+                None => false,
+            })
+    }
+
+    /// Apply the profile for the verbose and quiet flags of a command line tool,
+    /// see [`crate::Profile::name_for_verbosity`]
+    pub fn apply_verbosity(&self, verbose: u8, quiet: u8) -> bool {
+        self.apply_profile(Profile::name_for_verbosity(verbose, quiet))
+    }
+
+    /// The levels set with [`CompositeLoggerHandle::set_target_level`],
+    /// parents before their submodules
+    pub fn target_levels(&self) -> Vec<(String, LevelFilter)> {
+        self.shared
+            .read()
+            .target_levels
+            .iter()
+            .map(|(target, level)| (target, *level))
+            .collect()
+    }
+
+    /// Which loggers would receive a record of the level and target and why the others
+    /// would not, asking the loggers without delivering anything, so that a missing line
+    /// can be traced to the level, route or filter that dropped it:
+    ///
+    /// ```text
+    /// let metadata = Metadata::builder().level(Level::Debug).target("my_app::net").build();
+    /// eprintln!("{}", handle.explain(&metadata));
+    /// ```
+    ///
+    /// Deduplication and escalations are not taken into account.
+    pub fn explain(&self, metadata: &Metadata) -> RoutingReport {
+        self.shared.read().explain(metadata, None)
+    }
+
+    /// Like [`CompositeLoggerHandle::explain`], also checking the suppressed patterns
+    /// against the message of the record
+    pub fn explain_record(&self, record: &Record) -> RoutingReport {
+        let message = record.args().to_string();
+        self.shared
+            .read()
+            .explain(record.metadata(), Some(&message))
+    }
+
+    /// Ids of the installed loggers of the group in the order they receive records
+    pub fn group_logger_ids(&self, group: &str) -> Vec<LoggerId> {
+        self.shared
+            .read()
+            .loggers
+            .iter()
+            .filter(|logger| logger.group.as_deref() == Some(group))
+            .map(|logger| logger.id)
+            .collect()
+    }
+
+    /// The counters of the installed loggers since they were added,
+    /// the counters of replaced loggers are lost
+    pub fn stats(&self) -> Stats {
+        self.shared.stats()
+    }
+
+    /// Whether each of the installed loggers delivers the records it receives,
+    /// in the order they receive records, for example for a readiness probe.
+    /// All loggers are degraded while the queue of the asynchronous mode is nearly full.
+    pub fn health(&self) -> Vec<LoggerHealth> {
+        let saturated = self
+            .shared
+            .worker
+            .as_ref()
+            .is_some_and(Worker::is_saturated);
+        self.shared
+            .read()
+            .loggers
+            .iter()
+            .map(|entry| LoggerHealth {
+                id: entry.id,
+                name: entry.name.clone(),
+                health: match entry.health() {
+                    SinkHealth::Healthy if saturated => SinkHealth::Degraded(
+                        "the queue of the asynchronous mode is nearly full".to_string(),
+                    ),
+                    health => health,
+                },
+            })
+            .collect()
+    }
+
+    /// Whether all installed loggers are healthy, see [`CompositeLoggerHandle::health`]
+    pub fn is_healthy(&self) -> bool {
+        self.health()
+            .iter()
+            .all(|logger| logger.health.is_healthy())
+    }
+
+    /// How many times the logger panicked, or `None` if there is no logger with the given id
+    pub fn logger_panics(&self, id: LoggerId) -> Option<u64> {
+        self.shared
+            .read()
+            .loggers
+            .iter()
+            .find(|logger| logger.id == id)
+            .map(|logger| logger.panics.load(Ordering::Relaxed))
+    }
+
+    /// Add a logger to delegate the logs to
+    pub fn add_logger(&self, logger: impl Log + 'static) -> LoggerId {
+        self.add_logger_at(LevelFilter::max(), logger)
+    }
+
+    /// Add a logger to delegate the logs to, that only receives records
+    /// at or above the given level
+    pub fn add_logger_at(&self, level: LevelFilter, logger: impl Log + 'static) -> LoggerId {
+        let id = LoggerId(self.shared.next_id.fetch_add(1, Ordering::Relaxed));
+        self.shared.update(|loggers| {
+            loggers.push(Arc::new(SinkEntry::new(
+                id,
+                level,
+                Route::All,
+                Box::new(logger),
+            )))
+        });
+        self.logger_event("added", id, None);
+        id
+    }
+
+    /// Add a logger to delegate the logs to, that can be referred to by name
+    pub fn add_named_logger(
+        &self,
+        name: impl Into<String>,
+        logger: impl Log + 'static,
+    ) -> LoggerId {
+        let id = LoggerId(self.shared.next_id.fetch_add(1, Ordering::Relaxed));
+        let mut entry = SinkEntry::new(id, LevelFilter::max(), Route::All, Box::new(logger));
+        entry.name = Some(name.into());
+        let name = entry.name.clone();
+        self.shared.update(|loggers| loggers.push(Arc::new(entry)));
+        self.logger_event("added", id, name.as_deref());
+        id
+    }
+
+    /// Remove a logger, flushing it first.
+    /// Returns `false` if there is no logger with the given id.
+    pub fn remove_logger(&self, id: LoggerId) -> bool {
+        let removed = self.shared.update(|loggers| {
+            loggers
+                .iter()
+                .position(|logger| logger.id == id)
+                .map(|index| loggers.remove(index))
+        });
+
+        let Some(removed) = removed else {
+            return false;
+        };
+        self.shared.read().flush_removed(&removed);
+        self.logger_event("removed", id, removed.name.as_deref());
+        true
+    }
+
+    /// Replace a logger keeping its id, level and route, flushing the replaced logger.
+    /// Returns `false` if there is no logger with the given id.
+    pub fn replace_logger(&self, id: LoggerId, logger: impl Log + 'static) -> bool {
+        let replaced = self.shared.update(|loggers| {
+            loggers
+                .iter_mut()
+                .find(|entry| entry.id == id)
+                .map(|entry| {
+                    let replacement = entry.replaced_by(Box::new(logger));
+                    std::mem::replace(entry, Arc::new(replacement))
+                })
+        });
+
+        let Some(replaced) = replaced else {
+            return false;
+        };
+        self.shared.read().flush_removed(&replaced);
+        self.logger_event("replaced", id, replaced.name.as_deref());
+        true
+    }
+
+    fn logger_event(&self, change: &str, id: LoggerId, name: Option<&str>) {
+        let mut key_values = vec![("logger", id.0.to_string())];
+        if let Some(name) = name {
+            key_values.push(("name", name.to_string()));
+        }
+        self.shared.event(
+            Level::Info,
+            format_args!("{} a logger", change),
+            &key_values,
+        );
+    }
+
+    /// Let every logger receive records up to the level for the duration, whatever
+    /// its own level and the maximum level of the composite logger, for example to debug
+    /// an incident. Filters still apply. The boost reverts on its own, replaces a boost
+    /// still in effect and ends early with [`CompositeLoggerHandle::end_boost`] or a reload.
+    /// A duration too long to end at an instant, such as [`Duration::MAX`], lasts until then.
+    pub fn boost_level_for(&self, level: LevelFilter, duration: Duration) {
+        self.shared.boost(level, duration);
+    }
+
+    /// End the boost of [`CompositeLoggerHandle::boost_level_for`] early
+    pub fn end_boost(&self) {
+        self.shared.change(|dispatcher| dispatcher.boost = None);
+    }
+
+    /// Change the level of a logger, recomputing the global maximum level.
+    /// Returns `false` if there is no logger with the given id.
+    pub fn set_logger_level(&self, id: LoggerId, level: LevelFilter) -> bool {
+        self.shared.update(|loggers| {
+            loggers
+                .iter_mut()
+                .find(|entry| entry.id == id)
+                .map(|entry| entry.set_level(level))
+                .is_some()
+        })
+    }
+}
+
+/// Logging through the handle reaches the loggers even if the composite logger
+/// is not the global logger
+impl Log for CompositeLoggerHandle {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.shared.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        self.shared.log(record)
+    }
+
+    fn flush(&self) {
+        self.shared.flush()
+    }
+}
+
+/// What [`CompositeLoggerHandle::flush_timeout`] managed to flush in time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlushResult {
+    /// Whether the records queued in the asynchronous mode were dispatched in time,
+    /// always `true` otherwise
+    pub drained: bool,
+    /// The loggers that did not finish flushing in time, with their names
+    pub timed_out: Vec<(LoggerId, Option<String>)>,
+    /// The loggers that panicked while flushing, with their names
+    pub failed: Vec<(LoggerId, Option<String>)>,
+}
+
+impl FlushResult {
+    /// Whether all records were dispatched and all loggers flushed in time
+    pub fn is_complete(&self) -> bool {
+        self.drained && self.timed_out.is_empty() && self.failed.is_empty()
+    }
+}
+
+/// Flushes all loggers when dropped, so that buffered records are not lost when the program exits.
+/// In the asynchronous mode the queued records are dispatched first.
+/// The shutdown record of the banner is logged before, see [`crate::CompositeLogger::with_banner`].
+#[must_use = "the loggers are flushed as soon as the guard is dropped"]
+pub struct FlushGuard {
+    handle: CompositeLoggerHandle,
+}
+
+impl FlushGuard {
+    pub(crate) fn new(handle: CompositeLoggerHandle) -> Self {
+        Self { handle }
+    }
+
+    pub fn handle(&self) -> &CompositeLoggerHandle {
+        &self.handle
+    }
+}
+
+impl Drop for FlushGuard {
+    fn drop(&mut self) {
+        self.handle.log_shutdown();
+        self.handle.flush();
+    }
+}
+
+/// A logger of the composite logger accessed as its concrete type,
+/// see [`CompositeLoggerHandle::get`]. It stays alive while referenced
+/// even if it is removed or replaced in the meantime.
+pub struct LoggerRef<T> {
+    entry: Arc<SinkEntry>,
+    logger: PhantomData<T>,
+}
+
+impl<T: Log + 'static> Deref for LoggerRef<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.entry
+            .logger()
+            .downcast_ref()
+            .expect("the type of the logger was checked")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, CaptureLogger};
+    use crate::OwnedRecord;
+
+    fn handle(capture: &Arc<CaptureLogger>) -> CompositeLoggerHandle {
+        let handle = CompositeLogger::new()
+            .with_named_logger("capture", capture.clone())
+            .build();
+        handle.set_level("capture", LevelFilter::Info);
+        handle
+    }
+
+    fn log(logger: &impl Log, level: Level, message: &str) {
+        OwnedRecord::new(level, "app", message).with_record(|record| logger.log(record));
+    }
+
+    /// Counts how often it is flushed
+    #[derive(Default)]
+    struct Flushes(AtomicU64);
+
+    impl Log for Flushes {
+        fn enabled(&self, _metadata: &Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, _record: &Record) {}
+
+        fn flush(&self) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn boosts_the_level_for_a_while() {
+        testing::with_mock_clock(|clock| {
+            let capture = Arc::new(CaptureLogger::new());
+            let handle = handle(&capture);
+
+            handle.boost_level_for(LevelFilter::Debug, Duration::from_secs(10));
+            log(&handle, Level::Debug, "boosted");
+            log(&handle, Level::Trace, "too verbose");
+            clock.advance(Duration::from_secs(10));
+            log(&handle, Level::Debug, "over");
+
+            capture.assert_logged(Level::Debug, testing::equals("boosted"));
+            capture.assert_not_logged(Level::Trace, testing::equals("too verbose"));
+            capture.assert_not_logged(Level::Debug, testing::equals("over"));
+        });
+    }
+
+    #[test]
+    fn boosts_the_level_until_ended_for_a_duration_without_an_end() {
+        let capture = Arc::new(CaptureLogger::new());
+        let handle = handle(&capture);
+
+        handle.boost_level_for(LevelFilter::Debug, Duration::MAX);
+        log(&handle, Level::Debug, "boosted");
+        handle.end_boost();
+        log(&handle, Level::Debug, "ended");
+
+        capture.assert_logged(Level::Debug, testing::equals("boosted"));
+        capture.assert_not_logged(Level::Debug, testing::equals("ended"));
+    }
+
+    #[test]
+    fn reloads_the_loggers() {
+        let replaced = Arc::new(CaptureLogger::new());
+        let handle = handle(&replaced);
+        log(&handle, Level::Info, "before");
+
+        let reloaded = Arc::new(CaptureLogger::new());
+        handle
+            .reload(CompositeLogger::new().with_named_logger("reloaded", reloaded.clone()))
+            .unwrap();
+        log(&handle, Level::Info, "after");
+
+        replaced.assert_logged(Level::Info, testing::equals("before"));
+        replaced.assert_not_logged(Level::Info, testing::equals("after"));
+        reloaded.assert_logged(Level::Info, testing::equals("after"));
+        assert_eq!(handle.logger_names(), ["reloaded"]);
+    }
+
+    #[test]
+    fn keeps_the_loggers_when_the_reload_is_invalid() {
+        let capture = Arc::new(CaptureLogger::new());
+        let handle = handle(&capture);
+
+        let error = handle.reload(CompositeLogger::new()).unwrap_err();
+        log(&handle, Level::Info, "kept");
+
+        assert!(matches!(error, InitError::NoLoggers));
+        capture.assert_logged(Level::Info, testing::equals("kept"));
+    }
+
+    #[test]
+    fn flushes_the_loggers_once_the_guard_is_dropped() {
+        let flushes = Arc::new(Flushes::default());
+        let guard = FlushGuard::new(CompositeLogger::new().with_logger(flushes.clone()).build());
+        assert_eq!(flushes.0.load(Ordering::Relaxed), 0);
+
+        drop(guard);
+        assert_eq!(flushes.0.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn ignores_the_records_after_a_shutdown() {
+        let flushes = Arc::new(Flushes::default());
+        let capture = Arc::new(CaptureLogger::new());
+        let handle = CompositeLogger::new()
+            .with_logger(flushes.clone())
+            .with_logger(capture.clone())
+            .build();
+
+        handle.shutdown();
+        log(&handle, Level::Error, "ignored");
+
+        assert!(flushes.0.load(Ordering::Relaxed) > 0);
+        assert!(handle.logger_ids().is_empty());
+        capture.assert_not_logged(Level::Error, testing::equals("ignored"));
+    }
+}
@@ -0,0 +1,65 @@
+use crate::sinks::{NetworkLogger, RotatingFileLogger};
+use crate::LoggerId;
+use std::any::Any;
+use std::fmt::{Display, Formatter};
+
+/// Whether a logger delivers the records it receives,
+/// see [`crate::CompositeLoggerHandle::health`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SinkHealth {
+    Healthy,
+    /// Records are delayed or may be lost, for example while a network logger is disconnected
+    Degraded(String),
+    /// The logger receives no records
+    Disabled(String),
+}
+
+impl SinkHealth {
+    pub fn is_healthy(&self) -> bool {
+        *self == SinkHealth::Healthy
+    }
+}
+
+impl Display for SinkHealth {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SinkHealth::Healthy => write!(f, "healthy"),
+            SinkHealth::Degraded(reason) => write!(f, "degraded: {}", reason),
+            SinkHealth::Disabled(reason) => write!(f, "disabled: {}", reason),
+        }
+    }
+}
+
+/// Loggers that know whether they deliver their records, such as the network logger
+/// while connected and the file logger while writing succeeds.
+/// The loggers of this crate are checked on their own, other loggers are checked
+/// when added with [`crate::CompositeLogger::with_health_checked_logger`].
+pub trait HealthCheck {
+    fn health(&self) -> SinkHealth;
+}
+
+/// The health of one of the loggers of the composite logger
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoggerHealth {
+    pub id: LoggerId,
+    pub name: Option<String>,
+    pub health: SinkHealth,
+}
+
+/// Checks the health of a logger of a known type
+pub(crate) type Checker = fn(&dyn Any) -> Option<SinkHealth>;
+
+/// Check the logger of type `T`, see [`HealthCheck`]
+pub(crate) fn check<T: HealthCheck + 'static>(logger: &dyn Any) -> Option<SinkHealth> {
+    logger.downcast_ref::<T>().map(T::health)
+}
+
+/// The health of the logger if it is one of the loggers of this crate that check it
+pub(crate) fn check_builtin(logger: &dyn Any) -> Option<SinkHealth> {
+    let health = check::<NetworkLogger>(logger).or_else(|| check::<RotatingFileLogger>(logger));
+    #[cfg(feature = "pipe")]
+    let health = health.or_else(|| check::<crate::sinks::PipeLogger>(logger));
+    #[cfg(feature = "sqlite")]
+    let health = health.or_else(|| check::<crate::sinks::SqliteLogger>(logger));
+    health
+}
@@ -0,0 +1,29 @@
+/// The name of the machine, `localhost` if it can not be determined
+#[cfg(all(unix, any(feature = "enrich", feature = "syslog")))]
+pub(crate) fn hostname() -> String {
+    let mut buffer = [0u8; 256];
+    // SAFETY: the buffer is valid for its whole length
+    let result = unsafe { libc::gethostname(buffer.as_mut_ptr().cast(), buffer.len()) };
+    if result != 0 {
+        return "localhost".to_string();
+    }
+
+    let length = buffer
+        .iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(buffer.len());
+    String::from_utf8_lossy(&buffer[..length]).into_owned()
+}
+
+/// The name of the machine, `localhost` if it can not be determined
+#[cfg(not(all(unix, any(feature = "enrich", feature = "syslog"))))]
+pub(crate) fn hostname() -> String {
+    #[cfg(unix)]
+    if let Ok(hostname) = std::fs::read_to_string("/etc/hostname") {
+        return hostname.trim().to_string();
+    }
+
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .unwrap_or_else(|_| "localhost".to_string())
+}
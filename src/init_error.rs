@@ -0,0 +1,54 @@
+use crate::{EnvFilterError, OverridesError};
+use log::SetLoggerError;
+use std::fmt::{Display, Formatter};
+
+/// The composite logger could not be initialized as the global logger
+#[derive(Debug)]
+pub enum InitError {
+    /// Another global logger was initialized before
+    AlreadyInitialized(SetLoggerError),
+    /// Neither a logger nor a fallback logger was added, so every record would be ignored
+    NoLoggers,
+    /// The directives of a filter could not be parsed
+    InvalidFilter(EnvFilterError),
+    /// The overrides of named loggers are invalid or name no logger
+    InvalidOverrides(OverridesError),
+    /// The active profile is neither registered nor built-in
+    UnknownProfile(String),
+}
+
+impl Display for InitError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InitError::AlreadyInitialized(error) => write!(f, "{}", error),
+            InitError::NoLoggers => f.write_str("no loggers were added to the composite logger"),
+            InitError::InvalidFilter(error) => write!(f, "{}", error),
+            InitError::InvalidOverrides(error) => write!(f, "{}", error),
+            InitError::UnknownProfile(name) => write!(f, "unknown profile `{}`", name),
+        }
+    }
+}
+
+impl std::error::Error for InitError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            InitError::AlreadyInitialized(error) => Some(error),
+            InitError::NoLoggers => None,
+            InitError::InvalidFilter(error) => Some(error),
+            InitError::InvalidOverrides(error) => Some(error),
+            InitError::UnknownProfile(_) => None,
+        }
+    }
+}
+
+impl From<SetLoggerError> for InitError {
+    fn from(error: SetLoggerError) -> Self {
+        InitError::AlreadyInitialized(error)
+    }
+}
+
+impl From<EnvFilterError> for InitError {
+    fn from(error: EnvFilterError) -> Self {
+        InitError::InvalidFilter(error)
+    }
+}
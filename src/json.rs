@@ -0,0 +1,54 @@
+use crate::binary::{self, Binary};
+use log::kv::{self, Key, Value, VisitSource};
+use std::fmt::Write;
+
+/// Writes the string as a quoted and escaped JSON string
+pub(crate) fn write_str(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Writes numbers and booleans as such, and anything else as a string
+pub(crate) fn write_value(out: &mut String, value: &Value) {
+    if let Some(binary) = Binary::of(value) {
+        write_str(out, &binary::base64(binary.as_bytes()));
+    } else if let Some(value) = value.to_bool() {
+        let _ = write!(out, "{}", value);
+    } else if let Some(value) = value.to_u64() {
+        let _ = write!(out, "{}", value);
+    } else if let Some(value) = value.to_i64() {
+        let _ = write!(out, "{}", value);
+    } else if let Some(value) = value.to_f64().filter(|value| value.is_finite()) {
+        let _ = write!(out, "{}", value);
+    } else if let Some(value) = value.to_borrowed_str() {
+        write_str(out, value);
+    } else {
+        write_str(out, &value.to_string());
+    }
+}
+
+/// Writes key-values as the members of a JSON object, each preceded by a comma
+pub(crate) struct WriteMembers<'a>(pub(crate) &'a mut String);
+
+impl<'kvs> VisitSource<'kvs> for WriteMembers<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+        self.0.push(',');
+        write_str(self.0, key.as_str());
+        self.0.push(':');
+        write_value(self.0, &value);
+        Ok(())
+    }
+}
@@ -0,0 +1,30 @@
+use log::kv::{Error, Key, Source, Value, VisitSource};
+
+/// The key-values of a record followed by the ambient key-values
+/// that the record does not define itself
+pub(crate) struct WithAmbient<'a> {
+    pub(crate) record: &'a dyn Source,
+    pub(crate) ambient: &'a [(String, String)],
+}
+
+impl Source for WithAmbient<'_> {
+    fn visit<'kvs>(&'kvs self, visitor: &mut dyn VisitSource<'kvs>) -> Result<(), Error> {
+        self.record.visit(visitor)?;
+        for (key, value) in self.ambient {
+            if self.record.get(Key::from_str(key)).is_none() {
+                visitor.visit_pair(Key::from_str(key), Value::from(value.as_str()))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Collects key-values into owned strings
+pub(crate) struct Collect(pub(crate) Vec<(String, String)>);
+
+impl<'kvs> VisitSource<'kvs> for Collect {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), Error> {
+        self.0.push((key.to_string(), value.to_string()));
+        Ok(())
+    }
+}
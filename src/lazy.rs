@@ -0,0 +1,57 @@
+use log::{Log, Metadata, Record};
+use std::sync::{Mutex, OnceLock};
+
+type Factory = Box<dyn FnOnce() -> Box<dyn Log> + Send>;
+
+/// A logger built by its factory when it receives its first record or is activated,
+/// see [`crate::CompositeLogger::with_lazy_logger`]
+pub(crate) struct LazyLogger {
+    logger: OnceLock<Box<dyn Log>>,
+    factory: Mutex<Option<Factory>>,
+}
+
+impl LazyLogger {
+    pub(crate) fn new<L: Log + 'static>(factory: impl FnOnce() -> L + Send + 'static) -> Self {
+        Self {
+            logger: OnceLock::new(),
+            factory: Mutex::new(Some(Box::new(|| Box::new(factory())))),
+        }
+    }
+
+    /// The logger, built by the factory unless it was before
+    pub(crate) fn activate(&self) -> &dyn Log {
+        self.logger
+            .get_or_init(|| {
+                let factory = self
+                    .factory
+                    .lock()
+                    .unwrap_or_else(|error| error.into_inner())
+                    .take()
+                    .expect("the factory is only taken once");
+                factory()
+            })
+            .as_ref()
+    }
+}
+
+impl Log for LazyLogger {
+    /// Enabled until the logger is built, so that the first record it would receive builds it
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.logger
+            .get()
+            .is_none_or(|logger| logger.enabled(metadata))
+    }
+
+    fn log(&self, record: &Record) {
+        let logger = self.activate();
+        if logger.enabled(record.metadata()) {
+            logger.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(logger) = self.logger.get() {
+            logger.flush();
+        }
+    }
+}
@@ -1,38 +1,1190 @@
-use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+#![cfg_attr(not(feature = "std"), no_std)]
 
+extern crate alloc;
+
+#[cfg(all(unix, feature = "admin"))]
+pub mod admin;
+#[cfg(feature = "tokio")]
+mod async_log;
+#[cfg(feature = "std")]
+mod banner;
+#[cfg(not(feature = "std"))]
+mod bare;
+#[cfg(feature = "std")]
+mod batch;
+#[cfg(feature = "std")]
+mod binary;
+#[cfg(feature = "std")]
+mod breaker;
+#[cfg(feature = "clap")]
+pub mod cli;
+#[cfg(feature = "serde")]
+pub mod config;
+#[cfg(feature = "std")]
+pub mod context;
+#[cfg(feature = "std")]
+mod dedup;
+#[cfg(feature = "std")]
+mod dispatch;
+#[cfg(feature = "std")]
+mod early;
+#[cfg(feature = "std")]
+mod enabled_cache;
+#[cfg(feature = "encryption")]
+pub mod encryption;
+#[cfg(feature = "enrich")]
+mod enrich;
+#[cfg(feature = "std")]
+mod env_filter;
+#[cfg(feature = "std")]
+mod error_chain;
+#[cfg(feature = "std")]
+mod escalate;
+#[cfg(feature = "std")]
+mod explain;
+pub mod filter;
+#[cfg(feature = "std")]
+pub mod format;
+#[cfg(feature = "std")]
+mod formatted;
+#[cfg(feature = "std")]
+mod group;
+#[cfg(feature = "std")]
+mod handle;
+#[cfg(feature = "std")]
+mod health;
+#[cfg(feature = "std")]
+mod host;
+#[cfg(feature = "std")]
+mod init_error;
+#[cfg(feature = "std")]
+mod json;
+#[cfg(feature = "std")]
+mod kv;
+#[cfg(feature = "std")]
+mod lazy;
+#[cfg(feature = "std")]
+mod line_encoding;
+#[cfg(feature = "std")]
+mod macros;
+#[cfg(feature = "std")]
+mod map;
+#[cfg(feature = "std")]
+pub mod msgpack;
+#[cfg(feature = "std")]
+mod origin;
+#[cfg(feature = "std")]
+mod overrides;
+#[cfg(feature = "std")]
+mod persist;
+#[cfg(feature = "std")]
+mod profile;
+#[cfg(feature = "std")]
+mod rate_limit;
+#[cfg(feature = "std")]
+mod record;
+#[cfg(feature = "std")]
+mod redact;
+#[cfg(feature = "std")]
+mod sample;
+#[cfg(feature = "std")]
+mod scoped;
+#[cfg(feature = "shutdown")]
+mod shutdown;
+#[cfg(feature = "std")]
+mod sink_error;
+pub mod sinks;
+#[cfg(feature = "std")]
+mod stats;
+#[cfg(feature = "std")]
+mod supervise;
+#[cfg(feature = "std")]
+mod target_tree;
+#[cfg(feature = "std")]
+pub mod testing;
+#[cfg(feature = "std")]
+mod time;
+#[cfg(feature = "std")]
+mod timestamp;
+#[cfg(feature = "tracing")]
+pub mod tracing_bridge;
+#[cfg(feature = "std")]
+mod truncate;
+#[cfg(feature = "std")]
+mod worker;
+
+#[cfg(feature = "tokio")]
+pub use async_log::{AsyncLog, AsyncLogAdapter};
+#[cfg(feature = "std")]
+pub use banner::{Banner, BANNER_TARGET, GIT_HASH_ENV};
+#[cfg(not(feature = "std"))]
+pub use bare::CompositeLogger;
+#[cfg(feature = "std")]
+pub use batch::{BatchLog, BatchedLogger, PerRecord};
+#[cfg(feature = "std")]
+pub use binary::{Binary, BinaryRenderedLogger, BinaryRendering};
+#[cfg(feature = "std")]
+pub use breaker::CircuitBreaker;
+#[cfg(feature = "std")]
+pub use dedup::DeduplicatedLogger;
+#[cfg(feature = "std")]
+pub use dispatch::{
+    BacktraceMode, DispatchStrategy, FORMAT_ERROR_KEY, INTERNAL_TARGET, SEQUENCE_KEY,
+};
+#[cfg(feature = "std")]
+pub use env_filter::{EnvFilter, EnvFilterError, DEFAULT_FILTER_ENV};
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub use error_chain::log_error as __log_error;
+#[cfg(feature = "std")]
+pub use error_chain::ErrorChain;
+#[cfg(feature = "std")]
+pub use explain::{LoggerDecision, Rejection, RoutingReport, EXPLAIN_ENV};
+pub use filter::{Filter, FilterExt, FilteredLogger};
+#[cfg(feature = "std")]
+pub use format::{Format, JsonFormat, LogfmtFormat, RoutedFormat, TemplateFormat, Theme};
+#[cfg(feature = "std")]
+pub use formatted::FormattedRecord;
+#[cfg(feature = "std")]
+pub use group::LoggerGroup;
+#[cfg(feature = "std")]
+pub use handle::{CompositeLoggerHandle, FlushGuard, FlushResult, LoggerId, LoggerRef};
+#[cfg(feature = "std")]
+pub use health::{HealthCheck, LoggerHealth, SinkHealth};
+#[cfg(feature = "std")]
+pub use init_error::InitError;
+#[cfg(feature = "std")]
+pub use line_encoding::{LineEncoding, Newline, TextEncoding};
+#[cfg(feature = "std")]
+pub use map::MappedLogger;
+#[cfg(feature = "std")]
+pub use overrides::{OverridesError, DEFAULT_OVERRIDES_ENV};
+#[cfg(feature = "std")]
+pub use profile::{Profile, PROFILE_NAMES};
+#[cfg(feature = "std")]
+pub use rate_limit::RateLimitedLogger;
+#[cfg(feature = "std")]
+pub use record::OwnedRecord;
+#[cfg(feature = "std")]
+pub use redact::{RedactedLogger, Redaction};
+#[cfg(feature = "std")]
+pub use sample::SampledLogger;
+#[cfg(feature = "std")]
+pub use scoped::with_scoped;
+#[cfg(feature = "std")]
+pub use sink_error::{SinkError, SinkErrorKind};
+#[cfg(feature = "std")]
+pub use stats::{LatencyHistogram, LoggerStats, Stats};
+#[cfg(feature = "std")]
+pub use supervise::SupervisedLogger;
+#[cfg(feature = "std")]
+pub use target_tree::TargetTree;
+#[cfg(feature = "std")]
+pub use time::{set_clock, Clock, SystemClock};
+#[cfg(feature = "std")]
+pub use timestamp::{
+    set_time_zone, set_timestamp_format, TimeZone, TimeZoneError, TimestampFormat,
+};
+#[cfg(feature = "std")]
+pub use truncate::{TruncatedLogger, Truncation};
+#[cfg(feature = "std")]
+pub use worker::{Barrier, LoadShedding, OverflowPolicy};
+
+#[cfg(feature = "std")]
+use dispatch::{AnyLog, Dispatcher, Route, SinkEntry};
+#[cfg(feature = "std")]
+use handle::SharedLogger;
+#[cfg(feature = "std")]
+use lazy::LazyLogger;
+#[cfg(feature = "std")]
+use log::{Level, LevelFilter, Log, Metadata, Record};
+#[cfg(feature = "std")]
+use std::io::Write;
+#[cfg(feature = "std")]
+use std::sync::Arc;
+#[cfg(feature = "std")]
+use std::thread::ThreadId;
+#[cfg(feature = "std")]
+use std::time::Duration;
+#[cfg(feature = "std")]
+use worker::Lane;
+
+#[cfg(feature = "std")]
 #[derive(Default)]
 pub struct CompositeLogger {
-    loggers: Vec<Box<dyn Log>>,
+    dispatcher: Dispatcher,
+    async_capacity: Option<usize>,
+    overflow_policy: OverflowPolicy,
+    load_shedding: Option<LoadShedding>,
+    flush_interval: Option<Duration>,
+    panic_hook: bool,
+    stats_interval: Option<Duration>,
+    /// Applied once initialized, see [`CompositeLogger::boost_level_for`]
+    boost: Option<(LevelFilter, Duration)>,
+    /// Applied to the named loggers once initialized
+    overrides: overrides::Overrides,
+    /// Reported when initializing, so that the builder methods do not fail
+    filter_error: Option<EnvFilterError>,
+    overrides_error: Option<OverridesError>,
+    /// Applied once initialized, before the overrides
+    active_profile: Option<String>,
+    /// Applied to the whole process once initialized
+    timestamp_format: Option<TimestampFormat>,
+    time_zone: Option<TimeZone>,
 }
 
+#[cfg(feature = "std")]
 impl CompositeLogger {
     pub fn new() -> Self {
         Self::default()
     }
 
-    /// Add a logger to delegate the logs to
-    pub fn with_logger(mut self, logger: impl Log + 'static) -> Self {
-        self.loggers.push(Box::new(logger));
+    /// Add a logger to delegate the logs to.
+    /// Since its level is unknown, it is assumed to accept every record.
+    pub fn with_logger(self, logger: impl Log + 'static) -> Self {
+        self.with_logger_at(LevelFilter::max(), logger)
+    }
+
+    /// Add a logger to delegate the logs to, that only receives records
+    /// at or above the given level.
+    /// The global maximum level is computed from the levels of all loggers.
+    pub fn with_logger_at(self, level: LevelFilter, logger: impl Log + 'static) -> Self {
+        self.with_entry(level, Route::All, Box::new(logger))
+    }
+
+    /// Add an already boxed logger to delegate the logs to,
+    /// for example one of a list built from configuration
+    pub fn with_boxed_logger(self, logger: Box<dyn Log>) -> Self {
+        self.with_entry(LevelFilter::max(), Route::All, Box::new(logger))
+    }
+
+    /// Add a shared logger to delegate the logs to,
+    /// keeping a reference to it allows to access it after initialization
+    pub fn with_shared_logger(self, logger: Arc<dyn Log>) -> Self {
+        self.with_entry(LevelFilter::max(), Route::All, Box::new(logger))
+    }
+
+    /// Add a logger that should take at most the timeout to log a record.
+    /// Slower deliveries are reported to the error handler and counted in the stats,
+    /// and in the asynchronous mode the records queued for the logger in the meantime
+    /// are dropped, so that it catches up instead of delaying the others further.
+    pub fn with_logger_timeout(mut self, logger: impl Log + 'static, timeout: Duration) -> Self {
+        let entry = self.push_entry(LevelFilter::max(), Route::All, Box::new(logger));
+        entry.timeout = Some(timeout);
+        self
+    }
+
+    /// Add a named logger that receives its records from a thread and a queue of its own
+    /// holding up to `capacity` records, so that a slow logger such as a network shipper
+    /// does not delay the delivery to the others, in the asynchronous mode or not.
+    /// Records are dropped for the logger while its queue is full, and its failures
+    /// are reported when the next record is dispatched to it or when flushing.
+    /// Flushing, [`CompositeLoggerHandle::barrier`] and draining the queue of the
+    /// asynchronous mode wait for the records queued for the logger.
+    pub fn with_isolated_logger(
+        mut self,
+        name: impl Into<String>,
+        logger: impl Log + 'static,
+        capacity: usize,
+    ) -> Self {
+        let name = name.into();
+        let lane = Lane::spawn(Some(&name), capacity);
+        let entry = self.push_entry(LevelFilter::max(), Route::All, Box::new(logger));
+        entry.name = Some(name);
+        entry.lane = Some(lane);
+        self
+    }
+
+    /// Add a named logger that receives the records of the priority level, by default errors,
+    /// right away from the logging thread and flushes them, even in the asynchronous mode,
+    /// so that the error explaining why the process is about to die is not left in the queue.
+    /// The other records reach the logger through the queue, see
+    /// [`CompositeLogger::with_priority_level`].
+    pub fn with_priority_logger(
+        mut self,
+        name: impl Into<String>,
+        logger: impl Log + 'static,
+    ) -> Self {
+        let entry = self.push_entry(LevelFilter::max(), Route::All, Box::new(logger));
+        entry.name = Some(name.into());
+        entry.priority = true;
+        self.dispatcher.priority_level.get_or_insert(Level::Error);
+        self
+    }
+
+    /// Deliver the records of the level or more severe right away to the priority loggers,
+    /// see [`CompositeLogger::with_priority_logger`]
+    pub fn with_priority_level(mut self, level: Level) -> Self {
+        self.dispatcher.priority_level = Some(level);
+        self
+    }
+
+    /// Add a named logger that is only built by the factory once it receives its first record,
+    /// or once activated with [`CompositeLoggerHandle::activate`], so that loggers that are
+    /// expensive to set up, such as network exporters, do not slow down programs that never
+    /// log anything for them. The logger can not be accessed with [`CompositeLoggerHandle::get`].
+    pub fn with_lazy_logger<L: Log + 'static>(
+        self,
+        name: impl Into<String>,
+        factory: impl FnOnce() -> L + Send + 'static,
+    ) -> Self {
+        self.with_named_logger(name, LazyLogger::new(factory))
+    }
+
+    /// Add a logger that can be referred to by name at runtime,
+    /// see [`CompositeLoggerHandle::set_enabled`]
+    pub fn with_named_logger(
+        mut self,
+        name: impl Into<String>,
+        logger: impl Log + 'static,
+    ) -> Self {
+        self.push_entry(LevelFilter::max(), Route::All, Box::new(logger))
+            .name = Some(name.into());
+        self
+    }
+
+    /// Add a named logger of a type that reports whether it delivers its records,
+    /// see [`CompositeLoggerHandle::health`]
+    pub fn with_health_checked_logger<L: Log + HealthCheck + 'static>(
+        mut self,
+        name: impl Into<String>,
+        logger: L,
+    ) -> Self {
+        let entry = self.push_entry(LevelFilter::max(), Route::All, Box::new(logger));
+        entry.name = Some(name.into());
+        entry.health_check = Some(health::check::<L>);
+        self
+    }
+
+    /// Add the loggers of the other composite logger, for example one exported
+    /// by a library, keeping their names and groups.
+    /// The filter of the other composite logger only applies to its own loggers,
+    /// and its invalid filter or overrides are reported when initializing.
+    /// If it also has key-values, a fallback logger or any other dispatching option,
+    /// it is added as a single nested logger instead, so that these only apply to its
+    /// loggers as well. Its asynchronous mode and flush interval are ignored.
+    pub fn merge(mut self, other: CompositeLogger) -> Self {
+        self.filter_error = self.filter_error.or(other.filter_error);
+        self.overrides_error = self.overrides_error.or(other.overrides_error);
+        let mut other = other.dispatcher;
+        if !other.is_flat() {
+            let level = other.max_level();
+            return self.with_entry(level, Route::All, Box::new(other));
+        }
+
+        let filter = other.filter.take();
+        let max_level = other.max_level.unwrap_or(LevelFilter::max());
+        for entry in other.loggers {
+            let mut entry =
+                Arc::into_inner(entry).expect("loggers are not shared before they are installed");
+            entry.id = LoggerId(self.dispatcher.loggers.len() as u64);
+            entry.set_level(entry.level().min(max_level));
+            if let Some(filter) = &filter {
+                entry.set_level(entry.level().min(filter.max_level()));
+                entry.filter = Some(filter.clone());
+            }
+            self.dispatcher.loggers.push(Arc::new(entry));
+        }
+        self
+    }
+
+    /// Add the loggers of a group, which can be switched on and off or re-leveled
+    /// together at runtime by the group name,
+    /// see [`CompositeLoggerHandle::set_group_enabled`]
+    pub fn with_group(
+        mut self,
+        name: impl Into<String>,
+        group: impl FnOnce(LoggerGroup) -> LoggerGroup,
+    ) -> Self {
+        let name = name.into();
+        for mut entry in group(LoggerGroup::new()).loggers {
+            entry.id = LoggerId(self.dispatcher.loggers.len() as u64);
+            entry.group = Some(name.clone());
+            self.dispatcher.loggers.push(Arc::new(entry));
+        }
+        self
+    }
+
+    /// Add a logger that is never asked whether it is enabled for a record,
+    /// for loggers that accept everything but do non-trivial work in [`Log::enabled`]
+    pub fn with_always_enabled_logger(mut self, logger: impl Log + 'static) -> Self {
+        self.push_entry(LevelFilter::max(), Route::All, Box::new(logger))
+            .always_enabled = true;
+        self
+    }
+
+    /// Add a logger that only receives records of the given target or any of its
+    /// submodules, for example `"hyper"` matches both `hyper` and `hyper::client`
+    pub fn with_target_route(self, target: impl Into<String>, logger: impl Log + 'static) -> Self {
+        self.with_entry(
+            LevelFilter::max(),
+            Route::Target(target.into()),
+            Box::new(logger),
+        )
+    }
+
+    /// Add a logger that only receives records whose target starts with the given prefix
+    pub fn with_target_prefix_route(
+        self,
+        prefix: impl Into<String>,
+        logger: impl Log + 'static,
+    ) -> Self {
+        self.with_entry(
+            LevelFilter::max(),
+            Route::TargetPrefix(prefix.into()),
+            Box::new(logger),
+        )
+    }
+
+    /// Add a logger that only receives records accepted by the filter,
+    /// see the [`filter`] module for the available filters
+    pub fn with_filtered_logger(
+        self,
+        logger: impl Log + 'static,
+        filter: impl Filter + 'static,
+    ) -> Self {
+        self.with_logger(FilteredLogger::new(logger, filter))
+    }
+
+    /// Add a logger that only receives records while the condition holds,
+    /// for example a feature flag or an `AtomicBool` owned by the application.
+    /// The condition is evaluated for every record, see [`filter::When`],
+    /// so it should not be combined with [`CompositeLogger::with_enabled_cache`].
+    pub fn with_conditional_logger(
+        self,
+        logger: impl Log + 'static,
+        condition: impl Fn() -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.with_filtered_logger(logger, filter::When(condition))
+    }
+
+    /// Add a logger that only receives records while the condition holds,
+    /// evaluating it at most once per time to live, see [`filter::CachedWhen`]
+    pub fn with_cached_conditional_logger(
+        self,
+        logger: impl Log + 'static,
+        condition: impl Fn() -> bool + Send + Sync + 'static,
+        time_to_live: Duration,
+    ) -> Self {
+        self.with_filtered_logger(logger, filter::CachedWhen::new(condition, time_to_live))
+    }
+
+    /// Add a logger that only receives records whose formatted message matches
+    /// the regular expression, see [`filter::MessageMatches`]
+    ///
+    /// # Errors
+    ///
+    /// Fails if the pattern is not a valid regular expression.
+    #[cfg(feature = "regex")]
+    pub fn route_matching(
+        self,
+        pattern: &str,
+        logger: impl Log + 'static,
+    ) -> Result<Self, regex::Error> {
+        Ok(self.with_filtered_logger(logger, filter::MessageMatches::new(pattern)?))
+    }
+
+    /// Drop the records whose formatted message matches the regular expression
+    /// before they are dispatched to any logger, for example `r"health check ok"`
+    ///
+    /// # Errors
+    ///
+    /// Fails if the pattern is not a valid regular expression.
+    #[cfg(feature = "regex")]
+    pub fn suppress_matching(mut self, pattern: &str) -> Result<Self, regex::Error> {
+        self.dispatcher
+            .suppressions
+            .push(regex::Regex::new(pattern)?);
+        Ok(self)
+    }
+
+    /// Add a logger that only receives errors
+    pub fn route_errors_to(self, logger: impl Log + 'static) -> Self {
+        self.route_at_least(Level::Error, logger)
+    }
+
+    /// Add a logger that only receives records at the given level or more severe
+    pub fn route_at_least(self, level: Level, logger: impl Log + 'static) -> Self {
+        self.with_logger_at(level.to_level_filter(), logger)
+    }
+
+    /// Add a logger that only receives records of exactly the given level
+    pub fn route_exactly(self, level: Level, logger: impl Log + 'static) -> Self {
+        self.with_logger_at(
+            level.to_level_filter(),
+            FilteredLogger::new(logger, filter::LevelExactly(level)),
+        )
+    }
+
+    /// Add an asynchronous logger driven by a task spawned on the tokio runtime,
+    /// with a queue of the given capacity, see [`AsyncLogAdapter`]
+    #[cfg(feature = "tokio")]
+    pub fn with_async_logger(
+        self,
+        logger: impl AsyncLog,
+        runtime: &tokio::runtime::Handle,
+        capacity: usize,
+    ) -> Self {
+        self.with_logger(AsyncLogAdapter::spawn(logger, runtime, capacity))
+    }
+
+    /// Add a logger that receives records in batches of at most `max_batch` records,
+    /// delivered at the latest `max_delay` after the oldest of them was logged,
+    /// see [`BatchedLogger`]. Plain loggers can be batched with [`PerRecord`].
+    pub fn with_batched_logger(
+        self,
+        logger: impl BatchLog,
+        max_batch: usize,
+        max_delay: Duration,
+    ) -> Self {
+        self.with_logger(BatchedLogger::new(logger, max_batch, max_delay))
+    }
+
+    /// Add a logger that only receives records logged from threads whose name matches
+    /// the pattern, in which `*` matches any sequence of characters, for example `"render-*"`.
+    /// The thread is known in the asynchronous mode as well, but the decisions of
+    /// [`CompositeLogger::with_enabled_cache`] are not per thread, so it should not be used.
+    pub fn with_thread_route(self, pattern: impl Into<String>, logger: impl Log + 'static) -> Self {
+        self.with_entry(
+            LevelFilter::max(),
+            Route::ThreadName(pattern.into()),
+            Box::new(logger),
+        )
+    }
+
+    /// Add a logger that only receives records logged from the given thread,
+    /// see [`CompositeLogger::with_thread_route`]
+    pub fn with_thread_id_route(self, id: ThreadId, logger: impl Log + 'static) -> Self {
+        self.with_entry(LevelFilter::max(), Route::ThreadId(id), Box::new(logger))
+    }
+
+    /// Add a logger that receives records rewritten by the mapping function,
+    /// for example to change their level, target or message.
+    /// Records for which the function returns `None` are not delegated.
+    pub fn with_mapped_logger(
+        self,
+        logger: impl Log + 'static,
+        map: impl Fn(&Record) -> Option<OwnedRecord> + Send + Sync + 'static,
+    ) -> Self {
+        self.with_logger(MappedLogger::new(logger, map))
+    }
+
+    /// Add a logger writing the records formatted with the format to the writer,
+    /// see [`sinks::WriterLogger`]
+    pub fn with_formatted_logger(
+        self,
+        writer: impl Write + Send + 'static,
+        format: impl Format + 'static,
+    ) -> Self {
+        self.with_logger(sinks::WriterLogger::new(writer, format))
+    }
+
+    /// Add a logger writing the records to the standard input of the command line,
+    /// restarting it whenever it exits, see [`sinks::PipeLogger::command`]
+    ///
+    /// # Errors
+    ///
+    /// Fails if the command can not be started.
+    #[cfg(feature = "pipe")]
+    pub fn with_pipe_to_command(self, command: impl Into<String>) -> std::io::Result<Self> {
+        Ok(self.with_logger(sinks::PipeLogger::command(command)?))
+    }
+
+    /// Add a logger that receives messages of at most `max_length` bytes,
+    /// truncating or dropping longer ones, see [`TruncatedLogger`]
+    pub fn with_truncated_logger(
+        self,
+        logger: impl Log + 'static,
+        max_length: usize,
+        truncation: Truncation,
+    ) -> Self {
+        self.with_logger(TruncatedLogger::new(logger, max_length, truncation))
+    }
+
+    /// Add a logger that receives binary payloads rendered as strings, see [`BinaryRenderedLogger`]
+    pub fn with_binary_rendering(
+        self,
+        logger: impl Log + 'static,
+        rendering: BinaryRendering,
+    ) -> Self {
+        self.with_logger(BinaryRenderedLogger::new(logger, rendering))
+    }
+
+    /// Add a logger that only receives records scrubbed by the redaction rules
+    pub fn with_redacted_logger(self, logger: impl Log + 'static, redaction: Redaction) -> Self {
+        self.with_logger(RedactedLogger::new(logger, redaction))
+    }
+
+    /// Only dispatch records accepted by the filter to any of the loggers,
+    /// the global maximum level is limited by the filter
+    pub fn with_env_filter(mut self, filter: EnvFilter) -> Self {
+        self.dispatcher.filter = Some(Arc::new(filter));
+        self
+    }
+
+    /// Print which loggers receive the records accepted by the filter, and why the others
+    /// do not, to the standard error as the records are logged, see
+    /// [`CompositeLoggerHandle::explain`]. The global maximum level includes the levels
+    /// of the filter, so that records dropped by the maximum level are explained too.
+    ///
+    /// Without it, the `RUST_LOG` style directives of the [`EXPLAIN_ENV`]
+    /// environment variable are used, if it is set.
+    pub fn with_explain(mut self, filter: EnvFilter) -> Self {
+        self.dispatcher.explain = Some(Arc::new(filter));
+        self
+    }
+
+    /// Deliver a record describing the process to every logger once initialized, whatever
+    /// their levels, and a matching record when the [`FlushGuard`] is dropped, see [`Banner`]
+    pub fn with_banner(mut self, banner: Banner) -> Self {
+        self.dispatcher.banner = Some(Arc::new(banner));
+        self
+    }
+
+    /// Never dispatch records more verbose than the level, whatever the loggers accept,
+    /// so that the `log` macros skip them before they are built.
+    /// The global maximum level is also limited by the `max_level_*` and
+    /// `release_max_level_*` features of the `log` crate.
+    pub fn with_max_level(mut self, max_level: LevelFilter) -> Self {
+        self.dispatcher.max_level = Some(max_level);
+        self
+    }
+
+    /// Let every logger receive records up to the level for the duration after initializing,
+    /// for example to see what happens during startup,
+    /// see [`CompositeLoggerHandle::boost_level_for`]
+    pub fn boost_level_for(mut self, level: LevelFilter, duration: Duration) -> Self {
+        self.boost = Some((level, duration));
+        self
+    }
+
+    /// Let every logger receive `Debug` records for the duration after initializing,
+    /// see [`CompositeLogger::boost_level_for`]
+    pub fn verbose_for(self, duration: Duration) -> Self {
+        self.boost_level_for(LevelFilter::Debug, duration)
+    }
+
+    /// Only dispatch records accepted by the `RUST_LOG` style directives to any of the loggers.
+    /// Invalid directives are reported when initializing, see [`CompositeLogger::try_init`].
+    pub fn with_filter_directives(mut self, directives: &str) -> Self {
+        match EnvFilter::parse(directives) {
+            Ok(filter) => self.dispatcher.filter = Some(Arc::new(filter)),
+            Err(error) => self.filter_error = Some(error),
+        }
+        self
+    }
+
+    /// Override the levels of named loggers and switch them on or off with the comma separated
+    /// `name=level`, `name=on` and `name=off` directives of the environment variable,
+    /// such as `console=warn,file=debug,net=off`, once initialized.
+    /// Invalid directives and names of no logger are reported when initializing,
+    /// see [`CompositeLogger::try_init`].
+    pub fn with_env_overrides(mut self, variable: &str) -> Self {
+        match overrides::Overrides::parse(&std::env::var(variable).unwrap_or_default()) {
+            Ok(overrides) => self.overrides.extend(overrides),
+            Err(error) => self.overrides_error = Some(error),
+        }
+        self
+    }
+
+    /// Register a profile under the name, replacing the built-in profile of the same name,
+    /// see [`Profile`]
+    pub fn with_profile(mut self, name: impl Into<String>, profile: Profile) -> Self {
+        let name = name.into();
+        self.dispatcher
+            .profiles
+            .retain(|(existing, _)| *existing != name);
+        self.dispatcher.profiles.push((name, profile));
+        self
+    }
+
+    /// Apply the named profile once initialized, before the overrides of named loggers.
+    /// Unknown profiles are reported when initializing, see [`CompositeLogger::try_init`].
+    pub fn with_active_profile(mut self, name: impl Into<String>) -> Self {
+        self.active_profile = Some(name.into());
+        self
+    }
+
+    /// Apply the built-in profile for the verbose and quiet flags of a command line tool
+    /// once initialized, see [`Profile::name_for_verbosity`]
+    pub fn with_verbosity(self, verbose: u8, quiet: u8) -> Self {
+        self.with_active_profile(Profile::name_for_verbosity(verbose, quiet))
+    }
+
+    /// Override named loggers by the directives of the `COMPOSITE_LOG` environment variable,
+    /// see [`CompositeLogger::with_env_overrides`]
+    pub fn with_default_env_overrides(self) -> Self {
+        self.with_env_overrides(DEFAULT_OVERRIDES_ENV)
+    }
+
+    /// Add a key-value pair to every record before it is dispatched to the loggers,
+    /// such as the service name, version or environment.
+    /// Key-values defined by the record itself take precedence.
+    pub fn with_kv(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.dispatcher.key_values.push((key.into(), value.into()));
+        self
+    }
+
+    /// Add the `timestamp`, `pid`, `thread`, `thread_id` and `hostname` key-values to every record
+    /// once, before it is dispatched, so that all loggers see the same values
+    #[cfg(feature = "enrich")]
+    pub fn with_enrichment(mut self) -> Self {
+        self.dispatcher.enrichment = Some(enrich::Enrichment::new());
+        self
+    }
+
+    /// Capture a backtrace of where each record of the given level or more severe was logged
+    /// and attach it to the record, whatever `RUST_BACKTRACE` says.
+    /// Capturing a backtrace is slow, so this is meant for errors and warnings.
+    pub fn with_backtraces(mut self, level: Level, mode: BacktraceMode) -> Self {
+        self.dispatcher.backtraces = Some((level, mode));
         self
     }
 
+    /// Deliver the warnings of the target and its submodules at the level once `count` of them
+    /// occurred within the window, for example to let repeated warnings reach a logger that
+    /// only receives errors. Records more severe than warnings but less severe than the level
+    /// are counted and escalated as well, and escalated records have the `escalated_from`
+    /// key-value with their original level. Only records that any logger is enabled for
+    /// at their original level are counted, and the first rule escalating a record applies.
+    pub fn escalate_after(
+        mut self,
+        target: impl Into<String>,
+        count: usize,
+        window: Duration,
+        level: Level,
+    ) -> Self {
+        self.dispatcher
+            .escalations
+            .push(Arc::new(escalate::Escalation::new(
+                target.into(),
+                count,
+                window,
+                level,
+            )));
+        self
+    }
+
+    /// Stamp every record with a sequence number under the [`SEQUENCE_KEY`] key, so that the
+    /// output of loggers with different latencies can be merged in order afterwards.
+    ///
+    /// Records are numbered in the order they are dispatched, and every logger receives them
+    /// in that order: in the asynchronous mode the worker dispatches one record at a time,
+    /// otherwise logging threads wait for each other while the loggers receive a record.
+    /// Only records logged by the loggers themselves, and records dispatched with
+    /// [`DispatchStrategy::Parallel`], may be received out of order.
+    /// The numbering continues across reloads that keep sequence numbers.
+    pub fn with_sequence_numbers(mut self) -> Self {
+        self.dispatcher.sequencer = Some(Default::default());
+        self
+    }
+
+    /// Dispatch the events of the composite logger itself up to the level as records of the
+    /// [`INTERNAL_TARGET`], to debug the logging pipeline: loggers added, removed and replaced
+    /// at runtime and reloads at the `Info` level, records that were not delivered and
+    /// circuits that opened at the `Warn` level, and flushes with their duration at the
+    /// `Debug` level. Events about failing to deliver an event are not dispatched.
+    pub fn with_internal_events(mut self, level: LevelFilter) -> Self {
+        self.dispatcher.internal_events = Some(level);
+        self
+    }
+
+    /// Remember whether any logger is enabled for a target and level instead of asking
+    /// all loggers for every log statement. The decisions are forgotten whenever
+    /// the loggers change through the [`CompositeLoggerHandle`], so this is only correct
+    /// as long as the loggers themselves decide on the target and level alone.
+    pub fn with_enabled_cache(mut self) -> Self {
+        self.dispatcher.enabled_cache = Some(Default::default());
+        self
+    }
+
+    /// Disable a logger after it panicked the given number of times in a row.
+    /// Panics of the loggers are always caught and counted, so that a broken logger
+    /// can not take down the others or the logging call site.
+    pub fn with_max_consecutive_panics(mut self, max_panics: usize) -> Self {
+        self.dispatcher.max_consecutive_panics = Some(max_panics);
+        self
+    }
+
+    /// Report loggers that take longer than the threshold to log `repeats` records in a row
+    /// to the error handler and as internal events, with the percentiles of how long they
+    /// take, for example a logger resolving a host name for every record.
+    /// How long every logger takes is always part of its [`LoggerStats`].
+    pub fn with_slow_logger_warnings(mut self, threshold: Duration, repeats: usize) -> Self {
+        self.dispatcher.slow_threshold = Some(dispatch::SlowThreshold {
+            duration: threshold,
+            repeats,
+        });
+        self
+    }
+
+    /// Skip loggers that failed too often until they recover,
+    /// reporting the transitions to the error handler, see [`CircuitBreaker`]
+    pub fn with_circuit_breaker(mut self, breaker: CircuitBreaker) -> Self {
+        self.dispatcher.circuit_breaker = Some(breaker);
+        self
+    }
+
+    /// Add a logger that receives at most the given number of records per second,
+    /// allowing bursts of the given size, and is periodically told how many
+    /// records were suppressed
+    pub fn with_rate_limited_logger(
+        self,
+        logger: impl Log + 'static,
+        max_per_second: u32,
+        burst: u32,
+    ) -> Self {
+        self.with_logger(RateLimitedLogger::new(logger, max_per_second, burst))
+    }
+
+    /// Add a logger that receives all `Info` and more severe records, but only
+    /// the given fraction of the `Debug` and `Trace` records.
+    /// Use [`SampledLogger::with_target_ratio`] for per-target ratios.
+    pub fn with_sampled_logger(self, logger: impl Log + 'static, ratio: f64) -> Self {
+        self.with_logger(SampledLogger::new(logger, ratio))
+    }
+
+    /// Add a logger that receives consecutive identical records only once, followed by
+    /// a `last message repeated N times` record once a different record is logged
+    pub fn with_deduplicated_logger(self, logger: impl Log + 'static) -> Self {
+        self.with_logger(DeduplicatedLogger::new(logger))
+    }
+
+    /// Collapse consecutive identical records before they are dispatched to any of the loggers,
+    /// see [`CompositeLogger::with_deduplicated_logger`]
+    pub fn with_deduplication(mut self) -> Self {
+        self.dispatcher.deduplication = Some(Default::default());
+        self
+    }
+
+    /// Collapse consecutive identical records like [`CompositeLogger::with_deduplication`],
+    /// keeping the last record and its repetitions in the state file across restarts,
+    /// see [`DeduplicatedLogger::with_state_file`]
+    pub fn with_persisted_deduplication(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.dispatcher.deduplication = Some(Arc::new(dedup::Deduplication::persisted(path)));
+        self
+    }
+
+    /// Once initialized, dispatch the records to the loggers from a dedicated thread.
+    /// Logging then only converts a record into an [`OwnedRecord`] and sends it
+    /// to a queue of the given capacity, see [`CompositeLogger::with_overflow_policy`]
+    /// for what happens when the queue is full.
+    pub fn into_async(mut self, capacity: usize) -> Self {
+        self.async_capacity = Some(capacity);
+        self
+    }
+
+    /// What to do with records logged while the queue of the asynchronous mode is full,
+    /// by default the logging thread blocks
+    pub fn with_overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow_policy = policy;
+        self
+    }
+
+    /// Shed less severe records while the queue of the asynchronous mode is under pressure,
+    /// see [`LoadShedding`]
+    pub fn with_load_shedding(mut self, shedding: LoadShedding) -> Self {
+        self.load_shedding = Some(shedding);
+        self
+    }
+
+    /// Once initialized, flush all loggers on the given schedule from a background thread,
+    /// so that buffered records do not linger in low traffic programs
+    pub fn with_flush_interval(mut self, interval: Duration) -> Self {
+        self.flush_interval = Some(interval);
+        self
+    }
+
+    /// Once initialized, log the counters of every logger on the given schedule
+    /// as `Info` records of the `composite_logger::stats` target with the key-values
+    /// `logger`, `name`, `delivered`, `filtered`, `dropped` and `errored`,
+    /// see [`CompositeLoggerHandle::stats`]
+    pub fn with_stats_interval(mut self, interval: Duration) -> Self {
+        self.stats_interval = Some(interval);
+        self
+    }
+
+    /// Once initialized, log panics at the `Error` level with their message, location
+    /// and backtrace to the `panic` target, and flush all loggers before unwinding or aborting.
+    /// The panic is still printed by the previously registered panic hook.
+    pub fn with_panic_hook(mut self) -> Self {
+        self.panic_hook = true;
+        self
+    }
+
+    /// Once initialized, write the timestamps of all built-in formats and sinks
+    /// in the format, see [`set_timestamp_format`]
+    pub fn with_timestamp_format(mut self, format: TimestampFormat) -> Self {
+        self.timestamp_format = Some(format);
+        self
+    }
+
+    /// Once initialized, write the timestamps of all built-in formats and sinks
+    /// in the time zone, see [`set_time_zone`]
+    pub fn with_time_zone(mut self, zone: TimeZone) -> Self {
+        self.time_zone = Some(zone);
+        self
+    }
+
+    /// The most verbose level accepted by any of the loggers,
+    /// which becomes the global maximum level once initialized
+    pub fn max_level(&self) -> LevelFilter {
+        self.dispatcher.max_level()
+    }
+
+    /// Set a logger that only receives the records none of the other loggers is enabled for,
+    /// so that nothing is silently dropped when their filters are misconfigured
+    pub fn with_fallback_logger(mut self, logger: impl Log + 'static) -> Self {
+        self.dispatcher.fallback = Some(Arc::new(SinkEntry::new(
+            dispatch::FALLBACK_ID,
+            LevelFilter::max(),
+            Route::All,
+            Box::new(logger),
+        )));
+        self
+    }
+
+    /// Call the handler whenever records are not delivered, because a logger panicked
+    /// or was disabled, or because the queue of the asynchronous mode was full.
+    /// The handler is called from the logging thread and should not log
+    /// through the composite logger itself.
+    pub fn with_error_handler(
+        mut self,
+        handler: impl Fn(SinkError) + Send + Sync + 'static,
+    ) -> Self {
+        self.dispatcher.error_handler = Some(Arc::new(handler));
+        self
+    }
+
+    /// Set a logger that receives the records that were not delivered,
+    /// see [`CompositeLogger::with_error_handler`]
+    pub fn with_dead_letter_logger(mut self, logger: impl Log + 'static) -> Self {
+        self.dispatcher.dead_letter = Some(Arc::new(logger));
+        self
+    }
+
+    /// How records are delivered to the loggers enabled for them,
+    /// by default every enabled logger receives the record
+    pub fn with_dispatch_strategy(mut self, strategy: DispatchStrategy) -> Self {
+        self.dispatcher.strategy = strategy;
+        self
+    }
+
+    /// Add all loggers to delegate the logs to
+    pub fn with_loggers(mut self, loggers: impl IntoIterator<Item = Box<dyn Log>>) -> Self {
+        self.extend(loggers);
+        self
+    }
+
+    fn with_entry(mut self, level: LevelFilter, route: Route, logger: Box<dyn AnyLog>) -> Self {
+        self.push_entry(level, route, logger);
+        self
+    }
+
+    fn push_entry(
+        &mut self,
+        level: LevelFilter,
+        route: Route,
+        logger: Box<dyn AnyLog>,
+    ) -> &mut SinkEntry {
+        let id = LoggerId(self.dispatcher.loggers.len() as u64);
+        let loggers = &mut self.dispatcher.loggers;
+        loggers.push(Arc::new(SinkEntry::new(id, level, route, logger)));
+        Dispatcher::building(loggers.last_mut().expect("the logger was just added"))
+    }
+
+    /// Install a global logger right away that keeps the records logged until a composite
+    /// logger is initialized, which then receives them at the time they were logged,
+    /// so that the records logged while the loggers are configured are not lost.
+    /// At most `capacity` records are kept, the later ones are counted and dropped.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a global logger is already installed.
+    pub fn buffer_early(capacity: usize) -> Result<(), InitError> {
+        Ok(early::install(capacity)?)
+    }
+
     /// Initializes the global logger with the built composite logger.
     ///
     /// This should be called early in the execution of a Rust program. Any log
-    /// events that occur before initialization will be ignored.
+    /// events that occur before initialization will be ignored,
+    /// unless they are kept with [`CompositeLogger::buffer_early`].
+    ///
+    /// Returns a handle that allows to add, remove and replace loggers at runtime.
+    /// Loggers added with the builder are identified in the order they were added,
+    /// see [`CompositeLoggerHandle::logger_ids`].
     ///
     /// # Errors
     ///
-    /// This function will fail if it is called more than once, or if another
-    /// library has already initialized a global logger.
-    pub fn try_init(self) -> Result<(), SetLoggerError> {
-        let r = log::set_boxed_logger(Box::new(self));
+    /// This function will fail if it is called more than once, if another
+    /// library has already initialized a global logger, if no logger was added
+    /// or if filter directives or overrides are invalid.
+    pub fn try_init(self) -> Result<CompositeLoggerHandle, InitError> {
+        self.validate()?;
+        let (shared, startup) = self.into_unstarted();
+        shared.install()?;
+        startup.start(&shared);
+        Ok(CompositeLoggerHandle::new(shared))
+    }
+
+    /// Initializes the global logger with the built composite logger, or prints why it
+    /// could not to the standard error and returns `None`, so that a library embedding
+    /// the composite logger keeps working without logging instead of panicking
+    pub fn init_or_warn(self) -> Option<CompositeLoggerHandle> {
+        self.try_init()
+            .map_err(|error| eprintln!("composite_logger: failed to initialize: {}", error))
+            .ok()
+    }
 
-        if r.is_ok() {
-            log::set_max_level(LevelFilter::max());
+    /// Check the configuration of the builder before initializing
+    fn validate(&self) -> Result<(), InitError> {
+        if let Some(error) = &self.filter_error {
+            return Err(InitError::InvalidFilter(error.clone()));
+        }
+        if let Some(error) = self
+            .overrides_error
+            .clone()
+            .or_else(|| self.overrides.unknown(&self.dispatcher.loggers))
+        {
+            return Err(InitError::InvalidOverrides(error));
+        }
+        if let Some(name) = &self.active_profile {
+            if profile::find(&self.dispatcher, name).is_none() {
+                return Err(InitError::UnknownProfile(name.clone()));
+            }
+        }
+        if self.dispatcher.loggers.is_empty() && self.dispatcher.fallback.is_none() {
+            return Err(InitError::NoLoggers);
+        }
+        Ok(())
+    }
+
+    /// Initializes the global logger with the built composite logger, unless another
+    /// library has already initialized a global logger. In that case the composite logger
+    /// also delegates to the existing global logger and is returned without being installed,
+    /// so that records logged through the handle reach both, see
+    /// [`CompositeLoggerHandle::is_installed`].
+    /// Records of the `log` macros keep going to the existing global logger only,
+    /// since it can not be replaced.
+    ///
+    /// # Errors
+    ///
+    /// Fails if no logger was added or if filter directives or overrides are invalid.
+    pub fn init_wrapping(self) -> Result<CompositeLoggerHandle, InitError> {
+        self.validate()?;
+        let existing = log::logger();
+        let (shared, startup) = self.into_unstarted();
+        let handle = CompositeLoggerHandle::new(shared.clone());
+        if shared.install().is_err() {
+            handle.add_logger(existing);
         }
+        startup.start(&shared);
+        Ok(handle)
+    }
+
+    /// Initializes the global logger with the built composite logger, unless another
+    /// library has already initialized a global logger. In that case the composite logger
+    /// is returned without being installed, see [`CompositeLoggerHandle::is_installed`],
+    /// so that for example a plugin loaded into a host application can still log through
+    /// the handle, which is a [`Log`] itself, or pass it to code expecting a logger.
+    /// Unlike [`CompositeLogger::init_wrapping`], it does not delegate to the existing
+    /// global logger.
+    ///
+    /// # Errors
+    ///
+    /// Fails if no logger was added or if filter directives or overrides are invalid.
+    pub fn init_or_compose(self) -> Result<CompositeLoggerHandle, InitError> {
+        self.validate()?;
+        let (shared, startup) = self.into_unstarted();
+        let _ = shared.install();
+        startup.start(&shared);
+        Ok(CompositeLoggerHandle::new(shared))
+    }
+
+    /// Build the composite logger without installing it as the global logger,
+    /// for example for a library or a test that logs through the handle explicitly.
+    /// The loggers can still be changed at runtime through the handle.
+    pub fn build(self) -> CompositeLoggerHandle {
+        CompositeLoggerHandle::new(self.into_shared())
+    }
+
+    /// Build the composite logger without installing it, as a shared logger
+    /// that can be passed to code expecting any logger
+    pub fn into_arc(self) -> Arc<dyn Log> {
+        Arc::new(self.build())
+    }
+
+    /// The composite logger shared with its handle, not installed yet, started right away
+    fn into_shared(self) -> Arc<SharedLogger> {
+        let (shared, startup) = self.into_unstarted();
+        startup.start(&shared);
+        shared
+    }
 
-        r
+    /// The composite logger shared with its handle and what to start once it is used,
+    /// so that nothing is started for a composite logger that fails to be installed
+    fn into_unstarted(mut self) -> (Arc<SharedLogger>, Startup) {
+        self.prepare();
+        let shared = Arc::new(SharedLogger::new(
+            self.dispatcher,
+            self.async_capacity
+                .map(|capacity| (capacity, self.overflow_policy, self.load_shedding)),
+        ));
+        let startup = Startup {
+            flush_interval: self.flush_interval,
+            stats_interval: self.stats_interval,
+            panic_hook: self.panic_hook,
+            boost: self.boost,
+            timestamp_format: self.timestamp_format,
+            time_zone: self.time_zone,
+        };
+        (shared, startup)
+    }
+
+    /// Replace the loggers, filters and key-values of the composite logger by those
+    /// of the builder, applying its profile, overrides, timestamps and boost like
+    /// [`CompositeLogger::into_shared`], see [`CompositeLoggerHandle::reload`]
+    pub(crate) fn reload_into(mut self, shared: &Arc<SharedLogger>) {
+        self.prepare();
+        let startup = Startup {
+            // kept as initialized
+            flush_interval: None,
+            stats_interval: None,
+            panic_hook: false,
+            boost: self.boost,
+            timestamp_format: self.timestamp_format,
+            time_zone: self.time_zone,
+        };
+        shared.reload(self.dispatcher);
+        startup.start(shared);
+    }
+
+    /// Apply the active profile, the overrides and the explain directives of the environment
+    /// to the dispatcher, once the builder is validated
+    fn prepare(&mut self) {
+        if let Some(profile) = self
+            .active_profile
+            .as_deref()
+            .and_then(|name| profile::find(&self.dispatcher, name))
+        {
+            profile.apply(&mut self.dispatcher);
+        }
+        self.overrides.apply(&self.dispatcher.loggers);
+        if self.dispatcher.explain.is_none() {
+            let directives = std::env::var(EXPLAIN_ENV).ok();
+            self.dispatcher.explain = directives.and_then(|directives| {
+                EnvFilter::parse(&directives)
+                    .map_err(|error| {
+                        eprintln!("composite_logger: ignoring {}: {}", EXPLAIN_ENV, error)
+                    })
+                    .ok()
+                    .map(Arc::new)
+            });
+        }
+        if let Some(banner) = &mut self.dispatcher.banner {
+            Arc::make_mut(banner).start();
+        }
     }
 
     /// Initializes the global logger with the built composite logger.
@@ -42,27 +1194,110 @@ impl CompositeLogger {
     ///
     /// # Panics
     ///
-    /// This function will panic if it is called more than once, or if another
-    /// library has already initialized a global logger.
-    pub fn init(self) {
+    /// This function will panic if it is called more than once, if another
+    /// library has already initialized a global logger, if no logger was added
+    /// or if filter directives or overrides are invalid.
+    pub fn init(self) -> CompositeLoggerHandle {
         self.try_init()
-            .expect("CompositeLogger::init should not be called after logger initialized");
+            .unwrap_or_else(|error| panic!("CompositeLogger::init failed: {}", error))
+    }
+
+    /// Initializes the global logger with the built composite logger,
+    /// never dispatching records more verbose than the level,
+    /// see [`CompositeLogger::with_max_level`].
+    ///
+    /// # Panics
+    ///
+    /// Panics for the same reasons as [`CompositeLogger::init`].
+    pub fn init_with_max_level(self, max_level: LevelFilter) -> CompositeLoggerHandle {
+        self.with_max_level(max_level).init()
+    }
+
+    /// Initializes the global logger with the built composite logger,
+    /// returning a guard that flushes all loggers when dropped.
+    ///
+    /// # Errors
+    ///
+    /// Fails for the same reasons as [`CompositeLogger::try_init`].
+    pub fn try_init_with_guard(self) -> Result<FlushGuard, InitError> {
+        self.try_init().map(FlushGuard::new)
+    }
+
+    /// Initializes the global logger with the built composite logger,
+    /// returning a guard that flushes all loggers when dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics for the same reasons as [`CompositeLogger::init`].
+    pub fn init_with_guard(self) -> FlushGuard {
+        FlushGuard::new(self.init())
+    }
+}
+
+/// What a composite logger starts once it is installed or used, since it affects
+/// the whole process or spawns threads
+#[cfg(feature = "std")]
+struct Startup {
+    flush_interval: Option<Duration>,
+    stats_interval: Option<Duration>,
+    panic_hook: bool,
+    boost: Option<(LevelFilter, Duration)>,
+    timestamp_format: Option<TimestampFormat>,
+    time_zone: Option<TimeZone>,
+}
+
+#[cfg(feature = "std")]
+impl Startup {
+    fn start(self, shared: &Arc<SharedLogger>) {
+        if let Some(format) = self.timestamp_format {
+            set_timestamp_format(format);
+        }
+        if let Some(zone) = self.time_zone {
+            set_time_zone(zone);
+        }
+        if let Some(interval) = self.flush_interval {
+            shared.spawn_periodic_flush(interval);
+        }
+        if let Some(interval) = self.stats_interval {
+            shared.spawn_periodic_stats(interval);
+        }
+        if self.panic_hook {
+            shared.install_panic_hook();
+        }
+        if let Some((level, duration)) = self.boost {
+            shared.boost(level, duration);
+        }
+        shared.log_startup();
+    }
+}
+
+#[cfg(feature = "std")]
+impl Extend<Box<dyn Log>> for CompositeLogger {
+    fn extend<T: IntoIterator<Item = Box<dyn Log>>>(&mut self, loggers: T) {
+        loggers.into_iter().for_each(|logger| {
+            self.push_entry(LevelFilter::max(), Route::All, Box::new(logger));
+        });
+    }
+}
+
+#[cfg(feature = "std")]
+impl FromIterator<Box<dyn Log>> for CompositeLogger {
+    fn from_iter<T: IntoIterator<Item = Box<dyn Log>>>(loggers: T) -> Self {
+        Self::new().with_loggers(loggers)
     }
 }
 
+#[cfg(feature = "std")]
 impl Log for CompositeLogger {
     fn enabled(&self, metadata: &Metadata) -> bool {
-        self.loggers.iter().any(|logger| logger.enabled(metadata))
+        self.dispatcher.enabled(metadata)
     }
 
     fn log(&self, record: &Record) {
-        self.loggers
-            .iter()
-            .filter(|logger| logger.enabled(record.metadata()))
-            .for_each(|logger| logger.log(record));
+        self.dispatcher.log(record)
     }
 
     fn flush(&self) {
-        self.loggers.iter().for_each(|logger| logger.flush());
+        self.dispatcher.flush()
     }
 }
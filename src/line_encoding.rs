@@ -0,0 +1,164 @@
+use crate::format;
+
+/// The character encoding of the lines written by a sink
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TextEncoding {
+    #[default]
+    Utf8,
+    /// UTF-16 little endian without a byte order mark, as read by some Windows tools
+    Utf16Le,
+}
+
+/// How the lines written by a sink end
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Newline {
+    #[default]
+    Lf,
+    /// `\r\n`, as expected by many Windows tools
+    CrLf,
+}
+
+/// How a sink turns its formatted lines into bytes: the character encoding,
+/// the line breaks and whether control characters such as ANSI escape sequences
+/// are escaped. By default lines are written as UTF-8 ending with `\n`, as formatted.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LineEncoding {
+    encoding: TextEncoding,
+    newline: Newline,
+    escape_controls: bool,
+}
+
+impl LineEncoding {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_encoding(mut self, encoding: TextEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// End every line, including the lines of multi-line messages, with the newline
+    pub fn with_newline(mut self, newline: Newline) -> Self {
+        self.newline = newline;
+        self
+    }
+
+    /// Write control characters other than line breaks and tabs as escapes such as `\x1b`,
+    /// so that colors and terminal sequences in messages reach the output as text
+    pub fn with_escaped_controls(mut self, escape_controls: bool) -> Self {
+        self.escape_controls = escape_controls;
+        self
+    }
+
+    pub fn encoding(&self) -> TextEncoding {
+        self.encoding
+    }
+
+    pub fn newline(&self) -> Newline {
+        self.newline
+    }
+
+    pub fn escapes_controls(&self) -> bool {
+        self.escape_controls
+    }
+
+    /// Hand the bytes of the lines to the action, the text itself if nothing is to be changed
+    pub(crate) fn with_encoded<T>(&self, text: &str, action: impl FnOnce(&[u8]) -> T) -> T {
+        let replaced = |character: char| match character {
+            '\n' => self.newline == Newline::CrLf,
+            '\r' => true,
+            '\t' => false,
+            character => self.escape_controls && character.is_control(),
+        };
+        if self.encoding == TextEncoding::Utf8 && !text.contains(replaced) {
+            return action(text.as_bytes());
+        }
+        format::with_byte_buffer(|bytes| {
+            self.encode(text, bytes);
+            action(bytes)
+        })
+    }
+
+    /// Append the text with its line breaks and control characters replaced to the bytes
+    fn encode(&self, text: &str, bytes: &mut Vec<u8>) {
+        let newline = match self.newline {
+            Newline::Lf => "\n",
+            Newline::CrLf => "\r\n",
+        };
+        let mut characters = text.char_indices().peekable();
+        let mut start = 0;
+        while let Some((index, character)) = characters.next() {
+            let replacement = match character {
+                // part of a line break, which is written as the newline
+                '\r' if characters.peek().map(|(_, next)| *next) == Some('\n') => "",
+                '\n' => newline,
+                '\t' => continue,
+                character if self.escape_controls && character.is_control() => {
+                    self.push(bytes, &text[start..index]);
+                    self.push(bytes, &escape(character));
+                    start = index + character.len_utf8();
+                    continue;
+                }
+                _ => continue,
+            };
+            self.push(bytes, &text[start..index]);
+            self.push(bytes, replacement);
+            start = index + character.len_utf8();
+        }
+        self.push(bytes, &text[start..]);
+    }
+
+    fn push(&self, bytes: &mut Vec<u8>, text: &str) {
+        match self.encoding {
+            TextEncoding::Utf8 => bytes.extend_from_slice(text.as_bytes()),
+            TextEncoding::Utf16Le => bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes)),
+        }
+    }
+}
+
+/// A control character as `\x1b` if it is ASCII or as `\u{85}` otherwise
+fn escape(character: char) -> String {
+    if character.is_ascii() {
+        format!("\\x{:02x}", u32::from(character))
+    } else {
+        format!("\\u{{{:x}}}", u32::from(character))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoded(encoding: LineEncoding, text: &str) -> Vec<u8> {
+        encoding.with_encoded(text, <[u8]>::to_vec)
+    }
+
+    #[test]
+    fn writes_the_lines_as_formatted_by_default() {
+        let text = "a\tb\x1b[0m\n";
+        assert_eq!(encoded(LineEncoding::new(), text), text.as_bytes());
+        assert_eq!(encoded(LineEncoding::new(), "a\r\nb\n"), b"a\nb\n");
+    }
+
+    #[test]
+    fn ends_the_lines_with_the_newline() {
+        let encoding = LineEncoding::new().with_newline(Newline::CrLf);
+        assert_eq!(encoded(encoding, "a\nb\r\nc\n"), b"a\r\nb\r\nc\r\n");
+    }
+
+    #[test]
+    fn escapes_the_control_characters() {
+        let encoding = LineEncoding::new().with_escaped_controls(true);
+        assert_eq!(
+            encoded(encoding, "\x1b[31mred\u{85}\tdone\n"),
+            b"\\x1b[31mred\\u{85}\tdone\n"
+        );
+    }
+
+    #[test]
+    fn encodes_the_lines_as_utf16() {
+        let encoding = LineEncoding::new().with_encoding(TextEncoding::Utf16Le);
+        assert_eq!(encoded(encoding, "hé\n"), [b'h', 0, 0xe9, 0, b'\n', 0]);
+    }
+}
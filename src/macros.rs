@@ -0,0 +1,138 @@
+/// Builds a [`crate::CompositeLogger`] from a comma separated list of items,
+/// each expanding to a call of the builder method named in parentheses:
+///
+/// - `logger: <logger>` adds a logger ([`crate::CompositeLogger::with_logger`])
+/// - `logger(<level filter>): <logger>` adds a logger receiving records up to the level
+///   ([`crate::CompositeLogger::with_logger_at`])
+/// - `named(<name>): <logger>` adds a named logger ([`crate::CompositeLogger::with_named_logger`])
+/// - `target(<target>): <logger>` adds a logger of a target and its submodules
+///   ([`crate::CompositeLogger::with_target_route`])
+/// - `filtered(<filter>): <logger>` adds a logger receiving the records accepted by
+///   the filter ([`crate::CompositeLogger::with_filtered_logger`])
+/// - `errors: <logger>` adds a logger receiving errors ([`crate::CompositeLogger::route_errors_to`])
+/// - `fallback: <logger>` ([`crate::CompositeLogger::with_fallback_logger`])
+/// - `max_level: <level filter>` ([`crate::CompositeLogger::with_max_level`])
+/// - `filter: <directives>` ([`crate::CompositeLogger::with_filter_directives`])
+/// - `kv(<key>): <value>` ([`crate::CompositeLogger::with_kv`])
+/// - `dispatch: <strategy>` ([`crate::CompositeLogger::with_dispatch_strategy`])
+/// - `async: <capacity>` ([`crate::CompositeLogger::into_async`])
+///
+/// For example:
+///
+/// ```text
+/// composite_logger! {
+///     filter: "info,hyper=warn",
+///     kv("service"): "api",
+///     named("console"): ConsoleLogger::new(),
+///     logger(LevelFilter::Debug): RotatingFileLogger::new("app.log")?,
+///     target("audit"): JsonLogger::new(audit_file),
+/// }
+/// .init();
+/// ```
+#[macro_export]
+macro_rules! composite_logger {
+    (@build $builder:expr;) => {
+        $builder
+    };
+    (@build $builder:expr; logger: $logger:expr $(, $($rest:tt)*)?) => {
+        $crate::composite_logger!(@build $builder.with_logger($logger); $($($rest)*)?)
+    };
+    (@build $builder:expr; logger($level:expr): $logger:expr $(, $($rest:tt)*)?) => {
+        $crate::composite_logger!(@build $builder.with_logger_at($level, $logger); $($($rest)*)?)
+    };
+    (@build $builder:expr; named($name:expr): $logger:expr $(, $($rest:tt)*)?) => {
+        $crate::composite_logger!(@build $builder.with_named_logger($name, $logger); $($($rest)*)?)
+    };
+    (@build $builder:expr; target($target:expr): $logger:expr $(, $($rest:tt)*)?) => {
+        $crate::composite_logger!(@build $builder.with_target_route($target, $logger); $($($rest)*)?)
+    };
+    (@build $builder:expr; filtered($filter:expr): $logger:expr $(, $($rest:tt)*)?) => {
+        $crate::composite_logger!(
+            @build $builder.with_filtered_logger($logger, $filter); $($($rest)*)?
+        )
+    };
+    (@build $builder:expr; errors: $logger:expr $(, $($rest:tt)*)?) => {
+        $crate::composite_logger!(@build $builder.route_errors_to($logger); $($($rest)*)?)
+    };
+    (@build $builder:expr; fallback: $logger:expr $(, $($rest:tt)*)?) => {
+        $crate::composite_logger!(@build $builder.with_fallback_logger($logger); $($($rest)*)?)
+    };
+    (@build $builder:expr; max_level: $level:expr $(, $($rest:tt)*)?) => {
+        $crate::composite_logger!(@build $builder.with_max_level($level); $($($rest)*)?)
+    };
+    (@build $builder:expr; filter: $directives:expr $(, $($rest:tt)*)?) => {
+        $crate::composite_logger!(@build $builder.with_filter_directives($directives); $($($rest)*)?)
+    };
+    (@build $builder:expr; kv($key:expr): $value:expr $(, $($rest:tt)*)?) => {
+        $crate::composite_logger!(@build $builder.with_kv($key, $value); $($($rest)*)?)
+    };
+    (@build $builder:expr; dispatch: $strategy:expr $(, $($rest:tt)*)?) => {
+        $crate::composite_logger!(@build $builder.with_dispatch_strategy($strategy); $($($rest)*)?)
+    };
+    (@build $builder:expr; async: $capacity:expr $(, $($rest:tt)*)?) => {
+        $crate::composite_logger!(@build $builder.into_async($capacity); $($($rest)*)?)
+    };
+    ($($items:tt)*) => {
+        $crate::composite_logger!(@build $crate::CompositeLogger::new(); $($items)*)
+    };
+}
+
+/// Logs an error at the `Error` level with the chain of its sources as key-values,
+/// see [`crate::ErrorChain`], so that every logger receives the whole chain whatever
+/// the message says. The message is the error itself unless given after it,
+/// and the target and level can be given first like with the `log` macros:
+///
+/// ```text
+/// log_error!(error);
+/// log_error!(error, "could not load {}", path.display());
+/// log_error!(target: "config", level: Level::Warn, error);
+/// ```
+///
+/// The error is any `&dyn Error` or value implementing `Error`, use `&*error` for a boxed one.
+#[macro_export]
+macro_rules! log_error {
+    (target: $target:expr, level: $level:expr, $error:expr $(,)?) => {{
+        let error = &$error;
+        $crate::log_error!(target: $target, level: $level, error, "{}", error)
+    }};
+    (target: $target:expr, level: $level:expr, $error:expr, $($arg:tt)+) => {
+        $crate::__log_error(
+            $level,
+            $target,
+            &$error,
+            format_args!($($arg)+),
+            (module_path!(), file!(), line!()),
+        )
+    };
+    (target: $target:expr, $($rest:tt)+) => {
+        $crate::log_error!(target: $target, level: ::log::Level::Error, $($rest)+)
+    };
+    (level: $level:expr, $($rest:tt)+) => {
+        $crate::log_error!(target: module_path!(), level: $level, $($rest)+)
+    };
+    ($($rest:tt)+) => {
+        $crate::log_error!(target: module_path!(), level: ::log::Level::Error, $($rest)+)
+    };
+}
+
+/// A [`crate::Banner`] with the name and version of the crate calling the macro,
+/// and the git commit of the `GIT_HASH` environment variable when the crate was built,
+/// for example set by its build script:
+///
+/// ```text
+/// CompositeLogger::new()
+///     .with_logger(ConsoleLogger::new())
+///     .with_banner(banner!().with_kv("profile", "release"))
+///     .init_with_guard();
+/// ```
+#[macro_export]
+macro_rules! banner {
+    () => {{
+        let banner = $crate::Banner::new()
+            .with_application(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+        match option_env!("GIT_HASH") {
+            Some(git_hash) => banner.with_git_hash(git_hash),
+            None => banner,
+        }
+    }};
+}
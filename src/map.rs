@@ -0,0 +1,46 @@
+use crate::OwnedRecord;
+use log::{Log, Metadata, Record};
+
+/// A logger that receives records rewritten by the mapping function.
+/// Records for which the function returns `None` are dropped.
+///
+/// Since the mapping may change the level or target, the wrapped logger
+/// can only decide whether it is enabled for the rewritten record.
+pub struct MappedLogger<L, F> {
+    logger: L,
+    map: F,
+}
+
+impl<L, F> MappedLogger<L, F>
+where
+    L: Log,
+    F: Fn(&Record) -> Option<OwnedRecord> + Send + Sync,
+{
+    pub fn new(logger: L, map: F) -> Self {
+        Self { logger, map }
+    }
+}
+
+impl<L, F> Log for MappedLogger<L, F>
+where
+    L: Log,
+    F: Fn(&Record) -> Option<OwnedRecord> + Send + Sync,
+{
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        if let Some(mapped) = (self.map)(record) {
+            mapped.with_record(|record| {
+                if self.logger.enabled(record.metadata()) {
+                    self.logger.log(record);
+                }
+            });
+        }
+    }
+
+    fn flush(&self) {
+        self.logger.flush();
+    }
+}
@@ -0,0 +1,409 @@
+//! A compact binary encoding of records as MessagePack maps.
+//!
+//! Every record is a map with the same members as the objects of the
+//! [`crate::sinks::JsonLogger`], except that the `timestamp` is the number of microseconds
+//! since the unix epoch. Records are written back to back without separators,
+//! for example with [`crate::sinks::RotatingFileLogger::with_binary_format`],
+//! and [`to_json`] converts them back to newline delimited JSON.
+
+use crate::binary;
+use crate::format::BinaryFormat;
+use crate::json;
+use crate::{Binary, FormattedRecord};
+use log::kv::{self, Key, VisitSource};
+use log::Record;
+use std::io::{self, Read, Write};
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Encodes records as MessagePack maps, see the [module](self) documentation
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MessagePackFormat;
+
+impl BinaryFormat for MessagePackFormat {
+    fn encode(&self, record: &Record) -> Vec<u8> {
+        let mut out = Vec::with_capacity(128);
+        let has_key_values = record.key_values().count() > 0;
+        let members = 4
+            + usize::from(record.module_path().is_some())
+            + usize::from(record.file().is_some())
+            + usize::from(record.line().is_some())
+            + usize::from(has_key_values);
+        write_map_header(&mut out, members);
+
+        let timestamp = crate::time::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_micros() as u64;
+        write_str(&mut out, "timestamp");
+        write_u64(&mut out, timestamp);
+        write_str(&mut out, "level");
+        write_str(&mut out, record.level().as_str());
+        write_str(&mut out, "target");
+        write_str(&mut out, record.target());
+        write_str(&mut out, "message");
+        write_str(&mut out, FormattedRecord::new(record).message());
+        if let Some(module_path) = record.module_path() {
+            write_str(&mut out, "module");
+            write_str(&mut out, module_path);
+        }
+        if let Some(file) = record.file() {
+            write_str(&mut out, "file");
+            write_str(&mut out, file);
+        }
+        if let Some(line) = record.line() {
+            write_str(&mut out, "line");
+            write_u64(&mut out, u64::from(line));
+        }
+        if has_key_values {
+            write_str(&mut out, "kv");
+            write_map_header(&mut out, record.key_values().count());
+            let _ = record.key_values().visit(&mut WritePairs(&mut out));
+        }
+        out
+    }
+}
+
+/// Writes key-values as the pairs of a map
+struct WritePairs<'a>(&'a mut Vec<u8>);
+
+impl<'kvs> VisitSource<'kvs> for WritePairs<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: kv::Value<'kvs>) -> Result<(), kv::Error> {
+        write_str(self.0, key.as_str());
+        if let Some(binary) = Binary::of(&value) {
+            write_bin(self.0, binary.as_bytes());
+        } else if let Some(value) = value.to_bool() {
+            self.0.push(if value { 0xc3 } else { 0xc2 });
+        } else if let Some(value) = value.to_u64() {
+            write_u64(self.0, value);
+        } else if let Some(value) = value.to_i64() {
+            self.0.push(0xd3);
+            self.0.extend_from_slice(&value.to_be_bytes());
+        } else if let Some(value) = value.to_f64() {
+            self.0.push(0xcb);
+            self.0.extend_from_slice(&value.to_be_bytes());
+        } else if let Some(value) = value.to_borrowed_str() {
+            write_str(self.0, value);
+        } else {
+            write_str(self.0, &value.to_string());
+        }
+        Ok(())
+    }
+}
+
+fn write_map_header(out: &mut Vec<u8>, length: usize) {
+    match length {
+        0..=15 => out.push(0x80 | length as u8),
+        16..=0xffff => {
+            out.push(0xde);
+            out.extend_from_slice(&(length as u16).to_be_bytes());
+        }
+        _ => {
+            out.push(0xdf);
+            out.extend_from_slice(&(length as u32).to_be_bytes());
+        }
+    }
+}
+
+fn write_str(out: &mut Vec<u8>, value: &str) {
+    let length = value.len();
+    match length {
+        0..=31 => out.push(0xa0 | length as u8),
+        32..=0xff => out.extend_from_slice(&[0xd9, length as u8]),
+        0x100..=0xffff => {
+            out.push(0xda);
+            out.extend_from_slice(&(length as u16).to_be_bytes());
+        }
+        _ => {
+            out.push(0xdb);
+            out.extend_from_slice(&(length as u32).to_be_bytes());
+        }
+    }
+    out.extend_from_slice(value.as_bytes());
+}
+
+fn write_bin(out: &mut Vec<u8>, value: &[u8]) {
+    let length = value.len();
+    match length {
+        0..=0xff => out.extend_from_slice(&[0xc4, length as u8]),
+        0x100..=0xffff => {
+            out.push(0xc5);
+            out.extend_from_slice(&(length as u16).to_be_bytes());
+        }
+        _ => {
+            out.push(0xc6);
+            out.extend_from_slice(&(length as u32).to_be_bytes());
+        }
+    }
+    out.extend_from_slice(value);
+}
+
+fn write_u64(out: &mut Vec<u8>, value: u64) {
+    match value {
+        0..=0x7f => out.push(value as u8),
+        0x80..=0xff => out.extend_from_slice(&[0xcc, value as u8]),
+        0x100..=0xffff => {
+            out.push(0xcd);
+            out.extend_from_slice(&(value as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(0xce);
+            out.extend_from_slice(&(value as u32).to_be_bytes());
+        }
+        _ => {
+            out.push(0xcf);
+            out.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+}
+
+/// Convert records encoded with the [`MessagePackFormat`] from the reader to newline delimited
+/// JSON objects like those of the [`crate::sinks::JsonLogger`], written to the writer
+///
+/// # Errors
+///
+/// Fails if reading or writing fails, and with [`io::ErrorKind::InvalidData`]
+/// if the input is not MessagePack or ends in the middle of a record.
+pub fn to_json(reader: impl Read, mut writer: impl Write) -> io::Result<()> {
+    let mut reader = Decoder {
+        reader: io::BufReader::new(reader),
+    };
+    while let Some(marker) = reader.marker()? {
+        let mut line = String::with_capacity(256);
+        reader.write_json(marker, &mut line, true)?;
+        line.push('\n');
+        writer.write_all(line.as_bytes())?;
+    }
+    writer.flush()
+}
+
+struct Decoder<R> {
+    reader: R,
+}
+
+impl<R: Read> Decoder<R> {
+    /// The marker of the next value, `None` at the end of the input
+    fn marker(&mut self) -> io::Result<Option<u8>> {
+        let mut marker = [0];
+        match self.reader.read(&mut marker)? {
+            0 => Ok(None),
+            _ => Ok(Some(marker[0])),
+        }
+    }
+
+    fn bytes<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        let mut bytes = [0; N];
+        self.reader.read_exact(&mut bytes).map_err(truncated)?;
+        Ok(bytes)
+    }
+
+    fn next_value(&mut self, out: &mut String) -> io::Result<()> {
+        let marker = self
+            .marker()?
+            .ok_or_else(|| truncated(io::ErrorKind::UnexpectedEof.into()))?;
+        self.write_json(marker, out, false)
+    }
+
+    /// Write the value starting with the marker as JSON, the members of a record
+    /// with its timestamp as an RFC 3339 string
+    fn write_json(&mut self, marker: u8, out: &mut String, record: bool) -> io::Result<()> {
+        let scalar = match marker {
+            0x00..=0x7f => marker.to_string(),
+            0xe0..=0xff => (marker as i8).to_string(),
+            0xc0 => "null".to_string(),
+            0xc2 => "false".to_string(),
+            0xc3 => "true".to_string(),
+            0xcc => self.bytes::<1>()?[0].to_string(),
+            0xcd => u16::from_be_bytes(self.bytes()?).to_string(),
+            0xce => u32::from_be_bytes(self.bytes()?).to_string(),
+            0xcf => u64::from_be_bytes(self.bytes()?).to_string(),
+            0xd0 => (self.bytes::<1>()?[0] as i8).to_string(),
+            0xd1 => i16::from_be_bytes(self.bytes()?).to_string(),
+            0xd2 => i32::from_be_bytes(self.bytes()?).to_string(),
+            0xd3 => i64::from_be_bytes(self.bytes()?).to_string(),
+            0xca => float(f64::from(f32::from_be_bytes(self.bytes()?))),
+            0xcb => float(f64::from_be_bytes(self.bytes()?)),
+            0xa0..=0xbf => self.string(usize::from(marker & 0x1f))?,
+            0xd9 => {
+                let length = self.bytes::<1>()?[0];
+                self.string(usize::from(length))?
+            }
+            0xda => {
+                let length = u16::from_be_bytes(self.bytes()?);
+                self.string(usize::from(length))?
+            }
+            0xdb => {
+                let length = u32::from_be_bytes(self.bytes()?);
+                self.string(length as usize)?
+            }
+            0xc4 => {
+                let length = self.bytes::<1>()?[0];
+                self.binary(usize::from(length))?
+            }
+            0xc5 => {
+                let length = u16::from_be_bytes(self.bytes()?);
+                self.binary(usize::from(length))?
+            }
+            0xc6 => {
+                let length = u32::from_be_bytes(self.bytes()?);
+                self.binary(length as usize)?
+            }
+            0x90..=0x9f => return self.write_array(usize::from(marker & 0x0f), out),
+            0xdc => {
+                let length = u16::from_be_bytes(self.bytes()?);
+                return self.write_array(usize::from(length), out);
+            }
+            0xdd => {
+                let length = u32::from_be_bytes(self.bytes()?);
+                return self.write_array(length as usize, out);
+            }
+            0x80..=0x8f => return self.write_map(usize::from(marker & 0x0f), out, record),
+            0xde => {
+                let length = u16::from_be_bytes(self.bytes()?);
+                return self.write_map(usize::from(length), out, record);
+            }
+            0xdf => {
+                let length = u32::from_be_bytes(self.bytes()?);
+                return self.write_map(length as usize, out, record);
+            }
+            _ => return Err(invalid("unsupported MessagePack type")),
+        };
+        out.push_str(&scalar);
+        Ok(())
+    }
+
+    fn write_array(&mut self, length: usize, out: &mut String) -> io::Result<()> {
+        out.push('[');
+        for index in 0..length {
+            if index > 0 {
+                out.push(',');
+            }
+            self.next_value(out)?;
+        }
+        out.push(']');
+        Ok(())
+    }
+
+    fn write_map(&mut self, length: usize, out: &mut String, record: bool) -> io::Result<()> {
+        out.push('{');
+        for index in 0..length {
+            if index > 0 {
+                out.push(',');
+            }
+            let mut key = String::new();
+            self.next_value(&mut key)?;
+            if !key.starts_with('"') {
+                // JSON keys are strings
+                key = format!("\"{}\"", key);
+            }
+            out.push_str(&key);
+            out.push(':');
+            if record && key == "\"timestamp\"" {
+                let mut micros = String::new();
+                self.next_value(&mut micros)?;
+                let micros = micros
+                    .parse()
+                    .map_err(|_| invalid("the timestamp is not a number of microseconds"))?;
+                let time = UNIX_EPOCH + Duration::from_micros(micros);
+                json::write_str(out, &crate::timestamp::timestamp(time));
+            } else {
+                self.next_value(out)?;
+            }
+        }
+        out.push('}');
+        Ok(())
+    }
+
+    /// Read a string of the length as a JSON string
+    fn string(&mut self, length: usize) -> io::Result<String> {
+        let mut bytes = vec![0; length];
+        self.reader.read_exact(&mut bytes).map_err(truncated)?;
+        let mut string = String::with_capacity(length + 2);
+        json::write_str(&mut string, &String::from_utf8_lossy(&bytes));
+        Ok(string)
+    }
+
+    /// Read binary data of the length as a base64 JSON string, like binary payloads
+    /// of the [`crate::sinks::JsonLogger`]
+    fn binary(&mut self, length: usize) -> io::Result<String> {
+        let mut bytes = vec![0; length];
+        self.reader.read_exact(&mut bytes).map_err(truncated)?;
+        let mut string = String::with_capacity(length / 3 * 4 + 6);
+        json::write_str(&mut string, &binary::base64(&bytes));
+        Ok(string)
+    }
+}
+
+/// JSON has no infinite numbers
+fn float(value: f64) -> String {
+    match value.is_finite() {
+        true => value.to_string(),
+        false => "null".to_string(),
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn truncated(error: io::Error) -> io::Error {
+    match error.kind() {
+        io::ErrorKind::UnexpectedEof => invalid("the input ends in the middle of a record"),
+        _ => error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sinks::JsonLogger;
+    use crate::OwnedRecord;
+    use log::Level;
+
+    fn records() -> [OwnedRecord; 2] {
+        [
+            OwnedRecord::new(Level::Warn, "app::db", "slow \"query\"")
+                .with_module_path(Some("app::db".to_string()))
+                .with_file(Some("src/db.rs".to_string()))
+                .with_line(Some(42))
+                .with_key_value("table", "users"),
+            OwnedRecord::new(Level::Info, "app", "started"),
+        ]
+    }
+
+    /// The lines without their timestamps, which the formats write with different precisions
+    fn without_timestamps(lines: &str) -> Vec<String> {
+        lines
+            .lines()
+            .map(|line| line.split_once("\",").unwrap().1.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn converts_the_records_to_json_lines() {
+        let mut encoded = Vec::new();
+        let mut expected = String::new();
+        for record in records() {
+            record.with_record(|record| {
+                encoded.extend(MessagePackFormat.encode(record));
+                expected.push_str(&JsonLogger::<io::Sink>::format(record));
+            });
+        }
+
+        let mut json = Vec::new();
+        to_json(encoded.as_slice(), &mut json).unwrap();
+        let json = String::from_utf8(json).unwrap();
+
+        assert!(json.starts_with("{\"timestamp\":\""), "{}", json);
+        assert_eq!(without_timestamps(&json), without_timestamps(&expected));
+    }
+
+    #[test]
+    fn rejects_truncated_and_invalid_input() {
+        let encoded = records()[1].with_record(|record| MessagePackFormat.encode(record));
+        let truncated = to_json(&encoded[..encoded.len() - 1], io::sink()).unwrap_err();
+        let invalid = to_json([0xc1].as_slice(), io::sink()).unwrap_err();
+
+        assert_eq!(truncated.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(invalid.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,71 @@
+use crate::OwnedRecord;
+use std::cell::RefCell;
+use std::thread::{self, ThreadId};
+
+thread_local! {
+    /// The thread that logged the record being dispatched by the worker
+    static ORIGIN: RefCell<Option<Origin>> = const { RefCell::new(None) };
+}
+
+/// The thread a record was logged from, which is not the dispatching thread
+/// in the asynchronous mode
+#[derive(Debug, Clone)]
+pub(crate) struct Origin {
+    name: Option<String>,
+    id: ThreadId,
+}
+
+impl Origin {
+    /// The thread the record was logged from
+    pub(crate) fn of(record: &OwnedRecord) -> Self {
+        Self {
+            name: record.thread_name().map(str::to_string),
+            id: record.thread_id(),
+        }
+    }
+
+    /// Dispatch a record logged from this thread
+    pub(crate) fn dispatching<T>(self, f: impl FnOnce() -> T) -> T {
+        let previous = ORIGIN.with(|origin| origin.replace(Some(self)));
+        let result = f();
+        ORIGIN.with(|origin| *origin.borrow_mut() = previous);
+        result
+    }
+}
+
+/// The name of the thread the record being dispatched was logged from
+pub(crate) fn thread_name<T>(f: impl FnOnce(Option<&str>) -> T) -> T {
+    ORIGIN.with(|origin| match &*origin.borrow() {
+        Some(origin) => f(origin.name.as_deref()),
+        None => f(thread::current().name()),
+    })
+}
+
+/// The id of the thread the record being dispatched was logged from
+pub(crate) fn thread_id() -> ThreadId {
+    ORIGIN.with(|origin| match &*origin.borrow() {
+        Some(origin) => origin.id,
+        None => thread::current().id(),
+    })
+}
+
+/// Whether the text matches the pattern, in which `*` matches any sequence of characters
+pub(crate) fn wildcard_matches(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts = parts.collect::<Vec<_>>();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
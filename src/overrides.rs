@@ -0,0 +1,106 @@
+use crate::dispatch::SinkEntry;
+use log::LevelFilter;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+/// The environment variable read by [`crate::CompositeLogger::with_default_env_overrides`]
+pub const DEFAULT_OVERRIDES_ENV: &str = "COMPOSITE_LOG";
+
+#[derive(Debug, Clone, Copy)]
+enum Change {
+    Level(LevelFilter),
+    Enabled(bool),
+}
+
+/// Changes to the levels and enablement of named loggers parsed from comma separated
+/// `name=level` directives, such as `console=warn,file=debug,net=off`.
+///
+/// A level also switches the logger on, `off` switches it off and `on` back on.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Overrides {
+    directives: Vec<(String, Change)>,
+}
+
+impl Overrides {
+    pub(crate) fn parse(directives: &str) -> Result<Self, OverridesError> {
+        let directives = directives
+            .split(',')
+            .map(str::trim)
+            .filter(|directive| !directive.is_empty())
+            .map(|directive| {
+                let invalid = || OverridesError::Invalid(directive.to_string());
+                let (name, value) = directive.split_once('=').ok_or_else(invalid)?;
+                let change = match value.trim() {
+                    "off" => Change::Enabled(false),
+                    "on" => Change::Enabled(true),
+                    level => Change::Level(level.parse().map_err(|_| invalid())?),
+                };
+                match name.trim() {
+                    "" => Err(invalid()),
+                    name => Ok((name.to_string(), change)),
+                }
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { directives })
+    }
+
+    /// Apply the directives after those of the other overrides
+    pub(crate) fn extend(&mut self, other: Overrides) {
+        self.directives.extend(other.directives);
+    }
+
+    /// The first directive naming none of the loggers
+    pub(crate) fn unknown(&self, loggers: &[Arc<SinkEntry>]) -> Option<OverridesError> {
+        self.directives
+            .iter()
+            .find(|(name, _)| {
+                !loggers
+                    .iter()
+                    .any(|entry| entry.name.as_ref() == Some(name))
+            })
+            .map(|(name, _)| OverridesError::UnknownLogger(name.clone()))
+    }
+
+    /// Apply the directives in order to the loggers with their names
+    pub(crate) fn apply(&self, loggers: &[Arc<SinkEntry>]) {
+        for (name, change) in &self.directives {
+            for entry in loggers
+                .iter()
+                .filter(|entry| entry.name.as_ref() == Some(name))
+            {
+                match *change {
+                    Change::Level(level) => {
+                        entry.set_level(level);
+                        entry.set_paused(false);
+                    }
+                    Change::Enabled(enabled) => entry.set_paused(!enabled),
+                }
+            }
+        }
+    }
+}
+
+/// The overrides of named loggers could not be applied
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OverridesError {
+    /// The directive is not a `name=level`, `name=on` or `name=off` pair
+    Invalid(String),
+    /// No logger has the name
+    UnknownLogger(String),
+}
+
+impl Display for OverridesError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OverridesError::Invalid(directive) => {
+                write!(f, "invalid logger override `{}`", directive)
+            }
+            OverridesError::UnknownLogger(name) => {
+                write!(f, "there is no logger named `{}` to override", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for OverridesError {}
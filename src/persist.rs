@@ -0,0 +1,111 @@
+use crate::time;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// How often at most the state is saved while records are logged
+const SAVE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A small file of `key=value` lines keeping the state of a logger across restarts,
+/// so that a crash-looping program still suppresses and counts its records.
+/// Failures to read or write the file are ignored, the state then starts afresh.
+pub(crate) struct StateFile {
+    path: PathBuf,
+}
+
+impl StateFile {
+    pub(crate) fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// The saved values, empty if the file does not exist or can not be read
+    pub(crate) fn load(&self) -> HashMap<String, String> {
+        let Ok(contents) = fs::read_to_string(&self.path) else {
+            return HashMap::new();
+        };
+        contents
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| (key.to_string(), unescape(value)))
+            .collect()
+    }
+
+    /// Save the values unless they were saved less than a second ago, or anyway when forced,
+    /// replacing the file atomically so that a crash does not leave it half written
+    pub(crate) fn save(
+        &self,
+        saved_at: &mut Option<Instant>,
+        force: bool,
+        values: impl FnOnce() -> Vec<(&'static str, String)>,
+    ) {
+        if !force && saved_at.is_some_and(|saved_at| time::elapsed(saved_at) < SAVE_INTERVAL) {
+            return;
+        }
+        *saved_at = Some(time::instant());
+
+        let contents = values()
+            .into_iter()
+            .map(|(key, value)| format!("{}={}\n", key, escape(&value)))
+            .collect::<String>();
+        let mut temporary = self.path.clone().into_os_string();
+        temporary.push(".tmp");
+        if fs::write(&temporary, contents).is_ok() {
+            let _ = fs::rename(&temporary, &self.path);
+        }
+    }
+}
+
+/// The time as microseconds since the unix epoch
+pub(crate) fn encode_time(time: SystemTime) -> String {
+    let micros = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_micros();
+    micros.to_string()
+}
+
+pub(crate) fn decode_time(value: &str) -> Option<SystemTime> {
+    value
+        .parse()
+        .ok()
+        .map(|micros| UNIX_EPOCH + Duration::from_micros(micros))
+}
+
+/// The instant of this process corresponding to the time, the current instant
+/// for times in the future
+pub(crate) fn instant_of(time: SystemTime) -> Instant {
+    let now = time::instant();
+    let elapsed = time::wall_time().duration_since(time).unwrap_or_default();
+    now.checked_sub(elapsed).unwrap_or(now)
+}
+
+/// The time corresponding to the instant of this process
+pub(crate) fn time_of(instant: Instant) -> SystemTime {
+    time::wall_time() - time::elapsed(instant)
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(char) = chars.next() {
+        match (char, chars.clone().next()) {
+            ('\\', Some('n')) => unescaped.push('\n'),
+            ('\\', Some('r')) => unescaped.push('\r'),
+            ('\\', Some('\\')) => unescaped.push('\\'),
+            (char, _) => {
+                unescaped.push(char);
+                continue;
+            }
+        }
+        chars.next();
+    }
+    unescaped
+}
@@ -0,0 +1,164 @@
+use crate::dispatch::Dispatcher;
+use log::LevelFilter;
+
+/// The names of the built-in profiles in the order of their verbosity
+pub const PROFILE_NAMES: [&str; 4] = ["quiet", "normal", "verbose", "debug"];
+
+/// A named set of levels applied to the whole composite logger at once, so that the
+/// `-q`, `-v` and `-vv` flags of a command line tool reconfigure it consistently,
+/// see [`crate::CompositeLoggerHandle::apply_profile`].
+///
+/// The built-in profiles only change the maximum level: `quiet` to `Warn`, `normal` to `Info`,
+/// `verbose` to `Debug` and `debug` to `Trace`. They can be replaced with
+/// [`crate::CompositeLogger::with_profile`] to also change the levels of targets and of named
+/// loggers. Applying a profile replaces the levels of all targets, while the loggers it
+/// does not name keep their levels, so the profiles of a program should name the same loggers.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Profile {
+    max_level: Option<LevelFilter>,
+    target_levels: Vec<(String, LevelFilter)>,
+    logger_levels: Vec<(String, LevelFilter)>,
+}
+
+impl Profile {
+    /// A profile keeping the maximum level and clearing the levels of targets
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The built-in profile with the name, if any
+    pub fn builtin(name: &str) -> Option<Self> {
+        let level = match name {
+            "quiet" => LevelFilter::Warn,
+            "normal" => LevelFilter::Info,
+            "verbose" => LevelFilter::Debug,
+            "debug" => LevelFilter::Trace,
+            _ => return None,
+        };
+        Some(Self::new().with_max_level(level))
+    }
+
+    /// The name of the built-in profile for the number of times a command line tool was
+    /// passed its verbose flag, such as `-vv`, and its quiet flag
+    pub fn name_for_verbosity(verbose: u8, quiet: u8) -> &'static str {
+        match i16::from(verbose) - i16::from(quiet) {
+            ..0 => "quiet",
+            0 => "normal",
+            1 => "verbose",
+            _ => "debug",
+        }
+    }
+
+    /// Dispatch no record more verbose than the level,
+    /// see [`crate::CompositeLogger::with_max_level`]
+    pub fn with_max_level(mut self, level: LevelFilter) -> Self {
+        self.max_level = Some(level);
+        self
+    }
+
+    /// Let the records of the target through up to the level,
+    /// see [`crate::CompositeLoggerHandle::set_target_level`]
+    pub fn with_target_level(mut self, target: impl Into<String>, level: LevelFilter) -> Self {
+        self.target_levels.push((target.into(), level));
+        self
+    }
+
+    /// Change the level of the named logger, see [`crate::CompositeLoggerHandle::set_level`]
+    pub fn with_logger_level(mut self, name: impl Into<String>, level: LevelFilter) -> Self {
+        self.logger_levels.push((name.into(), level));
+        self
+    }
+
+    pub(crate) fn apply(&self, dispatcher: &mut Dispatcher) {
+        if let Some(level) = self.max_level {
+            dispatcher.max_level = Some(level);
+        }
+        dispatcher.target_levels.clear();
+        for (target, level) in &self.target_levels {
+            dispatcher.set_target_level(target, *level);
+        }
+        for (name, level) in &self.logger_levels {
+            for entry in dispatcher
+                .loggers
+                .iter()
+                .filter(|entry| entry.name.as_ref() == Some(name))
+            {
+                entry.set_level(*level);
+            }
+        }
+    }
+}
+
+/// The profile with the name, registered or else built-in
+pub(crate) fn find(dispatcher: &Dispatcher, name: &str) -> Option<Profile> {
+    dispatcher
+        .profiles
+        .iter()
+        .find(|(profile, _)| profile == name)
+        .map(|(_, profile)| profile.clone())
+        .or_else(|| Profile::builtin(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, CaptureLogger};
+    use crate::{CompositeLogger, OwnedRecord};
+    use log::{Level, Log};
+    use std::sync::Arc;
+
+    fn log(logger: &impl Log, level: Level, target: &str, message: &str) {
+        OwnedRecord::new(level, target, message).with_record(|record| logger.log(record));
+    }
+
+    #[test]
+    fn applies_the_levels_of_the_profile() {
+        let capture = Arc::new(CaptureLogger::new());
+        let profile = Profile::new()
+            .with_max_level(LevelFilter::Info)
+            .with_target_level("app::db", LevelFilter::Trace)
+            .with_logger_level("capture", LevelFilter::Debug);
+        let handle = CompositeLogger::new()
+            .with_named_logger("capture", capture.clone())
+            .with_profile("ops", profile)
+            .build();
+
+        assert!(handle.apply_profile("ops"));
+        log(&handle, Level::Debug, "app", "above the maximum level");
+        log(&handle, Level::Debug, "app::db", "debug");
+        log(&handle, Level::Trace, "app::db", "above the logger level");
+
+        assert_eq!(handle.level("capture"), Some(LevelFilter::Debug));
+        assert_eq!(
+            capture.entries(),
+            [(Level::Debug, "app::db".to_string(), "debug".to_string())]
+        );
+        assert!(!handle.apply_profile("missing"));
+    }
+
+    #[test]
+    fn applies_the_built_in_profiles() {
+        let capture = Arc::new(CaptureLogger::new());
+        let handle = CompositeLogger::new().with_logger(capture.clone()).build();
+
+        assert!(handle.apply_profile("quiet"));
+        log(&handle, Level::Info, "app", "hidden");
+        assert!(handle.apply_profile("debug"));
+        log(&handle, Level::Trace, "app", "shown");
+
+        capture.assert_not_logged(Level::Info, testing::equals("hidden"));
+        capture.assert_logged(Level::Trace, testing::equals("shown"));
+    }
+
+    #[test]
+    fn names_the_profile_for_the_verbosity_flags() {
+        assert_eq!(Profile::name_for_verbosity(0, 2), "quiet");
+        assert_eq!(Profile::name_for_verbosity(1, 1), "normal");
+        assert_eq!(Profile::name_for_verbosity(1, 0), "verbose");
+        assert_eq!(Profile::name_for_verbosity(3, 0), "debug");
+        assert_eq!(
+            PROFILE_NAMES.map(|name| Profile::builtin(name).is_some()),
+            [true; 4]
+        );
+    }
+}
@@ -0,0 +1,282 @@
+use crate::persist::{self, StateFile};
+use crate::time;
+use crate::OwnedRecord;
+use log::{Level, Log, Metadata, Record};
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// How often at most a summary of the suppressed records is logged
+const SUMMARY_INTERVAL: Duration = Duration::from_secs(10);
+
+struct State {
+    tokens: f64,
+    refilled_at: Instant,
+    suppressed: u64,
+    suppressed_since: Instant,
+    saved_at: Option<Instant>,
+}
+
+impl State {
+    /// Restore the state saved by a previous process, if any
+    fn load(&mut self, file: &StateFile, burst: f64) {
+        let values = file.load();
+        let time = |key| {
+            values
+                .get(key)
+                .and_then(|value| persist::decode_time(value))
+        };
+        if let (Some(tokens), Some(refilled_at)) = (
+            values
+                .get("tokens")
+                .and_then(|tokens| tokens.parse::<f64>().ok()),
+            time("refilled_at"),
+        ) {
+            self.tokens = tokens.clamp(0.0, burst);
+            self.refilled_at = persist::instant_of(refilled_at);
+        }
+        if let (Some(suppressed), Some(since)) = (
+            values
+                .get("suppressed")
+                .and_then(|count| count.parse().ok()),
+            time("suppressed_since"),
+        ) {
+            self.suppressed = suppressed;
+            self.suppressed_since = persist::instant_of(since);
+        }
+    }
+
+    fn values(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("tokens", self.tokens.to_string()),
+            (
+                "refilled_at",
+                persist::encode_time(persist::time_of(self.refilled_at)),
+            ),
+            ("suppressed", self.suppressed.to_string()),
+            (
+                "suppressed_since",
+                persist::encode_time(persist::time_of(self.suppressed_since)),
+            ),
+        ]
+    }
+}
+
+/// A logger that receives at most the given number of records per second,
+/// allowing short bursts, and is told how many records were suppressed
+/// at most once per ten seconds
+pub struct RateLimitedLogger<L> {
+    logger: L,
+    per_second: f64,
+    burst: f64,
+    state: Mutex<State>,
+    file: Option<StateFile>,
+}
+
+impl<L: Log> RateLimitedLogger<L> {
+    pub fn new(logger: L, max_per_second: u32, burst: u32) -> Self {
+        let burst = f64::from(burst.max(1));
+        let now = time::instant();
+
+        Self {
+            logger,
+            per_second: f64::from(max_per_second),
+            burst,
+            state: Mutex::new(State {
+                tokens: burst,
+                refilled_at: now,
+                suppressed: 0,
+                suppressed_since: now,
+                saved_at: None,
+            }),
+            file: None,
+        }
+    }
+
+    /// Keep the remaining allowance and the number of suppressed records in a small
+    /// state file, so that a crash-looping program is still limited across restarts
+    /// and the summary counts the records suppressed since before the restart.
+    /// The state is saved at most once a second and when the logger is flushed or dropped,
+    /// and flushing does not log the summary before the ten seconds are over.
+    pub fn with_state_file(mut self, path: impl Into<PathBuf>) -> Self {
+        let file = StateFile::new(path);
+        let burst = self.burst;
+        self.lock().load(&file, burst);
+        self.file = Some(file);
+        self
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    fn save(&self, state: &mut State, force: bool) {
+        if let Some(file) = &self.file {
+            let values = state.values();
+            file.save(&mut state.saved_at, force, || values);
+        }
+    }
+
+    /// Takes the pending summary, if any, once enough time has passed or when forced
+    fn take_summary(state: &mut State, force: bool) -> Option<OwnedRecord> {
+        let elapsed = time::elapsed(state.suppressed_since);
+        if state.suppressed == 0 || (!force && elapsed < SUMMARY_INTERVAL) {
+            return None;
+        }
+
+        let suppressed = std::mem::take(&mut state.suppressed);
+        Some(OwnedRecord::new(
+            Level::Warn,
+            module_path!(),
+            format!(
+                "suppressed {} records in the last {}s",
+                suppressed,
+                elapsed.as_secs().max(1)
+            ),
+        ))
+    }
+
+    fn log_summary(&self, summary: Option<OwnedRecord>) {
+        if let Some(summary) = summary {
+            summary.with_record(|summary| self.logger.log(summary));
+        }
+    }
+}
+
+impl<L: Log> Log for RateLimitedLogger<L> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.logger.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        let (allowed, summary) = {
+            let mut state = self.lock();
+            let now = time::instant();
+            let refill = now.duration_since(state.refilled_at).as_secs_f64() * self.per_second;
+            state.tokens = (state.tokens + refill).min(self.burst);
+            state.refilled_at = now;
+
+            let result = if state.tokens >= 1.0 {
+                state.tokens -= 1.0;
+                (true, Self::take_summary(&mut state, false))
+            } else {
+                if state.suppressed == 0 {
+                    state.suppressed_since = now;
+                }
+                state.suppressed += 1;
+                (false, None)
+            };
+            self.save(&mut state, false);
+            result
+        };
+
+        self.log_summary(summary);
+        if allowed {
+            self.logger.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        let summary = {
+            let mut state = self.lock();
+            let summary = Self::take_summary(&mut state, self.file.is_none());
+            self.save(&mut state, true);
+            summary
+        };
+        self.log_summary(summary);
+        self.logger.flush();
+    }
+}
+
+impl<L> Drop for RateLimitedLogger<L> {
+    fn drop(&mut self) {
+        if let Some(file) = &self.file {
+            let state = self
+                .state
+                .get_mut()
+                .unwrap_or_else(|error| error.into_inner());
+            let values = state.values();
+            file.save(&mut state.saved_at, true, || values);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, CaptureLogger};
+    use std::sync::Arc;
+
+    fn log(logger: &impl Log, message: &str) {
+        OwnedRecord::new(Level::Info, "my_app", message).with_record(|record| logger.log(record));
+    }
+
+    #[test]
+    fn bursts_are_allowed_then_limited() {
+        testing::with_mock_clock(|_| {
+            let capture = Arc::new(CaptureLogger::new());
+            let logger = RateLimitedLogger::new(capture.clone(), 1, 3);
+            for index in 0..5 {
+                log(&logger, &index.to_string());
+            }
+            let logged: Vec<_> = capture
+                .records()
+                .iter()
+                .map(|record| record.message().to_string())
+                .collect();
+            assert_eq!(logged, ["0", "1", "2"]);
+        });
+    }
+
+    #[test]
+    fn allowance_refills_over_time() {
+        testing::with_mock_clock(|clock| {
+            let capture = Arc::new(CaptureLogger::new());
+            let logger = RateLimitedLogger::new(capture.clone(), 2, 1);
+            log(&logger, "first");
+            log(&logger, "suppressed");
+            clock.advance(Duration::from_millis(500));
+            log(&logger, "refilled");
+            log(&logger, "suppressed");
+            assert_eq!(capture.records().len(), 2);
+            capture.assert_logged(Level::Info, testing::equals("refilled"));
+        });
+    }
+
+    #[test]
+    fn suppressed_records_are_summarized_after_the_interval() {
+        testing::with_mock_clock(|clock| {
+            let capture = Arc::new(CaptureLogger::new());
+            let logger = RateLimitedLogger::new(capture.clone(), 1, 1);
+            log(&logger, "first");
+            log(&logger, "suppressed");
+            log(&logger, "suppressed");
+            clock.advance(Duration::from_secs(1));
+            log(&logger, "before the summary is due");
+            capture.assert_not_logged(Level::Warn, testing::contains("suppressed"));
+
+            log(&logger, "suppressed");
+            clock.advance(SUMMARY_INTERVAL);
+            log(&logger, "after the interval");
+            capture.assert_logged(
+                Level::Warn,
+                testing::equals("suppressed 3 records in the last 11s"),
+            );
+        });
+    }
+
+    #[test]
+    fn flush_logs_the_summary() {
+        testing::with_mock_clock(|_| {
+            let capture = Arc::new(CaptureLogger::new());
+            let logger = RateLimitedLogger::new(capture.clone(), 1, 1);
+            log(&logger, "first");
+            log(&logger, "suppressed");
+            logger.flush();
+            capture.assert_logged(
+                Level::Warn,
+                testing::equals("suppressed 1 records in the last 1s"),
+            );
+        });
+    }
+}
@@ -0,0 +1,244 @@
+use crate::binary::{Binaries, Binary};
+use crate::kv::Collect;
+use crate::origin;
+use crate::FormattedRecord;
+use log::kv::{Error, Key, Source, ToValue, Value, VisitSource};
+use log::{Level, Metadata, Record};
+use std::thread::{self, ThreadId};
+use std::time::SystemTime;
+
+/// A log record that owns its data, so that it can be sent to another thread
+/// and dispatched later
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedRecord {
+    level: Level,
+    target: String,
+    message: String,
+    module_path: Option<String>,
+    file: Option<String>,
+    line: Option<u32>,
+    key_values: Vec<(String, String)>,
+    /// The binary payloads among the key-values, which keep them for the sinks
+    /// that render them other than as hex strings
+    binaries: Vec<(String, Binary)>,
+    timestamp: SystemTime,
+    thread_name: Option<String>,
+    thread_id: ThreadId,
+}
+
+impl OwnedRecord {
+    pub fn new(level: Level, target: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            level,
+            target: target.into(),
+            message: message.into(),
+            module_path: None,
+            file: None,
+            line: None,
+            key_values: Vec::new(),
+            binaries: Vec::new(),
+            timestamp: crate::time::wall_time(),
+            thread_name: thread::current().name().map(ToString::to_string),
+            thread_id: thread::current().id(),
+        }
+    }
+
+    pub fn with_level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    pub fn with_target(mut self, target: impl Into<String>) -> Self {
+        self.target = target.into();
+        self
+    }
+
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = message.into();
+        self
+    }
+
+    pub fn with_module_path(mut self, module_path: Option<String>) -> Self {
+        self.module_path = module_path;
+        self
+    }
+
+    pub fn with_file(mut self, file: Option<String>) -> Self {
+        self.file = file;
+        self
+    }
+
+    pub fn with_line(mut self, line: Option<u32>) -> Self {
+        self.line = line;
+        self
+    }
+
+    /// Add a key-value pair to the structured data of the record
+    pub fn with_key_value(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.key_values.push((key.into(), value.into()));
+        self
+    }
+
+    /// Remove the structured data of the record
+    pub fn without_key_values(mut self) -> Self {
+        self.key_values.clear();
+        self.binaries.clear();
+        self
+    }
+
+    /// Change when the record was logged, by default when it was created
+    pub fn with_timestamp(mut self, timestamp: SystemTime) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// Change the thread the record was logged from, by default the thread it was created on
+    pub fn with_thread(mut self, name: Option<String>, id: ThreadId) -> Self {
+        self.thread_name = name;
+        self.thread_id = id;
+        self
+    }
+
+    pub fn level(&self) -> Level {
+        self.level
+    }
+
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /// The formatted message of the record
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn module_path(&self) -> Option<&str> {
+        self.module_path.as_deref()
+    }
+
+    pub fn file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+
+    pub fn line(&self) -> Option<u32> {
+        self.line
+    }
+
+    /// The structured data of the record, with the values formatted as strings
+    pub fn key_values(&self) -> &[(String, String)] {
+        &self.key_values
+    }
+
+    /// When the record was logged
+    pub fn timestamp(&self) -> SystemTime {
+        self.timestamp
+    }
+
+    /// The name of the thread the record was logged from
+    pub fn thread_name(&self) -> Option<&str> {
+        self.thread_name.as_deref()
+    }
+
+    /// The id of the thread the record was logged from
+    pub fn thread_id(&self) -> ThreadId {
+        self.thread_id
+    }
+
+    pub fn metadata(&self) -> Metadata<'_> {
+        Metadata::builder()
+            .level(self.level)
+            .target(&self.target)
+            .build()
+    }
+
+    /// The approximate memory taken by the record
+    pub(crate) fn size(&self) -> usize {
+        let strings = [
+            Some(&self.target),
+            Some(&self.message),
+            self.module_path.as_ref(),
+            self.file.as_ref(),
+            self.thread_name.as_ref(),
+        ];
+        size_of::<Self>()
+            + strings
+                .iter()
+                .flatten()
+                .map(|string| string.len())
+                .sum::<usize>()
+            + self
+                .key_values
+                .iter()
+                .map(|(key, value)| size_of::<(String, String)>() + key.len() + value.len())
+                .sum::<usize>()
+            + self
+                .binaries
+                .iter()
+                .map(|(key, binary)| {
+                    size_of::<(String, Binary)>() + key.len() + binary.as_bytes().len()
+                })
+                .sum::<usize>()
+    }
+
+    /// Passes a borrowed [`Record`] built from this owned record to the given function.
+    /// While it runs, the built-in sinks and formats use the timestamp of the record
+    /// instead of the current time.
+    pub fn with_record<T>(&self, f: impl FnOnce(&Record) -> T) -> T {
+        crate::time::logged_at(self.timestamp, || {
+            f(&Record::builder()
+                .level(self.level)
+                .target(&self.target)
+                .args(format_args!("{}", self.message))
+                .module_path(self.module_path.as_deref())
+                .file(self.file.as_deref())
+                .line(self.line)
+                .key_values(&KeyValues {
+                    key_values: &self.key_values,
+                    binaries: &self.binaries,
+                })
+                .build())
+        })
+    }
+}
+
+impl From<&Record<'_>> for OwnedRecord {
+    fn from(record: &Record) -> Self {
+        let mut key_values = Collect(Vec::new());
+        let _ = record.key_values().visit(&mut key_values);
+        let mut binaries = Binaries(Vec::new());
+        let _ = record.key_values().visit(&mut binaries);
+
+        Self {
+            level: record.level(),
+            target: record.target().to_string(),
+            message: FormattedRecord::new(record).message().to_string(),
+            module_path: record.module_path().map(ToString::to_string),
+            file: record.file().map(ToString::to_string),
+            line: record.line(),
+            key_values: key_values.0,
+            binaries: binaries.0,
+            timestamp: crate::time::now(),
+            thread_name: origin::thread_name(|name| name.map(ToString::to_string)),
+            thread_id: origin::thread_id(),
+        }
+    }
+}
+
+/// The key-values of an owned record, with the binary payloads as they were logged
+struct KeyValues<'a> {
+    key_values: &'a [(String, String)],
+    binaries: &'a [(String, Binary)],
+}
+
+impl Source for KeyValues<'_> {
+    fn visit<'kvs>(&'kvs self, visitor: &mut dyn VisitSource<'kvs>) -> Result<(), Error> {
+        for (key, value) in self.key_values {
+            let value = match self.binaries.iter().find(|(name, _)| name == key) {
+                Some((_, binary)) => binary.to_value(),
+                None => Value::from(value.as_str()),
+            };
+            visitor.visit_pair(Key::from_str(key), value)?;
+        }
+        Ok(())
+    }
+}
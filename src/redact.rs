@@ -0,0 +1,218 @@
+use crate::OwnedRecord;
+use log::{Log, Metadata, Record};
+
+/// The replacement of redacted values unless configured otherwise
+const DEFAULT_MASK: &str = "[REDACTED]";
+
+/// Rules to scrub sensitive data from records, such as the values of `password`
+/// key-values or e-mail addresses in messages.
+///
+/// Key rules replace the whole value of key-values whose key matches ignoring case,
+/// the other rules mask parts of the message and of the remaining values.
+pub struct Redaction {
+    keys: Vec<String>,
+    emails: bool,
+    #[cfg(feature = "regex")]
+    patterns: Vec<regex::Regex>,
+    mask: String,
+}
+
+impl Default for Redaction {
+    fn default() -> Self {
+        Self {
+            keys: Vec::new(),
+            emails: false,
+            #[cfg(feature = "regex")]
+            patterns: Vec::new(),
+            mask: DEFAULT_MASK.to_string(),
+        }
+    }
+}
+
+impl Redaction {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the values of key-values with the given key, ignoring case
+    pub fn with_key(mut self, key: impl Into<String>) -> Self {
+        self.keys.push(key.into().to_lowercase());
+        self
+    }
+
+    /// Replace the values of key-values with any of the given keys, ignoring case
+    pub fn with_keys<K: Into<String>>(self, keys: impl IntoIterator<Item = K>) -> Self {
+        keys.into_iter().fold(self, Self::with_key)
+    }
+
+    /// Mask e-mail addresses
+    pub fn with_emails(mut self) -> Self {
+        self.emails = true;
+        self
+    }
+
+    /// Mask every match of the regular expression
+    ///
+    /// # Errors
+    ///
+    /// Fails if the regular expression is invalid.
+    #[cfg(feature = "regex")]
+    pub fn with_pattern(mut self, pattern: &str) -> Result<Self, regex::Error> {
+        self.patterns.push(regex::Regex::new(pattern)?);
+        Ok(self)
+    }
+
+    /// Replace redacted values with the given mask, by default `[REDACTED]`
+    pub fn with_mask(mut self, mask: impl Into<String>) -> Self {
+        self.mask = mask.into();
+        self
+    }
+
+    /// The record with its sensitive data scrubbed
+    pub fn redact(&self, record: &Record) -> OwnedRecord {
+        let owned = OwnedRecord::from(record);
+        let key_values = owned
+            .key_values()
+            .iter()
+            .map(|(key, value)| {
+                let value = if self.keys.contains(&key.to_lowercase()) {
+                    self.mask.clone()
+                } else {
+                    self.mask_text(value)
+                };
+                (key.clone(), value)
+            })
+            .collect::<Vec<_>>();
+        let message = self.mask_text(owned.message());
+
+        key_values.into_iter().fold(
+            owned.with_message(message).without_key_values(),
+            |redacted, (key, value)| redacted.with_key_value(key, value),
+        )
+    }
+
+    fn mask_text(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        if self.emails {
+            text = mask_emails(&text, &self.mask);
+        }
+        #[cfg(feature = "regex")]
+        for pattern in &self.patterns {
+            text = pattern.replace_all(&text, self.mask.as_str()).into_owned();
+        }
+        text
+    }
+}
+
+/// Replace every `local@domain.tld` address in the text with the mask
+fn mask_emails(text: &str, mask: &str) -> String {
+    let is_local = |char: char| char.is_ascii_alphanumeric() || "._%+-".contains(char);
+    let is_domain = |char: char| char.is_ascii_alphanumeric() || ".-".contains(char);
+    let mut masked = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(at) = rest.find('@') {
+        let start = rest[..at]
+            .rfind(|char| !is_local(char))
+            .map_or(0, |index| index + 1);
+        let domain = &rest[at + 1..];
+        let end = domain.find(|char| !is_domain(char)).unwrap_or(domain.len());
+        let domain = domain[..end].trim_end_matches('.');
+
+        if start < at && domain.contains('.') && !domain.starts_with('.') {
+            masked.push_str(&rest[..start]);
+            masked.push_str(mask);
+            rest = &rest[at + 1 + domain.len()..];
+        } else {
+            masked.push_str(&rest[..=at]);
+            rest = &rest[at + 1..];
+        }
+    }
+
+    masked.push_str(rest);
+    masked
+}
+
+/// A logger that receives records scrubbed by the redaction rules,
+/// so that some loggers store raw records while others only receive scrubbed ones
+pub struct RedactedLogger<L> {
+    logger: L,
+    redaction: Redaction,
+}
+
+impl<L: Log> RedactedLogger<L> {
+    pub fn new(logger: L, redaction: Redaction) -> Self {
+        Self { logger, redaction }
+    }
+}
+
+impl<L: Log> Log for RedactedLogger<L> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.logger.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        self.redaction
+            .redact(record)
+            .with_record(|record| self.logger.log(record));
+    }
+
+    fn flush(&self) {
+        self.logger.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::Level;
+
+    fn redact(redaction: &Redaction, record: OwnedRecord) -> OwnedRecord {
+        record.with_record(|record| redaction.redact(record))
+    }
+
+    #[test]
+    fn replaces_the_values_of_sensitive_keys() {
+        let redaction = Redaction::new().with_keys(["password", "Token"]);
+        let record = OwnedRecord::new(Level::Info, "app", "login")
+            .with_key_value("user", "ann")
+            .with_key_value("PASSWORD", "hunter2")
+            .with_key_value("token", "abc");
+
+        assert_eq!(
+            redact(&redaction, record).key_values(),
+            [
+                ("user".to_string(), "ann".to_string()),
+                ("PASSWORD".to_string(), "[REDACTED]".to_string()),
+                ("token".to_string(), "[REDACTED]".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn masks_emails_in_messages_and_values() {
+        let redaction = Redaction::new().with_emails().with_mask("***");
+        let record = OwnedRecord::new(
+            Level::Info,
+            "app",
+            "mail to ann.lee+x@mail.example.com, not to @home or a@b.",
+        )
+        .with_key_value("from", "<bob@example.org>");
+        let redacted = redact(&redaction, record);
+
+        assert_eq!(redacted.message(), "mail to ***, not to @home or a@b.");
+        assert_eq!(redacted.key_values()[0].1, "<***>");
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn masks_the_matches_of_patterns() {
+        let redaction = Redaction::new().with_pattern(r"\d{4}-\d{4}").unwrap();
+        let record = OwnedRecord::new(Level::Info, "app", "card 1234-5678 charged");
+
+        assert_eq!(
+            redact(&redaction, record).message(),
+            "card [REDACTED] charged"
+        );
+    }
+}
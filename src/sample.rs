@@ -0,0 +1,106 @@
+use crate::TargetTree;
+use log::{Level, Log, Metadata, Record};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Accepts an evenly spread fraction of the records it is asked about
+struct Sampler {
+    ratio: f64,
+    seen: AtomicU64,
+}
+
+impl Sampler {
+    fn new(ratio: f64) -> Self {
+        Self {
+            ratio: ratio.clamp(0.0, 1.0),
+            seen: AtomicU64::new(0),
+        }
+    }
+
+    fn sample(&self) -> bool {
+        let seen = self.seen.fetch_add(1, Ordering::Relaxed) as f64;
+        ((seen + 1.0) * self.ratio).floor() > (seen * self.ratio).floor()
+    }
+}
+
+/// A logger that receives all `Info` and more severe records,
+/// but only the given fraction of the `Debug` and `Trace` records.
+///
+/// Records are sampled evenly rather than randomly, so a ratio of `0.01`
+/// delivers exactly every hundredth record.
+pub struct SampledLogger<L> {
+    logger: L,
+    default: Sampler,
+    targets: TargetTree<Sampler>,
+}
+
+impl<L: Log> SampledLogger<L> {
+    /// The ratio is clamped between `0.0` (drop all) and `1.0` (keep all)
+    pub fn new(logger: L, ratio: f64) -> Self {
+        Self {
+            logger,
+            default: Sampler::new(ratio),
+            targets: TargetTree::new(),
+        }
+    }
+
+    /// Sample the records of the target and its submodules with a different ratio
+    pub fn with_target_ratio(mut self, target: impl Into<String>, ratio: f64) -> Self {
+        self.targets.insert(&target.into(), Sampler::new(ratio));
+        self
+    }
+
+    fn sampler(&self, target: &str) -> &Sampler {
+        self.targets.find(target).unwrap_or(&self.default)
+    }
+}
+
+impl<L: Log> Log for SampledLogger<L> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.logger.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if record.level() <= Level::Info || self.sampler(record.target()).sample() {
+            self.logger.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.logger.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::CaptureLogger;
+    use crate::OwnedRecord;
+    use std::sync::Arc;
+
+    /// How many of the records of the level and target reach the logger
+    fn delivered(ratio: f64, level: Level, target: &str, records: usize) -> usize {
+        let capture = Arc::new(CaptureLogger::new());
+        let logger = SampledLogger::new(capture.clone(), ratio).with_target_ratio("app::db", 0.5);
+        for _ in 0..records {
+            OwnedRecord::new(level, target, "message").with_record(|record| logger.log(record));
+        }
+        capture.entries().len()
+    }
+
+    #[test]
+    fn samples_verbose_records_evenly() {
+        assert_eq!(delivered(0.1, Level::Debug, "app", 100), 10);
+        assert_eq!(delivered(0.0, Level::Trace, "app", 100), 0);
+        assert_eq!(delivered(2.0, Level::Debug, "app", 100), 100);
+    }
+
+    #[test]
+    fn keeps_every_info_record() {
+        assert_eq!(delivered(0.0, Level::Info, "app", 10), 10);
+    }
+
+    #[test]
+    fn samples_the_records_of_a_target_with_its_ratio() {
+        assert_eq!(delivered(0.1, Level::Debug, "app::db::pool", 100), 50);
+    }
+}
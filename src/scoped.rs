@@ -0,0 +1,77 @@
+use log::{LevelFilter, Log, Metadata, Record};
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+thread_local! {
+    static SCOPED: RefCell<Vec<Box<dyn Log>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// How many scopes are active on all threads, while any is the global maximum level is `Trace`
+static ACTIVE: AtomicUsize = AtomicUsize::new(0);
+
+/// Deliver the records logged on the current thread while running `f` to the given logger
+/// instead of the loggers of the composite logger, for example to capture the records
+/// of a single test. Scopes can be nested, the innermost logger receives the records.
+///
+/// Only records of the global composite logger are delivered, since the composite logger
+/// has to be the global logger to see them. Records logged on other threads,
+/// including the worker of the asynchronous mode, are not affected.
+pub fn with_scoped<T>(logger: impl Log + 'static, f: impl FnOnce() -> T) -> T {
+    SCOPED.with(|scoped| scoped.borrow_mut().push(Box::new(logger)));
+    ACTIVE.fetch_add(1, Ordering::Relaxed);
+    log::set_max_level(LevelFilter::Trace);
+
+    let _scope = Scope;
+    f()
+}
+
+/// Ends the scope even if the closure panics
+struct Scope;
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        let logger = SCOPED.with(|scoped| scoped.borrow_mut().pop());
+        if let Some(logger) = logger {
+            logger.flush();
+        }
+        if ACTIVE.fetch_sub(1, Ordering::Relaxed) == 1 {
+            crate::handle::restore_max_level();
+        }
+    }
+}
+
+/// Whether any scope is active on any thread
+pub(crate) fn is_active() -> bool {
+    ACTIVE.load(Ordering::Relaxed) > 0
+}
+
+/// Whether the scoped logger of the current thread is enabled, `None` outside of a scope
+pub(crate) fn enabled(metadata: &Metadata) -> Option<bool> {
+    if !is_active() {
+        return None;
+    }
+    SCOPED.with(|scoped| {
+        let scoped = scoped.try_borrow().ok()?;
+        scoped.last().map(|logger| logger.enabled(metadata))
+    })
+}
+
+/// Deliver the record to the scoped logger of the current thread,
+/// returning `false` outside of a scope
+pub(crate) fn log(record: &Record) -> bool {
+    if !is_active() {
+        return false;
+    }
+    SCOPED.with(|scoped| {
+        let Ok(scoped) = scoped.try_borrow() else {
+            return false;
+        };
+        match scoped.last() {
+            Some(logger) => {
+                logger.log(record);
+                true
+            }
+            None => false,
+        }
+    })
+}
@@ -0,0 +1,59 @@
+//! Flushing the loggers when the process is asked to terminate,
+//! see [`crate::CompositeLoggerHandle::flush_on_shutdown_signals`].
+
+use crate::CompositeLoggerHandle;
+use std::io;
+use std::time::Duration;
+
+/// Flush the loggers for up to the timeout on `SIGINT` and `SIGTERM`,
+/// then terminate the process as the signal would have
+#[cfg(unix)]
+pub(crate) fn install(handle: CompositeLoggerHandle, timeout: Duration) -> io::Result<()> {
+    use signal_hook::consts::{SIGINT, SIGTERM};
+    use signal_hook::iterator::Signals;
+
+    let mut signals = Signals::new([SIGINT, SIGTERM])?;
+    std::thread::Builder::new()
+        .name("composite-logger-shutdown".to_string())
+        .spawn(move || {
+            if let Some(signal) = signals.forever().next() {
+                handle.flush_timeout(timeout);
+                let _ = signal_hook::low_level::emulate_default_handler(signal);
+            }
+        })?;
+    Ok(())
+}
+
+/// Flush the loggers for up to the timeout on the console control events,
+/// such as Ctrl+C or closing the console, before the default handler ends the process
+#[cfg(windows)]
+pub(crate) fn install(handle: CompositeLoggerHandle, timeout: Duration) -> io::Result<()> {
+    use std::sync::OnceLock;
+    use windows_sys::Win32::Foundation::BOOL;
+    use windows_sys::Win32::System::Console::SetConsoleCtrlHandler;
+
+    static SHUTDOWN: OnceLock<(CompositeLoggerHandle, Duration)> = OnceLock::new();
+
+    /// Called on a thread of its own, the process ends once it returns
+    unsafe extern "system" fn on_console_event(_event: u32) -> BOOL {
+        if let Some((handle, timeout)) = SHUTDOWN.get() {
+            handle.flush_timeout(*timeout);
+        }
+        // let the next handler, by default the one ending the process, handle the event
+        0
+    }
+
+    if SHUTDOWN.set((handle, timeout)).is_err() {
+        return Ok(());
+    }
+    // SAFETY: the handler only uses the handle, which lives until the process ends
+    if unsafe { SetConsoleCtrlHandler(Some(on_console_event), 1) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(any(unix, windows)))]
+pub(crate) fn install(_handle: CompositeLoggerHandle, _timeout: Duration) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
@@ -0,0 +1,107 @@
+use crate::LoggerId;
+use std::any::Any;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+/// Why records were not delivered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SinkErrorKind {
+    /// A logger panicked while logging or flushing
+    Panicked,
+    /// A logger panicked too many times in a row and no longer receives records
+    Disabled,
+    /// A logger took longer than its timeout to log a record,
+    /// see [`crate::CompositeLogger::with_logger_timeout`]
+    TimedOut,
+    /// A logger was slow to log several records in a row,
+    /// see [`crate::CompositeLogger::with_slow_logger_warnings`]
+    Slow,
+    /// A logger failed too often and is skipped for a while, see [`crate::CircuitBreaker`]
+    Tripped,
+    /// A logger that was skipped after failing too often succeeded again
+    Recovered,
+    /// The queue of the asynchronous mode was full and the record was dropped
+    Overflow,
+    /// A logger gave up delivering records itself, for example a network logger
+    /// whose buffer overflowed while disconnected
+    Dropped,
+    /// A logger could not write the records it keeps on disk,
+    /// for example the spool of [`crate::sinks::NetworkLogger::with_spool`]
+    Spooling,
+    /// A supervised logger that failed was reconstructed, see [`crate::SupervisedLogger`]
+    Restarted,
+    /// A supervised logger that failed could not be reconstructed and receives
+    /// no records until the next attempt
+    RestartFailed,
+}
+
+/// Reported to the error handler whenever records are not delivered
+/// or a logger recovered,
+/// see [`crate::CompositeLogger::with_error_handler`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SinkError {
+    kind: SinkErrorKind,
+    logger: Option<LoggerId>,
+    name: Option<String>,
+    message: String,
+}
+
+impl SinkError {
+    pub fn new(kind: SinkErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            logger: None,
+            name: None,
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn with_logger(mut self, logger: LoggerId, name: Option<&str>) -> Self {
+        self.logger = Some(logger);
+        self.name = name.map(str::to_string);
+        self
+    }
+
+    pub fn kind(&self) -> SinkErrorKind {
+        self.kind
+    }
+
+    /// The logger of the composite logger that failed, if any
+    pub fn logger(&self) -> Option<LoggerId> {
+        self.logger
+    }
+
+    /// The name of the logger that failed, if it was added with a name
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for SinkError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (&self.name, self.logger) {
+            (Some(name), _) => write!(f, "logger `{}`: {}", name, self.message),
+            (None, Some(logger)) => write!(f, "logger {:?}: {}", logger, self.message),
+            (None, None) => f.write_str(&self.message),
+        }
+    }
+}
+
+impl std::error::Error for SinkError {}
+
+/// Called with every error reported by the composite logger
+pub(crate) type ErrorHandler = Arc<dyn Fn(SinkError) + Send + Sync>;
+
+/// The message of a panic, if it was raised with a string
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("Box<dyn Any>")
+}
@@ -0,0 +1,208 @@
+use crate::OwnedRecord;
+use log::{Log, Metadata, Record};
+use sha2::{Digest, Sha256};
+use std::fmt::{Display, Formatter, Write};
+use std::sync::{Mutex, MutexGuard};
+
+/// The key of the sequence number added to every record
+const SEQUENCE_KEY: &str = "seq";
+/// The key of the chained hash added to every record
+const HASH_KEY: &str = "hash";
+
+/// The next sequence number and the hash of the previous record
+struct Chain {
+    sequence: u64,
+    hash: [u8; 32],
+}
+
+impl Chain {
+    /// The hash of the record with the given sequence number, chained to the previous hash
+    fn seal(&self, record: &OwnedRecord) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(self.hash);
+        hasher.update(self.sequence.to_be_bytes());
+        hasher.update(record.level().as_str());
+        for field in [record.target(), record.message()] {
+            hasher.update((field.len() as u64).to_be_bytes());
+            hasher.update(field);
+        }
+        for (key, value) in record.key_values() {
+            if key == SEQUENCE_KEY || key == HASH_KEY {
+                continue;
+            }
+            for field in [key, value] {
+                hasher.update((field.len() as u64).to_be_bytes());
+                hasher.update(field);
+            }
+        }
+        hasher.finalize().into()
+    }
+}
+
+/// Adds a sequence number and a hash to every record before passing it to the wrapped logger,
+/// so that missing and changed records can be detected with an [`AuditVerifier`].
+///
+/// The records are numbered from 0 under the `seq` key, and the `hash` key holds the
+/// hex encoded SHA-256 hash of the previous hash, the sequence number, the level,
+/// target, message and key-values of the record, starting from a hash of zeros.
+/// The timestamp and the call site of records are not part of the hash.
+pub struct AuditLogger {
+    logger: Box<dyn Log>,
+    chain: Mutex<Chain>,
+}
+
+impl AuditLogger {
+    pub fn new(logger: impl Log + 'static) -> Self {
+        Self {
+            logger: Box::new(logger),
+            chain: Mutex::new(Chain {
+                sequence: 0,
+                hash: [0; 32],
+            }),
+        }
+    }
+
+    /// Continue the chain of a previous run, from the sequence number and hash
+    /// returned by [`AuditLogger::head`]
+    pub fn resuming(self, sequence: u64, hash: [u8; 32]) -> Self {
+        *self.lock() = Chain { sequence, hash };
+        self
+    }
+
+    /// The next sequence number and the hash of the most recent record,
+    /// to resume the chain after a restart
+    pub fn head(&self) -> (u64, [u8; 32]) {
+        let chain = self.lock();
+        (chain.sequence, chain.hash)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Chain> {
+        self.chain.lock().unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+impl Log for AuditLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.logger.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        let record = OwnedRecord::from(record);
+        // the chain stays locked until the wrapped logger wrote the record,
+        // so that the records are written in the order of their sequence numbers
+        let mut chain = self.lock();
+        let hash = chain.seal(&record);
+        let record = record
+            .with_key_value(SEQUENCE_KEY, chain.sequence.to_string())
+            .with_key_value(HASH_KEY, hex(&hash));
+        chain.sequence += 1;
+        chain.hash = hash;
+
+        record.with_record(|record| self.logger.log(record));
+    }
+
+    fn flush(&self) {
+        self.logger.flush();
+    }
+}
+
+/// Checks that records written by an [`AuditLogger`] are complete and unchanged,
+/// fed one at a time in the order they were written
+pub struct AuditVerifier {
+    chain: Chain,
+}
+
+impl Default for AuditVerifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AuditVerifier {
+    /// Verify records from the start of the chain
+    pub fn new() -> Self {
+        Self::resuming(0, [0; 32])
+    }
+
+    /// Verify records from the given sequence number, following the record with the given hash
+    pub fn resuming(sequence: u64, hash: [u8; 32]) -> Self {
+        Self {
+            chain: Chain { sequence, hash },
+        }
+    }
+
+    /// Check the next record
+    ///
+    /// # Errors
+    ///
+    /// Fails if the record has no sequence number or hash, if records are missing
+    /// before it, or if it or one of the previous records was changed.
+    pub fn verify(&mut self, record: &OwnedRecord) -> Result<(), AuditViolation> {
+        let value = |key: &str| {
+            record
+                .key_values()
+                .iter()
+                .find(|(found, _)| found == key)
+                .map(|(_, value)| value.as_str())
+        };
+        let sequence = value(SEQUENCE_KEY).and_then(|sequence| sequence.parse::<u64>().ok());
+        let (Some(sequence), Some(hash)) = (sequence, value(HASH_KEY)) else {
+            return Err(AuditViolation::Unsealed);
+        };
+
+        if sequence != self.chain.sequence {
+            return Err(AuditViolation::Gap {
+                expected: self.chain.sequence,
+                found: sequence,
+            });
+        }
+        let expected = self.chain.seal(record);
+        if hex(&expected) != hash {
+            return Err(AuditViolation::Tampered { sequence });
+        }
+
+        self.chain = Chain {
+            sequence: sequence + 1,
+            hash: expected,
+        };
+        Ok(())
+    }
+}
+
+/// How records verified by an [`AuditVerifier`] were found to be incomplete or changed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuditViolation {
+    /// The record has no sequence number or hash
+    Unsealed,
+    /// Records are missing, or were reordered or added
+    Gap { expected: u64, found: u64 },
+    /// The record with the sequence number, or one before it, was changed
+    Tampered { sequence: u64 },
+}
+
+impl Display for AuditViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuditViolation::Unsealed => write!(f, "the record has no sequence number or hash"),
+            AuditViolation::Gap { expected, found } => write!(
+                f,
+                "expected the record with sequence number {} but found {}",
+                expected, found
+            ),
+            AuditViolation::Tampered { sequence } => write!(
+                f,
+                "the record with sequence number {} does not match its hash",
+                sequence
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AuditViolation {}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{:02x}", byte);
+        hex
+    })
+}
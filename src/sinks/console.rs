@@ -0,0 +1,137 @@
+use crate::binary::{self, Binaries};
+use crate::format::{self, Format, TemplateFormat, Theme};
+use crate::LineEncoding;
+use log::{Level, Log, Metadata, Record};
+use std::io::{self, IsTerminal, Write};
+
+/// The environment variable that disables colors with [`ColorChoice::Auto`], see <https://no-color.org>
+const NO_COLOR_ENV: &str = "NO_COLOR";
+
+/// Whether the console logger colors the level of the records
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Color if the stream is a terminal and `NO_COLOR` is not set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    fn colors(self, is_terminal: bool) -> bool {
+        match self {
+            ColorChoice::Auto => is_terminal && std::env::var_os(NO_COLOR_ENV).is_none(),
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+/// Writes `Warn` and `Error` records to the standard error and all others
+/// to the standard output as human readable lines, formatted with the [`TemplateFormat`]
+/// by default. Binary payloads among the key-values are printed as hex dumps below the line.
+/// Errors while writing can not be reported and are ignored.
+pub struct ConsoleLogger {
+    format: Box<dyn Format>,
+    stderr_level: Level,
+    stdout_colors: bool,
+    stderr_colors: bool,
+    hex_dumps: bool,
+    line_encoding: LineEncoding,
+}
+
+impl Default for ConsoleLogger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConsoleLogger {
+    /// Color the levels when writing to a terminal
+    pub fn new() -> Self {
+        Self {
+            format: Box::new(TemplateFormat::default()),
+            stderr_level: Level::Warn,
+            stdout_colors: false,
+            stderr_colors: false,
+            hex_dumps: true,
+            line_encoding: LineEncoding::default(),
+        }
+        .with_colors(ColorChoice::Auto)
+    }
+
+    /// Whether to color the levels, detecting terminals with [`ColorChoice::Auto`]
+    pub fn with_colors(mut self, colors: ColorChoice) -> Self {
+        self.stdout_colors = colors.colors(io::stdout().is_terminal());
+        self.stderr_colors = colors.colors(io::stderr().is_terminal());
+        self
+    }
+
+    /// Write records at or above the given level to the standard error, by default `Warn`
+    pub fn with_stderr_level(mut self, level: Level) -> Self {
+        self.stderr_level = level;
+        self
+    }
+
+    /// Format the records with the default template showing the levels with the theme,
+    /// replacing the format. Use [`TemplateFormat::with_theme`] for other templates.
+    pub fn with_theme(self, theme: Theme) -> Self {
+        self.with_format(TemplateFormat::default().with_theme(theme))
+    }
+
+    /// Format the records with the given format instead of the default template
+    pub fn with_format(mut self, format: impl Format + 'static) -> Self {
+        self.format = Box::new(format);
+        self
+    }
+
+    /// Whether to print hex dumps of binary payloads below the line, by default `true`
+    pub fn with_hex_dumps(mut self, hex_dumps: bool) -> Self {
+        self.hex_dumps = hex_dumps;
+        self
+    }
+
+    /// Encode the lines, for example as UTF-16 or ending with `\r\n`, see [`LineEncoding`]
+    pub fn with_line_encoding(mut self, encoding: LineEncoding) -> Self {
+        self.line_encoding = encoding;
+        self
+    }
+
+    fn write(&self, record: &Record, colors: bool, mut output: impl Write) {
+        format::with_line_buffer(|line| {
+            self.format.format_into(record, colors, line);
+            line.push('\n');
+            if self.hex_dumps {
+                let mut binaries = Binaries(Vec::new());
+                let _ = record.key_values().visit(&mut binaries);
+                for (key, binary) in binaries.0 {
+                    line.push_str(&format!("{} ({} bytes):\n", key, binary.as_bytes().len()));
+                    line.push_str(&binary::hex_dump(binary.as_bytes()));
+                    line.push('\n');
+                }
+            }
+            let _ = self
+                .line_encoding
+                .with_encoded(line, |bytes| output.write_all(bytes));
+        });
+    }
+}
+
+impl Log for ConsoleLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        if record.level() <= self.stderr_level {
+            self.write(record, self.stderr_colors, io::stderr().lock());
+        } else {
+            self.write(record, self.stdout_colors, io::stdout().lock());
+        }
+    }
+
+    fn flush(&self) {
+        let _ = io::stdout().flush();
+        let _ = io::stderr().flush();
+    }
+}
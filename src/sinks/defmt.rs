@@ -0,0 +1,44 @@
+use log::{Level, Log, Metadata, Record};
+
+/// Forwards the records to `defmt` at the same level, with the target and the
+/// message as strings, so that they are sent by the `defmt` global logger
+/// of the application, for example over RTT.
+/// Which levels are sent is decided by `defmt` at compile time.
+#[derive(Debug, Default)]
+pub struct DefmtLogger;
+
+impl DefmtLogger {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Log for DefmtLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let formatted;
+        let message = match record.args().as_str() {
+            Some(message) => message,
+            None => {
+                formatted = alloc::format!("{}", record.args());
+                formatted.as_str()
+            }
+        };
+        let target = record.target();
+
+        match record.level() {
+            Level::Error => defmt::error!("{=str}: {=str}", target, message),
+            Level::Warn => defmt::warn!("{=str}: {=str}", target, message),
+            Level::Info => defmt::info!("{=str}: {=str}", target, message),
+            Level::Debug => defmt::debug!("{=str}: {=str}", target, message),
+            Level::Trace => defmt::trace!("{=str}: {=str}", target, message),
+        }
+    }
+
+    fn flush(&self) {
+        defmt::flush();
+    }
+}
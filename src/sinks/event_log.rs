@@ -0,0 +1,107 @@
+use crate::FormattedRecord;
+use log::{Level, Log, Metadata, Record};
+use std::io;
+use std::iter;
+use windows_sys::Win32::Foundation::HANDLE;
+use windows_sys::Win32::System::EventLog::{
+    DeregisterEventSource, RegisterEventSourceW, ReportEventW, EVENTLOG_ERROR_TYPE,
+    EVENTLOG_INFORMATION_TYPE, EVENTLOG_WARNING_TYPE,
+};
+
+/// Writes `Warn` and `Error` records to the Windows Event Log under the given source,
+/// lower levels only when enabled with [`EventLogLogger::with_max_level`].
+/// Errors while reporting events can not be reported and are ignored.
+pub struct EventLogLogger {
+    handle: HANDLE,
+    max_level: Level,
+}
+
+// SAFETY: event source handles may be used from any thread
+unsafe impl Send for EventLogLogger {}
+unsafe impl Sync for EventLogLogger {}
+
+impl EventLogLogger {
+    /// Register the event source with the local Event Log.
+    /// The source should be registered by the installer of the service,
+    /// otherwise the Event Viewer can not show the message of the events.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the event source can not be registered.
+    pub fn new(source: &str) -> io::Result<Self> {
+        let source = wide(source);
+        // SAFETY: the source is a null terminated wide string
+        let handle = unsafe { RegisterEventSourceW(std::ptr::null(), source.as_ptr()) };
+        if handle.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Self {
+            handle,
+            max_level: Level::Warn,
+        })
+    }
+
+    /// Write records at or above the given level, by default `Warn`
+    pub fn with_max_level(mut self, level: Level) -> Self {
+        self.max_level = level;
+        self
+    }
+
+    fn event_type(level: Level) -> u16 {
+        match level {
+            Level::Error => EVENTLOG_ERROR_TYPE,
+            Level::Warn => EVENTLOG_WARNING_TYPE,
+            Level::Info | Level::Debug | Level::Trace => EVENTLOG_INFORMATION_TYPE,
+        }
+    }
+}
+
+impl Log for EventLogLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.max_level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let message = wide(&format!(
+            "[{}] {}",
+            record.target(),
+            FormattedRecord::new(record).message()
+        ));
+        let strings = [message.as_ptr()];
+        // SAFETY: the handle is registered, and the single string is null terminated
+        unsafe {
+            ReportEventW(
+                self.handle,
+                Self::event_type(record.level()),
+                0,
+                0,
+                std::ptr::null_mut(),
+                1,
+                0,
+                strings.as_ptr(),
+                std::ptr::null(),
+            );
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+impl Drop for EventLogLogger {
+    fn drop(&mut self) {
+        // SAFETY: the handle is registered and no longer used
+        unsafe {
+            DeregisterEventSource(self.handle);
+        }
+    }
+}
+
+/// The string as a null terminated UTF-16 string
+fn wide(string: &str) -> Vec<u16> {
+    string.encode_utf16().chain(iter::once(0)).collect()
+}
@@ -0,0 +1,246 @@
+use crate::format::Format;
+use crate::json;
+use crate::FormattedRecord;
+use log::kv::{self, Key, Value, VisitSource};
+use log::{Level, Log, Metadata, Record};
+use std::io;
+use std::net::{ToSocketAddrs, UdpSocket};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The largest datagram sent without chunking, as recommended by the GELF specification
+const DEFAULT_CHUNK_SIZE: usize = 8192;
+/// Identifies a datagram as a chunk of a message
+const CHUNK_MAGIC: [u8; 2] = [0x1e, 0x0f];
+/// The magic bytes, message id, sequence number and sequence count
+const CHUNK_HEADER_SIZE: usize = 12;
+/// Messages that need more chunks are dropped by the server
+const MAX_CHUNKS: usize = 128;
+
+/// Formats records as GELF 1.1 messages for Graylog.
+///
+/// The first line of the message becomes the `short_message`, the whole message
+/// the `full_message` if it has several lines. The target, module, file and line
+/// as well as the key-values become additional fields prefixed with an underscore.
+#[derive(Debug, Clone)]
+pub struct GelfFormat {
+    host: String,
+}
+
+impl GelfFormat {
+    /// Format messages sent from the given host
+    pub fn new(host: impl Into<String>) -> Self {
+        Self { host: host.into() }
+    }
+
+    fn severity(level: Level) -> u8 {
+        match level {
+            Level::Error => 3,
+            Level::Warn => 4,
+            Level::Info => 6,
+            Level::Debug | Level::Trace => 7,
+        }
+    }
+}
+
+impl Format for GelfFormat {
+    fn format(&self, record: &Record, _colors: bool) -> String {
+        let message = FormattedRecord::new(record);
+        let message = message.message();
+        let timestamp = crate::time::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let mut out = String::with_capacity(256);
+
+        out.push_str("{\"version\":\"1.1\",\"host\":");
+        json::write_str(&mut out, &self.host);
+        out.push_str(",\"short_message\":");
+        json::write_str(&mut out, message.lines().next().unwrap_or_default());
+        if message.contains('\n') {
+            out.push_str(",\"full_message\":");
+            json::write_str(&mut out, message);
+        }
+        out.push_str(&format!(
+            ",\"timestamp\":{}.{:03},\"level\":{}",
+            timestamp.as_secs(),
+            timestamp.subsec_millis(),
+            Self::severity(record.level())
+        ));
+        out.push_str(",\"_target\":");
+        json::write_str(&mut out, record.target());
+        if let Some(module_path) = record.module_path() {
+            out.push_str(",\"_module\":");
+            json::write_str(&mut out, module_path);
+        }
+        if let Some(file) = record.file() {
+            out.push_str(",\"_file\":");
+            json::write_str(&mut out, file);
+        }
+        if let Some(line) = record.line() {
+            out.push_str(&format!(",\"_line\":{}", line));
+        }
+        let _ = record.key_values().visit(&mut AdditionalFields(&mut out));
+        out.push('}');
+
+        out
+    }
+}
+
+/// Writes key-values as additional fields, which consist of word characters,
+/// dots and dashes and must not be named `_id`
+struct AdditionalFields<'a>(&'a mut String);
+
+impl<'kvs> VisitSource<'kvs> for AdditionalFields<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+        let name: String = key
+            .as_str()
+            .chars()
+            .filter(|char| char.is_alphanumeric() || matches!(char, '_' | '.' | '-'))
+            .collect();
+        if name.is_empty() || name == "id" {
+            return Ok(());
+        }
+
+        self.0.push(',');
+        json::write_str(self.0, &format!("_{}", name));
+        self.0.push(':');
+        json::write_value(self.0, &value);
+        Ok(())
+    }
+}
+
+/// Sends records as GELF messages over UDP, splitting large messages into chunks.
+/// Use [`crate::sinks::NetworkLogger::with_gelf`] to send them over TCP instead.
+/// Errors while sending can not be reported and are ignored.
+pub struct GelfLogger {
+    socket: UdpSocket,
+    format: GelfFormat,
+    chunk_size: usize,
+    next_message_id: AtomicU64,
+}
+
+impl GelfLogger {
+    /// Send the records to a Graylog GELF UDP input, as sent from the given host
+    ///
+    /// # Errors
+    ///
+    /// Fails if the address can not be resolved or the socket can not be bound.
+    pub fn udp(address: impl ToSocketAddrs, host: impl Into<String>) -> io::Result<Self> {
+        let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+        socket.connect(address)?;
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+
+        Ok(Self {
+            socket,
+            format: GelfFormat::new(host),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            next_message_id: AtomicU64::new(seed ^ u64::from(std::process::id()) << 32),
+        })
+    }
+
+    /// The largest datagram to send, by default 8192 bytes.
+    /// Use around 1420 bytes when sending over the internet.
+    pub fn with_chunk_size(mut self, bytes: usize) -> Self {
+        self.chunk_size = bytes.max(CHUNK_HEADER_SIZE + 1);
+        self
+    }
+
+    fn send(&self, message: &[u8]) -> io::Result<()> {
+        if message.len() <= self.chunk_size {
+            return self.socket.send(message).map(|_| ());
+        }
+
+        let payload_size = self.chunk_size - CHUNK_HEADER_SIZE;
+        let count = message.len().div_ceil(payload_size);
+        if count > MAX_CHUNKS {
+            return Err(io::Error::from(io::ErrorKind::InvalidInput));
+        }
+
+        let id = self.next_message_id.fetch_add(1, Ordering::Relaxed);
+        let mut chunk = Vec::with_capacity(self.chunk_size);
+        for (sequence, payload) in message.chunks(payload_size).enumerate() {
+            chunk.clear();
+            chunk.extend_from_slice(&CHUNK_MAGIC);
+            chunk.extend_from_slice(&id.to_be_bytes());
+            chunk.push(sequence as u8);
+            chunk.push(count as u8);
+            chunk.extend_from_slice(payload);
+            self.socket.send(&chunk)?;
+        }
+        Ok(())
+    }
+}
+
+impl Log for GelfLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let message = self.format.format(record, false);
+        let _ = self.send(message.as_bytes());
+    }
+
+    fn flush(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OwnedRecord;
+    use std::time::Duration;
+
+    /// The message without its timestamp
+    fn without_timestamp(message: &str) -> String {
+        let start = message.find(",\"timestamp\":").unwrap();
+        let end = start + message[start..].find(",\"level\"").unwrap();
+        format!("{}{}", &message[..start], &message[end..])
+    }
+
+    #[test]
+    fn formats_gelf_messages() {
+        let record = OwnedRecord::new(Level::Warn, "app::db", "slow query\nSELECT 1")
+            .with_line(Some(42))
+            .with_key_value("user id", "7")
+            .with_key_value("id", "hidden");
+        let message = record.with_record(|record| GelfFormat::new("web-1").format(record, false));
+
+        assert_eq!(
+            without_timestamp(&message),
+            "{\"version\":\"1.1\",\"host\":\"web-1\",\"short_message\":\"slow query\",\
+             \"full_message\":\"slow query\\nSELECT 1\",\"level\":4,\
+             \"_target\":\"app::db\",\"_line\":42,\"_userid\":\"7\"}"
+        );
+    }
+
+    #[test]
+    fn splits_large_messages_into_chunks() {
+        let server = UdpSocket::bind(("127.0.0.1", 0)).unwrap();
+        server
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let logger = GelfLogger::udp(server.local_addr().unwrap(), "web-1")
+            .unwrap()
+            .with_chunk_size(CHUNK_HEADER_SIZE + 50);
+        OwnedRecord::new(Level::Info, "app", "started").with_record(|record| logger.log(record));
+
+        let mut message = Vec::new();
+        let mut datagram = [0; 128];
+        loop {
+            let length = server.recv(&mut datagram).unwrap();
+            let (header, payload) = datagram[..length].split_at(CHUNK_HEADER_SIZE);
+            assert_eq!(header[..2], CHUNK_MAGIC);
+            message.extend_from_slice(payload);
+            if header[10] + 1 == header[11] {
+                break;
+            }
+        }
+
+        let message = String::from_utf8(message).unwrap();
+        assert!(message.starts_with("{\"version\":\"1.1\""), "{}", message);
+        assert!(message.ends_with("\"_target\":\"app\"}"), "{}", message);
+    }
+}
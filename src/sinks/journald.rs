@@ -0,0 +1,127 @@
+use crate::kv::Collect;
+use crate::FormattedRecord;
+use log::{Level, Log, Metadata, Record};
+use std::io;
+use std::os::unix::net::UnixDatagram;
+
+/// The socket of the native journald protocol
+const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// Sends records to systemd-journald over its native protocol.
+///
+/// Levels are mapped to the syslog priorities `err`, `warning`, `info` and `debug`,
+/// the target, module, file and line become the `TARGET`, `CODE_MODULE`, `CODE_FILE`
+/// and `CODE_LINE` fields and the key-values become fields with upper case names.
+/// Records too large for a single datagram and errors while sending are ignored.
+pub struct JournaldLogger {
+    socket: UnixDatagram,
+    identifier: String,
+}
+
+impl JournaldLogger {
+    /// Connect to the journal of the local systemd
+    ///
+    /// # Errors
+    ///
+    /// Fails if the journal socket can not be connected to, for example outside of systemd.
+    pub fn new() -> io::Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(JOURNAL_SOCKET)?;
+
+        let identifier = std::env::current_exe()
+            .ok()
+            .and_then(|path| {
+                path.file_stem()
+                    .map(|name| name.to_string_lossy().into_owned())
+            })
+            .unwrap_or_else(|| "rust".to_string());
+
+        Ok(Self { socket, identifier })
+    }
+
+    /// The `SYSLOG_IDENTIFIER` of the records, by default the name of the executable
+    pub fn with_identifier(mut self, identifier: impl Into<String>) -> Self {
+        self.identifier = identifier.into();
+        self
+    }
+
+    fn priority(level: Level) -> &'static str {
+        match level {
+            Level::Error => "3",
+            Level::Warn => "4",
+            Level::Info => "6",
+            Level::Debug | Level::Trace => "7",
+        }
+    }
+
+    fn encode(&self, record: &Record) -> Vec<u8> {
+        let mut datagram = Vec::with_capacity(256);
+        add_field(
+            &mut datagram,
+            "MESSAGE",
+            FormattedRecord::new(record).message(),
+        );
+        add_field(&mut datagram, "PRIORITY", Self::priority(record.level()));
+        add_field(&mut datagram, "SYSLOG_IDENTIFIER", &self.identifier);
+        add_field(&mut datagram, "TARGET", record.target());
+        if let Some(module) = record.module_path() {
+            add_field(&mut datagram, "CODE_MODULE", module);
+        }
+        if let Some(file) = record.file() {
+            add_field(&mut datagram, "CODE_FILE", file);
+        }
+        if let Some(line) = record.line() {
+            add_field(&mut datagram, "CODE_LINE", &line.to_string());
+        }
+
+        let mut collect = Collect(Vec::new());
+        let _ = record.key_values().visit(&mut collect);
+        for (key, value) in &collect.0 {
+            if let Some(name) = field_name(key) {
+                add_field(&mut datagram, &name, value);
+            }
+        }
+        datagram
+    }
+}
+
+impl Log for JournaldLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let _ = self.socket.send(&self.encode(record));
+    }
+
+    fn flush(&self) {}
+}
+
+/// Values with line breaks are length prefixed, all others are sent as `NAME=value` lines
+fn add_field(datagram: &mut Vec<u8>, name: &str, value: &str) {
+    datagram.extend_from_slice(name.as_bytes());
+    if value.contains('\n') {
+        datagram.push(b'\n');
+        datagram.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    } else {
+        datagram.push(b'=');
+    }
+    datagram.extend_from_slice(value.as_bytes());
+    datagram.push(b'\n');
+}
+
+/// Journal field names consist of upper case letters, digits and underscores
+/// and must not start with a digit or an underscore, which marks trusted fields
+fn field_name(key: &str) -> Option<String> {
+    let name: String = key
+        .chars()
+        .map(|char| match char {
+            'a'..='z' | 'A'..='Z' | '0'..='9' => char.to_ascii_uppercase(),
+            _ => '_',
+        })
+        .skip_while(|char| *char == '_' || char.is_ascii_digit())
+        .take(64)
+        .collect();
+
+    (!name.is_empty()).then_some(name)
+}
@@ -0,0 +1,168 @@
+use crate::json::{self, WriteMembers};
+use crate::{FormattedRecord, LineEncoding};
+use log::{Log, Metadata, Record};
+use std::fmt::Write as _;
+use std::io::Write;
+use std::sync::Mutex;
+
+/// Writes records as newline delimited JSON objects to any writer.
+///
+/// Every object has the `timestamp`, `level`, `target` and `message` members,
+/// `module`, `file` and `line` when known, and the key-values of the record
+/// as members of a nested `kv` object.
+/// Errors while writing can not be reported and are ignored.
+pub struct JsonLogger<W: Write + Send> {
+    writer: Mutex<W>,
+    line_encoding: LineEncoding,
+}
+
+impl<W: Write + Send> JsonLogger<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+            line_encoding: LineEncoding::default(),
+        }
+    }
+
+    /// Encode the lines, for example as UTF-16 or ending with `\r\n`, see [`LineEncoding`]
+    pub fn with_line_encoding(mut self, encoding: LineEncoding) -> Self {
+        self.line_encoding = encoding;
+        self
+    }
+
+    /// Formats the record as a single line JSON object, including the line break
+    pub fn format(record: &Record) -> String {
+        let mut line = String::with_capacity(256);
+        Self::format_into(record, &mut line);
+        line
+    }
+
+    /// Appends the [`JsonLogger::format`] of the record to the line, so that
+    /// the line can be reused for the next record
+    pub fn format_into(record: &Record, line: &mut String) {
+        line.push_str("{\"timestamp\":\"");
+        let start = line.len();
+        crate::timestamp::write_timestamp(line, crate::time::now());
+        if line[start..].contains(|c: char| c == '"' || c == '\\' || c.is_control()) {
+            // only custom timestamp formats need escaping
+            let timestamp = line.split_off(start);
+            json::write_str(line, &timestamp);
+            line.remove(start - 1);
+        } else {
+            line.push('"');
+        }
+        line.push_str(",\"level\":");
+        json::write_str(line, record.level().as_str());
+        line.push_str(",\"target\":");
+        json::write_str(line, record.target());
+        line.push_str(",\"message\":");
+        json::write_str(line, FormattedRecord::new(record).message());
+        if let Some(module_path) = record.module_path() {
+            line.push_str(",\"module\":");
+            json::write_str(line, module_path);
+        }
+        if let Some(file) = record.file() {
+            line.push_str(",\"file\":");
+            json::write_str(line, file);
+        }
+        if let Some(number) = record.line() {
+            let _ = write!(line, ",\"line\":{}", number);
+        }
+        if record.key_values().count() > 0 {
+            line.push_str(",\"kv\":{");
+            let start = line.len();
+            let _ = record.key_values().visit(&mut WriteMembers(line));
+            // the first member is preceded by a comma like the rest
+            line.remove(start);
+            line.push('}');
+        }
+        line.push_str("}\n");
+    }
+
+    /// The writer, for example to inspect what was written to a buffer
+    pub fn into_inner(self) -> W {
+        self.writer
+            .into_inner()
+            .unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+impl<W: Write + Send> Log for JsonLogger<W> {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        crate::format::with_line_buffer(|line| {
+            Self::format_into(record, line);
+            let mut writer = self
+                .writer
+                .lock()
+                .unwrap_or_else(|error| error.into_inner());
+            let _ = self
+                .line_encoding
+                .with_encoded(line, |bytes| writer.write_all(bytes));
+        });
+    }
+
+    fn flush(&self) {
+        let _ = self
+            .writer
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OwnedRecord;
+    use log::Level;
+
+    /// The lines written for the records, without their timestamps
+    fn lines(records: &[OwnedRecord]) -> Vec<String> {
+        let logger = JsonLogger::new(Vec::new());
+        for record in records {
+            record.with_record(|record| logger.log(record));
+        }
+        String::from_utf8(logger.into_inner())
+            .unwrap()
+            .lines()
+            .map(|line| {
+                let (timestamp, rest) = line.split_once("\",").unwrap();
+                assert!(timestamp.starts_with("{\"timestamp\":\""), "{}", line);
+                rest.to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn writes_a_json_object_per_line() {
+        let record = OwnedRecord::new(Level::Warn, "app::db", "slow query")
+            .with_module_path(Some("app::db".to_string()))
+            .with_file(Some("src/db.rs".to_string()))
+            .with_line(Some(42))
+            .with_key_value("table", "users");
+
+        assert_eq!(
+            lines(&[record, OwnedRecord::new(Level::Info, "app", "started")]),
+            [
+                "\"level\":\"WARN\",\"target\":\"app::db\",\"message\":\"slow query\",\
+                 \"module\":\"app::db\",\"file\":\"src/db.rs\",\"line\":42,\
+                 \"kv\":{\"table\":\"users\"}}",
+                "\"level\":\"INFO\",\"target\":\"app\",\"message\":\"started\"}",
+            ]
+        );
+    }
+
+    #[test]
+    fn escapes_the_strings() {
+        let record = OwnedRecord::new(Level::Info, "app", "say \"hi\"\\\n\u{1}");
+
+        assert_eq!(
+            lines(&[record]),
+            ["\"level\":\"INFO\",\"target\":\"app\",\"message\":\"say \\\"hi\\\"\\\\\\n\\u0001\"}"]
+        );
+    }
+}
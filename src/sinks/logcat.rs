@@ -0,0 +1,62 @@
+use crate::FormattedRecord;
+use log::{Level, Log, Metadata, Record};
+use std::ffi::{c_char, c_int, CString};
+
+#[link(name = "log")]
+extern "C" {
+    fn __android_log_write(priority: c_int, tag: *const c_char, text: *const c_char) -> c_int;
+}
+
+/// Writes records to the Android log, readable with `logcat`.
+///
+/// Levels are mapped to the priorities `VERBOSE`, `DEBUG`, `INFO`, `WARN` and `ERROR`.
+pub struct LogcatLogger {
+    tag: CString,
+}
+
+impl LogcatLogger {
+    /// Write the records with the given tag, which is truncated by older Android versions
+    /// to 23 characters
+    pub fn new(tag: &str) -> Self {
+        Self { tag: c_string(tag) }
+    }
+
+    fn priority(level: Level) -> c_int {
+        match level {
+            Level::Trace => 2,
+            Level::Debug => 3,
+            Level::Info => 4,
+            Level::Warn => 5,
+            Level::Error => 6,
+        }
+    }
+}
+
+impl Log for LogcatLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let message = c_string(&format!(
+            "[{}] {}",
+            record.target(),
+            FormattedRecord::new(record).message()
+        ));
+        // SAFETY: the tag and message are null terminated strings
+        unsafe {
+            __android_log_write(
+                Self::priority(record.level()),
+                self.tag.as_ptr(),
+                message.as_ptr(),
+            );
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// The string without interior null characters, which would truncate it
+fn c_string(string: &str) -> CString {
+    CString::new(string.replace('\0', "")).expect("null characters are removed")
+}
@@ -0,0 +1,115 @@
+//! Loggers to combine with the composite logger
+
+#[cfg(feature = "audit")]
+mod audit;
+#[cfg(feature = "std")]
+mod console;
+#[cfg(feature = "defmt")]
+mod defmt;
+#[cfg(all(windows, feature = "eventlog"))]
+mod event_log;
+#[cfg(feature = "std")]
+mod gelf;
+#[cfg(all(target_os = "linux", feature = "journald"))]
+mod journald;
+#[cfg(feature = "std")]
+mod json;
+#[cfg(all(target_os = "android", feature = "logcat"))]
+mod logcat;
+#[cfg(feature = "std")]
+mod network;
+#[cfg(all(target_vendor = "apple", feature = "oslog"))]
+mod os_log;
+#[cfg(feature = "otel")]
+mod otel;
+#[cfg(feature = "pipe")]
+mod pipe;
+#[cfg(feature = "std")]
+mod ring_buffer;
+#[cfg(feature = "std")]
+mod rotating_file;
+#[cfg(feature = "rtt")]
+mod rtt;
+#[cfg(feature = "sentry")]
+mod sentry;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+#[cfg(feature = "syslog")]
+mod syslog;
+#[cfg(feature = "tracing")]
+mod tracing;
+#[cfg(all(target_arch = "wasm32", feature = "web"))]
+mod web_console;
+#[cfg(feature = "std")]
+mod writer;
+
+#[cfg(feature = "audit")]
+pub use audit::{AuditLogger, AuditVerifier, AuditViolation};
+#[cfg(feature = "std")]
+pub use console::{ColorChoice, ConsoleLogger};
+#[cfg(feature = "defmt")]
+pub use defmt::DefmtLogger;
+#[cfg(all(windows, feature = "eventlog"))]
+pub use event_log::EventLogLogger;
+#[cfg(feature = "std")]
+pub use gelf::{GelfFormat, GelfLogger};
+#[cfg(all(target_os = "linux", feature = "journald"))]
+pub use journald::JournaldLogger;
+#[cfg(feature = "std")]
+pub use json::JsonLogger;
+#[cfg(all(target_os = "android", feature = "logcat"))]
+pub use logcat::LogcatLogger;
+#[cfg(feature = "std")]
+pub use network::NetworkLogger;
+#[cfg(all(target_vendor = "apple", feature = "oslog"))]
+pub use os_log::OsLogLogger;
+#[cfg(feature = "otel")]
+pub use otel::OtelLogger;
+#[cfg(feature = "pipe")]
+pub use pipe::PipeLogger;
+#[cfg(feature = "std")]
+pub use ring_buffer::RingBufferLogger;
+#[cfg(feature = "std")]
+pub use rotating_file::{Durability, RotatingFileLogger};
+#[cfg(feature = "rtt")]
+pub use rtt::{RttFraming, RttLogger};
+#[cfg(feature = "sentry")]
+pub use sentry::SentryLogger;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteLogger;
+#[cfg(feature = "syslog")]
+pub use syslog::{Facility, SyslogFormat, SyslogLogger};
+#[cfg(feature = "tracing")]
+pub use tracing::TracingLogger;
+#[cfg(all(target_arch = "wasm32", feature = "web"))]
+pub use web_console::WebConsoleLogger;
+#[cfg(feature = "std")]
+pub use writer::{WriteStrategy, WriterLogger};
+
+#[cfg(feature = "std")]
+use crate::FormattedRecord;
+#[cfg(feature = "std")]
+use log::Record;
+
+/// Formats a record as a single human readable line, including the line break
+#[cfg(feature = "std")]
+pub(crate) fn format_line(record: &Record) -> String {
+    let mut line = String::with_capacity(128);
+    write_line(record, &mut line);
+    line
+}
+
+/// Appends the [`format_line`] of the record to the line
+#[cfg(feature = "std")]
+pub(crate) fn write_line(record: &Record, line: &mut String) {
+    use std::fmt::Write;
+
+    crate::timestamp::write_timestamp(line, crate::time::now());
+    let _ = writeln!(
+        line,
+        " {:<5} [{}] {}",
+        record.level(),
+        record.target(),
+        FormattedRecord::new(record).message()
+    );
+}
@@ -0,0 +1,454 @@
+use crate::format::{BinaryFormat, Format, TemplateFormat};
+use crate::sink_error::ErrorHandler;
+use crate::sinks::{GelfFormat, JsonLogger};
+use crate::{HealthCheck, SinkError, SinkErrorKind, SinkHealth};
+use log::{Log, Metadata, Record};
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::net::{TcpStream, ToSocketAddrs, UdpSocket};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// How many records are kept while disconnected unless configured otherwise
+const DEFAULT_BUFFER_CAPACITY: usize = 1024;
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(30);
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Protocol {
+    Tcp,
+    Udp,
+}
+
+enum Encoding {
+    Format(Box<dyn Format>),
+    Json,
+    Gelf(GelfFormat),
+    Binary(Box<dyn BinaryFormat>),
+}
+
+enum Connection {
+    Tcp(TcpStream),
+    Udp(UdpSocket),
+}
+
+impl Connection {
+    fn send(&mut self, line: &[u8]) -> io::Result<()> {
+        match self {
+            Connection::Tcp(stream) => stream.write_all(line),
+            Connection::Udp(socket) => socket.send(line).map(|_| ()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Connection::Tcp(stream) => stream.flush(),
+            Connection::Udp(_) => Ok(()),
+        }
+    }
+}
+
+/// The records not sent yet written to a file, each prefixed by its length
+/// as 4 big-endian bytes, so that they survive restarts
+struct Spool {
+    path: PathBuf,
+    file: File,
+    /// Records in the file, including those sent or dropped since it was last trimmed
+    records: usize,
+}
+
+impl Spool {
+    /// Open the spool, returning the records it kept
+    fn open(path: PathBuf) -> io::Result<(Self, VecDeque<Vec<u8>>)> {
+        let mut buffer = VecDeque::new();
+        match fs::read(&path) {
+            Ok(bytes) => {
+                let mut rest = bytes.as_slice();
+                while let Some((length, frames)) = rest.split_first_chunk::<4>() {
+                    let Some(frame) = frames.get(..u32::from_be_bytes(*length) as usize) else {
+                        // the last record was cut short while it was written
+                        break;
+                    };
+                    buffer.push_back(frame.to_vec());
+                    rest = &frames[frame.len()..];
+                }
+            }
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+            Err(error) => return Err(error),
+        }
+
+        let mut spool = Self {
+            file: Self::append_to(&path)?,
+            path,
+            records: 0,
+        };
+        spool.rewrite(&buffer)?;
+        Ok((spool, buffer))
+    }
+
+    fn append_to(path: &Path) -> io::Result<File> {
+        OpenOptions::new().create(true).append(true).open(path)
+    }
+
+    fn frame(line: &[u8], frames: &mut Vec<u8>) {
+        frames.extend_from_slice(&(line.len() as u32).to_be_bytes());
+        frames.extend_from_slice(line);
+    }
+
+    fn append(&mut self, line: &[u8]) -> io::Result<()> {
+        let mut frame = Vec::with_capacity(4 + line.len());
+        Self::frame(line, &mut frame);
+        self.file.write_all(&frame)?;
+        self.records += 1;
+        Ok(())
+    }
+
+    /// Remove the records that are no longer buffered, once they take up
+    /// as much of the file as the buffered ones
+    fn trim(&mut self, buffer: &VecDeque<Vec<u8>>) -> io::Result<()> {
+        let removed = self.records.saturating_sub(buffer.len());
+        if removed > 0 && removed >= buffer.len() {
+            self.rewrite(buffer)?;
+        }
+        Ok(())
+    }
+
+    /// Replace the records of the file by the buffered ones, atomically
+    /// so that they are not lost if the process ends in the meantime
+    fn rewrite(&mut self, buffer: &VecDeque<Vec<u8>>) -> io::Result<()> {
+        if buffer.is_empty() {
+            self.file.set_len(0)?;
+        } else {
+            let mut frames = Vec::new();
+            buffer
+                .iter()
+                .for_each(|line| Self::frame(line, &mut frames));
+            let mut temporary = self.path.clone().into_os_string();
+            temporary.push(".tmp");
+            let mut file = File::create(&temporary)?;
+            file.write_all(&frames)?;
+            file.sync_data()?;
+            fs::rename(&temporary, &self.path)?;
+            self.file = Self::append_to(&self.path)?;
+        }
+        self.records = buffer.len();
+        Ok(())
+    }
+}
+
+struct State {
+    connection: Option<Connection>,
+    /// Records not sent yet, the oldest are dropped once the buffer is full
+    buffer: VecDeque<Vec<u8>>,
+    spool: Option<Spool>,
+    /// No connection is attempted before this instant
+    retry_at: Instant,
+    backoff: Duration,
+}
+
+/// Sends records as lines to a TCP or UDP collector, formatted with the [`TemplateFormat`]
+/// by default, as JSON objects or as GELF messages.
+///
+/// The connection is established when the first record is logged. Once it fails,
+/// records are kept in a bounded buffer and connecting is retried with an exponential
+/// backoff. Connecting blocks the logging thread for up to the connect timeout,
+/// so consider the asynchronous mode of the composite logger.
+pub struct NetworkLogger {
+    address: String,
+    protocol: Protocol,
+    encoding: Encoding,
+    buffer_capacity: usize,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    connect_timeout: Duration,
+    error_handler: Option<ErrorHandler>,
+    state: Mutex<State>,
+}
+
+impl NetworkLogger {
+    /// Send records over TCP to the address, such as `"localhost:5170"`,
+    /// which is resolved whenever connecting
+    pub fn tcp(address: impl Into<String>) -> Self {
+        Self::new(address.into(), Protocol::Tcp)
+    }
+
+    /// Send every record as a UDP datagram to the address, such as `"localhost:5170"`
+    pub fn udp(address: impl Into<String>) -> Self {
+        Self::new(address.into(), Protocol::Udp)
+    }
+
+    fn new(address: String, protocol: Protocol) -> Self {
+        Self {
+            address,
+            protocol,
+            encoding: Encoding::Format(Box::new(TemplateFormat::default())),
+            buffer_capacity: DEFAULT_BUFFER_CAPACITY,
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            error_handler: None,
+            state: Mutex::new(State {
+                connection: None,
+                buffer: VecDeque::new(),
+                spool: None,
+                retry_at: Instant::now(),
+                backoff: DEFAULT_INITIAL_BACKOFF,
+            }),
+        }
+    }
+
+    /// Format the records with the given format instead of the default template
+    pub fn with_format(mut self, format: impl Format + 'static) -> Self {
+        self.encoding = Encoding::Format(Box::new(format));
+        self
+    }
+
+    /// Send the records as JSON objects, see [`JsonLogger::format`]
+    pub fn with_json(mut self) -> Self {
+        self.encoding = Encoding::Json;
+        self
+    }
+
+    /// Send the records as GELF messages, for example to a Graylog GELF TCP input
+    pub fn with_gelf(mut self, format: GelfFormat) -> Self {
+        self.encoding = Encoding::Gelf(format);
+        self
+    }
+
+    /// Send the records encoded with the binary format, for example with the
+    /// [`crate::msgpack::MessagePackFormat`] to a collector reading MessagePack over TCP
+    pub fn with_binary_format(mut self, format: impl BinaryFormat + 'static) -> Self {
+        self.encoding = Encoding::Binary(Box::new(format));
+        self
+    }
+
+    /// How many records to keep while disconnected, by default 1024
+    pub fn with_buffer_capacity(mut self, capacity: usize) -> Self {
+        self.buffer_capacity = capacity;
+        self
+    }
+
+    /// Also keep the records not sent yet in a spool file, so that they are sent
+    /// once the collector is reachable again even if the process restarts in the meantime.
+    /// Records are appended to the spool before they are sent, and removed from it
+    /// after they were sent, so records sent just before a restart may be sent again.
+    /// The buffer capacity also bounds the records kept in the spool.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the records kept in an existing spool can not be read or the spool
+    /// can not be written.
+    pub fn with_spool(self, path: impl Into<PathBuf>) -> io::Result<Self> {
+        let (spool, buffer) = Spool::open(path.into())?;
+        {
+            let mut state = self.lock();
+            state.spool = Some(spool);
+            state.buffer = buffer;
+        }
+        Ok(self)
+    }
+
+    /// Wait `initial` before retrying to connect, doubling the wait after every failure
+    /// up to `max`, by default from 100 milliseconds up to 30 seconds
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self.lock().backoff = initial;
+        self
+    }
+
+    /// Call the handler whenever a buffered record is dropped because the buffer is full,
+    /// or the spool can not be written
+    pub fn with_error_handler(
+        mut self,
+        handler: impl Fn(SinkError) + Send + Sync + 'static,
+    ) -> Self {
+        self.error_handler = Some(Arc::new(handler));
+        self
+    }
+
+    /// How long to wait for a TCP connection, by default one second
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    fn encode(&self, record: &Record) -> Vec<u8> {
+        match &self.encoding {
+            Encoding::Format(format) => (format.format(record, false) + "\n").into_bytes(),
+            Encoding::Json => JsonLogger::<io::Sink>::format(record).into_bytes(),
+            // GELF messages are delimited by a null byte
+            Encoding::Gelf(format) => (format.format(record, false) + "\0").into_bytes(),
+            Encoding::Binary(format) => format.encode(record),
+        }
+    }
+
+    fn connect(&self) -> io::Result<Connection> {
+        let mut error = io::Error::from(io::ErrorKind::NotFound);
+        for address in self.address.to_socket_addrs()? {
+            let connection = match self.protocol {
+                Protocol::Tcp => {
+                    TcpStream::connect_timeout(&address, self.connect_timeout).map(Connection::Tcp)
+                }
+                Protocol::Udp => {
+                    let local = if address.is_ipv4() {
+                        "0.0.0.0:0"
+                    } else {
+                        "[::]:0"
+                    };
+                    UdpSocket::bind(local)
+                        .and_then(|socket| socket.connect(address).map(|_| socket))
+                        .map(Connection::Udp)
+                }
+            };
+            match connection {
+                Ok(connection) => return Ok(connection),
+                Err(connect_error) => error = connect_error,
+            }
+        }
+        Err(error)
+    }
+
+    /// Send the buffered records, connecting first unless waiting for the backoff
+    fn send_buffered(&self, state: &mut State) {
+        if state.connection.is_none() {
+            if Instant::now() < state.retry_at {
+                return;
+            }
+            match self.connect() {
+                Ok(connection) => {
+                    state.connection = Some(connection);
+                    state.backoff = self.initial_backoff;
+                }
+                Err(_) => return self.disconnected(state),
+            }
+        }
+
+        while let Some(line) = state.buffer.front() {
+            let sent = match &mut state.connection {
+                Some(connection) => connection.send(line),
+                None => return,
+            };
+            match sent {
+                Ok(()) => {
+                    state.buffer.pop_front();
+                }
+                Err(_) => return self.disconnected(state),
+            }
+        }
+    }
+
+    /// Send the buffered records and trim the spool, reporting when it fails
+    fn deliver(&self, state: &mut State) -> Option<SinkError> {
+        self.send_buffered(state);
+        let spool = state.spool.as_mut()?;
+        spool
+            .trim(&state.buffer)
+            .err()
+            .map(|error| self.spool_error(spool, error))
+    }
+
+    fn spool_error(&self, spool: &Spool, error: io::Error) -> SinkError {
+        SinkError::new(
+            SinkErrorKind::Spooling,
+            format!(
+                "could not write the spool {} of {}: {}",
+                spool.path.display(),
+                self.address,
+                error
+            ),
+        )
+    }
+
+    fn report(&self, error: SinkError) {
+        if let Some(handler) = &self.error_handler {
+            handler(error);
+        }
+    }
+
+    fn disconnected(&self, state: &mut State) {
+        state.connection = None;
+        state.retry_at = Instant::now() + state.backoff;
+        state.backoff = (state.backoff * 2).min(self.max_backoff);
+    }
+}
+
+impl Log for NetworkLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let line = self.encode(record);
+
+        let (dropped, spooling) = {
+            let mut state = self.lock();
+            let state = &mut *state;
+            let dropped =
+                state.buffer.len() >= self.buffer_capacity && state.buffer.pop_front().is_some();
+            let mut spooling = None;
+            if self.buffer_capacity > 0 {
+                if let Some(spool) = &mut state.spool {
+                    spooling = spool
+                        .append(&line)
+                        .err()
+                        .map(|error| self.spool_error(spool, error));
+                }
+                state.buffer.push_back(line);
+            }
+            (dropped, spooling.or(self.deliver(state)))
+        };
+
+        // outside of the lock, in case the handler logs to this logger again
+        if dropped {
+            self.report(SinkError::new(
+                SinkErrorKind::Dropped,
+                format!("dropped a record while disconnected from {}", self.address),
+            ));
+        }
+        if let Some(error) = spooling {
+            self.report(error);
+        }
+    }
+
+    fn flush(&self) {
+        let spooling = {
+            let mut state = self.lock();
+            let spooling = self.deliver(&mut state);
+            if let Some(connection) = &mut state.connection {
+                if connection.flush().is_err() {
+                    self.disconnected(&mut state);
+                }
+            }
+            spooling
+        };
+
+        if let Some(error) = spooling {
+            self.report(error);
+        }
+    }
+}
+
+impl HealthCheck for NetworkLogger {
+    fn health(&self) -> SinkHealth {
+        let state = self.lock();
+        let disconnected = state.connection.is_none()
+            && (!state.buffer.is_empty() || Instant::now() < state.retry_at);
+        if disconnected {
+            SinkHealth::Degraded(format!(
+                "disconnected from {} with {} records buffered",
+                self.address,
+                state.buffer.len()
+            ))
+        } else {
+            SinkHealth::Healthy
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use crate::FormattedRecord;
+use log::{Level, Log, Metadata, Record};
+use std::ffi::{c_char, c_void, CString};
+
+/// Marks the single argument of the format as a public string
+const PUBLIC_STRING: u8 = 0x22;
+/// Marks the arguments as containing a non-scalar value
+const HAS_NON_SCALAR: u8 = 0x02;
+
+extern "C" {
+    static __dso_handle: u8;
+
+    fn os_log_create(subsystem: *const c_char, category: *const c_char) -> *mut c_void;
+
+    fn _os_log_impl(
+        dso: *const c_void,
+        log: *mut c_void,
+        log_type: u8,
+        format: *const c_char,
+        buffer: *const u8,
+        size: u32,
+    );
+}
+
+/// Writes records to Apple's unified logging system, readable with Console.app
+/// or `log stream`.
+///
+/// Levels are mapped to the types `error`, `default`, `info` and `debug`,
+/// `Trace` records are written as `debug`. Messages are logged as public,
+/// so they are not redacted.
+pub struct OsLogLogger {
+    log: *mut c_void,
+}
+
+// SAFETY: os_log objects may be used from any thread
+unsafe impl Send for OsLogLogger {}
+unsafe impl Sync for OsLogLogger {}
+
+impl OsLogLogger {
+    /// Write the records with the given subsystem, usually a reverse DNS identifier,
+    /// and category
+    pub fn new(subsystem: &str, category: &str) -> Self {
+        let subsystem = c_string(subsystem);
+        let category = c_string(category);
+        // SAFETY: the subsystem and category are null terminated strings
+        let log = unsafe { os_log_create(subsystem.as_ptr(), category.as_ptr()) };
+        Self { log }
+    }
+
+    fn log_type(level: Level) -> u8 {
+        match level {
+            Level::Error => 0x10,
+            Level::Warn => 0x00,
+            Level::Info => 0x01,
+            Level::Debug | Level::Trace => 0x02,
+        }
+    }
+}
+
+impl Log for OsLogLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let message = c_string(&format!(
+            "[{}] {}",
+            record.target(),
+            FormattedRecord::new(record).message()
+        ));
+
+        // the arguments of the format as encoded by the os_log macro
+        let pointer = (message.as_ptr() as usize).to_ne_bytes();
+        let mut buffer = vec![HAS_NON_SCALAR, 1, PUBLIC_STRING, pointer.len() as u8];
+        buffer.extend_from_slice(&pointer);
+
+        // SAFETY: the format has a single string argument, which is encoded in the buffer
+        // and alive until the call returns
+        unsafe {
+            _os_log_impl(
+                std::ptr::addr_of!(__dso_handle).cast(),
+                self.log,
+                Self::log_type(record.level()),
+                c"%{public}s".as_ptr(),
+                buffer.as_ptr(),
+                buffer.len() as u32,
+            );
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// The string without interior null characters, which would truncate it
+fn c_string(string: &str) -> CString {
+    CString::new(string.replace('\0', "")).expect("null characters are removed")
+}
@@ -0,0 +1,88 @@
+use crate::FormattedRecord;
+use log::kv::{self, Key, Value, VisitSource};
+use log::{Level, Log, Metadata, Record};
+use opentelemetry::logs::{AnyValue, LogRecord, Logger, LoggerProvider, Severity};
+use std::time::SystemTime;
+
+/// Converts records into OpenTelemetry log records and emits them with a logger
+/// of the given provider, which exports them for example over OTLP when built
+/// with the `opentelemetry_sdk` and `opentelemetry-otlp` crates.
+///
+/// The level becomes the severity, the message the body, and the key-values,
+/// file and line become attributes. The provider is not flushed by this logger,
+/// it should be shut down before the program exits.
+pub struct OtelLogger<L: Logger> {
+    logger: L,
+}
+
+impl<L: Logger + Send + Sync> OtelLogger<L> {
+    /// Emit the records with a logger of the provider named after this crate
+    pub fn new<P: LoggerProvider<Logger = L>>(provider: &P) -> Self {
+        Self::with_logger(provider.logger(env!("CARGO_PKG_NAME")))
+    }
+
+    /// Emit the records with the given logger
+    pub fn with_logger(logger: L) -> Self {
+        Self { logger }
+    }
+
+    fn severity(level: Level) -> Severity {
+        match level {
+            Level::Error => Severity::Error,
+            Level::Warn => Severity::Warn,
+            Level::Info => Severity::Info,
+            Level::Debug => Severity::Debug,
+            Level::Trace => Severity::Trace,
+        }
+    }
+}
+
+impl<L: Logger + Send + Sync> Log for OtelLogger<L> {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let mut log_record = self.logger.create_log_record();
+        log_record.set_timestamp(crate::time::now());
+        log_record.set_observed_timestamp(SystemTime::now());
+        log_record.set_severity_number(Self::severity(record.level()));
+        log_record.set_severity_text(record.level().as_str());
+        log_record.set_target(record.target().to_string());
+        log_record.set_body(AnyValue::from(
+            FormattedRecord::new(record).message().to_string(),
+        ));
+        if let Some(file) = record.file() {
+            log_record.add_attribute("code.file.path", file.to_string());
+        }
+        if let Some(line) = record.line() {
+            log_record.add_attribute("code.line.number", i64::from(line));
+        }
+        let _ = record
+            .key_values()
+            .visit(&mut AddAttributes(&mut log_record));
+
+        self.logger.emit(log_record);
+    }
+
+    fn flush(&self) {}
+}
+
+/// Adds key-values as attributes, keeping booleans and numbers as such
+struct AddAttributes<'a, R>(&'a mut R);
+
+impl<'kvs, R: LogRecord> VisitSource<'kvs> for AddAttributes<'_, R> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+        let value = if let Some(value) = value.to_bool() {
+            AnyValue::from(value)
+        } else if let Some(value) = value.to_i64() {
+            AnyValue::from(value)
+        } else if let Some(value) = value.to_f64() {
+            AnyValue::from(value)
+        } else {
+            AnyValue::from(value.to_string())
+        };
+        self.0.add_attribute(key.as_str().to_string(), value);
+        Ok(())
+    }
+}
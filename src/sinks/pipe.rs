@@ -0,0 +1,311 @@
+use crate::format::{Format, TemplateFormat};
+use crate::sink_error::ErrorHandler;
+use crate::{HealthCheck, SinkError, SinkErrorKind, SinkHealth};
+use log::{Log, Metadata, Record};
+#[cfg(unix)]
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+enum Destination {
+    #[cfg(unix)]
+    Fifo(PathBuf),
+    /// A shell command line
+    Command(String),
+}
+
+enum Pipe {
+    #[cfg(unix)]
+    Fifo(File),
+    Child(Child, ChildStdin),
+}
+
+impl Pipe {
+    fn writer(&mut self) -> &mut dyn Write {
+        match self {
+            #[cfg(unix)]
+            Pipe::Fifo(file) => file,
+            Pipe::Child(_, stdin) => stdin,
+        }
+    }
+
+    /// Close the pipe, reaping the child process
+    fn close(self) {
+        if let Pipe::Child(mut child, stdin) = self {
+            drop(stdin);
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+struct State {
+    pipe: Option<Pipe>,
+    /// The pipe is not opened again before this instant
+    retry_at: Instant,
+    backoff: Duration,
+    /// Records dropped since the pipe broke
+    dropped: u64,
+}
+
+/// Writes records as lines to a named pipe or to the standard input of a child process,
+/// such as a log shipper, formatted with the [`TemplateFormat`] by default.
+///
+/// Once writing fails because the reader of the pipe went away or the child process
+/// exited, records are dropped while the pipe is opened again, or the command restarted,
+/// with an exponential backoff. Writing blocks the logging thread while the pipe is full,
+/// so consider the asynchronous mode of the composite logger.
+pub struct PipeLogger {
+    destination: Destination,
+    format: Box<dyn Format>,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    error_handler: Option<ErrorHandler>,
+    state: Mutex<State>,
+}
+
+impl PipeLogger {
+    /// Write to the named pipe (FIFO) at the path, opened when the first record
+    /// is logged while a process reads from it
+    #[cfg(unix)]
+    pub fn fifo(path: impl Into<PathBuf>) -> Self {
+        Self::new(Destination::Fifo(path.into()), None)
+    }
+
+    /// Run the command line with the shell, such as `"vector --config vector.toml"`,
+    /// and write to its standard input. The command inherits the standard output and error.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the command can not be started.
+    pub fn command(command: impl Into<String>) -> io::Result<Self> {
+        let destination = Destination::Command(command.into());
+        let pipe = Self::open(&destination)?;
+        Ok(Self::new(destination, Some(pipe)))
+    }
+
+    fn new(destination: Destination, pipe: Option<Pipe>) -> Self {
+        Self {
+            destination,
+            format: Box::new(TemplateFormat::default()),
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+            error_handler: None,
+            state: Mutex::new(State {
+                pipe,
+                retry_at: Instant::now(),
+                backoff: DEFAULT_INITIAL_BACKOFF,
+                dropped: 0,
+            }),
+        }
+    }
+
+    /// Format the records with the given format instead of the default template
+    pub fn with_format(mut self, format: impl Format + 'static) -> Self {
+        self.format = Box::new(format);
+        self
+    }
+
+    /// Wait `initial` before opening the pipe or restarting the command again, doubling
+    /// the wait after every failure up to `max`, by default from 100 milliseconds up to 30 seconds
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self.lock().backoff = initial;
+        self
+    }
+
+    /// Call the handler whenever the pipe breaks, and with the number of records
+    /// dropped in the meantime once it is open again
+    pub fn with_error_handler(
+        mut self,
+        handler: impl Fn(SinkError) + Send + Sync + 'static,
+    ) -> Self {
+        self.error_handler = Some(Arc::new(handler));
+        self
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    fn open(destination: &Destination) -> io::Result<Pipe> {
+        match destination {
+            #[cfg(unix)]
+            Destination::Fifo(path) => open_fifo(path).map(Pipe::Fifo),
+            Destination::Command(command) => {
+                let mut child = shell(command).stdin(Stdio::piped()).spawn()?;
+                let stdin = child
+                    .stdin
+                    .take()
+                    .ok_or_else(|| io::Error::from(io::ErrorKind::BrokenPipe))?;
+                Ok(Pipe::Child(child, stdin))
+            }
+        }
+    }
+
+    fn describe(&self) -> String {
+        match &self.destination {
+            #[cfg(unix)]
+            Destination::Fifo(path) => format!("the pipe {}", path.display()),
+            Destination::Command(command) => format!("the command `{}`", command),
+        }
+    }
+
+    /// Open the pipe unless it is open or waiting for the backoff,
+    /// returning the number of records dropped while it was not
+    fn reopen(&self, state: &mut State) -> Option<u64> {
+        if state.pipe.is_some() || Instant::now() < state.retry_at {
+            return None;
+        }
+        match Self::open(&self.destination) {
+            Ok(pipe) => {
+                state.pipe = Some(pipe);
+                state.backoff = self.initial_backoff;
+                Some(std::mem::take(&mut state.dropped))
+            }
+            Err(_) => {
+                self.broken(state);
+                None
+            }
+        }
+    }
+
+    fn broken(&self, state: &mut State) {
+        if let Some(pipe) = state.pipe.take() {
+            pipe.close();
+        }
+        state.retry_at = Instant::now() + state.backoff;
+        state.backoff = (state.backoff * 2).min(self.max_backoff);
+    }
+
+    fn report(&self, error: SinkError) {
+        if let Some(handler) = &self.error_handler {
+            handler(error);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn open_fifo(path: &std::path::Path) -> io::Result<File> {
+    use std::os::fd::AsRawFd;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    // opening without a reader fails instead of blocking, and writes block once opened
+    let file = OpenOptions::new()
+        .write(true)
+        .custom_flags(libc::O_NONBLOCK)
+        .open(path)?;
+    // SAFETY: the descriptor is open while the file is
+    unsafe {
+        let flags = libc::fcntl(file.as_raw_fd(), libc::F_GETFL);
+        if flags < 0 || libc::fcntl(file.as_raw_fd(), libc::F_SETFL, flags & !libc::O_NONBLOCK) < 0
+        {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(file)
+}
+
+fn shell(command: &str) -> Command {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    shell.arg(command);
+    shell
+}
+
+impl Log for PipeLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let mut line = self.format.format(record, false);
+        line.push('\n');
+
+        let (dropped, failure) = {
+            let mut state = self.lock();
+            let state = &mut *state;
+            let dropped = self.reopen(state);
+            let failure = match &mut state.pipe {
+                Some(pipe) => pipe.writer().write_all(line.as_bytes()).err(),
+                None => {
+                    state.dropped += 1;
+                    None
+                }
+            };
+            if failure.is_some() {
+                self.broken(state);
+                state.dropped += 1;
+            }
+            (dropped, failure)
+        };
+
+        // outside of the lock, in case the handler logs to this logger again
+        if let Some(dropped @ 1..) = dropped {
+            self.report(SinkError::new(
+                SinkErrorKind::Dropped,
+                format!(
+                    "dropped {} records while {} was unavailable",
+                    dropped,
+                    self.describe()
+                ),
+            ));
+        }
+        if let Some(error) = failure {
+            self.report(SinkError::new(
+                SinkErrorKind::Dropped,
+                format!("could not write to {}: {}", self.describe(), error),
+            ));
+        }
+    }
+
+    fn flush(&self) {
+        let mut state = self.lock();
+        let failed = match &mut state.pipe {
+            Some(pipe) => pipe.writer().flush().is_err(),
+            None => false,
+        };
+        if failed {
+            self.broken(&mut state);
+        }
+    }
+}
+
+impl HealthCheck for PipeLogger {
+    fn health(&self) -> SinkHealth {
+        let state = self.lock();
+        match state.pipe {
+            Some(_) => SinkHealth::Healthy,
+            None => SinkHealth::Degraded(format!(
+                "{} is unavailable with {} records dropped",
+                self.describe(),
+                state.dropped
+            )),
+        }
+    }
+}
+
+impl Drop for PipeLogger {
+    fn drop(&mut self) {
+        // let the child process read what was written before closing its input
+        if let Some(Pipe::Child(mut child, stdin)) = self.lock().pipe.take() {
+            drop(stdin);
+            let _ = child.wait();
+        }
+    }
+}
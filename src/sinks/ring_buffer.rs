@@ -0,0 +1,127 @@
+use log::{Log, Metadata, Record};
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::sync::{Mutex, MutexGuard};
+
+/// Keeps the most recent records formatted as lines in memory,
+/// for example to attach them to a crash report.
+///
+/// Share it with an `Arc` to access the records after adding it to the composite logger.
+pub struct RingBufferLogger {
+    capacity: usize,
+    lines: Mutex<VecDeque<String>>,
+}
+
+impl RingBufferLogger {
+    /// Keep at most the given number of records, dropping the oldest
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            lines: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, VecDeque<String>> {
+        self.lines.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    /// The kept records, from the oldest to the most recent, without line breaks
+    pub fn snapshot(&self) -> Vec<String> {
+        self.lock().iter().cloned().collect()
+    }
+
+    /// Write the kept records, one per line, from the oldest to the most recent
+    ///
+    /// # Errors
+    ///
+    /// Fails if writing to the writer fails.
+    pub fn dump_to(&self, mut writer: impl Write) -> io::Result<()> {
+        for line in self.lock().iter() {
+            writeln!(writer, "{}", line)?;
+        }
+        writer.flush()
+    }
+
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+}
+
+impl Log for RingBufferLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        self.capacity > 0
+    }
+
+    fn log(&self, record: &Record) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut line = super::format_line(record);
+        line.pop();
+
+        let mut lines = self.lock();
+        if lines.len() >= self.capacity {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+
+    fn flush(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OwnedRecord;
+    use log::Level;
+
+    fn log(logger: &impl Log, message: &str) {
+        OwnedRecord::new(Level::Info, "app", message).with_record(|record| logger.log(record));
+    }
+
+    /// The kept lines without their timestamps
+    fn messages(lines: &[String]) -> Vec<&str> {
+        lines
+            .iter()
+            .map(|line| line.split_once(' ').unwrap().1)
+            .collect()
+    }
+
+    #[test]
+    fn keeps_the_most_recent_records() {
+        let logger = RingBufferLogger::new(2);
+        for message in ["first", "second", "third"] {
+            log(&logger, message);
+        }
+
+        assert_eq!(
+            messages(&logger.snapshot()),
+            ["INFO  [app] second", "INFO  [app] third"]
+        );
+        logger.clear();
+        assert!(logger.snapshot().is_empty());
+    }
+
+    #[test]
+    fn dumps_the_records_as_lines() {
+        let logger = RingBufferLogger::new(2);
+        log(&logger, "first");
+        log(&logger, "second");
+
+        let mut dump = Vec::new();
+        logger.dump_to(&mut dump).unwrap();
+        let dump = String::from_utf8(dump).unwrap();
+        assert_eq!(dump.lines().collect::<Vec<_>>(), logger.snapshot());
+        assert!(dump.ends_with("second\n"));
+    }
+
+    #[test]
+    fn keeps_nothing_without_capacity() {
+        let logger = RingBufferLogger::new(0);
+        log(&logger, "dropped");
+
+        assert!(!logger.enabled(&log::Metadata::builder().build()));
+        assert!(logger.snapshot().is_empty());
+    }
+}
@@ -0,0 +1,993 @@
+#[cfg(feature = "encryption")]
+use crate::encryption::EncryptionKey;
+use crate::format::{self, BinaryFormat, Format};
+use crate::{HealthCheck, LineEncoding, SinkHealth};
+use log::{Level, Log, Metadata, Record};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+type DeletionHandler = Box<dyn Fn(&Path) + Send + Sync>;
+
+/// How many rotated files are kept unless configured otherwise
+const DEFAULT_MAX_FILES: usize = 5;
+/// How long rotated files left uncompressed by a failure wait to be compressed again
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+const COMPRESSION_RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+struct State {
+    /// The path of the open file, with the date of `day` if the path is dated
+    path: PathBuf,
+    /// Days since the unix epoch when the file was opened
+    day: i64,
+    file: BufWriter<File>,
+    size: u64,
+    opened_at: Instant,
+    /// Locked while writing with shared access, opened on the first write
+    lock_file: Option<File>,
+    /// Why the last record could not be written, if it could not
+    failure: Option<String>,
+    /// Compresses the most recently rotated file in the background
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    compressing: Option<JoinHandle<io::Result<()>>>,
+    /// Why a rotated file could not be compressed, while it is left uncompressed
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    compression_failure: Option<CompressionFailure>,
+}
+
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+struct CompressionFailure {
+    error: String,
+    /// The rotated files left uncompressed are compressed again once the retry interval is over
+    failed_at: Instant,
+}
+
+/// When the records written to a file reach the disk
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Durability {
+    /// Records are written in blocks, once the buffer is full or the logger is flushed
+    #[default]
+    Block,
+    /// Every record is written to the operating system right away,
+    /// so it is kept even if the process is killed
+    Line,
+    /// Every record is written and synced to the disk,
+    /// so it is kept even if the machine goes down
+    Record,
+}
+
+/// How rotated files are compressed
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+#[derive(Debug, Clone, Copy)]
+enum Codec {
+    #[cfg(feature = "gzip")]
+    Gzip,
+    /// With the compression level
+    #[cfg(feature = "zstd")]
+    Zstd(i32),
+}
+
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+impl Codec {
+    fn extension(self) -> &'static str {
+        match self {
+            #[cfg(feature = "gzip")]
+            Codec::Gzip => ".gz",
+            #[cfg(feature = "zstd")]
+            Codec::Zstd(_) => ".zst",
+        }
+    }
+
+    /// Compress the file into a new file, deleting the uncompressed file,
+    /// or the partly written compressed file if compressing fails
+    fn compress(self, from: &Path, to: &Path) -> io::Result<()> {
+        if let Err(error) = self.encode(from, to) {
+            let _ = fs::remove_file(to);
+            return Err(error);
+        }
+        fs::remove_file(from)
+    }
+
+    fn encode(self, from: &Path, to: &Path) -> io::Result<()> {
+        let mut input = File::open(from)?;
+        let output = File::create(to)?;
+        match self {
+            #[cfg(feature = "gzip")]
+            Codec::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(output, flate2::Compression::default());
+                io::copy(&mut input, &mut encoder)?;
+                encoder.finish()?;
+            }
+            #[cfg(feature = "zstd")]
+            Codec::Zstd(level) => {
+                let mut encoder = zstd::Encoder::new(output, level)?;
+                io::copy(&mut input, &mut encoder)?;
+                encoder.finish()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes records to a file that is rotated once it grows too large or gets too old.
+///
+/// Rotated files are renamed to `<path>.1`, `<path>.2` and so on, the most recent being `.1`,
+/// and only the configured number of rotated files is kept. The oldest rotated files are
+/// also deleted once they are too old or all files together are too large.
+///
+/// The path can contain the placeholders `{hostname}`, `{pid}` and `{date}`, the current
+/// date in the time zone of [`crate::set_time_zone`] such as `2024-01-31`. A dated path
+/// switches to the file of the next day at midnight, and each day's file is rotated
+/// and its rotated files retained on its own.
+/// Errors while writing or rotating can not be reported, but make the logger
+/// unhealthy until a record is written again, see [`HealthCheck`].
+pub struct RotatingFileLogger {
+    /// The path with the hostname and process id, but with the `{date}` placeholder
+    path: PathBuf,
+    /// Whether the path has a `{date}` placeholder
+    dated: bool,
+    /// Several processes write to and rotate the file, see [`RotatingFileLogger::with_shared_access`]
+    shared: bool,
+    durability: Durability,
+    /// Records of this level or more severe are synced to the disk whatever the durability
+    sync_level: Option<Level>,
+    max_size: Option<u64>,
+    rotation_interval: Option<Duration>,
+    max_files: usize,
+    max_total_size: Option<u64>,
+    max_age: Option<Duration>,
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    compression: Option<Codec>,
+    #[cfg(feature = "encryption")]
+    encryption: Option<EncryptionKey>,
+    /// Called with the path of every deleted rotated file
+    deletion_handler: Option<DeletionHandler>,
+    format: Option<Box<dyn Format>>,
+    binary_format: Option<Box<dyn BinaryFormat>>,
+    line_encoding: LineEncoding,
+    state: Mutex<State>,
+}
+
+impl RotatingFileLogger {
+    /// Open the file for appending, creating it if it does not exist,
+    /// after replacing the placeholders of the path.
+    /// Without a size or time limit the file is never rotated.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file can not be opened.
+    pub fn new(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let (path, dated) = match path.to_str() {
+            Some(template) => (
+                PathBuf::from(
+                    template
+                        .replace("{hostname}", &crate::host::hostname())
+                        .replace("{pid}", &std::process::id().to_string()),
+                ),
+                template.contains("{date}"),
+            ),
+            None => (path, false),
+        };
+        let day = today();
+        let state = Self::open(dated_path(&path, dated, day), day)?;
+
+        Ok(Self {
+            path,
+            dated,
+            shared: false,
+            durability: Durability::default(),
+            sync_level: None,
+            max_size: None,
+            rotation_interval: None,
+            max_files: DEFAULT_MAX_FILES,
+            max_total_size: None,
+            max_age: None,
+            #[cfg(any(feature = "gzip", feature = "zstd"))]
+            compression: None,
+            #[cfg(feature = "encryption")]
+            encryption: None,
+            deletion_handler: None,
+            format: None,
+            binary_format: None,
+            line_encoding: LineEncoding::default(),
+            state: Mutex::new(state),
+        })
+    }
+
+    /// Choose when records reach the disk, by default they are written in blocks
+    pub fn with_durability(mut self, durability: Durability) -> Self {
+        self.durability = durability;
+        self
+    }
+
+    /// Sync records of the given level or more severe to the disk right away,
+    /// for example errors that must not be lost while other records stay buffered
+    pub fn with_sync_level(mut self, level: Level) -> Self {
+        self.sync_level = Some(level);
+        self
+    }
+
+    /// Rotate the file before it grows beyond the given size in bytes
+    pub fn with_max_size(mut self, bytes: u64) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    /// Rotate the file once it has been written to for the given duration
+    pub fn with_rotation_interval(mut self, interval: Duration) -> Self {
+        self.rotation_interval = Some(interval);
+        self
+    }
+
+    /// How many rotated files to keep, the oldest are deleted
+    pub fn with_max_files(mut self, max_files: usize) -> Self {
+        self.max_files = max_files;
+        self
+    }
+
+    /// Delete the oldest rotated files once the current and the rotated files together
+    /// take more than the given number of bytes
+    pub fn with_max_total_size(mut self, bytes: u64) -> Self {
+        self.max_total_size = Some(bytes);
+        self
+    }
+
+    /// Delete rotated files that were last written to longer ago than the given duration
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Call the handler with the path of every rotated file that is deleted,
+    /// from the thread that rotates the file
+    pub fn with_deletion_handler(
+        mut self,
+        handler: impl Fn(&Path) + Send + Sync + 'static,
+    ) -> Self {
+        self.deletion_handler = Some(Box::new(handler));
+        self
+    }
+
+    /// Compress rotated files with gzip in the background, adding a `.gz` extension.
+    /// Rotated files that can not be compressed are rotated uncompressed, compressed again
+    /// at most once a minute, and make the logger unhealthy, see [`HealthCheck`].
+    #[cfg(feature = "gzip")]
+    pub fn with_compression(mut self, compress: bool) -> Self {
+        self.compression = compress.then_some(Codec::Gzip);
+        self
+    }
+
+    /// Compress rotated files with zstd at the given level in the background,
+    /// adding a `.zst` extension
+    #[cfg(feature = "zstd")]
+    pub fn with_zstd_compression(mut self, level: i32) -> Self {
+        self.compression = Some(Codec::Zstd(level));
+        self
+    }
+
+    /// Encrypt every record with the key, see [`crate::encryption`] for the format
+    /// and how to decrypt the files
+    #[cfg(feature = "encryption")]
+    pub fn with_encryption(mut self, key: EncryptionKey) -> Self {
+        self.encryption = Some(key);
+        self
+    }
+
+    /// Let several processes write to the same file and rotate it in turn, for example
+    /// worker processes sharing a log file. Every line is then written unbuffered with
+    /// a single write while holding an advisory lock on `<path>.lock`, and the file is
+    /// reopened whenever another process rotated it, so that lines never interleave.
+    pub fn with_shared_access(mut self, shared: bool) -> Self {
+        self.shared = shared;
+        self
+    }
+
+    /// Format the records with the given format instead of the default template,
+    /// see [`crate::format::TemplateFormat`]
+    pub fn with_format(mut self, format: impl Format + 'static) -> Self {
+        self.format = Some(Box::new(format));
+        self
+    }
+
+    /// Encode the records with the binary format instead of formatting them as lines,
+    /// for example with the [`crate::msgpack::MessagePackFormat`]
+    pub fn with_binary_format(mut self, format: impl BinaryFormat + 'static) -> Self {
+        self.binary_format = Some(Box::new(format));
+        self
+    }
+
+    /// Encode the lines, for example as UTF-16 or ending with `\r\n`, see [`LineEncoding`].
+    /// Binary formats are written as encoded.
+    pub fn with_line_encoding(mut self, encoding: LineEncoding) -> Self {
+        self.line_encoding = encoding;
+        self
+    }
+
+    /// The path with the hostname and process id replaced, but not the date
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The path of the file currently written to
+    pub fn current_path(&self) -> PathBuf {
+        self.lock().path.clone()
+    }
+
+    /// Close the file and open the file at the path again, for example once an external tool
+    /// such as logrotate renamed it, see [`crate::CompositeLoggerHandle::reopen_files`]
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file can not be opened, records are then still written to the old file.
+    pub fn reopen(&self) -> io::Result<()> {
+        let mut state = self.lock();
+        let reopened = state
+            .file
+            .flush()
+            .and_then(|()| self.reopen_file(&mut state));
+        state.failure = reopened.as_ref().err().map(|error| error.to_string());
+        reopened
+    }
+
+    fn open(path: PathBuf, day: i64) -> io::Result<State> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+
+        Ok(State {
+            path,
+            day,
+            file: BufWriter::new(file),
+            size,
+            opened_at: crate::time::instant(),
+            lock_file: None,
+            failure: None,
+            #[cfg(any(feature = "gzip", feature = "zstd"))]
+            compressing: None,
+            #[cfg(any(feature = "gzip", feature = "zstd"))]
+            compression_failure: None,
+        })
+    }
+
+    /// Open the file again, for example after it was rotated
+    fn reopen_file(&self, state: &mut State) -> io::Result<()> {
+        let mut reopened = Self::open(state.path.clone(), state.day)?;
+        reopened.lock_file = state.lock_file.take();
+        #[cfg(any(feature = "gzip", feature = "zstd"))]
+        {
+            reopened.compressing = state.compressing.take();
+            reopened.compression_failure = state.compression_failure.take();
+        }
+        *state = reopened;
+        Ok(())
+    }
+
+    /// Switch to the file of the current day once the day of a dated file is over,
+    /// or once the time zone set after the logger was created changed the date
+    fn roll_over(&self, state: &mut State) -> io::Result<()> {
+        let day = today();
+        if !self.dated || day == state.day {
+            return Ok(());
+        }
+
+        state.file.flush()?;
+        state.path = dated_path(&self.path, self.dated, day);
+        state.day = day;
+        // the lock file belongs to the file of the previous day
+        state.lock_file = None;
+        self.reopen_file(state)
+    }
+
+    /// Whether the open file is still the one at the path, and not rotated by another process
+    #[cfg(unix)]
+    fn is_current(&self, state: &State) -> io::Result<bool> {
+        use std::os::unix::fs::MetadataExt;
+
+        let open = state.file.get_ref().metadata()?;
+        Ok(fs::metadata(&state.path)
+            .is_ok_and(|current| current.dev() == open.dev() && current.ino() == open.ino()))
+    }
+
+    /// Without a way to tell files apart, the file is assumed to have been rotated
+    #[cfg(not(unix))]
+    fn is_current(&self, _state: &State) -> io::Result<bool> {
+        Ok(false)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    fn should_rotate(&self, state: &State, additional: u64) -> bool {
+        let too_large = self
+            .max_size
+            .is_some_and(|max_size| state.size > 0 && state.size + additional > max_size);
+        let too_old = self
+            .rotation_interval
+            .is_some_and(|interval| crate::time::elapsed(state.opened_at) >= interval);
+
+        too_large || too_old
+    }
+
+    fn rotated_path(&self, state: &State, index: usize) -> PathBuf {
+        let mut path = state.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        #[cfg(any(feature = "gzip", feature = "zstd"))]
+        if let Some(codec) = self.compression {
+            path.push(codec.extension());
+        }
+        PathBuf::from(path)
+    }
+
+    /// The paths the rotated file of the index can have, with compression also
+    /// the path of the rotated file if it was left uncompressed by a failure
+    fn rotated_paths(&self, state: &State, index: usize) -> Vec<PathBuf> {
+        let rotated = self.rotated_path(state, index);
+        #[cfg(any(feature = "gzip", feature = "zstd"))]
+        if self.compression.is_some() {
+            return vec![rotated, Self::uncompressed_path(state, index)];
+        }
+        vec![rotated]
+    }
+
+    fn rotate(&self, state: &mut State) -> io::Result<()> {
+        state.file.flush()?;
+        #[cfg(any(feature = "gzip", feature = "zstd"))]
+        self.finish_compression(state);
+
+        if self.max_files == 0 {
+            self.delete(&state.path)?;
+        } else {
+            for oldest in self.rotated_paths(state, self.max_files) {
+                if oldest.exists() {
+                    self.delete(&oldest)?;
+                }
+            }
+            for index in (1..self.max_files).rev() {
+                let to = self.rotated_paths(state, index + 1);
+                for (from, to) in self.rotated_paths(state, index).into_iter().zip(to) {
+                    if from.exists() {
+                        fs::rename(from, to)?;
+                    }
+                }
+            }
+            self.move_to_rotated(state)?;
+        }
+
+        self.reopen_file(state)?;
+        self.apply_retention(state);
+        Ok(())
+    }
+
+    /// Wait for the most recently rotated file to be compressed, and compress the rotated
+    /// files left uncompressed by a failure again once the retry interval is over
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    fn finish_compression(&self, state: &mut State) {
+        if let Some(compressing) = state.compressing.take() {
+            let compressed = compressing
+                .join()
+                .unwrap_or_else(|_| Err(io::Error::other("the compression panicked")));
+            Self::record_compression(state, compressed);
+        }
+
+        let retry = state.compression_failure.as_ref().is_some_and(|failure| {
+            crate::time::elapsed(failure.failed_at) >= COMPRESSION_RETRY_INTERVAL
+        });
+        if let Some(codec) = self.compression.filter(|_| retry) {
+            let mut compressed = Ok(());
+            for index in 1..=self.max_files {
+                let uncompressed = Self::uncompressed_path(state, index);
+                if uncompressed.exists() {
+                    let rotated = self.rotated_path(state, index);
+                    compressed = compressed.and(codec.compress(&uncompressed, &rotated));
+                }
+            }
+            Self::record_compression(state, compressed);
+        }
+        self.forget_compression_failure(state);
+    }
+
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    fn record_compression(state: &mut State, compressed: io::Result<()>) {
+        if let Err(error) = compressed {
+            state.compression_failure = Some(CompressionFailure {
+                error: error.to_string(),
+                failed_at: crate::time::instant(),
+            });
+        }
+    }
+
+    /// Forget the compression failure once no rotated file is left uncompressed,
+    /// for example because they were compressed again or deleted
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    fn forget_compression_failure(&self, state: &mut State) {
+        let uncompressed =
+            (1..=self.max_files).any(|index| Self::uncompressed_path(state, index).exists());
+        if !uncompressed {
+            state.compression_failure = None;
+        }
+    }
+
+    /// The rotated file of the index before it is compressed
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    fn uncompressed_path(state: &State, index: usize) -> PathBuf {
+        let mut uncompressed = state.path.clone().into_os_string();
+        uncompressed.push(format!(".{}", index));
+        PathBuf::from(uncompressed)
+    }
+
+    /// Rename the file to the most recent rotated file, compressing it in the background
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    fn move_to_rotated(&self, state: &mut State) -> io::Result<()> {
+        let rotated = self.rotated_path(state, 1);
+        let Some(codec) = self.compression else {
+            return fs::rename(&state.path, rotated);
+        };
+
+        let uncompressed = Self::uncompressed_path(state, 1);
+        fs::rename(&state.path, &uncompressed)?;
+
+        let compress = move || codec.compress(&uncompressed, &rotated);
+        match thread::Builder::new()
+            .name("composite-logger-compress".to_string())
+            .spawn(compress.clone())
+        {
+            Ok(compressing) => state.compressing = Some(compressing),
+            Err(_) => Self::record_compression(state, compress()),
+        }
+        Ok(())
+    }
+
+    #[cfg(not(any(feature = "gzip", feature = "zstd")))]
+    fn move_to_rotated(&self, state: &mut State) -> io::Result<()> {
+        fs::rename(&state.path, self.rotated_path(state, 1))
+    }
+
+    /// Delete the oldest rotated files beyond the total size,
+    /// and the rotated files that are too old
+    fn apply_retention(&self, state: &State) {
+        if self.max_total_size.is_none() && self.max_age.is_none() {
+            return;
+        }
+
+        let mut total_size = fs::metadata(&state.path).map_or(0, |metadata| metadata.len());
+        let now = crate::time::wall_time();
+        let rotated = (1..=self.max_files).flat_map(|index| self.rotated_paths(state, index));
+        for path in rotated {
+            let Ok(metadata) = fs::metadata(&path) else {
+                continue;
+            };
+            total_size += metadata.len();
+
+            let too_large = self
+                .max_total_size
+                .is_some_and(|max_total_size| total_size > max_total_size);
+            let too_old = self.max_age.is_some_and(|max_age| {
+                metadata
+                    .modified()
+                    .ok()
+                    .and_then(|modified| now.duration_since(modified).ok())
+                    .is_some_and(|age| age > max_age)
+            });
+            if too_large || too_old {
+                let _ = self.delete(&path);
+            }
+        }
+    }
+
+    fn delete(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)?;
+        if let Some(handler) = &self.deletion_handler {
+            handler(path);
+        }
+        Ok(())
+    }
+
+    /// Write the record, remembering whether writing failed for the health check
+    fn write(&self, line: &[u8], level: Level) {
+        let mut state = self.lock();
+        let written = self.write_to(&mut state, line, level);
+        state.failure = written.err().map(|error| error.to_string());
+    }
+
+    fn write_to(&self, state: &mut State, line: &[u8], level: Level) -> io::Result<()> {
+        self.roll_over(state)?;
+        if self.shared {
+            return self.write_shared(state, line, level);
+        }
+
+        if self.should_rotate(state, line.len() as u64) {
+            self.rotate(state)?;
+        }
+
+        state.file.write_all(line)?;
+        state.size += line.len() as u64;
+        self.persist(state, level)
+    }
+
+    /// Flush or sync the record just written as the durability requires
+    fn persist(&self, state: &mut State, level: Level) -> io::Result<()> {
+        let sync = self.durability == Durability::Record
+            || self
+                .sync_level
+                .is_some_and(|sync_level| level <= sync_level);
+        if sync || self.durability == Durability::Line {
+            state.file.flush()?;
+        }
+        if sync {
+            state.file.get_ref().sync_data()?;
+        }
+        Ok(())
+    }
+
+    /// Write the line while holding the lock shared with the other processes
+    fn write_shared(&self, state: &mut State, line: &[u8], level: Level) -> io::Result<()> {
+        if state.lock_file.is_none() {
+            let mut path = state.path.clone().into_os_string();
+            path.push(".lock");
+            state.lock_file = Some(OpenOptions::new().create(true).append(true).open(path)?);
+        }
+        let lock_file = state
+            .lock_file
+            .take()
+            .expect("the lock file was just opened");
+
+        lock_file.lock()?;
+        let written = self.write_locked(state, line, level);
+        let unlocked = lock_file.unlock();
+        state.lock_file = Some(lock_file);
+        written.and(unlocked)
+    }
+
+    fn write_locked(&self, state: &mut State, line: &[u8], level: Level) -> io::Result<()> {
+        if !self.is_current(state)? {
+            self.reopen_file(state)?;
+        }
+        state.size = state.file.get_ref().metadata()?.len();
+
+        if self.should_rotate(state, line.len() as u64) {
+            self.rotate(state)?;
+        }
+
+        state.file.get_mut().write_all(line)?;
+        state.size += line.len() as u64;
+        self.persist(state, level)
+    }
+}
+
+/// Waits until the most recently rotated file is compressed
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+impl Drop for RotatingFileLogger {
+    fn drop(&mut self) {
+        if let Some(compressing) = self.lock().compressing.take() {
+            let _ = compressing.join();
+        }
+    }
+}
+
+impl Log for RotatingFileLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let write = |line: &[u8]| {
+            #[cfg(feature = "encryption")]
+            if let Some(key) = &self.encryption {
+                self.write(&key.seal(line), record.level());
+                return;
+            }
+            self.write(line, record.level());
+        };
+        match (&self.binary_format, &self.format) {
+            (Some(format), _) => format::with_encoded_record(format, record, write),
+            (None, format) => format::with_line_buffer(|line| {
+                match format {
+                    Some(format) => {
+                        format.format_into(record, false, line);
+                        line.push('\n');
+                    }
+                    None => super::write_line(record, line),
+                }
+                self.line_encoding.with_encoded(line, write);
+            }),
+        }
+    }
+
+    fn flush(&self) {
+        let mut state = self.lock();
+        if let Err(error) = state.file.flush() {
+            state.failure = Some(error.to_string());
+        }
+    }
+}
+
+impl HealthCheck for RotatingFileLogger {
+    fn health(&self) -> SinkHealth {
+        let state = self.lock();
+        if let Some(error) = &state.failure {
+            return SinkHealth::Degraded(format!(
+                "could not write to {}: {}",
+                state.path.display(),
+                error
+            ));
+        }
+
+        #[cfg(any(feature = "gzip", feature = "zstd"))]
+        {
+            let mut state = state;
+            if let Some(compressing) = state
+                .compressing
+                .take_if(|compressing| compressing.is_finished())
+            {
+                let compressed = compressing
+                    .join()
+                    .unwrap_or_else(|_| Err(io::Error::other("the compression panicked")));
+                Self::record_compression(&mut state, compressed);
+            }
+            if state.compressing.is_none() && state.compression_failure.is_some() {
+                self.forget_compression_failure(&mut state);
+            }
+            if let Some(failure) = &state.compression_failure {
+                return SinkHealth::Degraded(format!(
+                    "could not compress the rotated files of {}: {}",
+                    state.path.display(),
+                    failure.error
+                ));
+            }
+        }
+        SinkHealth::Healthy
+    }
+}
+
+/// The days since the unix epoch of the current date in the configured time zone
+fn today() -> i64 {
+    crate::timestamp::day(crate::time::now())
+}
+
+/// The path with the `{date}` placeholder replaced by the given day since the unix epoch
+fn dated_path(path: &Path, dated: bool, day: i64) -> PathBuf {
+    match path.to_str() {
+        Some(template) if dated => {
+            let (year, month, day) = crate::time::civil_from_days(day);
+            PathBuf::from(
+                template.replace("{date}", &format!("{:04}-{:02}-{:02}", year, month, day)),
+            )
+        }
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use crate::OwnedRecord;
+
+    /// An empty directory of the test, removed once the test is over
+    struct Directory(PathBuf);
+
+    impl Directory {
+        fn new(test: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "composite-logger-{}-{}",
+                test,
+                std::process::id()
+            ));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+
+        fn read(&self, name: &str) -> String {
+            fs::read_to_string(self.0.join(name)).unwrap_or_default()
+        }
+
+        fn names(&self) -> Vec<String> {
+            let mut names: Vec<_> = fs::read_dir(&self.0)
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+                .collect();
+            names.sort();
+            names
+        }
+    }
+
+    impl Drop for Directory {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn log(logger: &RotatingFileLogger, message: &str) {
+        OwnedRecord::new(Level::Info, "my_app", message).with_record(|record| logger.log(record));
+        logger.flush();
+    }
+
+    #[test]
+    fn rotates_once_too_large_and_keeps_the_max_files() {
+        let directory = Directory::new("size");
+        let logger = RotatingFileLogger::new(directory.0.join("app.log"))
+            .unwrap()
+            .with_max_size(40)
+            .with_max_files(2);
+        for message in ["first", "second", "third", "fourth"] {
+            log(&logger, message);
+        }
+
+        assert_eq!(directory.names(), ["app.log", "app.log.1", "app.log.2"]);
+        assert!(directory.read("app.log").contains("fourth"));
+        assert!(directory.read("app.log.1").contains("third"));
+        assert!(directory.read("app.log.2").contains("second"));
+        assert_eq!(logger.health(), SinkHealth::Healthy);
+    }
+
+    #[test]
+    fn rotates_after_the_interval() {
+        testing::with_mock_clock(|clock| {
+            let directory = Directory::new("interval");
+            let logger = RotatingFileLogger::new(directory.0.join("app.log"))
+                .unwrap()
+                .with_rotation_interval(Duration::from_secs(60));
+            log(&logger, "first");
+            clock.advance(Duration::from_secs(59));
+            log(&logger, "second");
+            assert_eq!(directory.names(), ["app.log"]);
+
+            clock.advance(Duration::from_secs(1));
+            log(&logger, "third");
+            assert_eq!(directory.names(), ["app.log", "app.log.1"]);
+            assert!(directory.read("app.log.1").contains("second"));
+            assert!(directory.read("app.log").contains("third"));
+        });
+    }
+
+    #[test]
+    fn dated_path_switches_to_the_file_of_the_next_day() {
+        testing::with_mock_clock(|clock| {
+            let directory = Directory::new("dated");
+            let logger = RotatingFileLogger::new(directory.0.join("app-{date}.log")).unwrap();
+            log(&logger, "today");
+            let today = logger.current_path();
+            clock.advance(Duration::from_secs(86_400));
+            log(&logger, "tomorrow");
+
+            let tomorrow = logger.current_path();
+            assert_ne!(today, tomorrow);
+            assert!(fs::read_to_string(today).unwrap().contains("today"));
+            assert!(fs::read_to_string(tomorrow).unwrap().contains("tomorrow"));
+            assert_eq!(directory.names().len(), 2);
+        });
+    }
+
+    #[test]
+    fn deletes_the_rotated_files_beyond_the_total_size() {
+        let directory = Directory::new("total-size");
+        let logger = RotatingFileLogger::new(directory.0.join("app.log"))
+            .unwrap()
+            .with_max_size(40)
+            .with_max_total_size(100);
+        for message in ["first", "second", "third", "fourth"] {
+            log(&logger, message);
+        }
+        assert_eq!(directory.names(), ["app.log", "app.log.1", "app.log.2"]);
+    }
+
+    #[test]
+    fn deletes_the_rotated_files_that_are_too_old() {
+        testing::with_mock_clock(|clock| {
+            let directory = Directory::new("max-age");
+            let logger = RotatingFileLogger::new(directory.0.join("app.log"))
+                .unwrap()
+                .with_max_size(40)
+                .with_max_age(Duration::from_secs(3600));
+            log(&logger, "first");
+            log(&logger, "second");
+            assert_eq!(directory.names(), ["app.log", "app.log.1"]);
+
+            clock.advance(Duration::from_secs(3601));
+            log(&logger, "third");
+            assert_eq!(directory.names(), ["app.log"]);
+            assert!(directory.read("app.log").contains("third"));
+        });
+    }
+
+    #[cfg(feature = "gzip")]
+    fn compressing_logger(directory: &Directory) -> RotatingFileLogger {
+        RotatingFileLogger::new(directory.0.join("app.log"))
+            .unwrap()
+            .with_max_size(40)
+            .with_compression(true)
+    }
+
+    /// Wait for the most recently rotated file to be compressed in the background
+    #[cfg(feature = "gzip")]
+    fn wait_for_compression(logger: &RotatingFileLogger) {
+        let mut state = logger.lock();
+        if let Some(compressing) = state.compressing.take() {
+            let compressed = compressing.join().unwrap();
+            RotatingFileLogger::record_compression(&mut state, compressed);
+        }
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn compresses_the_rotated_files() {
+        let directory = Directory::new("compression");
+        let logger = compressing_logger(&directory);
+        for message in ["first", "second", "third"] {
+            log(&logger, message);
+        }
+        logger
+            .lock()
+            .compressing
+            .take()
+            .unwrap()
+            .join()
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            directory.names(),
+            ["app.log", "app.log.1.gz", "app.log.2.gz"]
+        );
+        assert_eq!(logger.health(), SinkHealth::Healthy);
+    }
+
+    /// Leave the most recently rotated file uncompressed as if compressing it failed
+    #[cfg(feature = "gzip")]
+    fn fail_compression(directory: &Directory, logger: &RotatingFileLogger) {
+        fs::write(directory.0.join("app.log.1"), "first\n").unwrap();
+        let failed = Err(io::Error::other("no space left on device"));
+        RotatingFileLogger::record_compression(&mut logger.lock(), failed);
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn rotates_the_files_left_uncompressed_and_compresses_them_again() {
+        testing::with_mock_clock(|clock| {
+            let directory = Directory::new("compression-retry");
+            let logger = compressing_logger(&directory);
+            log(&logger, "second");
+            fail_compression(&directory, &logger);
+            assert_eq!(
+                logger.health(),
+                SinkHealth::Degraded(format!(
+                    "could not compress the rotated files of {}: no space left on device",
+                    directory.0.join("app.log").display()
+                ))
+            );
+
+            log(&logger, "third");
+            wait_for_compression(&logger);
+            assert_eq!(directory.names(), ["app.log", "app.log.1.gz", "app.log.2"]);
+            assert_eq!(directory.read("app.log.2"), "first\n");
+            assert!(matches!(logger.health(), SinkHealth::Degraded(_)));
+
+            clock.advance(COMPRESSION_RETRY_INTERVAL);
+            log(&logger, "fourth");
+            wait_for_compression(&logger);
+            assert_eq!(
+                directory.names(),
+                ["app.log", "app.log.1.gz", "app.log.2.gz", "app.log.3.gz"]
+            );
+            assert_eq!(logger.health(), SinkHealth::Healthy);
+        });
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn forgets_the_compression_failure_once_the_uncompressed_file_is_deleted() {
+        let directory = Directory::new("compression-deleted");
+        let logger = compressing_logger(&directory);
+        fail_compression(&directory, &logger);
+        assert!(matches!(logger.health(), SinkHealth::Degraded(_)));
+
+        fs::remove_file(directory.0.join("app.log.1")).unwrap();
+        assert_eq!(logger.health(), SinkHealth::Healthy);
+    }
+}
@@ -0,0 +1,106 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt::Write;
+use critical_section::Mutex;
+use log::{Log, Metadata, Record};
+use rtt_target::UpChannel;
+
+/// How the records are written to the RTT channel
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RttFraming {
+    /// One `LEVEL target: message` line per record, readable by any RTT viewer
+    #[default]
+    Text,
+    /// One frame per record: the level as a byte from 1 for errors to 5 for trace,
+    /// then the target and the message, each prefixed by its length in bytes
+    /// as an unsigned LEB128 varint
+    Compact,
+}
+
+/// Writes the records to an RTT up channel, for example one initialized with
+/// `rtt_target::rtt_init!`, so that a debug probe receives them.
+/// The channel is written to from a critical section.
+pub struct RttLogger {
+    channel: Mutex<RefCell<UpChannel>>,
+    framing: RttFraming,
+}
+
+impl RttLogger {
+    pub fn new(channel: UpChannel) -> Self {
+        Self {
+            channel: Mutex::new(RefCell::new(channel)),
+            framing: RttFraming::default(),
+        }
+    }
+
+    pub fn with_framing(mut self, framing: RttFraming) -> Self {
+        self.framing = framing;
+        self
+    }
+
+    /// Frame the record as described by [`RttFraming::Compact`]
+    fn frame(record: &Record) -> Vec<u8> {
+        let message = match record.args().as_str() {
+            Some(message) => String::from(message),
+            None => alloc::format!("{}", record.args()),
+        };
+        let target = record.target();
+
+        let mut frame = Vec::with_capacity(target.len() + message.len() + 5);
+        frame.push(record.level() as u8);
+        for field in [target.as_bytes(), message.as_bytes()] {
+            let mut length = field.len();
+            while length >= 0x80 {
+                frame.push(length as u8 | 0x80);
+                length >>= 7;
+            }
+            frame.push(length as u8);
+            frame.extend_from_slice(field);
+        }
+        frame
+    }
+}
+
+/// Writes formatted text straight to the channel
+struct ChannelWriter<'a>(&'a mut UpChannel);
+
+impl Write for ChannelWriter<'_> {
+    fn write_str(&mut self, text: &str) -> core::fmt::Result {
+        self.0.write(text.as_bytes());
+        Ok(())
+    }
+}
+
+impl Log for RttLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let frame = match self.framing {
+            RttFraming::Text => None,
+            RttFraming::Compact => Some(Self::frame(record)),
+        };
+
+        critical_section::with(|cs| {
+            let mut channel = self.channel.borrow_ref_mut(cs);
+            match &frame {
+                Some(frame) => {
+                    channel.write(frame);
+                }
+                None => {
+                    let _ = writeln!(
+                        ChannelWriter(&mut channel),
+                        "{:<5} {}: {}",
+                        record.level(),
+                        record.target(),
+                        record.args()
+                    );
+                }
+            }
+        });
+    }
+
+    fn flush(&self) {}
+}
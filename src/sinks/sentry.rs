@@ -0,0 +1,115 @@
+use crate::kv::Collect;
+use crate::sinks::RingBufferLogger;
+use crate::FormattedRecord;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use sentry_core::protocol::{Breadcrumb, Event, Map, Value};
+use std::sync::Arc;
+
+/// Records less severe than `Error` as Sentry breadcrumbs and captures `Error` records
+/// as Sentry events, using the hub of the current thread.
+///
+/// The key-values of a record become the data of the breadcrumb or the extra data of the event.
+/// With a ring buffer the most recent records are attached to the events as well,
+/// see [`SentryLogger::with_recent_records`].
+pub struct SentryLogger {
+    breadcrumb_level: LevelFilter,
+    event_level: Level,
+    recent: Option<Arc<RingBufferLogger>>,
+}
+
+impl Default for SentryLogger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SentryLogger {
+    /// Record `Warn`, `Info` and `Debug` records as breadcrumbs and capture `Error` records
+    pub fn new() -> Self {
+        Self {
+            breadcrumb_level: LevelFilter::Debug,
+            event_level: Level::Error,
+            recent: None,
+        }
+    }
+
+    /// Record records at or above the given level as breadcrumbs, by default `Debug`
+    pub fn with_breadcrumb_level(mut self, level: LevelFilter) -> Self {
+        self.breadcrumb_level = level;
+        self
+    }
+
+    /// Capture records at or above the given level as events, by default `Error`
+    pub fn with_event_level(mut self, level: Level) -> Self {
+        self.event_level = level;
+        self
+    }
+
+    /// Attach the records kept by the ring buffer to the events as `recent_records`,
+    /// the ring buffer should be added to the composite logger as well
+    pub fn with_recent_records(mut self, recent: Arc<RingBufferLogger>) -> Self {
+        self.recent = Some(recent);
+        self
+    }
+
+    fn level(level: Level) -> sentry_core::Level {
+        match level {
+            Level::Error => sentry_core::Level::Error,
+            Level::Warn => sentry_core::Level::Warning,
+            Level::Info => sentry_core::Level::Info,
+            Level::Debug | Level::Trace => sentry_core::Level::Debug,
+        }
+    }
+
+    fn data(record: &Record) -> Map<String, Value> {
+        let mut collect = Collect(Vec::new());
+        let _ = record.key_values().visit(&mut collect);
+        collect
+            .0
+            .into_iter()
+            .map(|(key, value)| (key, Value::from(value)))
+            .collect()
+    }
+
+    fn event(&self, record: &Record) -> Event<'static> {
+        let mut extra = Self::data(record);
+        if let Some(recent) = &self.recent {
+            extra.insert("recent_records".to_string(), Value::from(recent.snapshot()));
+        }
+
+        Event {
+            level: Self::level(record.level()),
+            message: Some(FormattedRecord::new(record).message().to_string()),
+            logger: Some(record.target().to_string()),
+            extra,
+            ..Default::default()
+        }
+    }
+
+    fn breadcrumb(record: &Record) -> Breadcrumb {
+        Breadcrumb {
+            ty: "log".to_string(),
+            category: Some(record.target().to_string()),
+            level: Self::level(record.level()),
+            message: Some(FormattedRecord::new(record).message().to_string()),
+            data: Self::data(record),
+            ..Default::default()
+        }
+    }
+}
+
+impl Log for SentryLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.event_level || metadata.level() <= self.breadcrumb_level
+    }
+
+    fn log(&self, record: &Record) {
+        if record.level() <= self.event_level {
+            sentry_core::capture_event(self.event(record));
+        } else if record.level() <= self.breadcrumb_level {
+            sentry_core::add_breadcrumb(Self::breadcrumb(record));
+        }
+    }
+
+    fn flush(&self) {}
+}
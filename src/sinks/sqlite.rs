@@ -0,0 +1,269 @@
+use crate::json::WriteMembers;
+use crate::sink_error::ErrorHandler;
+use crate::{FormattedRecord, HealthCheck, SinkError, SinkErrorKind, SinkHealth};
+use log::{Log, Metadata, Record};
+use rusqlite::{params, Connection};
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// How many records are inserted at once unless configured otherwise
+const DEFAULT_BATCH_SIZE: usize = 64;
+
+/// The schema of the table of records, the timestamps sort in the order of time
+const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS logs (
+    id INTEGER PRIMARY KEY,
+    timestamp TEXT NOT NULL,
+    level TEXT NOT NULL,
+    target TEXT NOT NULL,
+    message TEXT NOT NULL,
+    module TEXT,
+    file TEXT,
+    line INTEGER,
+    kv TEXT
+);
+CREATE INDEX IF NOT EXISTS logs_timestamp ON logs (timestamp);";
+
+struct Row {
+    timestamp: String,
+    level: &'static str,
+    target: String,
+    message: String,
+    module: Option<String>,
+    file: Option<String>,
+    line: Option<u32>,
+    kv: Option<String>,
+}
+
+impl Row {
+    fn new(record: &Record) -> Self {
+        let kv = (record.key_values().count() > 0).then(|| {
+            let mut kv = String::from("{");
+            let _ = record.key_values().visit(&mut WriteMembers(&mut kv));
+            // the first member is preceded by a comma like the rest
+            kv.remove(1);
+            kv.push('}');
+            kv
+        });
+
+        Self {
+            timestamp: crate::time::rfc3339(crate::time::now()),
+            level: record.level().as_str(),
+            target: record.target().to_string(),
+            message: FormattedRecord::new(record).message().to_string(),
+            module: record.module_path().map(str::to_string),
+            file: record.file().map(str::to_string),
+            line: record.line(),
+            kv,
+        }
+    }
+}
+
+struct State {
+    connection: Connection,
+    /// Records not inserted yet
+    pending: Vec<Row>,
+    /// Why the last batch could not be inserted
+    failure: Option<String>,
+}
+
+/// Inserts records into the `logs` table of a SQLite database, so that they can be
+/// queried with SQL, for example
+/// `SELECT timestamp, message FROM logs WHERE level = 'ERROR' ORDER BY id DESC LIMIT 10`.
+///
+/// Every row has the `timestamp`, `level`, `target` and `message` columns, `module`, `file`
+/// and `line` when known, and the key-values of the record as a JSON object in `kv`.
+/// Records are inserted in batches within a transaction, and the database is opened
+/// in the write-ahead log mode, so that it can be queried while records are inserted.
+/// Records still pending are lost if the process ends without flushing the logger.
+pub struct SqliteLogger {
+    batch_size: usize,
+    max_rows: Option<u64>,
+    max_size: Option<u64>,
+    error_handler: Option<ErrorHandler>,
+    state: Mutex<State>,
+}
+
+impl SqliteLogger {
+    /// Open the database, creating it and the `logs` table if they do not exist
+    ///
+    /// # Errors
+    ///
+    /// Fails if the database can not be opened or the table can not be created.
+    pub fn new(path: impl AsRef<Path>) -> rusqlite::Result<Self> {
+        let connection = Connection::open(path)?;
+        connection.pragma_update(None, "journal_mode", "WAL")?;
+        connection.execute_batch(SCHEMA)?;
+
+        Ok(Self {
+            batch_size: DEFAULT_BATCH_SIZE,
+            max_rows: None,
+            max_size: None,
+            error_handler: None,
+            state: Mutex::new(State {
+                connection,
+                pending: Vec::new(),
+                failure: None,
+            }),
+        })
+    }
+
+    /// How many records to insert at once, by default 64, or 1 to insert every record right away.
+    /// Records of the `Error` level are inserted right away along with the pending ones.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Delete the oldest records once the table has more than the given number of rows
+    pub fn with_max_rows(mut self, rows: u64) -> Self {
+        self.max_rows = Some(rows);
+        self
+    }
+
+    /// Delete the oldest records once the records take more than about the given number
+    /// of bytes. The database file does not shrink, but the space of deleted records is reused.
+    pub fn with_max_size(mut self, bytes: u64) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    /// Call the handler whenever a batch of records can not be inserted
+    pub fn with_error_handler(
+        mut self,
+        handler: impl Fn(SinkError) + Send + Sync + 'static,
+    ) -> Self {
+        self.error_handler = Some(Arc::new(handler));
+        self
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    /// Insert the pending records and prune the table, reporting when it fails
+    fn insert_pending(&self, state: &mut State) -> Option<SinkError> {
+        if state.pending.is_empty() {
+            return None;
+        }
+        let rows = std::mem::take(&mut state.pending);
+        let inserted = self
+            .insert(&mut state.connection, &rows)
+            .and_then(|()| self.prune(&state.connection));
+        match inserted {
+            Ok(()) => {
+                state.failure = None;
+                None
+            }
+            Err(error) => {
+                state.failure = Some(error.to_string());
+                Some(SinkError::new(
+                    SinkErrorKind::Dropped,
+                    format!("could not insert {} records: {}", rows.len(), error),
+                ))
+            }
+        }
+    }
+
+    fn insert(&self, connection: &mut Connection, rows: &[Row]) -> rusqlite::Result<()> {
+        let transaction = connection.transaction()?;
+        {
+            let mut insert = transaction.prepare_cached(
+                "INSERT INTO logs (timestamp, level, target, message, module, file, line, kv)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            )?;
+            for row in rows {
+                insert.execute(params![
+                    row.timestamp,
+                    row.level,
+                    row.target,
+                    row.message,
+                    row.module,
+                    row.file,
+                    row.line,
+                    row.kv,
+                ])?;
+            }
+        }
+        transaction.commit()
+    }
+
+    fn prune(&self, connection: &Connection) -> rusqlite::Result<()> {
+        if let Some(max_rows) = self.max_rows {
+            connection.execute(
+                "DELETE FROM logs WHERE id <= (SELECT MAX(id) FROM logs) - ?1",
+                [max_rows],
+            )?;
+        }
+        if let Some(max_size) = self.max_size {
+            let used: u64 = connection.query_row(
+                "SELECT (page_count - freelist_count) * page_size
+                 FROM pragma_page_count(), pragma_freelist_count(), pragma_page_size()",
+                [],
+                |row| row.get(0),
+            )?;
+            if used > max_size {
+                // delete the share of the rows by which the records are too large
+                let rows: u64 =
+                    connection.query_row("SELECT COUNT(*) FROM logs", [], |row| row.get(0))?;
+                let excess = (rows as u128 * (used - max_size) as u128 / used as u128) as u64 + 1;
+                connection.execute(
+                    "DELETE FROM logs WHERE id IN (SELECT id FROM logs ORDER BY id LIMIT ?1)",
+                    [excess],
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn report(&self, error: SinkError) {
+        if let Some(handler) = &self.error_handler {
+            handler(error);
+        }
+    }
+}
+
+impl Log for SqliteLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let row = Row::new(record);
+        let failure = {
+            let mut state = self.lock();
+            state.pending.push(row);
+            if state.pending.len() >= self.batch_size || record.level() == log::Level::Error {
+                self.insert_pending(&mut state)
+            } else {
+                None
+            }
+        };
+
+        // outside of the lock, in case the handler logs to this logger again
+        if let Some(error) = failure {
+            self.report(error);
+        }
+    }
+
+    fn flush(&self) {
+        let failure = self.insert_pending(&mut self.lock());
+        if let Some(error) = failure {
+            self.report(error);
+        }
+    }
+}
+
+impl HealthCheck for SqliteLogger {
+    fn health(&self) -> SinkHealth {
+        match &self.lock().failure {
+            Some(error) => SinkHealth::Degraded(format!("could not insert records: {}", error)),
+            None => SinkHealth::Healthy,
+        }
+    }
+}
+
+impl Drop for SqliteLogger {
+    fn drop(&mut self) {
+        let _ = self.insert_pending(&mut self.lock());
+    }
+}
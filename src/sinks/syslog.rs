@@ -0,0 +1,399 @@
+use crate::binary::Binaries;
+use crate::kv::Collect;
+use crate::FormattedRecord;
+use log::{Level, Log, Metadata, Record};
+use std::io::{self, Write};
+use std::net::{TcpStream, ToSocketAddrs, UdpSocket};
+#[cfg(unix)]
+use std::os::unix::net::UnixDatagram;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The sockets of the local syslog daemon on Linux and macOS
+#[cfg(unix)]
+const LOCAL_SOCKETS: [&str; 2] = ["/dev/log", "/var/run/syslog"];
+
+/// The SD-ID of the structured data element holding the key-values in RFC 5424 messages,
+/// using the enterprise number reserved for documentation
+const STRUCTURED_DATA_ID: &str = "kv@32473";
+
+/// The message format of a [`SyslogLogger`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SyslogFormat {
+    /// The traditional BSD format understood by every syslog daemon
+    Rfc3164,
+    /// The structured format with the key-values of the record as structured data
+    #[default]
+    Rfc5424,
+}
+
+/// The facility of the messages, identifying the kind of program that logged them
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Facility {
+    Kern = 0,
+    #[default]
+    User = 1,
+    Mail = 2,
+    Daemon = 3,
+    Auth = 4,
+    Syslog = 5,
+    Lpr = 6,
+    News = 7,
+    Uucp = 8,
+    Cron = 9,
+    AuthPriv = 10,
+    Ftp = 11,
+    Local0 = 16,
+    Local1 = 17,
+    Local2 = 18,
+    Local3 = 19,
+    Local4 = 20,
+    Local5 = 21,
+    Local6 = 22,
+    Local7 = 23,
+}
+
+enum Transport {
+    #[cfg(unix)]
+    Local(UnixDatagram),
+    Udp(UdpSocket),
+    /// Messages are framed by octet counting, see RFC 6587
+    Tcp(TcpStream),
+}
+
+impl Transport {
+    fn send(&mut self, message: &str) -> io::Result<()> {
+        match self {
+            #[cfg(unix)]
+            Transport::Local(socket) => socket.send(message.as_bytes()).map(|_| ()),
+            Transport::Udp(socket) => socket.send(message.as_bytes()).map(|_| ()),
+            Transport::Tcp(stream) => {
+                write!(stream, "{} {}", message.len(), message)
+            }
+        }
+    }
+}
+
+/// Sends records to the local syslog daemon or to a remote syslog server.
+///
+/// Levels are mapped to the severities `err`, `warning`, `info` and `debug`,
+/// `Trace` records are sent as `debug`.
+/// Errors while sending can not be reported and are ignored.
+pub struct SyslogLogger {
+    transport: Mutex<Transport>,
+    format: SyslogFormat,
+    facility: Facility,
+    hostname: String,
+    app_name: String,
+    pid: u32,
+}
+
+impl SyslogLogger {
+    /// Send records to the local syslog daemon in the RFC 3164 format
+    ///
+    /// # Errors
+    ///
+    /// Fails if none of the sockets of the local syslog daemon can be connected to.
+    #[cfg(unix)]
+    pub fn local() -> io::Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        let mut error = io::Error::from(io::ErrorKind::NotFound);
+        for path in LOCAL_SOCKETS {
+            match socket.connect(path) {
+                Ok(()) => {
+                    return Ok(
+                        Self::new(Transport::Local(socket)).with_format(SyslogFormat::Rfc3164)
+                    )
+                }
+                Err(connect_error) => error = connect_error,
+            }
+        }
+        Err(error)
+    }
+
+    /// Send records to a remote syslog server over UDP in the RFC 5424 format
+    ///
+    /// # Errors
+    ///
+    /// Fails if the address can not be resolved or the socket can not be bound.
+    pub fn udp(address: impl ToSocketAddrs) -> io::Result<Self> {
+        let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+        socket.connect(address)?;
+        Ok(Self::new(Transport::Udp(socket)))
+    }
+
+    /// Send records to a remote syslog server over TCP in the RFC 5424 format
+    ///
+    /// # Errors
+    ///
+    /// Fails if the address can not be resolved or connected to.
+    pub fn tcp(address: impl ToSocketAddrs) -> io::Result<Self> {
+        Ok(Self::new(Transport::Tcp(TcpStream::connect(address)?)))
+    }
+
+    fn new(transport: Transport) -> Self {
+        let app_name = std::env::current_exe()
+            .ok()
+            .and_then(|path| {
+                path.file_stem()
+                    .map(|name| name.to_string_lossy().into_owned())
+            })
+            .unwrap_or_else(|| "rust".to_string());
+
+        Self {
+            transport: Mutex::new(transport),
+            format: SyslogFormat::default(),
+            facility: Facility::default(),
+            hostname: crate::host::hostname(),
+            app_name,
+            pid: std::process::id(),
+        }
+    }
+
+    pub fn with_format(mut self, format: SyslogFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// The facility of the messages, by default `user`
+    pub fn with_facility(mut self, facility: Facility) -> Self {
+        self.facility = facility;
+        self
+    }
+
+    /// The name of the program sending the messages, by default the name of the executable
+    pub fn with_app_name(mut self, app_name: impl Into<String>) -> Self {
+        self.app_name = app_name.into();
+        self
+    }
+
+    pub fn with_hostname(mut self, hostname: impl Into<String>) -> Self {
+        self.hostname = hostname.into();
+        self
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Transport> {
+        self.transport
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+    }
+
+    fn priority(&self, level: Level) -> u8 {
+        let severity = match level {
+            Level::Error => 3,
+            Level::Warn => 4,
+            Level::Info => 6,
+            Level::Debug | Level::Trace => 7,
+        };
+        self.facility as u8 * 8 + severity
+    }
+
+    fn format(&self, record: &Record) -> String {
+        let message = FormattedRecord::new(record);
+        let priority = self.priority(record.level());
+
+        match self.format {
+            SyslogFormat::Rfc3164 => format!(
+                "<{}>{} {} {}[{}]: {}",
+                priority,
+                rfc3164_timestamp(crate::time::now()),
+                self.hostname,
+                self.app_name,
+                self.pid,
+                message.message()
+            ),
+            SyslogFormat::Rfc5424 => format!(
+                "<{}>1 {} {} {} {} - {} {}",
+                priority,
+                crate::time::rfc3339(crate::time::now()),
+                header_field(&self.hostname, 255),
+                header_field(&self.app_name, 48),
+                self.pid,
+                structured_data(record),
+                message.message()
+            ),
+        }
+    }
+}
+
+impl Log for SyslogLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let message = self.format(record);
+        let _ = self.lock().send(&message);
+    }
+
+    fn flush(&self) {
+        if let Transport::Tcp(stream) = &mut *self.lock() {
+            let _ = stream.flush();
+        }
+    }
+}
+
+/// The `Mmm dd hh:mm:ss` timestamp of RFC 3164, in UTC
+fn rfc3164_timestamp(time: SystemTime) -> String {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (_, month, day) = crate::time::civil_from_days((seconds / 86_400) as i64);
+    let seconds_of_day = seconds % 86_400;
+
+    format!(
+        "{} {:>2} {:02}:{:02}:{:02}",
+        MONTHS[month as usize - 1],
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60
+    )
+}
+
+/// A header field of RFC 5424 consists of at most `max` printable ASCII characters,
+/// and the nil value `-` stands for an empty field
+fn header_field(value: &str, max: usize) -> String {
+    let field: String = value
+        .chars()
+        .filter(|char| char.is_ascii_graphic())
+        .take(max)
+        .collect();
+
+    if field.is_empty() {
+        "-".to_string()
+    } else {
+        field
+    }
+}
+
+/// The key-values of the record as a structured data element, or the nil value `-`.
+/// Binary payloads are left out, they would exceed the size of most messages.
+fn structured_data(record: &Record) -> String {
+    let mut collect = Collect(Vec::new());
+    let _ = record.key_values().visit(&mut collect);
+    let mut binaries = Binaries(Vec::new());
+    let _ = record.key_values().visit(&mut binaries);
+    collect
+        .0
+        .retain(|(key, _)| !binaries.0.iter().any(|(name, _)| name == key));
+    if collect.0.is_empty() {
+        return "-".to_string();
+    }
+
+    let mut data = format!("[{}", STRUCTURED_DATA_ID);
+    for (key, value) in &collect.0 {
+        let name: String = key
+            .chars()
+            .filter(|char| char.is_ascii_graphic() && !matches!(char, '=' | ']' | '"'))
+            .take(32)
+            .collect();
+        if name.is_empty() {
+            continue;
+        }
+
+        data.push(' ');
+        data.push_str(&name);
+        data.push_str("=\"");
+        for char in value.chars() {
+            if matches!(char, '"' | '\\' | ']') {
+                data.push('\\');
+            }
+            data.push(char);
+        }
+        data.push('"');
+    }
+    data.push(']');
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OwnedRecord;
+    use std::time::Duration;
+
+    /// Sends the record over UDP and returns the received message
+    fn send(logger: SyslogLogger, server: &UdpSocket, record: &OwnedRecord) -> String {
+        record.with_record(|record| logger.log(record));
+        let mut message = [0; 1024];
+        let length = server.recv(&mut message).unwrap();
+        String::from_utf8(message[..length].to_vec()).unwrap()
+    }
+
+    fn server() -> UdpSocket {
+        let server = UdpSocket::bind(("127.0.0.1", 0)).unwrap();
+        server
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        server
+    }
+
+    #[test]
+    fn sends_rfc5424_messages_with_structured_data() {
+        let server = server();
+        let logger = SyslogLogger::udp(server.local_addr().unwrap())
+            .unwrap()
+            .with_facility(Facility::Local0)
+            .with_hostname("web 1")
+            .with_app_name("app");
+        let record = OwnedRecord::new(Level::Warn, "app", "disk full")
+            .with_key_value("path", "/var \"log\"")
+            .with_key_value("a=b", "c");
+
+        let message = send(logger, &server, &record);
+        let (timestamp, rest) = message
+            .strip_prefix("<132>1 ")
+            .unwrap()
+            .split_once(' ')
+            .unwrap();
+        assert!(timestamp.ends_with('Z'), "{}", message);
+        assert_eq!(
+            rest,
+            format!(
+                "web1 app {} - [kv@32473 path=\"/var \\\"log\\\"\" ab=\"c\"] disk full",
+                std::process::id()
+            )
+        );
+    }
+
+    #[test]
+    fn sends_rfc3164_messages() {
+        let server = server();
+        let logger = SyslogLogger::udp(server.local_addr().unwrap())
+            .unwrap()
+            .with_format(SyslogFormat::Rfc3164)
+            .with_hostname("web-1")
+            .with_app_name("app");
+
+        let message = send(
+            logger,
+            &server,
+            &OwnedRecord::new(Level::Trace, "app", "tick"),
+        );
+        assert!(message.starts_with("<15>"), "{}", message);
+        assert!(
+            message.ends_with(&format!(" web-1 app[{}]: tick", std::process::id())),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn formats_rfc3164_timestamps_in_utc() {
+        // 2024-03-05T07:08:09Z
+        let time = UNIX_EPOCH + Duration::from_secs(1_709_622_489);
+        assert_eq!(rfc3164_timestamp(time), "Mar  5 07:08:09");
+    }
+
+    #[test]
+    fn leaves_empty_header_fields_nil() {
+        assert_eq!(header_field(" \t", 48), "-");
+        assert_eq!(header_field("a long name", 5), "along");
+    }
+}
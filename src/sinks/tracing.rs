@@ -0,0 +1,28 @@
+use log::{Log, Metadata, Record};
+
+/// Forwards records to the current `tracing` subscriber as events,
+/// so that the composite logger can feed a `tracing` subscriber.
+/// Events forwarded from `tracing` by [`crate::tracing_bridge::CompositeLayer`]
+/// are not forwarded back.
+#[derive(Debug, Default)]
+pub struct TracingLogger;
+
+impl TracingLogger {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Log for TracingLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        crate::tracing_bridge::forwarding(|| {
+            let _ = tracing_log::format_trace(record);
+        });
+    }
+
+    fn flush(&self) {}
+}
@@ -0,0 +1,42 @@
+use crate::FormattedRecord;
+use log::{Level, Log, Metadata, Record};
+use web_sys::console;
+
+/// Writes records to the console of the browser, mapping the levels to
+/// `console.error`, `console.warn`, `console.info` and `console.debug`,
+/// `Trace` records are written with `console.debug` as well.
+///
+/// The browser adds the time itself, so only the level, target and message are written.
+#[derive(Default)]
+pub struct WebConsoleLogger;
+
+impl WebConsoleLogger {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Log for WebConsoleLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let message = format!(
+            "{:<5} [{}] {}",
+            record.level(),
+            record.target(),
+            FormattedRecord::new(record).message()
+        );
+        let message = &message.as_str().into();
+
+        match record.level() {
+            Level::Error => console::error_1(message),
+            Level::Warn => console::warn_1(message),
+            Level::Info => console::info_1(message),
+            Level::Debug | Level::Trace => console::debug_1(message),
+        }
+    }
+
+    fn flush(&self) {}
+}
@@ -0,0 +1,274 @@
+use crate::format::{self, Format};
+use crate::{HealthCheck, LineEncoding, SinkHealth};
+use log::{Log, Metadata, Record};
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::thread;
+
+/// Numbers the threads, so that each thread keeps buffering into the same buffer
+static NEXT_THREAD: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static THREAD: usize = NEXT_THREAD.fetch_add(1, Ordering::Relaxed);
+}
+
+/// How a [`WriterLogger`] hands the lines to its writer
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum WriteStrategy {
+    /// Every line is written right away while holding the lock of the writer
+    #[default]
+    Locked,
+    /// The lines are collected in buffers of about the given number of bytes and written
+    /// together once a buffer is full or the logger is flushed or dropped, so that busy
+    /// threads rarely wait for the writer. There is a buffer per available core, and each
+    /// thread keeps using the one of its number modulo their count, so that threads only
+    /// wait for each other while sharing a buffer. Lines are never split, and the lines
+    /// of a thread stay in order, but may be written after later lines of other threads.
+    PerThread(usize),
+}
+
+/// Writes records formatted with any [`Format`] as lines to any writer, such as
+/// a `Vec<u8>`, a socket or a device, so that most custom sinks are a single line:
+///
+/// ```text
+/// let logger = CompositeLogger::new()
+///     .with_logger(WriterLogger::new(TcpStream::connect("logs:5170")?, JsonFormat));
+/// ```
+///
+/// The lines are formatted before the writer is locked. The logger is degraded
+/// while the writer fails, see [`HealthCheck`]. Dropping the logger writes
+/// the buffered lines and flushes the writer.
+pub struct WriterLogger<W: Write + Send> {
+    /// Only taken by [`WriterLogger::into_inner`]
+    writer: Mutex<Option<W>>,
+    format: Box<dyn Format>,
+    colors: bool,
+    strategy: WriteStrategy,
+    line_encoding: LineEncoding,
+    /// Indexed by thread number modulo their count, only used by [`WriteStrategy::PerThread`]
+    buffers: Box<[Mutex<Vec<u8>>]>,
+    /// Why the last line could not be written, if it could not
+    failure: Mutex<Option<String>>,
+}
+
+impl<W: Write + Send> WriterLogger<W> {
+    pub fn new(writer: W, format: impl Format + 'static) -> Self {
+        Self {
+            writer: Mutex::new(Some(writer)),
+            format: Box::new(format),
+            colors: false,
+            strategy: WriteStrategy::Locked,
+            line_encoding: LineEncoding::default(),
+            buffers: Box::new([]),
+            failure: Mutex::new(None),
+        }
+    }
+
+    /// Ask the format for ANSI colors, for example when the writer is a terminal
+    pub fn with_colors(mut self, colors: bool) -> Self {
+        self.colors = colors;
+        self
+    }
+
+    /// Encode the lines, for example as UTF-16 or ending with `\r\n`, see [`LineEncoding`]
+    pub fn with_line_encoding(mut self, encoding: LineEncoding) -> Self {
+        self.line_encoding = encoding;
+        self
+    }
+
+    /// Lock the writer for every line or buffer the lines per thread,
+    /// by default [`WriteStrategy::Locked`]
+    pub fn with_strategy(mut self, strategy: WriteStrategy) -> Self {
+        self.strategy = strategy;
+        self.buffers = match strategy {
+            WriteStrategy::Locked => Box::new([]),
+            WriteStrategy::PerThread(_) => {
+                let buffers = thread::available_parallelism().map_or(4, |threads| threads.get());
+                (0..buffers).map(|_| Mutex::new(Vec::new())).collect()
+            }
+        };
+        self
+    }
+
+    /// The writer after writing the buffered lines, for example to inspect
+    /// what was written to a `Vec<u8>`
+    pub fn into_inner(self) -> W {
+        self.write_buffers();
+        Self::lock(&self.writer)
+            .take()
+            .expect("the writer is only taken once")
+    }
+
+    fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+        mutex.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    fn write(&self, bytes: &[u8]) {
+        if bytes.is_empty() {
+            return;
+        }
+        if let Some(writer) = Self::lock(&self.writer).as_mut() {
+            let written = writer.write_all(bytes);
+            *Self::lock(&self.failure) = written.err().map(|error| error.to_string());
+        }
+    }
+
+    /// Write the lines buffered by all threads, buffer by buffer
+    fn write_buffers(&self) {
+        for buffer in self.buffers.iter() {
+            let mut buffer = Self::lock(buffer);
+            self.write(&buffer);
+            buffer.clear();
+        }
+    }
+}
+
+impl<W: Write + Send> Log for WriterLogger<W> {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        format::with_line_buffer(|line| {
+            self.format.format_into(record, self.colors, line);
+            line.push('\n');
+            self.line_encoding
+                .with_encoded(line, |bytes| match self.strategy {
+                    WriteStrategy::Locked => self.write(bytes),
+                    WriteStrategy::PerThread(capacity) => {
+                        let index = THREAD.try_with(|thread| *thread).unwrap_or_default()
+                            % self.buffers.len();
+                        let mut buffer = Self::lock(&self.buffers[index]);
+                        buffer.extend_from_slice(bytes);
+                        if buffer.len() >= capacity {
+                            self.write(&buffer);
+                            buffer.clear();
+                        }
+                    }
+                });
+        });
+    }
+
+    fn flush(&self) {
+        self.write_buffers();
+        let flushed = Self::lock(&self.writer)
+            .as_mut()
+            .map(|writer| writer.flush());
+        if let Some(Err(error)) = flushed {
+            *Self::lock(&self.failure) = Some(error.to_string());
+        }
+    }
+}
+
+impl<W: Write + Send> Drop for WriterLogger<W> {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+impl<W: Write + Send> HealthCheck for WriterLogger<W> {
+    fn health(&self) -> SinkHealth {
+        match &*Self::lock(&self.failure) {
+            Some(error) => SinkHealth::Degraded(format!("could not write: {}", error)),
+            None => SinkHealth::Healthy,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OwnedRecord;
+    use log::Level;
+    use std::io;
+    use std::sync::Arc;
+
+    /// A writer whose lines can be read while the logger still owns it
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Shared {
+        fn written(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    impl Write for Shared {
+        fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(bytes)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    struct Failing;
+
+    impl Write for Failing {
+        fn write(&mut self, _bytes: &[u8]) -> io::Result<usize> {
+            Err(io::Error::other("disconnected"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn message(record: &Record, _colors: bool) -> String {
+        format!("{} {}", record.level(), record.args())
+    }
+
+    fn log(logger: &impl Log, message: &str) {
+        OwnedRecord::new(Level::Info, "app", message).with_record(|record| logger.log(record));
+    }
+
+    #[test]
+    fn writes_a_line_per_record() {
+        let logger = WriterLogger::new(Vec::new(), message);
+        log(&logger, "first");
+        log(&logger, "second");
+
+        assert_eq!(logger.into_inner(), b"INFO first\nINFO second\n");
+    }
+
+    #[test]
+    fn buffers_the_lines_until_the_buffer_is_full_or_flushed() {
+        let writer = Shared::default();
+        let logger =
+            WriterLogger::new(writer.clone(), message).with_strategy(WriteStrategy::PerThread(20));
+
+        log(&logger, "first");
+        assert_eq!(writer.written(), "");
+        log(&logger, "second");
+        assert_eq!(writer.written(), "INFO first\nINFO second\n");
+
+        log(&logger, "third");
+        logger.flush();
+        assert_eq!(writer.written(), "INFO first\nINFO second\nINFO third\n");
+    }
+
+    #[test]
+    fn writes_the_buffered_lines_when_dropped() {
+        let writer = Shared::default();
+        let logger = WriterLogger::new(writer.clone(), message)
+            .with_strategy(WriteStrategy::PerThread(1024));
+        log(&logger, "buffered");
+        drop(logger);
+
+        assert_eq!(writer.written(), "INFO buffered\n");
+    }
+
+    #[test]
+    fn is_degraded_while_the_writer_fails() {
+        let logger = WriterLogger::new(Failing, message);
+        assert_eq!(logger.health(), SinkHealth::Healthy);
+
+        log(&logger, "lost");
+        assert_eq!(
+            logger.health(),
+            SinkHealth::Degraded("could not write: disconnected".to_string())
+        );
+    }
+}
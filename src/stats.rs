@@ -0,0 +1,309 @@
+use crate::LoggerId;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// The upper bounds of the latency buckets, the last bucket has no upper bound
+const LATENCY_BOUNDS: [Duration; 9] = [
+    Duration::from_micros(1),
+    Duration::from_micros(4),
+    Duration::from_micros(16),
+    Duration::from_micros(64),
+    Duration::from_micros(256),
+    Duration::from_millis(1),
+    Duration::from_millis(4),
+    Duration::from_millis(16),
+    Duration::from_millis(64),
+];
+
+/// Counts what happened to the records dispatched to a logger
+#[derive(Default)]
+pub(crate) struct Counters {
+    delivered: AtomicU64,
+    filtered: AtomicU64,
+    dropped: AtomicU64,
+    timed_out: AtomicU64,
+    overage_nanos: AtomicU64,
+    latency: [AtomicU64; LATENCY_BOUNDS.len() + 1],
+    latency_nanos: AtomicU64,
+    max_latency_nanos: AtomicU64,
+}
+
+impl Counters {
+    pub(crate) fn delivered(&self, elapsed: Duration) {
+        self.delivered.fetch_add(1, Ordering::Relaxed);
+        let bucket = LATENCY_BOUNDS
+            .iter()
+            .position(|bound| elapsed < *bound)
+            .unwrap_or(LATENCY_BOUNDS.len());
+        self.latency[bucket].fetch_add(1, Ordering::Relaxed);
+        self.latency_nanos
+            .fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+        self.max_latency_nanos
+            .fetch_max(elapsed.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn filtered(&self) {
+        self.filtered.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn dropped(&self) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn timed_out(&self, overage: Duration) {
+        self.timed_out.fetch_add(1, Ordering::Relaxed);
+        self.overage_nanos
+            .fetch_add(overage.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self, id: LoggerId, name: Option<String>, errored: u64) -> LoggerStats {
+        LoggerStats {
+            id,
+            name,
+            delivered: self.delivered.load(Ordering::Relaxed),
+            filtered: self.filtered.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            errored,
+            timed_out: self.timed_out.load(Ordering::Relaxed),
+            overage: Duration::from_nanos(self.overage_nanos.load(Ordering::Relaxed)),
+            latency: self.latency(),
+        }
+    }
+
+    pub(crate) fn latency(&self) -> LatencyHistogram {
+        LatencyHistogram {
+            counts: self
+                .latency
+                .iter()
+                .map(|count| count.load(Ordering::Relaxed))
+                .collect(),
+            sum: Duration::from_nanos(self.latency_nanos.load(Ordering::Relaxed)),
+            max: Duration::from_nanos(self.max_latency_nanos.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// What happened to the records dispatched to one of the loggers since it was added
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoggerStats {
+    pub id: LoggerId,
+    pub name: Option<String>,
+    /// Records the logger received
+    pub delivered: u64,
+    /// Records dispatched to the composite logger the logger was not enabled for
+    pub filtered: u64,
+    /// Records the logger was enabled for but that were dropped
+    /// because the queue of the asynchronous mode was full,
+    /// or because they were queued while the logger took longer than its timeout
+    pub dropped: u64,
+    /// Times the logger panicked while logging or flushing
+    pub errored: u64,
+    /// Deliveries that took longer than the timeout of the logger,
+    /// see [`crate::CompositeLogger::with_logger_timeout`]
+    pub timed_out: u64,
+    /// How much longer than the timeout these deliveries took in total
+    pub overage: Duration,
+    /// How long the logger took to log the delivered records
+    pub latency: LatencyHistogram,
+}
+
+/// How many durations fell into buckets of increasing upper bounds,
+/// from below one microsecond to above 64 milliseconds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LatencyHistogram {
+    counts: Vec<u64>,
+    sum: Duration,
+    max: Duration,
+}
+
+impl LatencyHistogram {
+    /// The upper bound of every bucket with the number of durations below it and above
+    /// the bound of the previous bucket, the upper bound of the last bucket is `None`
+    pub fn buckets(&self) -> impl Iterator<Item = (Option<Duration>, u64)> + '_ {
+        LATENCY_BOUNDS
+            .iter()
+            .map(|bound| Some(*bound))
+            .chain([None])
+            .zip(self.counts.iter().copied())
+    }
+
+    /// The number of durations in all buckets
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// The total of all durations
+    pub fn sum(&self) -> Duration {
+        self.sum
+    }
+
+    /// The longest duration
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+
+    /// An upper bound of the given quantile of the durations between 0 and 1,
+    /// such as 0.99 for the 99th percentile: the upper bound of its bucket, or the
+    /// longest duration if that is shorter. `None` if there are no durations.
+    pub fn percentile(&self, quantile: f64) -> Option<Duration> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+        let rank = ((quantile.clamp(0.0, 1.0) * count as f64).ceil() as u64).max(1);
+        let mut cumulative = 0;
+        let bound = self.buckets().find_map(|(bound, bucket)| {
+            cumulative += bucket;
+            (cumulative >= rank).then_some(bound)
+        })?;
+        Some(bound.map_or(self.max, |bound| bound.min(self.max)))
+    }
+}
+
+/// Reads one of the counters of a logger
+type Counter = fn(&LoggerStats) -> u64;
+
+/// The counters of the composite logger, see [`crate::CompositeLoggerHandle::stats`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stats {
+    /// Of the currently installed loggers, in the order they receive records
+    pub loggers: Vec<LoggerStats>,
+    pub fallback: Option<LoggerStats>,
+    /// Records dropped because the queue of the asynchronous mode was full
+    pub queue_dropped: u64,
+    /// Records waiting in the queue of the asynchronous mode
+    pub queue_len: usize,
+    /// The approximate memory taken by the records waiting in the queue
+    pub queue_bytes: usize,
+    /// Records shed from the queue under load, see [`crate::LoadShedding`]
+    pub queue_shed: u64,
+}
+
+impl Stats {
+    /// Encode the counters in the Prometheus text exposition format,
+    /// for example to serve them from a metrics endpoint.
+    /// The loggers are labeled by their id and name, the fallback logger by `fallback`.
+    pub fn to_prometheus(&self) -> String {
+        let loggers = self
+            .loggers
+            .iter()
+            .map(|logger| (Self::labels(logger, logger.id.0.to_string()), logger))
+            .chain(
+                self.fallback
+                    .iter()
+                    .map(|logger| (Self::labels(logger, "fallback".to_string()), logger)),
+            )
+            .collect::<Vec<_>>();
+        let mut text = String::new();
+
+        let counters: [(&str, &str, Counter); 5] = [
+            ("delivered", "Records the logger received", |logger| {
+                logger.delivered
+            }),
+            (
+                "filtered",
+                "Records the logger was not enabled for",
+                |logger| logger.filtered,
+            ),
+            ("dropped", "Records dropped from the full queue", |logger| {
+                logger.dropped
+            }),
+            ("errored", "Times the logger panicked", |logger| {
+                logger.errored
+            }),
+            (
+                "timed_out",
+                "Records the logger took longer than its timeout to log",
+                |logger| logger.timed_out,
+            ),
+        ];
+        for (name, help, value) in counters {
+            let name = format!("composite_logger_records_{}_total", name);
+            Self::header(&mut text, &name, help, "counter");
+            for (labels, logger) in &loggers {
+                text.push_str(&format!("{}{{{}}} {}\n", name, labels, value(logger)));
+            }
+        }
+
+        let name = "composite_logger_log_duration_seconds";
+        Self::header(
+            &mut text,
+            name,
+            "How long the logger took to log",
+            "histogram",
+        );
+        for (labels, logger) in &loggers {
+            let mut cumulative = 0;
+            for (bound, count) in logger.latency.buckets() {
+                cumulative += count;
+                let bound = bound
+                    .map(|bound| bound.as_secs_f64().to_string())
+                    .unwrap_or_else(|| "+Inf".to_string());
+                text.push_str(&format!(
+                    "{}_bucket{{{},le=\"{}\"}} {}\n",
+                    name, labels, bound, cumulative
+                ));
+            }
+            text.push_str(&format!(
+                "{}_sum{{{}}} {}\n",
+                name,
+                labels,
+                logger.latency.sum().as_secs_f64()
+            ));
+            text.push_str(&format!("{}_count{{{}}} {}\n", name, labels, cumulative));
+        }
+
+        let name = "composite_logger_queue_dropped_total";
+        Self::header(
+            &mut text,
+            name,
+            "Records dropped from the full queue",
+            "counter",
+        );
+        text.push_str(&format!("{} {}\n", name, self.queue_dropped));
+
+        let name = "composite_logger_queue_length";
+        Self::header(&mut text, name, "Records waiting in the queue", "gauge");
+        text.push_str(&format!("{} {}\n", name, self.queue_len));
+
+        let name = "composite_logger_queue_bytes";
+        Self::header(
+            &mut text,
+            name,
+            "Approximate memory of the records waiting in the queue",
+            "gauge",
+        );
+        text.push_str(&format!("{} {}\n", name, self.queue_bytes));
+
+        let name = "composite_logger_queue_shed_total";
+        Self::header(
+            &mut text,
+            name,
+            "Records shed from the queue under load",
+            "counter",
+        );
+        text.push_str(&format!("{} {}\n", name, self.queue_shed));
+
+        text
+    }
+
+    fn header(text: &mut String, name: &str, help: &str, kind: &str) {
+        text.push_str(&format!(
+            "# HELP {} {}\n# TYPE {} {}\n",
+            name, help, name, kind
+        ));
+    }
+
+    fn labels(logger: &LoggerStats, id: String) -> String {
+        match &logger.name {
+            Some(name) => {
+                let name = name
+                    .replace('\\', "\\\\")
+                    .replace('"', "\\\"")
+                    .replace('\n', "\\n");
+                format!("logger=\"{}\",name=\"{}\"", id, name)
+            }
+            None => format!("logger=\"{}\"", id),
+        }
+    }
+}
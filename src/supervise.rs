@@ -0,0 +1,204 @@
+use crate::sink_error::ErrorHandler;
+use crate::{time, HealthCheck, SinkError, SinkErrorKind, SinkHealth};
+use log::{Log, Metadata, Record};
+use std::io;
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+use std::time::{Duration, Instant};
+
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+type Factory<L> = Box<dyn Fn() -> io::Result<L> + Send + Sync>;
+type FailureCheck<L> = Box<dyn Fn(&L) -> bool + Send + Sync>;
+
+/// A logger that tears down the logger it supervises once it failed, and builds
+/// a new one with the factory, so that a long running program recovers from a full disk,
+/// a log file that was removed or a dead socket without being restarted.
+///
+/// The logger is checked after each record and flush. Restarts are at least a second apart,
+/// doubling up to a minute while the new loggers keep failing.
+/// Records are dropped while the factory fails.
+pub struct SupervisedLogger<L> {
+    factory: Factory<L>,
+    failed: FailureCheck<L>,
+    logger: RwLock<Option<L>>,
+    state: Mutex<State>,
+    max_backoff: Duration,
+    error_handler: Option<ErrorHandler>,
+}
+
+struct State {
+    /// When the logger may be restarted next
+    restart_at: Instant,
+    backoff: Duration,
+    restarts: u64,
+    /// Why the factory could not build the logger
+    failure: Option<String>,
+}
+
+impl<L: Log + HealthCheck + 'static> SupervisedLogger<L> {
+    /// Supervise the logger built by the factory, restarting it whenever
+    /// it is not [`SinkHealth::Healthy`]
+    pub fn new(factory: impl Fn() -> io::Result<L> + Send + Sync + 'static) -> Self {
+        Self::new_checked(factory, |logger: &L| !logger.health().is_healthy())
+    }
+}
+
+impl<L: Log + 'static> SupervisedLogger<L> {
+    /// Supervise the logger built by the factory, restarting it whenever the check
+    /// tells that it failed
+    pub fn new_checked(
+        factory: impl Fn() -> io::Result<L> + Send + Sync + 'static,
+        failed: impl Fn(&L) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        let mut state = State {
+            restart_at: time::instant(),
+            backoff: DEFAULT_INITIAL_BACKOFF,
+            restarts: 0,
+            failure: None,
+        };
+        let logger = match factory() {
+            Ok(logger) => Some(logger),
+            Err(error) => {
+                state.failure = Some(error.to_string());
+                state.restart_at += state.backoff;
+                None
+            }
+        };
+        Self {
+            factory: Box::new(factory),
+            failed: Box::new(failed),
+            logger: RwLock::new(logger),
+            state: Mutex::new(state),
+            max_backoff: DEFAULT_MAX_BACKOFF,
+            error_handler: None,
+        }
+    }
+
+    /// The longest time between failed restarts, by default one minute
+    pub fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Call the handler whenever the logger is restarted or could not be restarted
+    pub fn with_error_handler(
+        mut self,
+        handler: impl Fn(SinkError) + Send + Sync + 'static,
+    ) -> Self {
+        self.error_handler = Some(Arc::new(handler));
+        self
+    }
+
+    /// How many times the logger was rebuilt after failing
+    pub fn restarts(&self) -> u64 {
+        self.lock().restarts
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    /// Run the action with the supervised logger and restart it if it failed meanwhile,
+    /// or run it with the restarted logger if it could not be built before
+    fn supervise(&self, action: impl FnOnce(&L)) {
+        let mut action = Some(action);
+        let failed = {
+            let logger = self
+                .logger
+                .read()
+                .unwrap_or_else(|error| error.into_inner());
+            match &*logger {
+                Some(logger) => {
+                    if let Some(action) = action.take() {
+                        action(logger);
+                    }
+                    (self.failed)(logger)
+                }
+                None => true,
+            }
+        };
+
+        let mut state = self.lock();
+        if !failed {
+            state.backoff = DEFAULT_INITIAL_BACKOFF;
+            return;
+        }
+        let now = time::instant();
+        if now < state.restart_at {
+            return;
+        }
+        state.restart_at = now + state.backoff;
+        state.backoff = (state.backoff * 2).min(self.max_backoff.max(DEFAULT_INITIAL_BACKOFF));
+
+        let mut logger = self
+            .logger
+            .write()
+            .unwrap_or_else(|error| error.into_inner());
+        if let Some(logger) = logger.take() {
+            logger.flush();
+        }
+        let error = match (self.factory)() {
+            Ok(restarted) => {
+                if let Some(action) = action {
+                    action(&restarted);
+                }
+                *logger = Some(restarted);
+                state.restarts += 1;
+                state.failure = None;
+                SinkError::new(SinkErrorKind::Restarted, "restarted after failing")
+            }
+            Err(error) => {
+                let message = format!("could not restart: {}", error);
+                state.failure = Some(error.to_string());
+                SinkError::new(SinkErrorKind::RestartFailed, message)
+            }
+        };
+        drop(logger);
+        drop(state);
+        if let Some(handler) = &self.error_handler {
+            handler(error);
+        }
+    }
+}
+
+impl<L: Log + 'static> Log for SupervisedLogger<L> {
+    /// Enabled while the logger can not be built, so that records keep retrying the factory
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.logger
+            .read()
+            .unwrap_or_else(|error| error.into_inner())
+            .as_ref()
+            .is_none_or(|logger| logger.enabled(metadata))
+    }
+
+    fn log(&self, record: &Record) {
+        self.supervise(|logger| {
+            if logger.enabled(record.metadata()) {
+                logger.log(record);
+            }
+        });
+    }
+
+    fn flush(&self) {
+        self.supervise(L::flush);
+    }
+}
+
+impl<L: Log + HealthCheck + 'static> HealthCheck for SupervisedLogger<L> {
+    /// The health of the supervised logger, disabled while it can not be built
+    fn health(&self) -> SinkHealth {
+        let health = self
+            .logger
+            .read()
+            .unwrap_or_else(|error| error.into_inner())
+            .as_ref()
+            .map(L::health);
+        health.unwrap_or_else(|| {
+            SinkHealth::Disabled(format!(
+                "could not restart: {}",
+                self.lock().failure.as_deref().unwrap_or("unknown error")
+            ))
+        })
+    }
+}
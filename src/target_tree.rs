@@ -0,0 +1,244 @@
+use std::collections::BTreeMap;
+
+/// Values keyed by targets such as `my_app::net`, looked up with inheritance:
+/// a target inherits the value of its closest ancestor, so that `my_app::net::tcp`
+/// gets the value of `my_app::net` unless it has one of its own.
+///
+/// The targets are stored as a tree of their `::` separated segments, so that a lookup
+/// walks the segments of the target once instead of comparing it with every stored target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetTree<T> {
+    root: Node<T>,
+    len: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Node<T> {
+    value: Option<T>,
+    children: BTreeMap<String, Node<T>>,
+}
+
+impl<T> Default for Node<T> {
+    fn default() -> Self {
+        Self {
+            value: None,
+            children: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T> Default for TargetTree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> TargetTree<T> {
+    pub fn new() -> Self {
+        Self {
+            root: Node::default(),
+            len: 0,
+        }
+    }
+
+    /// Set the value of the target and, by inheritance, of its submodules,
+    /// returning the value the target had before
+    pub fn insert(&mut self, target: &str, value: T) -> Option<T> {
+        let mut node = &mut self.root;
+        for segment in segments(target) {
+            node = node.children.entry(segment.to_string()).or_default();
+        }
+        let previous = node.value.replace(value);
+        if previous.is_none() {
+            self.len += 1;
+        }
+        previous
+    }
+
+    /// Remove the value of the target, its submodules then inherit from its ancestors again
+    pub fn remove(&mut self, target: &str) -> Option<T> {
+        let removed = Self::remove_from(&mut self.root, &mut segments(target));
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    /// Remove the value and prune the nodes left without values or children
+    fn remove_from<'a>(
+        node: &mut Node<T>,
+        segments: &mut impl Iterator<Item = &'a str>,
+    ) -> Option<T> {
+        let Some(segment) = segments.next() else {
+            return node.value.take();
+        };
+        let child = node.children.get_mut(segment)?;
+        let removed = Self::remove_from(child, segments);
+        if child.value.is_none() && child.children.is_empty() {
+            node.children.remove(segment);
+        }
+        removed
+    }
+
+    /// The value of exactly the target, without inheritance
+    pub fn get(&self, target: &str) -> Option<&T> {
+        let mut node = &self.root;
+        for segment in segments(target) {
+            node = node.children.get(segment)?;
+        }
+        node.value.as_ref()
+    }
+
+    /// The value of the target or of its closest ancestor
+    pub fn find(&self, target: &str) -> Option<&T> {
+        self.find_target(target).map(|(_, value)| value)
+    }
+
+    /// The value of the target or of its closest ancestor, together with the target
+    /// or ancestor it was set for
+    pub fn find_target<'t>(&self, target: &'t str) -> Option<(&'t str, &T)> {
+        let mut node = &self.root;
+        let mut found = node.value.as_ref().map(|value| ("", value));
+        let mut end = 0;
+        for segment in target.split("::") {
+            end += segment.len();
+            if !segment.is_empty() {
+                match node.children.get(segment) {
+                    Some(child) => node = child,
+                    None => break,
+                }
+                if let Some(value) = &node.value {
+                    found = Some((&target[..end], value));
+                }
+            }
+            end += 2;
+        }
+        found
+    }
+
+    /// How many targets have a value
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// The values, parents before their submodules
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.iter().map(|(_, value)| value)
+    }
+
+    /// The targets with their values, parents before their submodules
+    /// and siblings in alphabetical order
+    pub fn iter(&self) -> impl Iterator<Item = (String, &T)> {
+        let mut entries = Vec::with_capacity(self.len);
+        Self::collect(&self.root, &mut String::new(), &mut entries);
+        entries.into_iter()
+    }
+
+    fn collect<'a>(node: &'a Node<T>, target: &mut String, entries: &mut Vec<(String, &'a T)>) {
+        if let Some(value) = &node.value {
+            entries.push((target.clone(), value));
+        }
+        for (segment, child) in &node.children {
+            let length = target.len();
+            if !target.is_empty() {
+                target.push_str("::");
+            }
+            target.push_str(segment);
+            Self::collect(child, target, entries);
+            target.truncate(length);
+        }
+    }
+}
+
+impl<T, S: AsRef<str>> FromIterator<(S, T)> for TargetTree<T> {
+    fn from_iter<I: IntoIterator<Item = (S, T)>>(entries: I) -> Self {
+        let mut tree = Self::new();
+        for (target, value) in entries {
+            tree.insert(target.as_ref(), value);
+        }
+        tree
+    }
+}
+
+/// The segments of a target, none for the empty target, which is the root of all targets
+fn segments(target: &str) -> impl Iterator<Item = &str> {
+    target.split("::").filter(|segment| !segment.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn targets_inherit_from_their_closest_ancestor() {
+        let tree: TargetTree<u8> = [("my_app", 1), ("my_app::net", 2)].into_iter().collect();
+        assert_eq!(tree.find("my_app"), Some(&1));
+        assert_eq!(tree.find("my_app::db"), Some(&1));
+        assert_eq!(tree.find("my_app::net::tcp"), Some(&2));
+        assert_eq!(tree.find("my_application"), None);
+        assert_eq!(
+            tree.find_target("my_app::net::tcp"),
+            Some(("my_app::net", &2))
+        );
+        assert_eq!(tree.get("my_app::db"), None);
+    }
+
+    #[test]
+    fn empty_target_is_the_root() {
+        let mut tree = TargetTree::new();
+        tree.insert("", 0);
+        tree.insert("hyper", 1);
+        assert_eq!(tree.find("my_app"), Some(&0));
+        assert_eq!(tree.find_target("my_app"), Some(("", &0)));
+        assert_eq!(tree.find("hyper::client"), Some(&1));
+    }
+
+    #[test]
+    fn insert_replaces_and_counts_targets() {
+        let mut tree = TargetTree::new();
+        assert_eq!(tree.insert("my_app", 1), None);
+        assert_eq!(tree.insert("my_app", 2), Some(1));
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree.get("my_app"), Some(&2));
+    }
+
+    #[test]
+    fn removed_targets_inherit_again() {
+        let mut tree: TargetTree<u8> = [("my_app", 1), ("my_app::net", 2)].into_iter().collect();
+        assert_eq!(tree.remove("my_app::net"), Some(2));
+        assert_eq!(tree.remove("my_app::net"), None);
+        assert_eq!(tree.find("my_app::net::tcp"), Some(&1));
+        assert_eq!(tree.len(), 1);
+
+        assert_eq!(tree.remove("my_app"), Some(1));
+        assert!(tree.is_empty());
+        assert_eq!(tree, TargetTree::new());
+    }
+
+    #[test]
+    fn iterates_parents_before_submodules() {
+        let tree: TargetTree<u8> = [("my_app::net", 2), ("hyper", 3), ("my_app", 1)]
+            .into_iter()
+            .collect();
+        let targets: Vec<_> = tree
+            .iter()
+            .map(|(target, value)| (target, *value))
+            .collect();
+        assert_eq!(
+            targets,
+            [
+                ("hyper".to_string(), 3),
+                ("my_app".to_string(), 1),
+                ("my_app::net".to_string(), 2)
+            ]
+        );
+    }
+}
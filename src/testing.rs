@@ -0,0 +1,361 @@
+//! Helpers to assert on what was logged in tests
+
+use crate::{Clock, CompositeLogger, CompositeLoggerHandle, OwnedRecord};
+use log::{Level, Log, Metadata, Record};
+use std::fmt::Debug;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Initialize the global logger with the composite logger, or replace the loggers
+/// of the composite logger initialized before, for test binaries that run
+/// scenarios with different loggers. The asynchronous mode and the flush interval
+/// are kept as initialized the first time.
+///
+/// # Panics
+///
+/// Panics if another library has already initialized a global logger.
+pub fn init(logger: CompositeLogger) -> CompositeLoggerHandle {
+    // tests running in parallel must not both try to initialize the global logger
+    static INIT: Mutex<()> = Mutex::new(());
+    let _init = INIT.lock().unwrap_or_else(PoisonError::into_inner);
+
+    match crate::handle::installed() {
+        Some(handle) => {
+            handle
+                .reload(logger)
+                .unwrap_or_else(|error| panic!("testing::init failed: {}", error));
+            handle
+        }
+        None => logger.init(),
+    }
+}
+
+/// Remove the loggers of the global composite logger, see [`CompositeLoggerHandle::shutdown`].
+/// Does nothing if no composite logger is the global logger.
+pub fn reset() {
+    if let Some(handle) = crate::handle::installed() {
+        handle.shutdown();
+    }
+}
+
+/// Decides whether a captured message is the expected one
+pub trait MessageMatcher: Debug {
+    fn matches(&self, message: &str) -> bool;
+}
+
+impl<M: MessageMatcher> MessageMatcher for &M {
+    fn matches(&self, message: &str) -> bool {
+        (**self).matches(message)
+    }
+}
+
+/// Matches messages containing the string
+#[derive(Debug, Clone)]
+pub struct Contains(pub String);
+
+impl MessageMatcher for Contains {
+    fn matches(&self, message: &str) -> bool {
+        message.contains(self.0.as_str())
+    }
+}
+
+/// Matches messages equal to the string
+#[derive(Debug, Clone)]
+pub struct Equals(pub String);
+
+impl MessageMatcher for Equals {
+    fn matches(&self, message: &str) -> bool {
+        message == self.0
+    }
+}
+
+pub fn contains(part: impl Into<String>) -> Contains {
+    Contains(part.into())
+}
+
+pub fn equals(message: impl Into<String>) -> Equals {
+    Equals(message.into())
+}
+
+/// Captures every record it receives so that tests can assert on them.
+///
+/// Share it with an `Arc` to access the records after adding it to the composite logger.
+#[derive(Default)]
+pub struct CaptureLogger {
+    records: Mutex<Vec<OwnedRecord>>,
+}
+
+impl CaptureLogger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<OwnedRecord>> {
+        self.records
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+    }
+
+    /// The captured records in the order they were logged
+    pub fn records(&self) -> Vec<OwnedRecord> {
+        self.lock().clone()
+    }
+
+    /// The level, target and message of the captured records
+    pub fn entries(&self) -> Vec<(Level, String, String)> {
+        self.lock()
+            .iter()
+            .map(|record| {
+                (
+                    record.level(),
+                    record.target().to_string(),
+                    record.message().to_string(),
+                )
+            })
+            .collect()
+    }
+
+    /// Whether a record with the level and a matching message was captured
+    pub fn logged(&self, level: Level, matcher: impl MessageMatcher) -> bool {
+        self.lock()
+            .iter()
+            .any(|record| record.level() == level && matcher.matches(record.message()))
+    }
+
+    /// # Panics
+    ///
+    /// Panics if no record with the level and a matching message was captured.
+    #[track_caller]
+    pub fn assert_logged(&self, level: Level, matcher: impl MessageMatcher) {
+        if !self.logged(level, &matcher) {
+            panic!(
+                "expected a {} record with a message matching {:?}, captured: {:#?}",
+                level,
+                matcher,
+                self.entries()
+            );
+        }
+    }
+
+    /// # Panics
+    ///
+    /// Panics if a record with the level and a matching message was captured.
+    #[track_caller]
+    pub fn assert_not_logged(&self, level: Level, matcher: impl MessageMatcher) {
+        if self.logged(level, &matcher) {
+            panic!(
+                "expected no {} record with a message matching {:?}, captured: {:#?}",
+                level,
+                matcher,
+                self.entries()
+            );
+        }
+    }
+
+    /// Forget the captured records, for example between tests
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+}
+
+impl Log for CaptureLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        self.lock().push(record.into());
+    }
+
+    fn flush(&self) {}
+}
+
+/// A clock that only advances when told to, so that tests of rate limits, rotation
+/// or periodic flushes do not sleep, see [`crate::set_clock`]:
+///
+/// ```text
+/// let clock = MockClock::new();
+/// composite_logger::set_clock(clock.clone());
+/// clock.advance(Duration::from_secs(60));
+/// ```
+///
+/// Its clones share the same time. Threads sleeping by the clock, such as the one
+/// of [`CompositeLogger::with_flush_interval`], wake up once it advanced past their wait.
+#[derive(Clone)]
+pub struct MockClock {
+    /// The time elapsed since the clock was created
+    elapsed: Arc<(Mutex<Duration>, Condvar)>,
+    start: SystemTime,
+    start_instant: Instant,
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockClock {
+    /// A clock stopped at the current time
+    pub fn new() -> Self {
+        Self::starting_at(SystemTime::now())
+    }
+
+    /// A clock stopped at the given time of the wall clock
+    pub fn starting_at(time: SystemTime) -> Self {
+        Self {
+            elapsed: Default::default(),
+            start: time,
+            start_instant: Instant::now(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Duration> {
+        self.elapsed
+            .0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Move the clock forward by the duration
+    pub fn advance(&self, duration: Duration) {
+        *self.lock() += duration;
+        self.elapsed.1.notify_all();
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> SystemTime {
+        self.start + *self.lock()
+    }
+
+    fn instant(&self) -> Instant {
+        self.start_instant + *self.lock()
+    }
+
+    /// Wait until the clock advanced by the duration
+    fn sleep(&self, duration: Duration) {
+        let mut elapsed = self.lock();
+        let until = elapsed.saturating_add(duration);
+        while *elapsed < until {
+            elapsed = self
+                .elapsed
+                .1
+                .wait(elapsed)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+}
+
+/// Run the test with a [`MockClock`] as the clock of the process, one test at a time
+/// since the clock is shared by all tests of the crate
+#[cfg(test)]
+pub(crate) fn with_mock_clock(test: impl FnOnce(&MockClock)) {
+    /// Restores the system clock once the test returns or fails
+    struct Reset;
+
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            crate::set_clock(crate::SystemClock);
+        }
+    }
+
+    static CLOCK: Mutex<()> = Mutex::new(());
+    let _clock = CLOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    let clock = MockClock::new();
+    crate::set_clock(clock.clone());
+    let _reset = Reset;
+    test(&clock);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::catch_unwind;
+
+    fn log(logger: &impl Log, level: Level, message: &str) {
+        OwnedRecord::new(level, "my_app::db", message).with_record(|record| logger.log(record));
+    }
+
+    fn capture() -> CaptureLogger {
+        let capture = CaptureLogger::new();
+        log(&capture, Level::Warn, "slow query took 1200ms");
+        log(&capture, Level::Info, "connected");
+        capture
+    }
+
+    #[test]
+    fn captures_records_in_order() {
+        let capture = capture();
+        assert_eq!(
+            capture.entries(),
+            [
+                (
+                    Level::Warn,
+                    "my_app::db".to_string(),
+                    "slow query took 1200ms".to_string()
+                ),
+                (
+                    Level::Info,
+                    "my_app::db".to_string(),
+                    "connected".to_string()
+                ),
+            ]
+        );
+        assert_eq!(capture.records()[1].message(), "connected");
+    }
+
+    #[test]
+    fn matches_messages_by_level() {
+        let capture = capture();
+        assert!(capture.logged(Level::Warn, contains("slow query")));
+        assert!(capture.logged(Level::Info, equals("connected")));
+        assert!(!capture.logged(Level::Info, contains("slow query")));
+        assert!(!capture.logged(Level::Info, equals("connect")));
+        capture.assert_logged(Level::Warn, contains("1200ms"));
+        capture.assert_not_logged(Level::Error, contains("slow query"));
+    }
+
+    #[test]
+    fn failed_assertions_tell_what_was_captured() {
+        let capture = capture();
+        let failure = catch_unwind(|| capture.assert_logged(Level::Error, contains("timeout")))
+            .err()
+            .and_then(|panic| panic.downcast::<String>().ok())
+            .expect("the assertion fails");
+        assert!(failure
+            .contains("expected a ERROR record with a message matching Contains(\"timeout\")"));
+        assert!(failure.contains("slow query took 1200ms"));
+
+        let failure = catch_unwind(|| capture.assert_not_logged(Level::Info, equals("connected")));
+        assert!(failure.is_err());
+    }
+
+    #[test]
+    fn clear_forgets_the_records() {
+        let capture = capture();
+        capture.clear();
+        assert!(capture.records().is_empty());
+        log(&capture, Level::Error, "disconnected");
+        assert_eq!(capture.entries().len(), 1);
+    }
+
+    #[test]
+    fn replaces_the_global_loggers_between_tests() {
+        let first = Arc::new(CaptureLogger::new());
+        init(CompositeLogger::new().with_logger(first.clone()));
+        log::info!("first");
+        reset();
+        log::info!("ignored");
+
+        let second = Arc::new(CaptureLogger::new());
+        init(CompositeLogger::new().with_logger(second.clone()));
+        log::info!("second");
+        reset();
+
+        assert_eq!(first.entries().len(), 1);
+        first.assert_logged(Level::Info, equals("first"));
+        assert_eq!(second.entries().len(), 1);
+        second.assert_logged(Level::Info, equals("second"));
+    }
+}
@@ -0,0 +1,215 @@
+use std::cell::Cell;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, PoisonError, RwLock};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+thread_local! {
+    /// When the record being delivered was logged, if it was logged earlier
+    static LOGGED_AT: Cell<Option<SystemTime>> = const { Cell::new(None) };
+}
+
+/// The clock set with [`set_clock`], if it is not the [`SystemClock`]
+static CLOCK: RwLock<Option<Arc<dyn Clock>>> = RwLock::new(None);
+/// Whether a clock was set, so that the system clock is read without taking the lock
+static HAS_CLOCK: AtomicBool = AtomicBool::new(false);
+
+/// The source of time of the timestamps, rate limits, rotation and periodic flushes,
+/// so that tests can control the time with a [`crate::testing::MockClock`]
+/// instead of sleeping, see [`set_clock`]
+pub trait Clock: Send + Sync + 'static {
+    /// The current time of the wall clock
+    fn now(&self) -> SystemTime;
+
+    /// The current time of the monotonic clock, of intervals
+    fn instant(&self) -> Instant;
+
+    /// Wait for the duration, such as between periodic flushes
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+/// The clock of the operating system, used unless another clock is set
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn instant(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Replace the clock of all composite loggers of the process, or restore the system clock
+/// with [`SystemClock`]. Intervals that already started, such as the wait for the next
+/// periodic flush, end by the clock they started with.
+pub fn set_clock(clock: impl Clock) {
+    let clock: Arc<dyn Clock> = Arc::new(clock);
+    let mut current = CLOCK.write().unwrap_or_else(PoisonError::into_inner);
+    *current = Some(clock);
+    HAS_CLOCK.store(true, Ordering::Release);
+}
+
+fn with_clock<T>(f: impl FnOnce(&dyn Clock) -> T) -> T {
+    if !HAS_CLOCK.load(Ordering::Acquire) {
+        return f(&SystemClock);
+    }
+    let clock = CLOCK.read().unwrap_or_else(PoisonError::into_inner).clone();
+    match clock {
+        Some(clock) => f(clock.as_ref()),
+        None => f(&SystemClock),
+    }
+}
+
+/// When the record being delivered was logged, which is earlier than now for
+/// records delivered later, such as by the worker of the asynchronous mode
+pub(crate) fn now() -> SystemTime {
+    LOGGED_AT
+        .with(Cell::get)
+        .unwrap_or_else(|| with_clock(|clock| clock.now()))
+}
+
+/// The current time of the wall clock, regardless of when the record being delivered was logged
+pub(crate) fn wall_time() -> SystemTime {
+    with_clock(|clock| clock.now())
+}
+
+/// The current time of the monotonic clock
+pub(crate) fn instant() -> Instant {
+    with_clock(|clock| clock.instant())
+}
+
+/// The time elapsed since the instant of the monotonic clock
+pub(crate) fn elapsed(since: Instant) -> Duration {
+    instant().saturating_duration_since(since)
+}
+
+/// Wait for the duration by the clock
+pub(crate) fn sleep(duration: Duration) {
+    with_clock(|clock| clock.sleep(duration));
+}
+
+/// Deliver a record that was logged at the given time
+pub(crate) fn logged_at<T>(time: SystemTime, f: impl FnOnce() -> T) -> T {
+    /// Restores the time of the enclosing delivery once the action returns or unwinds
+    struct Reset(Option<SystemTime>);
+
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            let _ = LOGGED_AT.try_with(|logged_at| logged_at.set(self.0));
+        }
+    }
+
+    let _reset = Reset(LOGGED_AT.with(|logged_at| logged_at.replace(Some(time))));
+    f()
+}
+
+/// Formats the time as an RFC 3339 timestamp in UTC with millisecond precision,
+/// for example `2024-01-31T12:34:56.789Z`
+#[cfg(any(feature = "sqlite", feature = "syslog"))]
+pub(crate) fn rfc3339(time: SystemTime) -> String {
+    let mut timestamp = String::with_capacity(24);
+    write_rfc3339(&mut timestamp, time);
+    timestamp
+}
+
+/// Appends the RFC 3339 timestamp of the time in UTC to the line
+pub(crate) fn write_rfc3339(line: &mut String, time: SystemTime) {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+    let seconds_of_day = seconds % 86_400;
+
+    let _ = write!(
+        line,
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60,
+        since_epoch.subsec_millis()
+    );
+}
+
+/// Converts days since the unix epoch into a (year, month, day) date,
+/// see <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use std::panic::catch_unwind;
+
+    #[test]
+    fn records_are_delivered_at_the_time_they_were_logged() {
+        let logged = UNIX_EPOCH + Duration::from_secs(1_706_704_496);
+        assert_eq!(logged_at(logged, now), logged);
+        let nested = logged_at(logged, || logged_at(UNIX_EPOCH, now));
+        assert_eq!(nested, UNIX_EPOCH);
+        assert_ne!(now(), logged);
+    }
+
+    #[test]
+    fn logged_at_is_restored_when_the_delivery_panics() {
+        let logged = UNIX_EPOCH + Duration::from_secs(1_706_704_496);
+        let panicked = catch_unwind(|| logged_at(logged, || panic!("the sink failed")));
+        assert!(panicked.is_err());
+        assert_ne!(now(), logged);
+    }
+
+    #[test]
+    fn mock_clock_drives_the_time() {
+        testing::with_mock_clock(|clock| {
+            let (start, started) = (now(), instant());
+            clock.advance(Duration::from_secs(90));
+            assert_eq!(now(), start + Duration::from_secs(90));
+            assert_eq!(elapsed(started), Duration::from_secs(90));
+        });
+    }
+
+    #[test]
+    fn dates_of_days_since_the_epoch() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(11_017), (2000, 3, 1));
+        assert_eq!(civil_from_days(19_753), (2024, 1, 31));
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+        assert_eq!(civil_from_days(19_783), (2024, 3, 1));
+    }
+
+    #[test]
+    fn rfc3339_in_utc() {
+        let mut line = String::new();
+        write_rfc3339(
+            &mut line,
+            UNIX_EPOCH + Duration::from_millis(1_706_704_496_789),
+        );
+        assert_eq!(line, "2024-01-31T12:34:56.789Z");
+    }
+}
@@ -0,0 +1,376 @@
+use crate::time;
+use std::fmt::{Display, Formatter, Write};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{PoisonError, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The format and time zone set with [`set_timestamp_format`] and [`set_time_zone`]
+static TIMESTAMPS: RwLock<(TimestampFormat, TimeZone)> =
+    RwLock::new((TimestampFormat::Rfc3339, TimeZone::Utc));
+/// Whether the timestamps were configured, so that the default is written without the lock
+static CONFIGURED: AtomicBool = AtomicBool::new(false);
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// How the built-in formats and sinks write the time of records, see [`set_timestamp_format`]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum TimestampFormat {
+    /// RFC 3339 with millisecond precision, such as `2024-01-31T12:34:56.789Z`,
+    /// or `2024-01-31T13:34:56.789+01:00` outside of UTC
+    #[default]
+    Rfc3339,
+    /// Milliseconds since the unix epoch, such as `1706704496789`, whatever the time zone
+    EpochMillis,
+    /// A `strftime` pattern of the specifiers `%Y`, `%y`, `%m`, `%d`, `%e`, `%j`, `%H`, `%M`,
+    /// `%S`, `%f` (nanoseconds), `%3f`, `%6f`, `%9f`, `%s`, `%z`, `%:z`, `%a`, `%b`, `%F`, `%T`
+    /// and `%%`, other text is written as is
+    Strftime(String),
+}
+
+impl FromStr for TimestampFormat {
+    type Err = std::convert::Infallible;
+
+    /// `rfc3339`, `epoch_millis` or else a `strftime` pattern
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        Ok(match format {
+            "rfc3339" => TimestampFormat::Rfc3339,
+            "epoch_millis" => TimestampFormat::EpochMillis,
+            pattern => TimestampFormat::Strftime(pattern.to_string()),
+        })
+    }
+}
+
+/// The time zone of the timestamps, see [`set_time_zone`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TimeZone {
+    #[default]
+    Utc,
+    /// The time zone of the operating system, including its daylight saving time.
+    /// Needs the `localtime` feature on unix, the timestamps are in UTC otherwise.
+    Local,
+    /// A fixed offset east of UTC in seconds
+    Offset(i32),
+}
+
+impl TimeZone {
+    /// The offset east of UTC in seconds at the time
+    fn offset(self, time: SystemTime) -> i64 {
+        match self {
+            TimeZone::Utc => 0,
+            TimeZone::Local => local_offset(time),
+            TimeZone::Offset(seconds) => i64::from(seconds),
+        }
+    }
+}
+
+impl FromStr for TimeZone {
+    type Err = TimeZoneError;
+
+    /// `utc`, `local` or an offset such as `+01:00` or `-0530`
+    fn from_str(zone: &str) -> Result<Self, Self::Err> {
+        let error = || TimeZoneError {
+            zone: zone.to_string(),
+        };
+        match zone {
+            "utc" | "UTC" | "Z" => return Ok(TimeZone::Utc),
+            "local" => return Ok(TimeZone::Local),
+            _ => {}
+        }
+
+        let (sign, offset) = match zone.split_at_checked(1).ok_or_else(error)? {
+            ("+", offset) => (1, offset),
+            ("-", offset) => (-1, offset),
+            _ => return Err(error()),
+        };
+        let digits = offset.replace(':', "");
+        if digits.len() != 4 || !digits.bytes().all(|digit| digit.is_ascii_digit()) {
+            return Err(error());
+        }
+        let hours: i32 = digits[..2].parse().map_err(|_| error())?;
+        let minutes: i32 = digits[2..].parse().map_err(|_| error())?;
+        if hours > 23 || minutes > 59 {
+            return Err(error());
+        }
+        Ok(TimeZone::Offset(sign * (hours * 3600 + minutes * 60)))
+    }
+}
+
+/// A time zone could not be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeZoneError {
+    zone: String,
+}
+
+impl TimeZoneError {
+    pub fn zone(&self) -> &str {
+        &self.zone
+    }
+}
+
+impl Display for TimeZoneError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid time zone `{}`", self.zone)
+    }
+}
+
+impl std::error::Error for TimeZoneError {}
+
+/// Write the timestamps of all built-in formats and sinks of the process in the format,
+/// by default [`TimestampFormat::Rfc3339`]. Sinks whose protocol defines the timestamps,
+/// such as syslog or GELF, keep them.
+pub fn set_timestamp_format(format: TimestampFormat) {
+    let mut timestamps = TIMESTAMPS.write().unwrap_or_else(PoisonError::into_inner);
+    timestamps.0 = format;
+    CONFIGURED.store(true, Ordering::Release);
+}
+
+/// Write the timestamps of all built-in formats and sinks of the process in the time zone,
+/// by default [`TimeZone::Utc`], see [`set_timestamp_format`]
+pub fn set_time_zone(zone: TimeZone) {
+    let mut timestamps = TIMESTAMPS.write().unwrap_or_else(PoisonError::into_inner);
+    timestamps.1 = zone;
+    CONFIGURED.store(true, Ordering::Release);
+}
+
+/// The timestamp of the time in the configured format and time zone
+pub(crate) fn timestamp(time: SystemTime) -> String {
+    let mut timestamp = String::with_capacity(32);
+    write_timestamp(&mut timestamp, time);
+    timestamp
+}
+
+/// Appends the [`timestamp`] of the time to the line
+pub(crate) fn write_timestamp(line: &mut String, time: SystemTime) {
+    if !CONFIGURED.load(Ordering::Acquire) {
+        return time::write_rfc3339(line, time);
+    }
+    let timestamps = TIMESTAMPS.read().unwrap_or_else(PoisonError::into_inner);
+    let (format, zone) = &*timestamps;
+    let civil = Civil::new(time, zone.offset(time));
+    match format {
+        TimestampFormat::Rfc3339 if *zone == TimeZone::Utc => time::write_rfc3339(line, time),
+        TimestampFormat::Rfc3339 => civil.write(line, "%FT%T.%3f%:z"),
+        TimestampFormat::EpochMillis => {
+            let millis = time
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis();
+            let _ = write!(line, "{}", millis);
+        }
+        TimestampFormat::Strftime(pattern) => civil.write(line, pattern),
+    }
+}
+
+/// The days since the unix epoch of the date of the time in the configured time zone
+pub(crate) fn day(time: SystemTime) -> i64 {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    if !CONFIGURED.load(Ordering::Acquire) {
+        return seconds.div_euclid(86_400);
+    }
+    let zone = TIMESTAMPS.read().unwrap_or_else(PoisonError::into_inner).1;
+    (seconds + zone.offset(time)).div_euclid(86_400)
+}
+
+/// The calendar date and time of day at an offset from UTC
+struct Civil {
+    /// Seconds since the unix epoch
+    epoch: u64,
+    offset: i64,
+    year: i64,
+    month: u32,
+    day: u32,
+    day_of_year: u32,
+    weekday: usize,
+    seconds_of_day: u32,
+    nanos: u32,
+}
+
+impl Civil {
+    fn new(time: SystemTime, offset: i64) -> Self {
+        let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let seconds = since_epoch.as_secs() as i64 + offset;
+        let days = seconds.div_euclid(86_400);
+        let (year, month, day) = time::civil_from_days(days);
+        Self {
+            epoch: since_epoch.as_secs(),
+            offset,
+            year,
+            month,
+            day,
+            day_of_year: (days - days_from_civil(year)) as u32 + 1,
+            // the first of January 1970 was a Thursday
+            weekday: (days + 3).rem_euclid(7) as usize,
+            seconds_of_day: seconds.rem_euclid(86_400) as u32,
+            nanos: since_epoch.subsec_nanos(),
+        }
+    }
+
+    fn write(&self, line: &mut String, pattern: &str) {
+        let (hour, minute, second) = (
+            self.seconds_of_day / 3600,
+            self.seconds_of_day % 3600 / 60,
+            self.seconds_of_day % 60,
+        );
+        let mut rest = pattern;
+        while let Some(start) = rest.find('%') {
+            line.push_str(&rest[..start]);
+            rest = &rest[start + 1..];
+            let specifier = ["3f", "6f", "9f", ":z"]
+                .into_iter()
+                .find(|specifier| rest.starts_with(specifier))
+                .unwrap_or_else(|| rest.get(..1).unwrap_or_default());
+            let _ = match specifier {
+                "Y" => write!(line, "{:04}", self.year),
+                "y" => write!(line, "{:02}", self.year.rem_euclid(100)),
+                "m" => write!(line, "{:02}", self.month),
+                "d" => write!(line, "{:02}", self.day),
+                "e" => write!(line, "{:>2}", self.day),
+                "j" => write!(line, "{:03}", self.day_of_year),
+                "H" => write!(line, "{:02}", hour),
+                "M" => write!(line, "{:02}", minute),
+                "S" => write!(line, "{:02}", second),
+                "f" | "9f" => write!(line, "{:09}", self.nanos),
+                "3f" => write!(line, "{:03}", self.nanos / 1_000_000),
+                "6f" => write!(line, "{:06}", self.nanos / 1_000),
+                "s" => write!(line, "{}", self.epoch),
+                "z" | ":z" => {
+                    let sign = if self.offset < 0 { '-' } else { '+' };
+                    let minutes = self.offset.abs() / 60;
+                    let separator = if specifier == ":z" { ":" } else { "" };
+                    write!(
+                        line,
+                        "{}{:02}{}{:02}",
+                        sign,
+                        minutes / 60,
+                        separator,
+                        minutes % 60
+                    )
+                }
+                "a" => write!(line, "{}", WEEKDAYS[self.weekday]),
+                "b" => write!(line, "{}", MONTHS[self.month as usize - 1]),
+                "F" => write!(line, "{:04}-{:02}-{:02}", self.year, self.month, self.day),
+                "T" => write!(line, "{:02}:{:02}:{:02}", hour, minute, second),
+                "%" => write!(line, "%"),
+                // not a specifier, written as is
+                specifier => write!(line, "%{}", specifier),
+            };
+            rest = &rest[specifier.len()..];
+        }
+        line.push_str(rest);
+    }
+}
+
+/// The days since the unix epoch of the first of January of the year,
+/// see <http://howardhinnant.github.io/date_algorithms.html#days_from_civil>
+fn days_from_civil(year: i64) -> i64 {
+    // the first of January is in the "year" starting on the first of March before
+    let year = year - 1;
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    // the first of January is the 306th day after the first of March
+    let day_of_year = 306;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The offset of the local time zone east of UTC in seconds at the time
+#[cfg(all(unix, feature = "localtime"))]
+fn local_offset(time: SystemTime) -> i64 {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as libc::time_t;
+    // SAFETY: localtime_r only writes to the given tm, which is valid for writes
+    unsafe {
+        let mut tm = std::mem::zeroed::<libc::tm>();
+        if libc::localtime_r(&seconds, &mut tm).is_null() {
+            return 0;
+        }
+        tm.tm_gmtoff as i64
+    }
+}
+
+#[cfg(not(all(unix, feature = "localtime")))]
+fn local_offset(_time: SystemTime) -> i64 {
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// 2024-01-31T12:34:56.789012345Z, a Wednesday
+    fn time() -> SystemTime {
+        UNIX_EPOCH + Duration::new(1_706_704_496, 789_012_345)
+    }
+
+    fn format(offset: i64, pattern: &str) -> String {
+        let mut line = String::new();
+        Civil::new(time(), offset).write(&mut line, pattern);
+        line
+    }
+
+    #[test]
+    fn strftime_specifiers() {
+        assert_eq!(format(0, "%FT%T.%3f%:z"), "2024-01-31T12:34:56.789+00:00");
+        assert_eq!(
+            format(0, "%y%m%d %H%M%S %6f %9f %f"),
+            "240131 123456 789012 789012345 789012345"
+        );
+        assert_eq!(format(0, "%a %b %e %j %s"), "Wed Jan 31 031 1706704496");
+        assert_eq!(format(0, "100%% %q"), "100% %q");
+    }
+
+    #[test]
+    fn offsets_change_the_date_and_time() {
+        assert_eq!(format(3600, "%FT%T%z"), "2024-01-31T13:34:56+0100");
+        assert_eq!(format(12 * 3600, "%F %a %:z"), "2024-02-01 Thu +12:00");
+        assert_eq!(
+            format(-(13 * 3600 + 1800), "%F %T %:z"),
+            "2024-01-30 23:04:56 -13:30"
+        );
+    }
+
+    #[test]
+    fn day_of_year() {
+        let march = UNIX_EPOCH + Duration::from_secs(19_783 * 86_400);
+        let mut line = String::new();
+        Civil::new(march, 0).write(&mut line, "%F %j");
+        assert_eq!(line, "2024-03-01 061");
+    }
+
+    #[test]
+    fn time_zones() {
+        assert_eq!("utc".parse(), Ok(TimeZone::Utc));
+        assert_eq!("local".parse(), Ok(TimeZone::Local));
+        assert_eq!("+01:00".parse(), Ok(TimeZone::Offset(3600)));
+        assert_eq!("-0530".parse(), Ok(TimeZone::Offset(-19_800)));
+        for invalid in ["", "+1", "+24:00", "+01:60", "01:00", "+0a:00"] {
+            assert_eq!(
+                invalid
+                    .parse::<TimeZone>()
+                    .err()
+                    .map(|error| error.to_string()),
+                Some(format!("invalid time zone `{}`", invalid))
+            );
+        }
+    }
+
+    #[test]
+    fn timestamp_formats() {
+        assert_eq!("rfc3339".parse(), Ok(TimestampFormat::Rfc3339));
+        assert_eq!("epoch_millis".parse(), Ok(TimestampFormat::EpochMillis));
+        assert_eq!(
+            "%H:%M".parse(),
+            Ok(TimestampFormat::Strftime("%H:%M".to_string()))
+        );
+    }
+}
@@ -0,0 +1,115 @@
+//! Bridges between the `log` and `tracing` facades.
+//!
+//! [`crate::sinks::TracingLogger`] forwards the records of the composite logger
+//! to the `tracing` subscriber, and [`CompositeLayer`] forwards `tracing` events
+//! to the installed `log` logger, so that mixed code bases do not lose either side.
+//! Records forwarded by one bridge are never forwarded back by the other.
+
+use log::kv::Source;
+use log::{Level, Record};
+use std::cell::Cell;
+use std::fmt::Debug;
+use tracing::field::{Field, Visit};
+use tracing::subscriber::SetGlobalDefaultError;
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::Registry;
+
+thread_local! {
+    static FORWARDING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Run `f` while forwarding a record or event to the other facade,
+/// unless already doing so on this thread
+pub(crate) fn forwarding(f: impl FnOnce()) {
+    if FORWARDING.with(|forwarding| forwarding.replace(true)) {
+        return;
+    }
+    f();
+    FORWARDING.with(|forwarding| forwarding.set(false));
+}
+
+/// A `tracing` layer dispatching events to the installed `log` logger,
+/// with the `message` field as the message and the other fields as key-values
+#[derive(Debug, Default)]
+pub struct CompositeLayer;
+
+impl CompositeLayer {
+    pub fn new() -> Self {
+        Self
+    }
+
+    fn level(level: &tracing::Level) -> Level {
+        match *level {
+            tracing::Level::ERROR => Level::Error,
+            tracing::Level::WARN => Level::Warn,
+            tracing::Level::INFO => Level::Info,
+            tracing::Level::DEBUG => Level::Debug,
+            tracing::Level::TRACE => Level::Trace,
+        }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for CompositeLayer {
+    fn on_event(&self, event: &Event<'_>, _context: Context<'_, S>) {
+        let metadata = event.metadata();
+        let level = Self::level(metadata.level());
+        if level > log::max_level() {
+            return;
+        }
+
+        forwarding(|| {
+            let mut fields = Fields::default();
+            event.record(&mut fields);
+            let key_values: &dyn Source = &fields.key_values;
+
+            log::logger().log(
+                &Record::builder()
+                    .level(level)
+                    .target(metadata.target())
+                    .module_path(metadata.module_path())
+                    .file(metadata.file())
+                    .line(metadata.line())
+                    .key_values(key_values)
+                    .args(format_args!("{}", fields.message))
+                    .build(),
+            );
+        });
+    }
+}
+
+#[derive(Default)]
+struct Fields {
+    message: String,
+    key_values: Vec<(String, String)>,
+}
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            self.key_values
+                .push((field.name().to_string(), value.to_string()));
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+        } else {
+            self.key_values
+                .push((field.name().to_string(), format!("{:?}", value)));
+        }
+    }
+}
+
+/// Install a global `tracing` subscriber that dispatches all events to the installed
+/// `log` logger, such as the composite logger
+///
+/// # Errors
+///
+/// Fails if a global `tracing` subscriber is already installed.
+pub fn install_tracing_bridge() -> Result<(), SetGlobalDefaultError> {
+    tracing::subscriber::set_global_default(Registry::default().with(CompositeLayer::new()))
+}
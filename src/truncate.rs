@@ -0,0 +1,178 @@
+use crate::{FormattedRecord, OwnedRecord};
+use log::{Log, Metadata, Record};
+
+/// Marks where a truncated message was cut
+const ELLIPSIS: &str = "…";
+
+/// What to do with records whose message is longer than the maximum length,
+/// see [`TruncatedLogger`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Truncation {
+    /// Keep the start of the message followed by an ellipsis
+    #[default]
+    Cut,
+    /// Keep the start and the end of the message with an ellipsis in between,
+    /// for example to keep both the context and the cause of an error
+    HeadAndTail,
+    /// Do not deliver the record
+    Drop,
+}
+
+/// A logger that receives records whose message is at most the given number of bytes,
+/// so that large messages reach a file logger in full but do not exceed the size
+/// of a datagram for a network logger. The message is cut between characters and,
+/// with the ellipsis, is at most the maximum length long. Messages are cut without
+/// an ellipsis if the maximum length is shorter than the ellipsis.
+pub struct TruncatedLogger<L> {
+    logger: L,
+    max_length: usize,
+    truncation: Truncation,
+}
+
+impl<L: Log> TruncatedLogger<L> {
+    pub fn new(logger: L, max_length: usize, truncation: Truncation) -> Self {
+        Self {
+            logger,
+            max_length,
+            truncation,
+        }
+    }
+
+    /// The message truncated to the maximum length, without an ellipsis if even
+    /// the ellipsis is longer
+    fn truncate(&self, message: &str) -> String {
+        if self.max_length < ELLIPSIS.len() {
+            return message[..floor_char_boundary(message, self.max_length)].to_string();
+        }
+        let length = self.max_length.saturating_sub(ELLIPSIS.len());
+        match self.truncation {
+            Truncation::HeadAndTail => {
+                let head = floor_char_boundary(message, length - length / 2);
+                let tail = ceil_char_boundary(message, message.len() - length / 2);
+                format!("{}{}{}", &message[..head], ELLIPSIS, &message[tail..])
+            }
+            _ => {
+                let head = floor_char_boundary(message, length);
+                format!("{}{}", &message[..head], ELLIPSIS)
+            }
+        }
+    }
+}
+
+/// The largest index of a character boundary not after the index
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    (0..=index.min(text.len()))
+        .rev()
+        .find(|index| text.is_char_boundary(*index))
+        .unwrap_or(0)
+}
+
+/// The smallest index of a character boundary not before the index
+fn ceil_char_boundary(text: &str, index: usize) -> usize {
+    (index..=text.len())
+        .find(|index| text.is_char_boundary(*index))
+        .unwrap_or(text.len())
+}
+
+impl<L: Log> Log for TruncatedLogger<L> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.logger.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        let formatted = FormattedRecord::new(record);
+        let message = formatted.message();
+        if message.len() <= self.max_length {
+            return self.logger.log(record);
+        }
+        if self.truncation == Truncation::Drop {
+            return;
+        }
+
+        OwnedRecord::from(record)
+            .with_message(self.truncate(message))
+            .with_record(|record| self.logger.log(record));
+    }
+
+    fn flush(&self) {
+        self.logger.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::CaptureLogger;
+    use log::Level;
+    use std::sync::Arc;
+
+    /// The message the logger delivers for the message, if it delivers the record
+    fn truncated(max_length: usize, truncation: Truncation, message: &str) -> Option<String> {
+        let capture = Arc::new(CaptureLogger::new());
+        let logger = TruncatedLogger::new(capture.clone(), max_length, truncation);
+        OwnedRecord::new(Level::Info, "my_app", message).with_record(|record| logger.log(record));
+        let records = capture.records();
+        records.first().map(|record| record.message().to_string())
+    }
+
+    #[test]
+    fn short_messages_are_kept() {
+        assert_eq!(
+            truncated(5, Truncation::Cut, "hello").as_deref(),
+            Some("hello")
+        );
+        assert_eq!(
+            truncated(5, Truncation::Drop, "hello").as_deref(),
+            Some("hello")
+        );
+    }
+
+    #[test]
+    fn cut_keeps_the_start() {
+        let cut = truncated(8, Truncation::Cut, "hello world").unwrap();
+        assert_eq!(cut, "hello…");
+        assert!(cut.len() <= 8);
+    }
+
+    #[test]
+    fn head_and_tail_keeps_both_ends() {
+        let cut = truncated(9, Truncation::HeadAndTail, "hello wide world").unwrap();
+        assert_eq!(cut, "hel…rld");
+        assert!(cut.len() <= 9);
+    }
+
+    #[test]
+    fn messages_are_cut_between_characters() {
+        assert_eq!(
+            truncated(6, Truncation::Cut, "ééééé").as_deref(),
+            Some("é…")
+        );
+        assert_eq!(
+            truncated(8, Truncation::HeadAndTail, "äääääää").as_deref(),
+            Some("ä…ä")
+        );
+    }
+
+    #[test]
+    fn no_ellipsis_below_its_length() {
+        assert_eq!(
+            truncated(2, Truncation::Cut, "hello").as_deref(),
+            Some("he")
+        );
+        assert_eq!(
+            truncated(1, Truncation::HeadAndTail, "é").as_deref(),
+            Some("")
+        );
+        assert_eq!(truncated(0, Truncation::Cut, "hello").as_deref(), Some(""));
+    }
+
+    #[test]
+    fn drop_skips_long_messages() {
+        assert_eq!(truncated(4, Truncation::Drop, "hello"), None);
+    }
+}
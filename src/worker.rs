@@ -0,0 +1,788 @@
+use crate::dispatch::{Delivery, Dispatcher, EnabledSet};
+use crate::origin::Origin;
+use crate::sink_error::SinkError;
+use crate::time;
+use crate::OwnedRecord;
+use arc_swap::ArcSwap;
+use log::{Level, Log};
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{sync_channel, SyncSender};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+use std::thread::{self, ThreadId};
+use std::time::{Duration, Instant};
+
+thread_local! {
+    static IS_WORKER: Cell<bool> = const { Cell::new(false) };
+}
+
+/// How often the number of dropped records is reported with [`OverflowPolicy::DropWithCounter`]
+const DROPPED_REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What to do with a record logged while the queue of the asynchronous mode is full
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum OverflowPolicy {
+    /// Block the logging thread until there is space in the queue
+    #[default]
+    Block,
+    /// Drop the record being logged
+    DropNewest,
+    /// Drop the oldest queued record to make space for the one being logged
+    DropOldest,
+    /// Drop the record being logged and periodically log how many records were dropped
+    DropWithCounter,
+}
+
+/// Sheds less severe records while the queue of the asynchronous mode is under pressure,
+/// so that under heavy load the verbosity degrades instead of the logging threads blocking
+/// or the queue taking up too much memory, see [`crate::CompositeLogger::with_load_shedding`].
+///
+/// Records less severe than the minimum level are dropped when they are logged while
+/// the queue holds more records or bytes than its high-water marks, and when they waited
+/// in the queue for longer than the maximum delay. `Error` records are never shed.
+/// How many records were shed is logged once a second and counted in the [`crate::Stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadShedding {
+    min_level: Level,
+    max_len: Option<usize>,
+    max_bytes: Option<usize>,
+    max_delay: Option<Duration>,
+}
+
+impl Default for LoadShedding {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LoadShedding {
+    /// Shed `Debug` and `Trace` records once the queue is three quarters full
+    pub fn new() -> Self {
+        Self {
+            min_level: Level::Info,
+            max_len: None,
+            max_bytes: None,
+            max_delay: None,
+        }
+    }
+
+    /// Shed records less severe than the level, by default `Info`
+    pub fn with_min_level(mut self, level: Level) -> Self {
+        self.min_level = level;
+        self
+    }
+
+    /// Shed once the queue holds the given number of records, by default three quarters
+    /// of its capacity
+    pub fn with_max_len(mut self, records: usize) -> Self {
+        self.max_len = Some(records);
+        self
+    }
+
+    /// Shed once the queued records take about the given number of bytes of memory
+    pub fn with_max_bytes(mut self, bytes: usize) -> Self {
+        self.max_bytes = Some(bytes);
+        self
+    }
+
+    /// Shed records that waited in the queue for longer than the delay,
+    /// since they are stale by the time they would be written
+    pub fn with_max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = Some(delay);
+        self
+    }
+
+    fn sheds(&self, level: Level) -> bool {
+        level > self.min_level && level != Level::Error
+    }
+}
+
+#[derive(Default)]
+struct BarrierState {
+    reached: bool,
+    waker: Option<Waker>,
+}
+
+/// Reached once every logger received the records logged before the barrier was created,
+/// see [`crate::CompositeLoggerHandle::barrier`]. It can be waited for from a thread
+/// or awaited as a future.
+#[derive(Clone, Default)]
+pub struct Barrier {
+    state: Arc<(Mutex<BarrierState>, Condvar)>,
+}
+
+impl Barrier {
+    pub(crate) fn reached() -> Self {
+        let barrier = Self::default();
+        barrier.reach();
+        barrier
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BarrierState> {
+        self.state
+            .0
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+    }
+
+    fn reach(&self) {
+        let waker = {
+            let mut state = self.lock();
+            state.reached = true;
+            state.waker.take()
+        };
+        self.state.1.notify_all();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    pub fn is_reached(&self) -> bool {
+        self.lock().reached
+    }
+
+    /// Block until the barrier is reached
+    pub fn wait(&self) {
+        let mut state = self.lock();
+        while !state.reached {
+            state = self
+                .state
+                .1
+                .wait(state)
+                .unwrap_or_else(|error| error.into_inner());
+        }
+    }
+
+    /// Block until the barrier is reached or the timeout elapsed,
+    /// returning whether it was reached
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let state = self.lock();
+        let (state, _) = self
+            .state
+            .1
+            .wait_timeout_while(state, timeout, |state| !state.reached)
+            .unwrap_or_else(|error| error.into_inner());
+        state.reached
+    }
+}
+
+impl Future for Barrier {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.lock();
+        if state.reached {
+            return Poll::Ready(());
+        }
+        state.waker = Some(context.waker().clone());
+        Poll::Pending
+    }
+}
+
+/// A record dropped because the queue was full, with the loggers it was meant for
+pub(crate) type Dropped = (OwnedRecord, Option<EnabledSet>);
+
+enum Message {
+    /// Boxed so that requests do not take the space of a record in the queue
+    Record(Box<OwnedRecord>, Option<EnabledSet>),
+    Flush(SyncSender<()>),
+    /// Acknowledged once the records queued before are dispatched
+    Drain(SyncSender<()>),
+    Barrier(Barrier),
+}
+
+#[derive(Default)]
+struct State {
+    messages: VecDeque<Message>,
+    /// The records among the messages, which alone count towards the capacity
+    records: usize,
+    /// The approximate memory taken by the queued records
+    bytes: usize,
+    dropped: u64,
+    /// Records shed since the last report
+    shed: u64,
+    closed: bool,
+}
+
+/// A bounded queue of messages shared between the logging threads and the worker
+struct Queue {
+    state: Mutex<State>,
+    not_empty: Condvar,
+    not_full: Condvar,
+    capacity: usize,
+    overflow: OverflowPolicy,
+    shedding: Option<LoadShedding>,
+    shed_total: AtomicU64,
+}
+
+impl Queue {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    /// Returns the record dropped because the queue is full, if any
+    fn push_record(&self, record: OwnedRecord, enabled: Option<EnabledSet>) -> Option<Dropped> {
+        let mut state = self.lock();
+        let mut dropped = None;
+
+        if let Some(shedding) = &self.shedding {
+            let max_len = shedding.max_len.unwrap_or(self.capacity * 3 / 4);
+            let under_pressure = state.records >= max_len
+                || shedding.max_bytes.is_some_and(|max| state.bytes >= max);
+            if under_pressure && shedding.sheds(record.level()) {
+                self.shed(&mut state);
+                return None;
+            }
+        }
+
+        if state.records >= self.capacity {
+            match self.overflow {
+                OverflowPolicy::Block => {
+                    while state.records >= self.capacity && !state.closed {
+                        state = self
+                            .not_full
+                            .wait(state)
+                            .unwrap_or_else(|error| error.into_inner());
+                    }
+                }
+                OverflowPolicy::DropNewest => return Some((record, enabled)),
+                OverflowPolicy::DropOldest => {
+                    if let Some(index) = state
+                        .messages
+                        .iter()
+                        .position(|message| matches!(message, Message::Record(..)))
+                    {
+                        if let Some(Message::Record(oldest, oldest_enabled)) =
+                            state.messages.remove(index)
+                        {
+                            state.bytes -= oldest.size();
+                            state.records -= 1;
+                            dropped = Some((*oldest, oldest_enabled));
+                        }
+                    }
+                }
+                OverflowPolicy::DropWithCounter => {
+                    state.dropped += 1;
+                    return Some((record, enabled));
+                }
+            }
+        }
+
+        state.bytes += record.size();
+        state.records += 1;
+        state
+            .messages
+            .push_back(Message::Record(Box::new(record), enabled));
+        self.not_empty.notify_one();
+        dropped
+    }
+
+    fn shed(&self, state: &mut State) {
+        state.shed += 1;
+        self.shed_total.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Whether the record waited for too long and is shed
+    fn is_stale(&self, record: &OwnedRecord) -> bool {
+        self.shedding.is_some_and(|shedding| {
+            shedding.sheds(record.level())
+                && shedding.max_delay.is_some_and(|max_delay| {
+                    time::wall_time()
+                        .duration_since(record.timestamp())
+                        .is_ok_and(|delay| delay > max_delay)
+                })
+        })
+    }
+
+    /// Flush and drain requests and barriers are never dropped and do not count towards the capacity
+    fn push_request(&self, request: Message) {
+        self.lock().messages.push_back(request);
+        self.not_empty.notify_one();
+    }
+
+    /// Waits for the next message, returning `None` once the queue is closed and drained.
+    /// Records dropped since the last report are reported at most once per interval.
+    fn pop(&self, last_report: &mut Instant) -> Option<Message> {
+        let mut state = self.lock();
+
+        loop {
+            let unreported = state.dropped > 0 || state.shed > 0;
+            if unreported && time::elapsed(*last_report) >= DROPPED_REPORT_INTERVAL {
+                *last_report = time::instant();
+                let message = match (
+                    std::mem::take(&mut state.dropped),
+                    std::mem::take(&mut state.shed),
+                ) {
+                    (dropped, 0) => format!("{} records dropped", dropped),
+                    (0, shed) => format!("{} records shed under load", shed),
+                    (dropped, shed) => {
+                        format!("{} records dropped, {} shed under load", dropped, shed)
+                    }
+                };
+                return Some(Message::Record(
+                    Box::new(OwnedRecord::new(Level::Warn, module_path!(), message)),
+                    None,
+                ));
+            }
+
+            if let Some(message) = state.messages.pop_front() {
+                self.not_full.notify_one();
+                if let Message::Record(record, _) = &message {
+                    state.bytes -= record.size();
+                    state.records -= 1;
+                    if self.is_stale(record) {
+                        self.shed(&mut state);
+                        continue;
+                    }
+                }
+                return Some(message);
+            }
+
+            if state.closed {
+                return None;
+            }
+
+            state = if state.dropped > 0 || state.shed > 0 {
+                self.not_empty
+                    .wait_timeout(state, DROPPED_REPORT_INTERVAL)
+                    .unwrap_or_else(|error| error.into_inner())
+                    .0
+            } else {
+                self.not_empty
+                    .wait(state)
+                    .unwrap_or_else(|error| error.into_inner())
+            };
+        }
+    }
+
+    fn close(&self) {
+        self.lock().closed = true;
+        self.not_empty.notify_all();
+        self.not_full.notify_all();
+    }
+}
+
+/// Dispatches records to the loggers from a dedicated background thread.
+/// The thread stops once the worker is dropped and the queue is drained.
+pub(crate) struct Worker {
+    queue: Arc<Queue>,
+}
+
+impl Worker {
+    pub(crate) fn spawn(
+        capacity: usize,
+        overflow: OverflowPolicy,
+        shedding: Option<LoadShedding>,
+        dispatcher: Arc<ArcSwap<Dispatcher>>,
+    ) -> Self {
+        let queue = Arc::new(Queue {
+            state: Default::default(),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+            capacity: capacity.max(1),
+            overflow,
+            shedding,
+            shed_total: AtomicU64::new(0),
+        });
+
+        let worker_queue = queue.clone();
+        thread::Builder::new()
+            .name("composite-logger".to_string())
+            .spawn(move || Self::run(&worker_queue, dispatcher))
+            .expect("failed to spawn the composite logger worker thread");
+
+        Self { queue }
+    }
+
+    /// Records logged by the loggers themselves are dispatched synchronously,
+    /// otherwise a full queue would dead-lock the worker
+    pub(crate) fn is_worker_thread() -> bool {
+        IS_WORKER.with(Cell::get)
+    }
+
+    /// Queue the record, returning the record dropped because the queue is full, if any
+    pub(crate) fn log(&self, record: OwnedRecord, enabled: Option<EnabledSet>) -> Option<Dropped> {
+        self.queue.push_record(record, enabled)
+    }
+
+    /// The number of queued records, not counting flush requests and barriers
+    pub(crate) fn len(&self) -> usize {
+        self.queue.lock().records
+    }
+
+    /// The approximate memory taken by the queued records
+    pub(crate) fn bytes(&self) -> usize {
+        self.queue.lock().bytes
+    }
+
+    /// The number of records shed so far, see [`LoadShedding`]
+    pub(crate) fn shed(&self) -> u64 {
+        self.queue.shed_total.load(Ordering::Relaxed)
+    }
+
+    /// Whether the queue is nearly full, so that records may soon be dropped or delayed
+    pub(crate) fn is_saturated(&self) -> bool {
+        self.len() * 10 >= self.queue.capacity * 9
+    }
+
+    /// Waits until all queued records are dispatched and the loggers are flushed
+    pub(crate) fn flush(&self) {
+        let (sender, receiver) = sync_channel(1);
+        self.queue.push_request(Message::Flush(sender));
+        let _ = receiver.recv();
+    }
+
+    /// A barrier reached once the records queued so far are dispatched
+    pub(crate) fn barrier(&self) -> Barrier {
+        let barrier = Barrier::default();
+        self.queue.push_request(Message::Barrier(barrier.clone()));
+        barrier
+    }
+
+    /// Waits until the records queued so far are dispatched, returning `false`
+    /// if that takes longer than the timeout
+    pub(crate) fn drain(&self, timeout: Duration) -> bool {
+        let (sender, receiver) = sync_channel(1);
+        self.queue.push_request(Message::Drain(sender));
+        receiver.recv_timeout(timeout).is_ok()
+    }
+
+    fn run(queue: &Queue, dispatcher: Arc<ArcSwap<Dispatcher>>) {
+        IS_WORKER.with(|is_worker| is_worker.set(true));
+
+        let mut last_report = time::instant();
+        while let Some(message) = queue.pop(&mut last_report) {
+            let dispatcher = dispatcher.load();
+            match message {
+                Message::Record(record, enabled) => Origin::of(&record).dispatching(|| {
+                    record.with_record(|record| {
+                        dispatcher.dispatch_with(record, enabled, Delivery::Queued)
+                    })
+                }),
+                Message::Flush(done) => {
+                    dispatcher.flush();
+                    let _ = done.send(());
+                }
+                Message::Drain(done) => {
+                    dispatcher.drain_lanes();
+                    let _ = done.send(());
+                }
+                Message::Barrier(barrier) => {
+                    dispatcher.drain_lanes();
+                    barrier.reach();
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        self.queue.close();
+    }
+}
+
+/// Delivers a record to an isolated logger, or tells that the jobs before it are done
+type Job = Box<dyn FnOnce() + Send>;
+
+/// A failure of an isolated logger with the record it failed to log, if any,
+/// reported by the thread dispatching to the logger
+pub(crate) type LaneFailure = (SinkError, Option<OwnedRecord>);
+
+#[derive(Default)]
+struct LaneState {
+    jobs: VecDeque<Job>,
+    failures: Vec<LaneFailure>,
+    closed: bool,
+}
+
+struct LaneQueue {
+    state: Mutex<LaneState>,
+    not_empty: Condvar,
+    capacity: usize,
+}
+
+impl LaneQueue {
+    fn lock(&self) -> MutexGuard<'_, LaneState> {
+        self.state.lock().unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+/// A thread and a bounded queue of their own for one logger, so that a slow logger
+/// does not delay the delivery to the others, see [`crate::CompositeLogger::with_isolated_logger`].
+/// Records are dropped for the logger while its queue is full.
+/// The thread stops once the lane is dropped and its queue is drained.
+pub(crate) struct Lane {
+    queue: Arc<LaneQueue>,
+    thread: ThreadId,
+}
+
+impl Lane {
+    pub(crate) fn spawn(name: Option<&str>, capacity: usize) -> Self {
+        let queue = Arc::new(LaneQueue {
+            state: Default::default(),
+            not_empty: Condvar::new(),
+            capacity: capacity.max(1),
+        });
+
+        let lane_queue = queue.clone();
+        let thread = thread::Builder::new()
+            .name(match name {
+                Some(name) => format!("composite-logger-{}", name),
+                None => "composite-logger-lane".to_string(),
+            })
+            .spawn(move || Self::run(&lane_queue))
+            .expect("failed to spawn the thread of an isolated logger");
+
+        Self {
+            queue,
+            thread: thread.thread().id(),
+        }
+    }
+
+    pub(crate) fn capacity(&self) -> usize {
+        self.queue.capacity
+    }
+
+    /// Queue the delivery of a record, returning `false` if the queue is full
+    pub(crate) fn deliver(&self, job: impl FnOnce() + Send + 'static) -> bool {
+        let mut state = self.queue.lock();
+        if state.jobs.len() >= self.queue.capacity {
+            return false;
+        }
+        state.jobs.push_back(Box::new(job));
+        self.queue.not_empty.notify_one();
+        true
+    }
+
+    /// Keep the failure until [`Lane::take_failures`], at most as many as the capacity
+    pub(crate) fn failed(&self, error: SinkError, record: Option<OwnedRecord>) {
+        let mut state = self.queue.lock();
+        if state.failures.len() < self.queue.capacity {
+            state.failures.push((error, record));
+        }
+    }
+
+    pub(crate) fn take_failures(&self) -> Vec<LaneFailure> {
+        std::mem::take(&mut self.queue.lock().failures)
+    }
+
+    /// Waits until the records queued so far are delivered, unless called
+    /// from the thread of the lane itself
+    pub(crate) fn drain(&self) {
+        if self.thread == thread::current().id() {
+            return;
+        }
+
+        let (sender, receiver) = sync_channel(1);
+        {
+            let mut state = self.queue.lock();
+            state.jobs.push_back(Box::new(move || {
+                let _ = sender.send(());
+            }));
+            self.queue.not_empty.notify_one();
+        }
+        let _ = receiver.recv();
+    }
+
+    fn run(queue: &LaneQueue) {
+        // records logged by the logger itself are dispatched synchronously
+        IS_WORKER.with(|is_worker| is_worker.set(true));
+
+        loop {
+            let job = {
+                let mut state = queue.lock();
+                loop {
+                    if let Some(job) = state.jobs.pop_front() {
+                        break job;
+                    }
+                    if state.closed {
+                        return;
+                    }
+                    state = queue
+                        .not_empty
+                        .wait(state)
+                        .unwrap_or_else(|error| error.into_inner());
+                }
+            };
+            job();
+        }
+    }
+}
+
+impl Drop for Lane {
+    fn drop(&mut self) {
+        self.queue.lock().closed = true;
+        self.queue.not_empty.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    fn queue(capacity: usize, overflow: OverflowPolicy, shedding: Option<LoadShedding>) -> Queue {
+        Queue {
+            state: Default::default(),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+            capacity,
+            overflow,
+            shedding,
+            shed_total: AtomicU64::new(0),
+        }
+    }
+
+    fn push(queue: &Queue, level: Level, message: &str) -> Option<String> {
+        queue
+            .push_record(OwnedRecord::new(level, "app", message), None)
+            .map(|(dropped, _)| dropped.message().to_string())
+    }
+
+    /// The messages of the queued records, oldest first
+    fn queued(queue: &Queue) -> Vec<String> {
+        queue
+            .lock()
+            .messages
+            .iter()
+            .filter_map(|message| match message {
+                Message::Record(record, _) => Some(record.message().to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The message of the next record, reporting dropped records right away
+    fn pop(queue: &Queue) -> Option<String> {
+        let mut last_report = time::instant() - DROPPED_REPORT_INTERVAL;
+        match queue.pop(&mut last_report)? {
+            Message::Record(record, _) => Some(record.message().to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn drops_the_newest_record_once_full() {
+        let queue = queue(2, OverflowPolicy::DropNewest, None);
+        assert_eq!(push(&queue, Level::Info, "first"), None);
+        assert_eq!(push(&queue, Level::Info, "second"), None);
+        assert_eq!(
+            push(&queue, Level::Info, "third"),
+            Some("third".to_string())
+        );
+
+        assert_eq!(queued(&queue), ["first", "second"]);
+    }
+
+    #[test]
+    fn drops_the_oldest_record_once_full() {
+        let queue = queue(2, OverflowPolicy::DropOldest, None);
+        push(&queue, Level::Info, "first");
+        push(&queue, Level::Info, "second");
+        queue.push_request(Message::Barrier(Barrier::default()));
+        assert_eq!(
+            push(&queue, Level::Info, "third"),
+            Some("first".to_string())
+        );
+
+        assert_eq!(queued(&queue), ["second", "third"]);
+        assert_eq!(queue.lock().records, 2);
+    }
+
+    #[test]
+    fn reports_how_many_records_were_dropped() {
+        let queue = queue(1, OverflowPolicy::DropWithCounter, None);
+        push(&queue, Level::Info, "kept");
+        push(&queue, Level::Info, "dropped");
+        push(&queue, Level::Info, "dropped");
+
+        assert_eq!(pop(&queue), Some("2 records dropped".to_string()));
+        assert_eq!(pop(&queue), Some("kept".to_string()));
+    }
+
+    #[test]
+    fn blocks_until_there_is_space() {
+        let queue = Arc::new(queue(1, OverflowPolicy::Block, None));
+        push(&queue, Level::Info, "first");
+
+        let pushing = thread::spawn({
+            let queue = queue.clone();
+            move || push(&queue, Level::Info, "second")
+        });
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(queued(&queue), ["first"]);
+
+        assert_eq!(pop(&queue), Some("first".to_string()));
+        assert_eq!(pushing.join().unwrap(), None);
+        assert_eq!(queued(&queue), ["second"]);
+    }
+
+    #[test]
+    fn stops_blocking_once_closed() {
+        let queue = Arc::new(queue(1, OverflowPolicy::Block, None));
+        push(&queue, Level::Info, "first");
+
+        let pushing = thread::spawn({
+            let queue = queue.clone();
+            move || push(&queue, Level::Info, "second")
+        });
+        thread::sleep(Duration::from_millis(50));
+        queue.close();
+
+        assert_eq!(pushing.join().unwrap(), None);
+        assert_eq!(pop(&queue), Some("first".to_string()));
+    }
+
+    #[test]
+    fn sheds_less_severe_records_under_pressure() {
+        let shedding = LoadShedding::new().with_max_len(1);
+        let queue = queue(4, OverflowPolicy::DropNewest, Some(shedding));
+        push(&queue, Level::Debug, "first");
+        push(&queue, Level::Debug, "shed");
+        push(&queue, Level::Info, "info");
+        push(&queue, Level::Trace, "shed");
+
+        assert_eq!(queued(&queue), ["first", "info"]);
+        assert_eq!(queue.shed_total.load(Ordering::Relaxed), 2);
+        assert_eq!(pop(&queue), Some("2 records shed under load".to_string()));
+    }
+
+    #[test]
+    fn never_sheds_errors() {
+        let shedding = LoadShedding::new()
+            .with_min_level(Level::Error)
+            .with_max_bytes(1);
+        let queue = queue(4, OverflowPolicy::DropNewest, Some(shedding));
+        push(&queue, Level::Error, "first");
+        push(&queue, Level::Warn, "shed");
+        push(&queue, Level::Error, "second");
+
+        assert_eq!(queued(&queue), ["first", "second"]);
+    }
+
+    #[test]
+    fn sheds_records_that_waited_for_too_long() {
+        testing::with_mock_clock(|clock| {
+            let shedding = LoadShedding::new().with_max_delay(Duration::from_secs(1));
+            let queue = queue(4, OverflowPolicy::DropNewest, Some(shedding));
+            push(&queue, Level::Debug, "stale");
+            push(&queue, Level::Warn, "late");
+            clock.advance(Duration::from_secs(2));
+            push(&queue, Level::Debug, "fresh");
+
+            assert_eq!(pop(&queue), Some("1 records shed under load".to_string()));
+            assert_eq!(pop(&queue), Some("late".to_string()));
+            assert_eq!(pop(&queue), Some("fresh".to_string()));
+        });
+    }
+}